@@ -0,0 +1,33 @@
+// benches/clean_text.rs - PDFExtractor::clean_text on a 100 KB agreement
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+// The crate is binary-only, so compile the extractor and its dependency directly
+#[allow(dead_code)]
+#[path = "../src/ocr_preprocessing.rs"]
+mod ocr_preprocessing;
+#[allow(dead_code, unused_imports)]
+#[path = "../src/pdf_extractor.rs"]
+mod pdf_extractor;
+
+use pdf_extractor::PDFExtractor;
+
+/// Roughly 100 KB of contract-like text with irregular spacing and control characters
+fn sample_text() -> String {
+    let paragraph = "THIS AGREEMENT  is made on 1st August 2024 between\tVyjayanthi Movies (the \"Licensor\")\r\n\
+                     and   Netflix India (the \"Licensee\").\u{000C}\n\n\n\
+                     1. TERRITORY: The Territory shall be INDIA   and NEPAL.\u{0007}\n";
+    paragraph.repeat(100 * 1024 / paragraph.len() + 1)
+}
+
+fn bench_clean_text(c: &mut Criterion) {
+    let extractor = PDFExtractor::new();
+    let text = sample_text();
+
+    let mut group = c.benchmark_group("clean_text");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("100kb", |b| b.iter(|| extractor.clean_text(black_box(&text))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_clean_text);
+criterion_main!(benches);
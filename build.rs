@@ -0,0 +1,11 @@
+// build.rs - Generate gRPC bindings from proto/
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Fall back to the bundled protoc so builds don't need a system install
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+
+    println!("cargo:rerun-if-changed=proto/rights_parser.proto");
+    tonic_build::compile_protos("proto/rights_parser.proto")?;
+    Ok(())
+}
@@ -49,6 +49,127 @@ CREATE INDEX idx_jobs_created_at ON jobs(created_at DESC);
 CREATE INDEX idx_jobs_api_key_hash ON jobs(api_key_hash);
 CREATE INDEX idx_jobs_completed_at ON jobs(completed_at DESC) WHERE status = 'completed';
 
+-- Parsed agreements table - one row per agreement uploaded to IPFS
+CREATE TABLE parsed_agreements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+
+    -- IPFS location (the decryption key is never stored)
+    ipfs_cid VARCHAR(100) NOT NULL UNIQUE,
+
+    -- Extracted summary fields
+    agreement_id VARCHAR(255) UNIQUE,
+    title VARCHAR(500),
+    licensor VARCHAR(255),
+    licensee VARCHAR(255),
+
+    -- Source file
+    file_name VARCHAR(255) NOT NULL,
+    file_size BIGINT NOT NULL,
+
+    -- Metadata
+    processing_time_ms BIGINT,
+    model_used VARCHAR(50),
+
+    -- User-defined labels
+    tags TEXT[] NOT NULL DEFAULT '{}',
+
+    -- Delivery SLA tracking
+    webhook_url TEXT,
+    delivery_deadline DATE,
+    delivery_notice_sent_at TIMESTAMP WITH TIME ZONE,
+
+    -- License term
+    start_date DATE,
+    end_date DATE,
+    status VARCHAR(20) NOT NULL DEFAULT 'Active',
+
+    -- Portfolio reporting
+    agreement_type VARCHAR(50),
+    deal_value BIGINT,
+    currency VARCHAR(3),
+    territories TEXT[] NOT NULL DEFAULT '{}',
+
+    -- On-chain anchor, set by export-blockchain
+    tx_hash VARCHAR(66),
+    block_number BIGINT,
+    contract_address VARCHAR(42),
+
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Composite index for cursor pagination over (created_at, id)
+CREATE INDEX idx_parsed_agreements_cursor ON parsed_agreements(created_at DESC, id DESC);
+-- GIN index for tag containment filters
+CREATE INDEX idx_parsed_agreements_tags ON parsed_agreements USING GIN (tags);
+-- Pending delivery notices for the background worker
+CREATE INDEX idx_parsed_agreements_delivery ON parsed_agreements(delivery_deadline)
+    WHERE delivery_notice_sent_at IS NULL;
+-- Expiry window queries ordered by end date
+CREATE INDEX idx_parsed_agreements_end_date ON parsed_agreements(end_date ASC, id ASC)
+    WHERE end_date IS NOT NULL;
+-- Duplicate upload checks by parties or title
+CREATE INDEX idx_parsed_agreements_parties ON parsed_agreements(LOWER(licensor), LOWER(licensee));
+CREATE INDEX idx_parsed_agreements_title ON parsed_agreements(LOWER(title));
+
+-- One row per agreement with every field as a column, for SQL analytics.
+-- Generated by flat_agreement::create_table_ddl - regenerate rather than edit.
+CREATE TABLE IF NOT EXISTS parsed_agreements_flat (
+    agreement_id TEXT PRIMARY KEY,
+    content_director TEXT NOT NULL,
+    content_duration_minutes BIGINT NOT NULL,
+    content_genres TEXT[] NOT NULL,
+    content_language TEXT NOT NULL,
+    content_original_title TEXT NOT NULL,
+    content_producer TEXT NOT NULL,
+    content_production_company TEXT,
+    content_production_country TEXT,
+    content_rating_cbfc TEXT NOT NULL,
+    content_rating_mpaa TEXT,
+    content_release_date TEXT NOT NULL,
+    content_title TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    deliverables_delivery_deadline DATE,
+    deliverables_status TEXT,
+    financial_currency TEXT NOT NULL,
+    financial_deal_value BIGINT NOT NULL,
+    financial_milestone_count BIGINT NOT NULL,
+    financial_net_to_rights_holder BIGINT NOT NULL,
+    financial_on_delivery BIGINT NOT NULL,
+    financial_payment_type TEXT NOT NULL,
+    financial_platform_fee_amount BIGINT NOT NULL,
+    financial_platform_fee_percentage DOUBLE PRECISION NOT NULL,
+    financial_upfront BIGINT NOT NULL,
+    legal_dispute_mechanism TEXT,
+    legal_governing_law TEXT,
+    licensee_contact_email TEXT,
+    licensee_country TEXT,
+    licensee_name TEXT,
+    licensee_signatory_name TEXT,
+    licensor_contact_email TEXT,
+    licensor_country TEXT,
+    licensor_name TEXT,
+    licensor_signatory_name TEXT,
+    metadata_created_date TEXT,
+    metadata_status TEXT,
+    metadata_tags TEXT[] NOT NULL,
+    metadata_version TEXT,
+    restrictions_holdback_free_tv_days BIGINT,
+    restrictions_holdback_physical_media_days BIGINT,
+    restrictions_holdback_theatrical_days BIGINT,
+    restrictions_platforms_excluded TEXT[] NOT NULL,
+    restrictions_territories_excluded TEXT[] NOT NULL,
+    rights_exclusive BOOLEAN NOT NULL,
+    rights_holder_name TEXT NOT NULL,
+    rights_holder_wallet_address TEXT NOT NULL,
+    rights_media_type_codes TEXT[] NOT NULL,
+    rights_media_types TEXT[] NOT NULL,
+    rights_term_end_date DATE,
+    rights_term_start_date DATE,
+    rights_term_years BIGINT NOT NULL,
+    rights_territories TEXT[] NOT NULL,
+    special_terms_count BIGINT NOT NULL
+);
+
 -- API Keys table - manage multiple API keys
 CREATE TABLE api_keys (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
@@ -65,6 +186,7 @@ CREATE TABLE api_keys (
     -- Permissions
     is_active BOOLEAN DEFAULT TRUE,
     rate_limit INTEGER DEFAULT 100, -- requests per hour
+    daily_parse_quota INTEGER NOT NULL DEFAULT 100, -- parses per UTC day
     
     -- Usage tracking
     requests_count BIGINT DEFAULT 0,
@@ -83,6 +205,67 @@ CREATE TABLE api_keys (
 CREATE INDEX idx_api_keys_hash ON api_keys(key_hash);
 CREATE INDEX idx_api_keys_active ON api_keys(is_active) WHERE is_active = TRUE;
 
+-- Webhook subscriptions registered through POST /api/webhooks. Each only
+-- receives events for agreements parsed with the API key that registered it.
+CREATE TABLE webhook_subscriptions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    api_key_id UUID NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    events TEXT[] NOT NULL, -- e.g. agreement.parsed
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_subscriptions_key ON webhook_subscriptions(api_key_id);
+CREATE INDEX idx_webhook_subscriptions_events ON webhook_subscriptions USING GIN (events);
+
+-- Agreement IDs handed out by the JSON builder. Reserving an ID before the
+-- agreement is stored means concurrent uploads never pick the same one;
+-- parsed_agreements.agreement_id being UNIQUE backs this up.
+CREATE TABLE agreement_ids (
+    agreement_id VARCHAR(255) PRIMARY KEY,
+    reserved_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Lineage of agreement CIDs: each renewal, rekey or edit stores a new CID
+-- pointing at the one it was derived from
+CREATE TABLE agreement_versions (
+    cid VARCHAR(100) PRIMARY KEY,
+    predecessor_cid VARCHAR(100),
+    version VARCHAR(20),
+    status VARCHAR(20),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Encryption key rotations. Keys themselves are never stored.
+CREATE TABLE key_rotations (
+    id BIGSERIAL PRIMARY KEY,
+    old_cid VARCHAR(100) NOT NULL,
+    new_cid VARCHAR(100) NOT NULL,
+    rotated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_key_rotations_old_cid ON key_rotations(old_cid);
+
+-- On-chain exports, one per CID. The row is claimed before the transaction
+-- is sent, so concurrent or repeated exports never register a CID twice.
+CREATE TABLE blockchain_exports (
+    cid VARCHAR(100) PRIMARY KEY,
+    tx_hash VARCHAR(66),
+    anchored_cid VARCHAR(100),
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Audit trail of sensitive reads, e.g. party contact lookups
+CREATE TABLE audit_events (
+    id BIGSERIAL PRIMARY KEY,
+    event_type VARCHAR(50) NOT NULL,
+    ipfs_cid VARCHAR(100),
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_events_cid ON audit_events(ipfs_cid, created_at DESC);
+
 -- Usage logs for analytics
 CREATE TABLE usage_logs (
     id BIGSERIAL PRIMARY KEY,
@@ -0,0 +1,182 @@
+// src/address_validation.rs - Offline plausibility checks for party addresses
+use regex::Regex;
+
+/// Static reference data for one country
+struct CountryInfo {
+    code: &'static str,
+    names: &'static [&'static str],
+    /// `None` for countries without a postal code system
+    postal_pattern: Option<&'static str>,
+    major_cities: &'static [&'static str],
+}
+
+const COUNTRIES: &[CountryInfo] = &[
+    CountryInfo {
+        code: "IN",
+        names: &["india", "bharat"],
+        postal_pattern: Some(r"\b[1-9]\d{2}\s?\d{3}\b"),
+        major_cities: &["mumbai", "delhi", "new delhi", "bengaluru", "bangalore", "hyderabad", "chennai", "kolkata", "pune", "ahmedabad", "noida", "gurugram", "gurgaon", "kochi"],
+    },
+    CountryInfo {
+        code: "US",
+        names: &["united states", "united states of america", "usa", "u.s.a."],
+        postal_pattern: Some(r"\b\d{5}(?:-\d{4})?\b"),
+        major_cities: &["new york", "los angeles", "burbank", "santa monica", "culver city", "san francisco", "chicago", "atlanta", "seattle", "mountain view"],
+    },
+    CountryInfo {
+        code: "GB",
+        names: &["united kingdom", "uk", "england", "scotland", "wales", "great britain"],
+        postal_pattern: Some(r"(?i)\b[A-Z]{1,2}\d[A-Z\d]?\s?\d[A-Z]{2}\b"),
+        major_cities: &["london", "manchester", "birmingham", "edinburgh", "glasgow", "bristol"],
+    },
+    CountryInfo {
+        code: "CA",
+        names: &["canada"],
+        postal_pattern: Some(r"(?i)\b[A-Z]\d[A-Z]\s?\d[A-Z]\d\b"),
+        major_cities: &["toronto", "vancouver", "montreal", "ottawa", "calgary"],
+    },
+    CountryInfo {
+        code: "AU",
+        names: &["australia"],
+        postal_pattern: Some(r"\b\d{4}\b"),
+        major_cities: &["sydney", "melbourne", "brisbane", "perth", "adelaide"],
+    },
+    CountryInfo {
+        code: "SG",
+        names: &["singapore"],
+        postal_pattern: Some(r"\b\d{6}\b"),
+        major_cities: &["singapore"],
+    },
+    CountryInfo {
+        code: "AE",
+        names: &["united arab emirates", "uae"],
+        postal_pattern: None,
+        major_cities: &["dubai", "abu dhabi", "sharjah"],
+    },
+    CountryInfo {
+        code: "DE",
+        names: &["germany", "deutschland"],
+        postal_pattern: Some(r"\b\d{5}\b"),
+        major_cities: &["berlin", "munich", "münchen", "hamburg", "frankfurt", "cologne", "köln"],
+    },
+    CountryInfo {
+        code: "FR",
+        names: &["france"],
+        postal_pattern: Some(r"\b\d{5}\b"),
+        major_cities: &["paris", "lyon", "marseille", "cannes", "nice"],
+    },
+    CountryInfo {
+        code: "JP",
+        names: &["japan"],
+        postal_pattern: Some(r"\b\d{3}-\d{4}\b"),
+        major_cities: &["tokyo", "osaka", "kyoto", "yokohama"],
+    },
+    CountryInfo {
+        code: "CN",
+        names: &["china", "people's republic of china", "prc"],
+        postal_pattern: Some(r"\b\d{6}\b"),
+        major_cities: &["beijing", "shanghai", "shenzhen", "guangzhou", "hong kong"],
+    },
+];
+
+/// Outcome of validating a raw address string
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddressValidation {
+    pub validated: bool,
+    pub country_code: Option<String>,
+    pub issues: Vec<String>,
+}
+
+fn contains_word(text: &str, word: &str) -> bool {
+    Regex::new(&format!(r"(?i)(^|[^\p{{L}}]){}($|[^\p{{L}}])", regex::escape(word)))
+        .map(|re| re.is_match(text))
+        .unwrap_or(false)
+}
+
+/// Find the country by name, or by an ISO code given as the final token
+fn detect_country(address: &str) -> Option<&'static CountryInfo> {
+    let by_name = COUNTRIES
+        .iter()
+        .find(|c| c.names.iter().any(|n| contains_word(address, n)));
+
+    by_name.or_else(|| {
+        let last = address
+            .rsplit(|c: char| c == ',' || c.is_whitespace())
+            .find(|t| !t.is_empty())?
+            .trim_matches('.');
+        COUNTRIES.iter().find(|c| c.code == last)
+    })
+}
+
+/// Check an extracted address for a recognisable country, a postal code in
+/// that country's format and a plausible city
+pub fn validate_address(address: &str) -> AddressValidation {
+    let address = address.trim();
+    let mut issues = Vec::new();
+
+    if address.is_empty() || address.eq_ignore_ascii_case("TBD") {
+        return AddressValidation {
+            validated: false,
+            country_code: None,
+            issues: vec!["No address extracted".to_string()],
+        };
+    }
+
+    let country = detect_country(address);
+    if country.is_none() {
+        issues.push("No recognisable country name or code".to_string());
+    }
+
+    if let Some(pattern) = country.and_then(|c| c.postal_pattern) {
+        let has_postal_code = Regex::new(pattern).map(|re| re.is_match(address)).unwrap_or(false);
+        if !has_postal_code {
+            issues.push(format!("No postal code in {} format", country.map(|c| c.code).unwrap_or("")));
+        }
+    }
+
+    // A known city, or otherwise a comma-separated segment that reads like a place name
+    let known_city = country
+        .map(|c| c.major_cities.iter().any(|city| contains_word(address, city)))
+        .unwrap_or(false);
+    let place_like_segment = address.split(',').map(str::trim).any(|segment| {
+        segment.len() >= 3
+            && segment.chars().all(|c| c.is_alphabetic() || c == ' ' || c == '-' || c == '.')
+            && !country.map_or(false, |c| {
+                c.names.iter().any(|n| segment.eq_ignore_ascii_case(n)) || segment == c.code
+            })
+    });
+    if !known_city && !place_like_segment {
+        issues.push("No plausible city name".to_string());
+    }
+
+    AddressValidation {
+        validated: issues.is_empty(),
+        country_code: country.map(|c| c.code.to_string()),
+        issues,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validates_indian_address() {
+        let result = validate_address("Plot 12, Film Nagar, Jubilee Hills, Hyderabad, Telangana 500096, India");
+        assert!(result.validated, "{:?}", result.issues);
+        assert_eq!(result.country_code.as_deref(), Some("IN"));
+    }
+
+    #[test]
+    fn test_flags_postal_code_mismatch() {
+        let result = validate_address("221B Baker Street, London, 12345, United Kingdom");
+        assert!(!result.validated);
+        assert_eq!(result.country_code.as_deref(), Some("GB"));
+
+        let result = validate_address("Sunset Boulevard 10");
+        assert!(!result.validated);
+        assert!(result.country_code.is_none());
+
+        assert!(!validate_address("TBD").validated);
+    }
+}
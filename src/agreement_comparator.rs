@@ -0,0 +1,152 @@
+// src/agreement_comparator.rs - Spot uploads that repeat an existing agreement
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::models::RightsAgreementJSON;
+
+/// Share of compared fields that must match for an agreement to be reported
+const MIN_SIMILARITY_SCORE: f64 = 0.75;
+
+/// Most candidate rows compared per upload
+const MAX_CANDIDATES: i64 = 50;
+
+/// A recorded agreement that may be the same as the one just parsed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilarAgreement {
+    pub cid: String,
+    pub agreement_id: Option<String>,
+    /// Share of licensor, licensee, title and start date that match
+    pub similarity_score: f64,
+    pub matching_fields: Vec<String>,
+}
+
+/// The fields uploads are compared on
+#[derive(Debug, Clone, Default)]
+pub struct AgreementFingerprint<'a> {
+    pub licensor: Option<&'a str>,
+    pub licensee: Option<&'a str>,
+    pub content_title: Option<&'a str>,
+    pub start_date: Option<NaiveDate>,
+}
+
+impl<'a> AgreementFingerprint<'a> {
+    pub fn from_agreement(agreement: &'a RightsAgreementJSON) -> Self {
+        let parties = agreement.parties.as_ref();
+        Self {
+            licensor: Some(parties.map_or(agreement.rights_holder.name.as_str(), |p| p.licensor.name.as_str())),
+            licensee: parties.map(|p| p.licensee.name.as_str()),
+            content_title: Some(agreement.content.title.as_str()),
+            start_date: agreement.rights.term.start_date.date(),
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Candidate {
+    ipfs_cid: String,
+    agreement_id: Option<String>,
+    title: Option<String>,
+    licensor: Option<String>,
+    licensee: Option<String>,
+    start_date: Option<NaiveDate>,
+}
+
+fn same_text(a: Option<&str>, b: Option<&str>) -> bool {
+    match (a.map(str::trim), b.map(str::trim)) {
+        (Some(a), Some(b)) => !a.is_empty() && a.eq_ignore_ascii_case(b),
+        _ => false,
+    }
+}
+
+fn compare(fingerprint: &AgreementFingerprint<'_>, candidate: Candidate) -> Option<SimilarAgreement> {
+    let checks = [
+        ("licensor", same_text(fingerprint.licensor, candidate.licensor.as_deref())),
+        ("licensee", same_text(fingerprint.licensee, candidate.licensee.as_deref())),
+        ("content_title", same_text(fingerprint.content_title, candidate.title.as_deref())),
+        ("start_date", fingerprint.start_date.is_some() && fingerprint.start_date == candidate.start_date),
+    ];
+    let matching_fields: Vec<String> = checks
+        .iter()
+        .filter(|(_, matched)| *matched)
+        .map(|(field, _)| field.to_string())
+        .collect();
+
+    let similarity_score = matching_fields.len() as f64 / checks.len() as f64;
+    (similarity_score >= MIN_SIMILARITY_SCORE).then(|| SimilarAgreement {
+        cid: candidate.ipfs_cid,
+        agreement_id: candidate.agreement_id,
+        similarity_score,
+        matching_fields,
+    })
+}
+
+pub struct AgreementComparator;
+
+impl AgreementComparator {
+    /// Recorded agreements sharing most of the licensor, licensee, content
+    /// title and term start date with `agreement`, most similar first
+    pub async fn find_similar(agreement: &RightsAgreementJSON, pool: &PgPool) -> Result<Vec<SimilarAgreement>> {
+        Self::find_similar_to(&AgreementFingerprint::from_agreement(agreement), pool).await
+    }
+
+    /// `find_similar` for agreements not in the structured format
+    pub async fn find_similar_to(fingerprint: &AgreementFingerprint<'_>, pool: &PgPool) -> Result<Vec<SimilarAgreement>> {
+        let candidates = sqlx::query_as::<_, Candidate>(
+            r#"
+            SELECT ipfs_cid, agreement_id, title, licensor, licensee, start_date
+            FROM parsed_agreements
+            WHERE (LOWER(licensor) = LOWER($1) AND LOWER(licensee) = LOWER($2))
+               OR LOWER(title) = LOWER($3)
+            ORDER BY created_at DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(fingerprint.licensor.map(str::trim))
+        .bind(fingerprint.licensee.map(str::trim))
+        .bind(fingerprint.content_title.map(str::trim))
+        .bind(MAX_CANDIDATES)
+        .fetch_all(pool)
+        .await
+        .context("Failed to look up similar agreements")?;
+
+        let mut similar: Vec<SimilarAgreement> = candidates
+            .into_iter()
+            .filter_map(|candidate| compare(fingerprint, candidate))
+            .collect();
+        similar.sort_by(|a, b| b.similarity_score.total_cmp(&a.similarity_score));
+        Ok(similar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_scores_matching_fields() {
+        let fingerprint = AgreementFingerprint {
+            licensor: Some("Vyjayanthi Movies"),
+            licensee: Some("Netflix India"),
+            content_title: Some("Kalki 2898 AD"),
+            start_date: NaiveDate::from_ymd_opt(2024, 8, 1),
+        };
+        let candidate = |licensee: &str, start_date| Candidate {
+            ipfs_cid: "bafkrei-existing".to_string(),
+            agreement_id: None,
+            title: Some("KALKI 2898 AD ".to_string()),
+            licensor: Some("vyjayanthi movies".to_string()),
+            licensee: Some(licensee.to_string()),
+            start_date,
+        };
+
+        let duplicate = compare(&fingerprint, candidate("Netflix India", fingerprint.start_date)).unwrap();
+        assert_eq!(duplicate.similarity_score, 1.0);
+        assert_eq!(duplicate.matching_fields, vec!["licensor", "licensee", "content_title", "start_date"]);
+
+        let renewal = compare(&fingerprint, candidate("Netflix India", None)).unwrap();
+        assert_eq!(renewal.similarity_score, 0.75);
+        assert!(compare(&fingerprint, candidate("Amazon Prime Video", None)).is_none());
+    }
+}
@@ -0,0 +1,233 @@
+// src/agreement_diff.rs - Field-level diffs between agreement versions
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::models::RightsAgreementJSON;
+
+/// A single changed field, addressed by dot-notation path (e.g. `financial.dealValue`).
+/// `None` means the field is absent on that side.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FieldChange {
+    pub path: String,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+}
+
+/// All field changes needed to turn one agreement version into another
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AgreementDiff {
+    pub changes: Vec<FieldChange>,
+}
+
+impl AgreementDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The diff that undoes this one
+    pub fn inverse(&self) -> AgreementDiff {
+        let changes = self
+            .changes
+            .iter()
+            .rev()
+            .map(|change| FieldChange {
+                path: change.path.clone(),
+                old_value: change.new_value.clone(),
+                new_value: change.old_value.clone(),
+            })
+            .collect();
+        AgreementDiff { changes }
+    }
+
+    /// Rebuild `base` with every change applied. Fails if a change's old value
+    /// does not match `base`, i.e. the diff was taken against a different version.
+    pub fn apply_patch(base: &RightsAgreementJSON, diff: &AgreementDiff) -> Result<RightsAgreementJSON> {
+        let mut value = serde_json::to_value(base).context("Failed to serialize base agreement")?;
+
+        for change in &diff.changes {
+            let segments: Vec<&str> = change.path.split('.').collect();
+
+            let current = segments
+                .iter()
+                .try_fold(&value, |v, key| v.get(*key));
+            if current != change.old_value.as_ref() {
+                anyhow::bail!("Diff does not apply: {} has changed", change.path);
+            }
+
+            set_path(&mut value, &segments, change.new_value.clone())
+                .with_context(|| format!("Cannot apply change at {}", change.path))?;
+        }
+
+        serde_json::from_value(value).context("Patched agreement is not a valid agreement")
+    }
+}
+
+impl RightsAgreementJSON {
+    /// Field-level changes from `self` to `other`. Arrays are compared as a whole.
+    pub fn diff(&self, other: &RightsAgreementJSON) -> AgreementDiff {
+        let old = serde_json::to_value(self).unwrap_or_default();
+        let new = serde_json::to_value(other).unwrap_or_default();
+
+        let mut changes = Vec::new();
+        diff_values("", Some(&old), Some(&new), &mut changes);
+        AgreementDiff { changes }
+    }
+}
+
+/// Agreement state after the first `amendment` diffs of an amendment history
+pub fn reconstruct_version(
+    original: &RightsAgreementJSON,
+    amendments: &[AgreementDiff],
+    amendment: usize,
+) -> Result<RightsAgreementJSON> {
+    if amendment > amendments.len() {
+        anyhow::bail!("Amendment {} does not exist ({} recorded)", amendment, amendments.len());
+    }
+
+    amendments[..amendment]
+        .iter()
+        .try_fold(original.clone(), |agreement, diff| AgreementDiff::apply_patch(&agreement, diff))
+}
+
+/// State of `latest` as of amendment `amendment`, 0 being the original. The
+/// original is recovered by undoing `metadata.amendments` from the newest,
+/// then replayed forward. The history itself is not part of any version.
+pub fn version_at(latest: &RightsAgreementJSON, amendment: usize) -> Result<RightsAgreementJSON> {
+    let mut current = latest.clone();
+    let amendments = current
+        .metadata
+        .as_mut()
+        .map(|metadata| std::mem::take(&mut metadata.amendments))
+        .unwrap_or_default();
+
+    let original = amendments
+        .iter()
+        .rev()
+        .try_fold(current, |agreement, diff| AgreementDiff::apply_patch(&agreement, &diff.inverse()))
+        .context("Amendment history does not match the agreement")?;
+    reconstruct_version(&original, &amendments, amendment)
+}
+
+fn diff_values(path: &str, old: Option<&Value>, new: Option<&Value>, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys().filter(|k| !a.contains_key(*k))).collect();
+            keys.sort();
+
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff_values(&child, a.get(key), b.get(key), changes);
+            }
+        }
+        (a, b) if a != b => changes.push(FieldChange {
+            path: path.to_string(),
+            old_value: a.cloned(),
+            new_value: b.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+fn set_path(value: &mut Value, segments: &[&str], new_value: Option<Value>) -> Result<()> {
+    let (last, parents) = segments.split_last().context("Empty path")?;
+
+    let mut target = value;
+    for key in parents {
+        let map = target.as_object_mut().context("Path passes through a non-object")?;
+        target = map
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+
+    let map = target.as_object_mut().context("Path passes through a non-object")?;
+    match new_value {
+        Some(v) => {
+            map.insert(last.to_string(), v);
+        }
+        None => {
+            map.remove(*last);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn agreement() -> RightsAgreementJSON {
+        serde_json::from_value(json!({
+            "agreementId": "AGR-1",
+            "rightsHolder": { "name": "Vyjayanthi Movies", "walletAddress": "" },
+            "content": {
+                "title": "Kalki 2898 AD", "originalTitle": "Kalki 2898 AD", "type": "Film",
+                "language": "Telugu", "genre": ["Sci-Fi"], "duration": 180,
+                "releaseDate": "2024-06-27", "director": "Nag Ashwin", "producer": "C. Aswani Dutt",
+                "rating": { "cbfc": "UA" }
+            },
+            "rights": {
+                "territories": ["India"], "mediaTypes": ["SVOD"], "exclusivity": true,
+                "term": { "years": 5, "startDate": "2024-08-01", "endDate": "2029-07-31" }
+            },
+            "financial": {
+                "dealValue": 1000000, "currency": "INR",
+                "platformFee": { "percentage": 0.0, "amount": 0 },
+                "netToRightsHolder": 1000000,
+                "paymentStructure": { "type": "fixed", "breakdown": { "upfront": 0, "onDelivery": 1000000 } }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff_patch_round_trip() {
+        let original = agreement();
+        let mut amended = original.clone();
+        amended.financial.deal_value = 1_500_000;
+        amended.rights.territories.push("Nepal".to_string());
+        amended.special_terms = Some(vec![crate::models::SpecialTerm::parse("Dubbed versions included")]);
+
+        let diff = original.diff(&amended);
+        assert_eq!(diff.changes.len(), 3);
+
+        let patched = AgreementDiff::apply_patch(&original, &diff).unwrap();
+        assert_eq!(
+            serde_json::to_value(&patched).unwrap(),
+            serde_json::to_value(&amended).unwrap()
+        );
+
+        // The amendment history replays to the same state
+        let rebuilt = reconstruct_version(&original, &[diff.clone()], 1).unwrap();
+        assert_eq!(rebuilt.financial.deal_value, 1_500_000);
+
+        // Patching a version the diff was not taken from is rejected
+        assert!(AgreementDiff::apply_patch(&amended, &diff).is_err());
+    }
+
+    #[test]
+    fn test_version_at_replays_stored_amendments() {
+        let original = agreement();
+        let mut first = original.clone();
+        first.financial.deal_value = 1_500_000;
+        let mut second = first.clone();
+        second.rights.territories.push("Nepal".to_string());
+
+        let mut latest = second.clone();
+        latest.metadata.get_or_insert_with(crate::models::Metadata::new).amendments =
+            vec![original.diff(&first), first.diff(&second)];
+
+        let at = |n| serde_json::to_value(version_at(&latest, n).unwrap()).unwrap();
+        assert_eq!(at(0)["financial"], serde_json::to_value(&original).unwrap()["financial"]);
+        assert_eq!(at(1)["financial"]["dealValue"], 1_500_000);
+        assert_eq!(at(1)["rights"]["territories"], json!(["India"]));
+        assert_eq!(at(2)["rights"]["territories"], json!(["India", "Nepal"]));
+        assert!(version_at(&latest, 3).is_err());
+    }
+}
@@ -0,0 +1,698 @@
+// src/agreement_store.rs - PostgreSQL persistence for parsed agreements
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::flat_agreement::{self, RightsAgreementFlat};
+use crate::models::RightsAgreementJSON;
+
+/// Summary row for an agreement that has been parsed and uploaded to IPFS
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AgreementRecord {
+    pub id: Uuid,
+    pub ipfs_cid: String,
+    pub agreement_id: Option<String>,
+    pub title: Option<String>,
+    pub licensor: Option<String>,
+    pub licensee: Option<String>,
+    pub file_name: String,
+    pub file_size: i64,
+    pub processing_time_ms: Option<i64>,
+    pub model_used: Option<String>,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Values needed to insert a new agreement row
+pub struct NewAgreementRecord<'a> {
+    pub ipfs_cid: &'a str,
+    pub agreement_id: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub licensor: Option<&'a str>,
+    pub licensee: Option<&'a str>,
+    pub file_name: &'a str,
+    pub file_size: i64,
+    pub processing_time_ms: i64,
+    pub model_used: &'a str,
+    pub webhook_url: Option<&'a str>,
+    pub delivery_deadline: Option<NaiveDate>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub status: Option<&'a str>,
+    pub agreement_type: Option<&'a str>,
+    pub deal_value: Option<i64>,
+    pub currency: Option<&'a str>,
+    pub territories: Vec<String>,
+}
+
+/// Portfolio roll-up over all parsed agreements
+#[derive(Debug, Clone, Serialize)]
+pub struct AgreementStatistics {
+    pub total_agreements: i64,
+    pub count_by_status: BTreeMap<String, i64>,
+    pub deal_value_by_currency: BTreeMap<String, i64>,
+    pub median_processing_time_ms: Option<f64>,
+    pub p90_processing_time_ms: Option<f64>,
+    pub count_by_agreement_type: BTreeMap<String, i64>,
+    pub top_territories: Vec<TerritoryCount>,
+    pub expiring_next_90_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TerritoryCount {
+    pub territory: String,
+    pub count: i64,
+}
+
+/// An agreement whose license term ends within the requested window
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExpiringAgreement {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub agreement: AgreementRecord,
+    pub end_date: NaiveDate,
+    pub status: String,
+    pub days_until_expiry: i32,
+}
+
+/// Position in the `(end_date, id)` ordering of expiring agreements
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpiryCursor {
+    pub end_date: NaiveDate,
+    pub id: Uuid,
+}
+
+impl ExpiryCursor {
+    pub fn encode(&self) -> String {
+        let raw = format!("{}|{}", self.end_date, self.id);
+        general_purpose::URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    pub fn decode(token: &str) -> Result<Self> {
+        let bytes = general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .context("Cursor is not valid base64")?;
+        let raw = String::from_utf8(bytes).context("Cursor is not valid UTF-8")?;
+
+        let (date, id) = raw.split_once('|').context("Malformed cursor")?;
+        let end_date = NaiveDate::parse_from_str(date, "%Y-%m-%d").context("Malformed cursor date")?;
+        let id = Uuid::parse_str(id).context("Malformed cursor id")?;
+
+        Ok(Self { end_date, id })
+    }
+}
+
+pub struct ExpiringPage {
+    pub data: Vec<ExpiringAgreement>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+/// An agreement whose delivery deadline is close enough to notify about
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeliveryNotice {
+    pub id: Uuid,
+    pub ipfs_cid: String,
+    pub agreement_id: Option<String>,
+    pub title: Option<String>,
+    pub webhook_url: String,
+    pub delivery_deadline: NaiveDate,
+}
+
+/// Position in the `(created_at, id)` ordering of `parsed_agreements`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    /// Encode as an opaque, URL-safe token
+    pub fn encode(&self) -> String {
+        let raw = format!("{}|{}", self.created_at.to_rfc3339(), self.id);
+        general_purpose::URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    /// Decode a token produced by `encode`
+    pub fn decode(token: &str) -> Result<Self> {
+        let bytes = general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .context("Cursor is not valid base64")?;
+        let raw = String::from_utf8(bytes).context("Cursor is not valid UTF-8")?;
+
+        let (ts, id) = raw.split_once('|').context("Malformed cursor")?;
+        let created_at = DateTime::parse_from_rfc3339(ts)
+            .context("Malformed cursor timestamp")?
+            .with_timezone(&Utc);
+        let id = Uuid::parse_str(id).context("Malformed cursor id")?;
+
+        Ok(Self { created_at, id })
+    }
+}
+
+pub struct AgreementPage {
+    pub data: Vec<AgreementRecord>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+/// Record a parsed agreement
+pub async fn insert_agreement(pool: &PgPool, record: &NewAgreementRecord<'_>) -> Result<Uuid> {
+    let id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO parsed_agreements
+            (ipfs_cid, agreement_id, title, licensor, licensee,
+             file_name, file_size, processing_time_ms, model_used,
+             webhook_url, delivery_deadline, end_date, status,
+             agreement_type, deal_value, currency, territories, start_date)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, COALESCE($13, 'Active'),
+                $14, $15, $16, $17, $18)
+        RETURNING id
+        "#,
+    )
+    .bind(record.ipfs_cid)
+    .bind(record.agreement_id)
+    .bind(record.title)
+    .bind(record.licensor)
+    .bind(record.licensee)
+    .bind(record.file_name)
+    .bind(record.file_size)
+    .bind(record.processing_time_ms)
+    .bind(record.model_used)
+    .bind(record.webhook_url)
+    .bind(record.delivery_deadline)
+    .bind(record.end_date)
+    .bind(record.status)
+    .bind(record.agreement_type)
+    .bind(record.deal_value)
+    .bind(record.currency)
+    .bind(&record.territories)
+    .bind(record.start_date)
+    .fetch_one(pool)
+    .await
+    .context("Failed to insert parsed agreement")?;
+
+    info!("💾 Recorded agreement {} ({})", id, record.ipfs_cid);
+    Ok(id)
+}
+
+/// Record the flat projection of a parsed agreement, replacing any earlier row
+/// for the same agreement ID
+pub async fn upsert_flat_agreement(pool: &PgPool, agreement: &RightsAgreementJSON) -> Result<()> {
+    let flat = serde_json::to_value(RightsAgreementFlat::from(agreement)).context("Failed to serialize flat agreement")?;
+    sqlx::query(&flat_agreement::upsert_sql(flat_agreement::FLAT_TABLE))
+        .bind(flat)
+        .execute(pool)
+        .await
+        .context("Failed to record flat agreement")?;
+    Ok(())
+}
+
+/// Agreements with a webhook whose delivery deadline is within `notice_days`
+/// and that have not been notified yet
+pub async fn due_delivery_notices(pool: &PgPool, notice_days: i32) -> Result<Vec<DeliveryNotice>> {
+    sqlx::query_as(
+        r#"
+        SELECT id, ipfs_cid, agreement_id, title, webhook_url, delivery_deadline
+        FROM parsed_agreements
+        WHERE webhook_url IS NOT NULL
+          AND delivery_deadline IS NOT NULL
+          AND delivery_notice_sent_at IS NULL
+          AND delivery_deadline < CURRENT_DATE + $1
+        ORDER BY delivery_deadline ASC
+        "#,
+    )
+    .bind(notice_days)
+    .fetch_all(pool)
+    .await
+    .context("Failed to query upcoming delivery deadlines")
+}
+
+pub async fn mark_delivery_notice_sent(pool: &PgPool, id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE parsed_agreements SET delivery_notice_sent_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .context("Failed to mark delivery notice as sent")?;
+    Ok(())
+}
+
+/// Point an agreement's row at its re-uploaded CID and replace its tags.
+/// Returns false if no row exists for `old_cid`.
+pub async fn update_tags(pool: &PgPool, old_cid: &str, new_cid: &str, tags: &[String]) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE parsed_agreements
+        SET ipfs_cid = $2, tags = $3
+        WHERE ipfs_cid = $1
+        "#,
+    )
+    .bind(old_cid)
+    .bind(new_cid)
+    .bind(tags)
+    .execute(pool)
+    .await
+    .context("Failed to update agreement tags")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// One version in an agreement's chain of title
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ChainLink {
+    pub cid: String,
+    pub version: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub status: Option<String>,
+}
+
+/// Record that `cid` was derived from `predecessor_cid`. A missing version or
+/// status is carried over from the predecessor, e.g. for a key rotation.
+pub async fn record_version(
+    pool: &PgPool,
+    cid: &str,
+    predecessor_cid: Option<&str>,
+    version: Option<&str>,
+    status: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO agreement_versions (cid, predecessor_cid, version, status)
+        VALUES ($1, $2,
+                COALESCE($3, (SELECT version FROM agreement_versions WHERE cid = $2)),
+                COALESCE($4, (SELECT status FROM agreement_versions WHERE cid = $2)))
+        ON CONFLICT (cid) DO NOTHING
+        "#,
+    )
+    .bind(cid)
+    .bind(predecessor_cid)
+    .bind(version)
+    .bind(status)
+    .execute(pool)
+    .await
+    .context("Failed to record agreement version")?;
+    Ok(())
+}
+
+/// `cid` and up to `max_depth` of its predecessors, oldest first
+pub async fn chain_of_title(pool: &PgPool, cid: &str, max_depth: i32) -> Result<Vec<ChainLink>> {
+    sqlx::query_as::<_, ChainLink>(
+        r#"
+        WITH RECURSIVE chain AS (
+            SELECT cid, predecessor_cid, version, status, created_at, 0 AS depth
+            FROM agreement_versions
+            WHERE cid = $1
+            UNION ALL
+            SELECT v.cid, v.predecessor_cid, v.version, v.status, v.created_at, chain.depth + 1
+            FROM agreement_versions v
+            JOIN chain ON v.cid = chain.predecessor_cid
+            WHERE chain.depth < $2
+        )
+        SELECT cid, version, created_at, status
+        FROM chain
+        ORDER BY depth DESC
+        "#,
+    )
+    .bind(cid)
+    .bind(max_depth)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load chain of title")
+}
+
+/// Record that the content at `old_cid` was re-encrypted to `new_cid`,
+/// returning when the rotation was stored
+pub async fn record_key_rotation(pool: &PgPool, old_cid: &str, new_cid: &str) -> Result<DateTime<Utc>> {
+    sqlx::query_scalar("INSERT INTO key_rotations (old_cid, new_cid) VALUES ($1, $2) RETURNING rotated_at")
+        .bind(old_cid)
+        .bind(new_cid)
+        .fetch_one(pool)
+        .await
+        .context("Failed to record key rotation")
+}
+
+/// Claim `cid` for an on-chain export. Returns false if it was already
+/// claimed by an earlier or concurrent export.
+pub async fn reserve_blockchain_export(pool: &PgPool, cid: &str) -> Result<bool> {
+    let result = sqlx::query("INSERT INTO blockchain_exports (cid) VALUES ($1) ON CONFLICT (cid) DO NOTHING")
+        .bind(cid)
+        .execute(pool)
+        .await
+        .context("Failed to reserve blockchain export")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Transaction a claimed export of `cid` was sent as, if it got that far
+pub async fn blockchain_export_tx(pool: &PgPool, cid: &str) -> Result<Option<String>> {
+    let tx_hash: Option<Option<String>> = sqlx::query_scalar("SELECT tx_hash FROM blockchain_exports WHERE cid = $1")
+        .bind(cid)
+        .fetch_optional(pool)
+        .await
+        .context("Failed to look up blockchain export")?;
+
+    Ok(tx_hash.flatten())
+}
+
+/// Record the transaction an export of `cid` was sent as and, once stored,
+/// the CID of the anchored copy
+pub async fn record_blockchain_export(pool: &PgPool, cid: &str, tx_hash: &str, anchored_cid: Option<&str>) -> Result<()> {
+    sqlx::query("UPDATE blockchain_exports SET tx_hash = $2, anchored_cid = $3 WHERE cid = $1")
+        .bind(cid)
+        .bind(tx_hash)
+        .bind(anchored_cid)
+        .execute(pool)
+        .await
+        .context("Failed to record blockchain export")?;
+    Ok(())
+}
+
+/// Give up the claim on `cid` after an export that sent no transaction
+pub async fn release_blockchain_export(pool: &PgPool, cid: &str) -> Result<()> {
+    sqlx::query("DELETE FROM blockchain_exports WHERE cid = $1 AND tx_hash IS NULL")
+        .bind(cid)
+        .execute(pool)
+        .await
+        .context("Failed to release blockchain export")?;
+    Ok(())
+}
+
+/// Append an entry to `audit_events`
+pub async fn record_audit_event(
+    pool: &PgPool,
+    event_type: &str,
+    ipfs_cid: &str,
+    details: &serde_json::Value,
+) -> Result<()> {
+    sqlx::query("INSERT INTO audit_events (event_type, ipfs_cid, details) VALUES ($1, $2, $3)")
+        .bind(event_type)
+        .bind(ipfs_cid)
+        .bind(details)
+        .execute(pool)
+        .await
+        .context("Failed to record audit event")?;
+    Ok(())
+}
+
+/// Claim `agreement_id` for a new agreement. Returns false if it was already
+/// reserved or is used by an agreement stored before reservations existed.
+/// The insert is atomic, so two uploads can never claim the same ID.
+pub async fn reserve_agreement_id(pool: &PgPool, agreement_id: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO agreement_ids (agreement_id)
+        SELECT $1
+        WHERE NOT EXISTS (SELECT 1 FROM parsed_agreements WHERE agreement_id = $1)
+        ON CONFLICT (agreement_id) DO NOTHING
+        "#,
+    )
+    .bind(agreement_id)
+    .execute(pool)
+    .await
+    .context("Failed to reserve agreement ID")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Point an agreement's row at its re-uploaded CID, e.g. after a key rotation.
+/// Returns false if no row exists for `old_cid`.
+pub async fn update_cid(pool: &PgPool, old_cid: &str, new_cid: &str) -> Result<bool> {
+    let result = sqlx::query("UPDATE parsed_agreements SET ipfs_cid = $2 WHERE ipfs_cid = $1")
+        .bind(old_cid)
+        .bind(new_cid)
+        .execute(pool)
+        .await
+        .context("Failed to update agreement CID")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Point an agreement's row at its re-uploaded CID and record the transaction
+/// that anchored it on-chain. Returns false if no row exists for `old_cid`.
+pub async fn update_blockchain_anchor(
+    pool: &PgPool,
+    old_cid: &str,
+    new_cid: &str,
+    tx_hash: &str,
+    block_number: Option<u64>,
+    contract_address: &str,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE parsed_agreements
+        SET ipfs_cid = $2, tx_hash = $3, block_number = $4, contract_address = $5
+        WHERE ipfs_cid = $1
+        "#,
+    )
+    .bind(old_cid)
+    .bind(new_cid)
+    .bind(tx_hash)
+    .bind(block_number.map(|n| n as i64))
+    .bind(contract_address)
+    .execute(pool)
+    .await
+    .context("Failed to record blockchain anchor")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Point an agreement's row at its re-uploaded CID and set its status.
+/// Returns false if no row exists for `old_cid`.
+pub async fn update_status(pool: &PgPool, old_cid: &str, new_cid: &str, status: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE parsed_agreements
+        SET ipfs_cid = $2, status = $3
+        WHERE ipfs_cid = $1
+        "#,
+    )
+    .bind(old_cid)
+    .bind(new_cid)
+    .bind(status)
+    .execute(pool)
+    .await
+    .context("Failed to update agreement status")?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// List agreements newest-first, starting strictly after `after`,
+/// optionally only those carrying `tag`
+pub async fn list_agreements(
+    pool: &PgPool,
+    after: Option<&Cursor>,
+    tag: Option<&str>,
+    limit: i64,
+) -> Result<AgreementPage> {
+    // Fetch one extra row to know whether another page exists
+    let mut rows: Vec<AgreementRecord> = sqlx::query_as(
+        r#"
+        SELECT id, ipfs_cid, agreement_id, title, licensor, licensee,
+               file_name, file_size, processing_time_ms, model_used, tags, created_at
+        FROM parsed_agreements
+        WHERE ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2))
+          AND ($3::text IS NULL OR tags @> ARRAY[$3::text])
+        ORDER BY created_at DESC, id DESC
+        LIMIT $4
+        "#,
+    )
+    .bind(after.map(|c| c.created_at))
+    .bind(after.map(|c| c.id))
+    .bind(tag)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await
+    .context("Failed to list parsed agreements")?;
+
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+
+    let next_cursor = if has_more {
+        rows.last().map(|r| {
+            Cursor {
+                created_at: r.created_at,
+                id: r.id,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(AgreementPage {
+        data: rows,
+        next_cursor,
+        has_more,
+    })
+}
+
+/// Agreements whose term ends between today and `days` from now, soonest first
+pub async fn list_expiring(
+    pool: &PgPool,
+    days: i32,
+    status: Option<&str>,
+    after: Option<&ExpiryCursor>,
+    limit: i64,
+) -> Result<ExpiringPage> {
+    let mut rows: Vec<ExpiringAgreement> = sqlx::query_as(
+        r#"
+        SELECT id, ipfs_cid, agreement_id, title, licensor, licensee,
+               file_name, file_size, processing_time_ms, model_used, tags, created_at,
+               end_date, status, (end_date - CURRENT_DATE) AS days_until_expiry
+        FROM parsed_agreements
+        WHERE end_date BETWEEN CURRENT_DATE AND CURRENT_DATE + $1
+          AND ($2::text IS NULL OR status = $2)
+          AND ($3::date IS NULL OR (end_date, id) > ($3, $4))
+        ORDER BY end_date ASC, id ASC
+        LIMIT $5
+        "#,
+    )
+    .bind(days)
+    .bind(status)
+    .bind(after.map(|c| c.end_date))
+    .bind(after.map(|c| c.id))
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await
+    .context("Failed to list expiring agreements")?;
+
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+
+    let next_cursor = if has_more {
+        rows.last().map(|r| {
+            ExpiryCursor {
+                end_date: r.end_date,
+                id: r.agreement.id,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(ExpiringPage {
+        data: rows,
+        next_cursor,
+        has_more,
+    })
+}
+
+/// `(key, count)` rows from a `GROUP BY` query as a map
+async fn grouped_counts(pool: &PgPool, sql: &str) -> Result<BTreeMap<String, i64>> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(sql)
+        .fetch_all(pool)
+        .await
+        .context("Failed to aggregate parsed agreements")?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Aggregate counts, deal values and processing times across all agreements
+pub async fn agreement_statistics(pool: &PgPool) -> Result<AgreementStatistics> {
+    let count_by_status = grouped_counts(
+        pool,
+        "SELECT status, COUNT(*) FROM parsed_agreements GROUP BY status",
+    )
+    .await?;
+
+    let deal_value_by_currency = grouped_counts(
+        pool,
+        r#"
+        SELECT currency, SUM(deal_value)::BIGINT
+        FROM parsed_agreements
+        WHERE currency IS NOT NULL AND deal_value IS NOT NULL
+        GROUP BY currency
+        "#,
+    )
+    .await?;
+
+    let count_by_agreement_type = grouped_counts(
+        pool,
+        r#"
+        SELECT COALESCE(agreement_type, 'unknown'), COUNT(*)
+        FROM parsed_agreements
+        GROUP BY 1
+        "#,
+    )
+    .await?;
+
+    let (median_processing_time_ms, p90_processing_time_ms): (Option<f64>, Option<f64>) = sqlx::query_as(
+        r#"
+        SELECT percentile_cont(0.5) WITHIN GROUP (ORDER BY processing_time_ms),
+               percentile_cont(0.9) WITHIN GROUP (ORDER BY processing_time_ms)
+        FROM parsed_agreements
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to compute processing time percentiles")?;
+
+    let top_territories: Vec<TerritoryCount> = sqlx::query_as(
+        r#"
+        SELECT territory, COUNT(*) AS count
+        FROM parsed_agreements, unnest(territories) AS territory
+        GROUP BY territory
+        ORDER BY count DESC, territory ASC
+        LIMIT 10
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to count territories")?;
+
+    let expiring_next_90_days: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM parsed_agreements WHERE end_date BETWEEN CURRENT_DATE AND CURRENT_DATE + 90",
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to count expiring agreements")?;
+
+    Ok(AgreementStatistics {
+        total_agreements: count_by_status.values().sum(),
+        count_by_status,
+        deal_value_by_currency,
+        median_processing_time_ms,
+        p90_processing_time_ms,
+        count_by_agreement_type,
+        top_territories,
+        expiring_next_90_days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor {
+            created_at: Utc::now(),
+            id: Uuid::new_v4(),
+        };
+
+        let decoded = Cursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(decoded, cursor);
+    }
+
+    #[test]
+    fn test_cursor_rejects_garbage() {
+        assert!(Cursor::decode("not a cursor!").is_err());
+        assert!(Cursor::decode(&general_purpose::URL_SAFE_NO_PAD.encode("no-separator")).is_err());
+    }
+
+    #[test]
+    fn test_expiry_cursor_round_trip() {
+        let cursor = ExpiryCursor {
+            end_date: NaiveDate::from_ymd_opt(2029, 7, 31).unwrap(),
+            id: Uuid::new_v4(),
+        };
+
+        assert_eq!(ExpiryCursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(ExpiryCursor::decode(&Cursor { created_at: Utc::now(), id: cursor.id }.encode()).is_err());
+    }
+}
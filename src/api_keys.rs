@@ -0,0 +1,120 @@
+// src/api_keys.rs - API key lookup and per-key daily parse quotas
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use dashmap::DashMap;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::integrity::sha256_hex;
+
+/// Request header carrying the caller's API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Response header with the parses left today for the caller's key
+pub const QUOTA_REMAINING_HEADER: &str = "x-quota-remaining";
+
+/// Quotas by API key hash, shared across requests
+pub type QuotaRegistry = Arc<DashMap<String, KeyQuota>>;
+
+/// An active, unexpired row from `api_keys`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiKeyRecord {
+    pub id: Uuid,
+    pub key_hash: String,
+    pub key_prefix: String,
+    pub daily_parse_quota: i32,
+}
+
+/// Hex SHA-256 of a raw key, as stored in `api_keys.key_hash`
+pub fn hash_key(raw: &str) -> String {
+    sha256_hex(raw.trim().as_bytes())
+}
+
+/// Look up an API key, returning `None` if it is unknown, inactive or expired
+pub async fn validate_key(pool: &PgPool, raw: &str) -> Result<Option<ApiKeyRecord>> {
+    let record = sqlx::query_as::<_, ApiKeyRecord>(
+        r#"
+        SELECT id, key_hash, key_prefix, daily_parse_quota
+        FROM api_keys
+        WHERE key_hash = $1
+          AND is_active = TRUE
+          AND (expires_at IS NULL OR expires_at > NOW())
+        "#,
+    )
+    .bind(hash_key(raw))
+    .fetch_optional(pool)
+    .await
+    .context("Failed to look up API key")?;
+
+    Ok(record)
+}
+
+/// Record a use of the key. Callers run this in the background.
+pub async fn touch_key(pool: &PgPool, id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE api_keys SET requests_count = requests_count + 1, last_used_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .context("Failed to record API key usage")?;
+    Ok(())
+}
+
+/// Parses allowed and used today for one API key
+#[derive(Debug)]
+pub struct KeyQuota {
+    pub daily_parses: u32,
+    pub current_day_parses: AtomicU32,
+    pub last_reset_date: Mutex<NaiveDate>,
+}
+
+impl KeyQuota {
+    pub fn new(daily_parses: u32, today: NaiveDate) -> Self {
+        Self {
+            daily_parses,
+            current_day_parses: AtomicU32::new(0),
+            last_reset_date: Mutex::new(today),
+        }
+    }
+
+    /// Count one parse against today's quota, resetting the counter when the
+    /// date has changed. Returns the parses left afterwards, or `None` if the
+    /// quota was already used up.
+    pub fn try_consume(&self, today: NaiveDate) -> Option<u32> {
+        {
+            let mut last_reset = self.last_reset_date.lock().expect("quota lock poisoned");
+            if *last_reset != today {
+                *last_reset = today;
+                self.current_day_parses.store(0, Ordering::SeqCst);
+            }
+        }
+
+        self.current_day_parses
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                (used < self.daily_parses).then_some(used + 1)
+            })
+            .ok()
+            .map(|used| self.daily_parses - used - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_exhausts_and_resets_daily() {
+        let monday = NaiveDate::from_ymd_opt(2024, 6, 24).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let quota = KeyQuota::new(2, monday);
+
+        assert_eq!(quota.try_consume(monday), Some(1));
+        assert_eq!(quota.try_consume(monday), Some(0));
+        assert_eq!(quota.try_consume(monday), None);
+        assert_eq!(quota.try_consume(tuesday), Some(1));
+
+        assert_eq!(KeyQuota::new(0, monday).try_consume(monday), None);
+        assert_eq!(hash_key(" abc "), sha256_hex(b"abc"));
+    }
+}
@@ -0,0 +1,126 @@
+// src/blockchain.rs - Anchor agreement commitments in the on-chain rights registry
+use alloy::network::EthereumWallet;
+use alloy::primitives::{Address, FixedBytes};
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionReceipt;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol;
+use anyhow::{Context, Result};
+use tracing::info;
+
+sol! {
+    #[sol(rpc)]
+    interface IRightsRegistry {
+        function registerAgreement(string cid, bytes32 contentHash, address licensor, address licensee) external;
+    }
+}
+
+/// Decode a hex SHA-256 such as `metadata.jsonSha256` into registry form
+pub fn content_hash_bytes(hex_digest: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(hex_digest.trim().trim_start_matches("0x")).context("Content hash is not hex")?;
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| anyhow::anyhow!("Content hash is {} bytes, expected 32", b.len()))
+}
+
+fn parse_address(address: &str, role: &str) -> Result<Address> {
+    address
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid {} address {:?}", role, address))
+}
+
+/// `registerAgreement` was sent, but whether it was mined is unknown, so the
+/// export must not be retried blindly
+#[derive(Debug)]
+pub struct UnconfirmedTransaction {
+    pub tx_hash: String,
+}
+
+impl std::fmt::Display for UnconfirmedTransaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "registerAgreement transaction {} was sent but not confirmed", self.tx_hash)
+    }
+}
+
+/// Sends `registerAgreement` transactions signed by the deployer key
+pub struct BlockchainClient {
+    provider: DynProvider,
+    contract_address: Address,
+}
+
+impl BlockchainClient {
+    pub fn new(rpc_url: &str, contract_address: &str, private_key: &str) -> Result<Self> {
+        let signer: PrivateKeySigner = private_key
+            .trim()
+            .parse()
+            .context("DEPLOYER_PRIVATE_KEY is not a valid private key")?;
+        let contract_address = parse_address(contract_address, "registry contract")?;
+        let url = rpc_url.parse().context("Invalid Ethereum RPC URL")?;
+
+        let provider = ProviderBuilder::new()
+            .wallet(EthereumWallet::from(signer))
+            .connect_http(url)
+            .erased();
+        Ok(Self { provider, contract_address })
+    }
+
+    /// EIP-55 address of the registry contract
+    pub fn contract_address(&self) -> String {
+        self.contract_address.to_checksum(None)
+    }
+
+    /// Record `cid` and its content hash against both parties' addresses and
+    /// wait for the transaction to be mined. Reverted transactions are errors;
+    /// failing to get the receipt is an `UnconfirmedTransaction`.
+    pub async fn register_agreement(
+        &self,
+        cid: &str,
+        content_hash: &[u8; 32],
+        licensor_address: &str,
+        licensee_address: &str,
+    ) -> Result<TransactionReceipt> {
+        let licensor = parse_address(licensor_address, "licensor")?;
+        let licensee = parse_address(licensee_address, "licensee")?;
+
+        let registry = IRightsRegistry::new(self.contract_address, &self.provider);
+        let pending = registry
+            .registerAgreement(cid.to_string(), FixedBytes::from(*content_hash), licensor, licensee)
+            .send()
+            .await
+            .context("Failed to send registerAgreement transaction")?;
+        let tx_hash = pending.tx_hash().to_string();
+        info!("⛓️  Sent registerAgreement for {} as {}", cid, tx_hash);
+
+        let receipt = pending
+            .get_receipt()
+            .await
+            .map_err(|e| anyhow::Error::new(e).context(UnconfirmedTransaction { tx_hash }))?;
+        if !receipt.status() {
+            anyhow::bail!("registerAgreement transaction {} reverted", receipt.transaction_hash);
+        }
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash_and_client_config() {
+        let digest = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(content_hash_bytes(digest).unwrap()[..2], [0xe3, 0xb0]);
+        assert!(content_hash_bytes("e3b0").is_err());
+
+        // Well-known Hardhat development key; nothing is sent
+        let client = BlockchainClient::new(
+            "http://localhost:8545",
+            "0x5fbdb2315678afecb367f032d93f642f64180aa3",
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        )
+        .unwrap();
+        assert_eq!(client.contract_address(), "0x5FbDB2315678afecb367f032d93F642f64180aa3");
+        assert!(BlockchainClient::new("http://localhost:8545", "0x5fbdb2315678afecb367f032d93f642f64180aa3", "not-a-key").is_err());
+    }
+}
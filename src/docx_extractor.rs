@@ -0,0 +1,184 @@
+// src/docx_extractor.rs - Text from Word (.docx) agreements
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Cursor, Read};
+use tracing::info;
+
+use crate::pdf_extractor::TextExtractor;
+
+pub const DOCX_MIME: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+/// Every .docx is a zip archive
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Password-protected .docx files are OLE compound files, not zips
+const OLE_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+/// Most uncompressed bytes read from word/document.xml, so a small zip
+/// cannot inflate into gigabytes
+const MAX_DOCUMENT_XML_BYTES: u64 = 50 * 1024 * 1024;
+
+const DOCUMENT_PART: &str = "word/document.xml";
+
+/// Whether an upload is a Word document: a zip holding word/document.xml.
+/// A password-protected file counts when its name or content type says
+/// .docx, so the caller gets a clear error for it.
+pub fn is_docx(file_name: &str, content_type: Option<&str>, data: &[u8]) -> bool {
+    if data.starts_with(OLE_MAGIC) {
+        return content_type == Some(DOCX_MIME) || file_name.to_ascii_lowercase().ends_with(".docx");
+    }
+    data.starts_with(ZIP_MAGIC)
+        && zip::ZipArchive::new(Cursor::new(data)).is_ok_and(|mut archive| archive.by_name(DOCUMENT_PART).is_ok())
+}
+
+/// Reads the main document part, keeping paragraph breaks and writing
+/// table rows as `| cell | cell |` lines so columns survive
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DocxExtractor;
+
+impl DocxExtractor {
+    /// Every error is a problem with the document itself
+    pub fn extract(&self, data: &[u8]) -> Result<String> {
+        self.extract_with_limit(data, MAX_DOCUMENT_XML_BYTES)
+    }
+
+    fn extract_with_limit(&self, data: &[u8], max_xml_bytes: u64) -> Result<String> {
+        if data.starts_with(OLE_MAGIC) {
+            anyhow::bail!("Password-protected Word documents are not supported");
+        }
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).context("Not a Word document")?;
+        let mut xml = Vec::new();
+        archive
+            .by_name(DOCUMENT_PART)
+            .context("Word document has no word/document.xml")?
+            .take(max_xml_bytes + 1)
+            .read_to_end(&mut xml)
+            .context("Failed to read word/document.xml")?;
+        if xml.len() as u64 > max_xml_bytes {
+            anyhow::bail!("word/document.xml is over {} bytes uncompressed", max_xml_bytes);
+        }
+        let xml = String::from_utf8(xml).context("word/document.xml is not UTF-8")?;
+
+        let text = document_text(&xml)?;
+        info!("✅ Extracted {} characters from Word document", text.len());
+        Ok(text)
+    }
+}
+
+#[async_trait]
+impl TextExtractor for DocxExtractor {
+    async fn extract_text(&self, data: &[u8], _password: Option<&str>) -> Result<String> {
+        self.extract(data)
+    }
+}
+
+/// Text of a WordprocessingML body. Nested tables are flattened into the
+/// cell that holds them.
+fn document_text(xml: &str) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut text = String::new();
+    let mut in_text = false;
+    let mut row_depth = 0usize;
+    let mut row: Vec<String> = Vec::new();
+    let mut cell = String::new();
+
+    loop {
+        let event = reader.read_event().context("Malformed word/document.xml")?;
+        let in_table = row_depth > 0;
+        match event {
+            Event::Start(e) => match e.name().as_ref() {
+                b"w:t" => in_text = true,
+                b"w:tr" => {
+                    row_depth += 1;
+                    if row_depth == 1 {
+                        row.clear();
+                    }
+                }
+                b"w:tc" if row_depth == 1 => cell.clear(),
+                _ => {}
+            },
+            Event::End(e) => match e.name().as_ref() {
+                b"w:t" => in_text = false,
+                b"w:p" if in_table => cell.push(' '),
+                b"w:p" => text.push('\n'),
+                b"w:tc" if row_depth == 1 => row.push(cell.split_whitespace().collect::<Vec<_>>().join(" ")),
+                b"w:tr" => {
+                    row_depth = row_depth.saturating_sub(1);
+                    if row_depth == 0 {
+                        text.push_str(&format!("| {} |\n", row.join(" | ")));
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"w:tab" | b"w:br" | b"w:cr" if in_table => cell.push(' '),
+                b"w:tab" => text.push('\t'),
+                b"w:br" | b"w:cr" => text.push('\n'),
+                _ => {}
+            },
+            Event::Text(t) if in_text => {
+                let unescaped = t.unescape().context("Malformed text in word/document.xml")?;
+                if in_table { cell.push_str(&unescaped) } else { text.push_str(&unescaped) }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(text.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_extracts_paragraphs_and_tables() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+<w:p><w:r><w:t>LICENSE AGREEMENT</w:t></w:r></w:p>
+<w:p><w:r><w:t xml:space="preserve">Licensor: </w:t></w:r><w:r><w:t>Vyjayanthi Movies &amp; Co.</w:t></w:r></w:p>
+<w:tbl>
+<w:tr><w:tc><w:p><w:r><w:t>Milestone</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Amount</w:t></w:r></w:p></w:tc></w:tr>
+<w:tr><w:tc><w:p><w:r><w:t>On</w:t></w:r></w:p><w:p><w:r><w:t>signing</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>5,00,000</w:t></w:r></w:p></w:tc></w:tr>
+</w:tbl>
+</w:body></w:document>"#;
+
+        let docx = zip_with("word/document.xml", xml);
+
+        assert!(is_docx("agreement.bin", None, &docx));
+        assert!(!is_docx("agreement.pdf", Some("application/pdf"), b"%PDF-1.7"));
+        // Other zips, and PDFs named .docx, are not Word documents
+        assert!(!is_docx("agreement.docx", Some(DOCX_MIME), &zip_with("content.xml", xml)));
+        assert!(!is_docx("agreement.docx", Some(DOCX_MIME), b"%PDF-1.7"));
+        assert!(is_docx("agreement.docx", None, OLE_MAGIC));
+        assert_eq!(
+            DocxExtractor.extract(&docx).unwrap(),
+            "LICENSE AGREEMENT\nLicensor: Vyjayanthi Movies & Co.\n| Milestone | Amount |\n| On signing | 5,00,000 |"
+        );
+        assert!(DocxExtractor.extract(OLE_MAGIC).is_err());
+    }
+
+    #[test]
+    fn test_document_xml_size_limit() {
+        let xml = format!("<w:document><w:body>{}</w:body></w:document>", "<w:p/>".repeat(1000));
+        let docx = zip_with("word/document.xml", &xml);
+
+        assert!(DocxExtractor.extract_with_limit(&docx, xml.len() as u64).is_ok());
+        let err = DocxExtractor.extract_with_limit(&docx, 1024).unwrap_err();
+        assert!(err.to_string().contains("uncompressed"));
+    }
+
+    fn zip_with(name: &str, contents: &str) -> Vec<u8> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = zip::ZipWriter::new(&mut buffer);
+        zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+        zip.finish().unwrap();
+        buffer.into_inner()
+    }
+}
@@ -1,82 +1,538 @@
-// src/encryption.rs - AES-256-GCM Encryption Service
+// src/encryption.rs - AES-256-GCM / ChaCha20-Poly1305 Encryption Service
 use aes_gcm::{
     aead::{Aead, KeyInit, OsRng},
     Aes256Gcm, Nonce,
 };
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose, Engine as _};
+use argon2::Argon2;
+use chacha20poly1305::ChaCha20Poly1305;
 use rand::RngCore;
-use tracing::{info, error};
+use async_trait::async_trait;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, error, warn};
+
+use crate::ipfs_client::IPFSClientTrait;
+
+/// Delays between retries of an unpin that failed during a rekey
+const UNPIN_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(30 * 60),
+];
+
+/// Outcome of a key rotation
+#[derive(Debug, Clone, Serialize)]
+pub struct RekeyResult {
+    pub new_cid: String,
+    pub new_key: String,
+    /// False until `unpin_replaced` succeeds; a failed unpin is queued for retry
+    pub old_cid_unpinned: bool,
+}
+
+/// The key given for a rekey does not open the stored content
+#[derive(Debug)]
+pub struct InvalidKeyError;
+
+impl std::fmt::Display for InvalidKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Decryption failed - invalid key")
+    }
+}
+
+/// Encryption used for stored agreements, so `EncryptionService` can be
+/// replaced by the deterministic `MemoryEncryptionService` in tests
+#[async_trait]
+pub trait EncryptionServiceTrait: Send + Sync {
+    /// Encrypt with a new key. Returns (encrypted_data, base64_encoded_key)
+    fn encrypt(&self, plaintext: &str) -> Result<(Vec<u8>, String)>;
+
+    fn encrypt_with_key(&self, plaintext: &str, key_b64: &str) -> Result<Vec<u8>>;
+
+    /// `encrypt_with_key` for binary content such as the source PDF
+    fn encrypt_bytes_with_key(&self, data: &[u8], key_b64: &str) -> Result<Vec<u8>>;
+
+    fn decrypt(&self, encrypted_data: &[u8], key_b64: &str) -> Result<String>;
+
+    /// Decrypt content written by `encrypt_bytes_with_key`
+    fn decrypt_bytes(&self, encrypted_data: &[u8], key_b64: &str) -> Result<Vec<u8>> {
+        open(encrypted_data, &decode_key(key_b64)?)
+    }
+
+    /// See `EncryptionService::encrypt_with_passphrase`
+    fn encrypt_with_passphrase(&self, plaintext: &str, passphrase: &str) -> Result<Vec<u8>>;
+
+    /// Decrypt content written by `EncryptionService::encrypt_with_passphrase`
+    fn decrypt_with_passphrase(&self, encrypted_data: &[u8], passphrase: &str) -> Result<String>;
+
+    /// Wrap a per-document key with the master key, see `EncryptionService::envelope_encrypt`
+    fn envelope_encrypt(&self, data_key: &[u8], master_key: &[u8]) -> Result<Vec<u8>>;
+
+    fn envelope_decrypt(&self, envelope: &[u8], master_key: &[u8]) -> Result<Vec<u8>>;
+
+    /// A fresh document key, used when rekeying
+    fn new_key(&self) -> String {
+        EncryptionService::generate_key()
+    }
+
+    /// Encrypt the values at `fields_to_encrypt` (dot paths such as
+    /// `financial` or `rights.territories`) in place of whole-document
+    /// encryption, leaving the rest readable. Each value gets its own key,
+    /// see `field_key`, and becomes `{"_enc": "<base64>", "_alg": "..."}`.
+    /// A `_fieldEncryption` marker listing the paths and holding a value
+    /// sealed with the document key is added at the top level.
+    /// Fails if any path is not in `json`, see `missing_field_paths`.
+    fn encrypt_fields(&self, json: &serde_json::Value, fields_to_encrypt: &[&str], key_b64: &str) -> Result<serde_json::Value> {
+        let missing = missing_field_paths(json, fields_to_encrypt);
+        if !missing.is_empty() {
+            anyhow::bail!("Fields to encrypt are not in the document: {}", missing.join(", "));
+        }
+        if json.get(FIELD_ENCRYPTION_MARKER).is_some() {
+            anyhow::bail!("Document already has a {} marker", FIELD_ENCRYPTION_MARKER);
+        }
+
+        let mut json = json.clone();
+        for path in fields_to_encrypt {
+            let target = path
+                .split('.')
+                .try_fold(&mut json, |value, segment| value.as_object_mut()?.get_mut(segment))
+                .context("Field to encrypt is not in the document")?;
+
+            let encrypted = self.encrypt_with_key(&target.to_string(), &field_key(key_b64, path)?)?;
+            let algorithm = CipherAlgorithm::from_tag(encrypted[0]).unwrap_or_default();
+            *target = serde_json::json!({
+                ENCRYPTED_FIELD_KEY: general_purpose::STANDARD.encode(&encrypted),
+                ENCRYPTED_FIELD_ALG_KEY: algorithm.id(),
+            });
+        }
+
+        let check = self.encrypt_with_key(FIELD_KEY_CHECK, &field_key(key_b64, FIELD_KEY_CHECK_PATH)?)?;
+        let marker = serde_json::json!({
+            "version": 1,
+            "fields": fields_to_encrypt,
+            "check": general_purpose::STANDARD.encode(&check),
+        });
+        json.as_object_mut()
+            .context("Field-encrypted documents must be JSON objects")?
+            .insert(FIELD_ENCRYPTION_MARKER.to_string(), marker);
+        Ok(json)
+    }
+
+    /// Restore every value `encrypt_fields` encrypted with `key_b64`. Fails
+    /// without the `_fieldEncryption` marker, or if its check value does not
+    /// open with `key_b64`, so a wrong key is never silently accepted.
+    fn decrypt_fields(&self, json: &serde_json::Value, key_b64: &str) -> Result<serde_json::Value> {
+        let check = json
+            .get(FIELD_ENCRYPTION_MARKER)
+            .and_then(|marker| marker.get("check"))
+            .and_then(|check| check.as_str())
+            .context("Document is not field-encrypted")?;
+        let check = general_purpose::STANDARD.decode(check).context("Field encryption check is not base64")?;
+        let opened = self
+            .decrypt(&check, &field_key(key_b64, FIELD_KEY_CHECK_PATH)?)
+            .context("Decryption failed - wrong key")?;
+        if opened != FIELD_KEY_CHECK {
+            anyhow::bail!("Decryption failed - wrong key");
+        }
+
+        let mut json = json.clone();
+        if let Some(map) = json.as_object_mut() {
+            map.remove(FIELD_ENCRYPTION_MARKER);
+        }
+        decrypt_fields_at(self, &mut json, "", key_b64)?;
+        Ok(json)
+    }
+
+    /// Decrypt a stored agreement, whether sealed whole or by `encrypt_fields`.
+    /// Every reader of stored agreements goes through here, and anything that
+    /// writes one back uses `seal_document` so the document keeps its mode.
+    fn open_document(&self, encrypted_data: &[u8], key_b64: &str) -> Result<OpenedDocument> {
+        let protected = serde_json::from_slice::<serde_json::Value>(encrypted_data)
+            .ok()
+            .filter(|json| json.get(FIELD_ENCRYPTION_MARKER).is_some());
+        let Some(protected) = protected else {
+            return Ok(OpenedDocument {
+                plaintext: self.decrypt(encrypted_data, key_b64)?,
+                encrypted_fields: Vec::new(),
+            });
+        };
+
+        let encrypted_fields = protected[FIELD_ENCRYPTION_MARKER]["fields"]
+            .as_array()
+            .map(|fields| fields.iter().filter_map(|f| f.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+        Ok(OpenedDocument {
+            plaintext: self.decrypt_fields(&protected, key_b64)?.to_string(),
+            encrypted_fields,
+        })
+    }
+
+    /// Encrypt `plaintext` whole, or only `encrypted_fields` when given
+    fn seal_document(&self, plaintext: &str, encrypted_fields: &[String], key_b64: &str) -> Result<Vec<u8>> {
+        if encrypted_fields.is_empty() {
+            return self.encrypt_with_key(plaintext, key_b64);
+        }
+        let json = serde_json::from_str(plaintext).context("Field-encrypted agreement is not JSON")?;
+        let paths: Vec<&str> = encrypted_fields.iter().map(String::as_str).collect();
+        Ok(self.encrypt_fields(&json, &paths, key_b64)?.to_string().into_bytes())
+    }
+
+    /// Rotate the key of content stored on IPFS: fetch, decrypt, re-encrypt with
+    /// a fresh key and upload. A key that does not open the content fails with
+    /// `InvalidKeyError`.
+    ///
+    /// The old CID is left pinned, so any failure leaves it as it was. Call
+    /// `unpin_replaced` once the new CID has been recorded.
+    async fn rekey_in_place(
+        &self,
+        ipfs_client: Arc<dyn IPFSClientTrait>,
+        old_cid: &str,
+        old_key: &str,
+    ) -> Result<RekeyResult> {
+        info!("🔑 Rotating key for {}", old_cid);
+
+        let encrypted_data = ipfs_client.fetch(old_cid).await.context("Failed to fetch content to rekey")?;
+        let document = self.open_document(&encrypted_data, old_key).context(InvalidKeyError)?;
+        let new_key = self.new_key();
+        let reencrypted = self.seal_document(&document.plaintext, &document.encrypted_fields, &new_key)?;
+        let new_cid = ipfs_client.upload(&reencrypted).await.context("Failed to upload rekeyed content")?;
+
+        info!("✅ Rekeyed {} → {}", old_cid, new_cid);
+
+        Ok(RekeyResult {
+            new_cid,
+            new_key,
+            old_cid_unpinned: false,
+        })
+    }
+}
+
+/// Unpin `old_cid` now that `new_cid` replaces it. A failed unpin is retried
+/// in the background. Returns whether the unpin succeeded right away.
+pub async fn unpin_replaced(ipfs_client: Arc<dyn IPFSClientTrait>, old_cid: &str, new_cid: &str) -> bool {
+    // Identical content under a new key always yields a new CID, but never
+    // unpin the content that was just uploaded
+    if new_cid == old_cid {
+        return false;
+    }
+    match ipfs_client.unpin(old_cid).await {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Failed to unpin {} after rekey, queuing cleanup: {}", old_cid, e);
+            queue_unpin_cleanup(ipfs_client, old_cid.to_string());
+            false
+        }
+    }
+}
+
+/// Key of the ciphertext in a value replaced by `encrypt_fields`
+pub const ENCRYPTED_FIELD_KEY: &str = "_enc";
+const ENCRYPTED_FIELD_ALG_KEY: &str = "_alg";
+/// Top-level key `encrypt_fields` adds to mark a field-encrypted document
+pub const FIELD_ENCRYPTION_MARKER: &str = "_fieldEncryption";
+/// Sealed into the marker so `decrypt_fields` can tell a wrong key apart,
+/// under a path no document field can have
+const FIELD_KEY_CHECK: &str = "rights-parser field encryption v1";
+const FIELD_KEY_CHECK_PATH: &str = "\0check";
+
+/// A stored agreement opened by `open_document`
+#[derive(Debug, Clone)]
+pub struct OpenedDocument {
+    pub plaintext: String,
+    /// Paths encrypted by `encrypt_fields`, empty if sealed whole
+    pub encrypted_fields: Vec<String>,
+}
+
+/// The paths among `paths` that do not exist in `json`
+pub fn missing_field_paths(json: &serde_json::Value, paths: &[&str]) -> Vec<String> {
+    paths
+        .iter()
+        .filter(|path| path.split('.').try_fold(json, |value, segment| value.get(segment)).is_none())
+        .map(|path| path.to_string())
+        .collect()
+}
+
+/// The key `encrypt_fields` uses for `path`, derived from the document key
+/// so one field can be disclosed by sharing its key alone
+pub fn field_key(key_b64: &str, path: &str) -> Result<String> {
+    let digest = Sha256::new()
+        .chain_update(b"rights-parser field key\0")
+        .chain_update(decode_key(key_b64)?)
+        .chain_update(path.as_bytes())
+        .finalize();
+    Ok(general_purpose::STANDARD.encode(digest))
+}
+
+fn decrypt_fields_at<S: EncryptionServiceTrait + ?Sized>(
+    service: &S,
+    value: &mut serde_json::Value,
+    path: &str,
+    key_b64: &str,
+) -> Result<()> {
+    match value {
+        serde_json::Value::Object(map) => {
+            if let Some(serde_json::Value::String(encoded)) = map.get(ENCRYPTED_FIELD_KEY) {
+                let encrypted = general_purpose::STANDARD
+                    .decode(encoded)
+                    .with_context(|| format!("Encrypted field {} is not base64", path))?;
+                let plaintext = service
+                    .decrypt(&encrypted, &field_key(key_b64, path)?)
+                    .with_context(|| format!("Failed to decrypt field {}", path))?;
+                *value = serde_json::from_str(&plaintext).context("Decrypted field is not JSON")?;
+                return Ok(());
+            }
+            for (key, child) in map.iter_mut() {
+                let child_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                decrypt_fields_at(service, child, &child_path, key_b64)?;
+            }
+            Ok(())
+        }
+        serde_json::Value::Array(items) => items
+            .iter_mut()
+            .enumerate()
+            .try_for_each(|(i, item)| decrypt_fields_at(service, item, &format!("{}.{}", path, i), key_b64)),
+        _ => Ok(()),
+    }
+}
+
+/// Retry an unpin in the background until it succeeds or the retries run out
+fn queue_unpin_cleanup(ipfs_client: Arc<dyn IPFSClientTrait>, cid: String) {
+    tokio::spawn(async move {
+        for delay in UNPIN_RETRY_DELAYS {
+            tokio::time::sleep(delay).await;
+            match ipfs_client.unpin(&cid).await {
+                Ok(()) => return,
+                Err(e) => warn!("Unpin retry for {} failed: {}", cid, e),
+            }
+        }
+        error!("Giving up unpinning {}; it must be unpinned manually", cid);
+    });
+}
+
+/// Both ciphers take a 96-bit nonce and add a 128-bit tag
+const NONCE_LEN: usize = 12;
+const AUTH_TAG_LEN: usize = 16;
+
+/// AEAD cipher used for new encryptions. Blobs start with the cipher's tag
+/// byte, so `decrypt` needs only the key whichever cipher wrote them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CipherAlgorithm {
+    /// Fastest where AES-NI is available, e.g. x86-64
+    #[default]
+    Aes256Gcm,
+    /// Faster in software, e.g. on Raspberry Pi or AWS Graviton
+    ChaCha20Poly1305,
+}
+
+impl CipherAlgorithm {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().replace('_', "-").as_str() {
+            "aes-256-gcm" | "aes256gcm" | "aes" => Some(Self::Aes256Gcm),
+            "chacha20-poly1305" | "chacha20poly1305" | "chacha20" => Some(Self::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    /// `_alg` value of fields encrypted with this cipher
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Aes256Gcm => "aes256gcm",
+            Self::ChaCha20Poly1305 => "chacha20poly1305",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Aes256Gcm => "AES-256-GCM",
+            Self::ChaCha20Poly1305 => "ChaCha20-Poly1305",
+        }
+    }
+
+    /// Byte at offset 0 of blobs encrypted with this cipher
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::Aes256Gcm => 0x01,
+            Self::ChaCha20Poly1305 => 0x02,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        [Self::Aes256Gcm, Self::ChaCha20Poly1305].into_iter().find(|a| a.tag() == tag)
+    }
+
+    fn encrypt(&self, key: &[u8], nonce: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let result = match self {
+            Self::Aes256Gcm => Aes256Gcm::new_from_slice(key)?.encrypt(Nonce::from_slice(nonce), plaintext),
+            Self::ChaCha20Poly1305 => ChaCha20Poly1305::new_from_slice(key)?.encrypt(chacha20poly1305::Nonce::from_slice(nonce), plaintext),
+        };
+        result.map_err(|e| anyhow::anyhow!("Encryption failed: {:?}", e))
+    }
+
+    fn decrypt(&self, key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let result = match self {
+            Self::Aes256Gcm => Aes256Gcm::new_from_slice(key)?.decrypt(Nonce::from_slice(nonce), ciphertext),
+            Self::ChaCha20Poly1305 => ChaCha20Poly1305::new_from_slice(key)?.decrypt(chacha20poly1305::Nonce::from_slice(nonce), ciphertext),
+        };
+        result.map_err(|e| anyhow::anyhow!("Decryption failed - invalid key or corrupted data: {:?}", e))
+    }
+}
+
+/// Decode a base64 256-bit key, e.g. `MASTER_ENCRYPTION_KEY`
+pub fn decode_key(key_b64: &str) -> Result<Vec<u8>> {
+    let key_bytes = general_purpose::STANDARD
+        .decode(key_b64)
+        .context("Invalid base64 key")?;
+
+    if key_bytes.len() != 32 {
+        anyhow::bail!("Invalid key length: expected 32 bytes, got {}", key_bytes.len());
+    }
+    Ok(key_bytes)
+}
+
+/// Encrypt `plaintext` with a base64 key and the given nonce.
+/// Returns algorithm tag + nonce + ciphertext.
+pub(crate) fn seal(algorithm: CipherAlgorithm, plaintext: &[u8], key_b64: &str, nonce_bytes: [u8; NONCE_LEN]) -> Result<Vec<u8>> {
+    seal_with_key(algorithm, plaintext, &decode_key(key_b64)?, nonce_bytes)
+}
+
+fn seal_with_key(algorithm: CipherAlgorithm, plaintext: &[u8], key: &[u8], nonce_bytes: [u8; NONCE_LEN]) -> Result<Vec<u8>> {
+    let ciphertext = algorithm.encrypt(key, &nonce_bytes, plaintext)?;
+
+    let mut encrypted_data = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+    encrypted_data.push(algorithm.tag());
+    encrypted_data.extend_from_slice(&nonce_bytes);
+    encrypted_data.extend_from_slice(&ciphertext);
+    Ok(encrypted_data)
+}
+
+fn random_nonce() -> [u8; NONCE_LEN] {
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    nonce_bytes
+}
+
+/// Decrypt a `seal` blob with a raw 32-byte key, detecting the cipher from
+/// the tag byte. Untagged AES-256-GCM blobs (nonce + ciphertext) from before
+/// the tag was added are still accepted.
+fn open(encrypted_data: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    if encrypted_data.len() < NONCE_LEN + AUTH_TAG_LEN {
+        anyhow::bail!("Encrypted data too short");
+    }
+
+    // A legacy nonce can start with a tag byte, so a tagged read that
+    // fails authentication is retried as legacy before giving up
+    let tagged = CipherAlgorithm::from_tag(encrypted_data[0])
+        .filter(|_| encrypted_data.len() > NONCE_LEN + AUTH_TAG_LEN)
+        .map(|algorithm| {
+            let (nonce, ciphertext) = encrypted_data[1..].split_at(NONCE_LEN);
+            algorithm.decrypt(key, nonce, ciphertext)
+        });
+    let (nonce, ciphertext) = encrypted_data.split_at(NONCE_LEN);
+    match tagged {
+        Some(Ok(plaintext)) => Ok(plaintext),
+        Some(Err(e)) => CipherAlgorithm::Aes256Gcm.decrypt(key, nonce, ciphertext).map_err(|_| e),
+        None => CipherAlgorithm::Aes256Gcm.decrypt(key, nonce, ciphertext),
+    }
+}
+
+/// First byte of passphrase-encrypted blobs, distinct from the cipher tags
+const PASSPHRASE_TAG: u8 = 0x10;
+const SALT_LEN: usize = 16;
+/// Tag, three u32 Argon2 parameters and the salt
+const PASSPHRASE_HEADER_LEN: usize = 1 + 3 * 4 + SALT_LEN;
+
+/// Argon2id cost parameters, stored in each passphrase blob so the defaults
+/// can be raised without breaking existing content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory in KiB
+    pub m_cost: u32,
+    /// Iterations
+    pub t_cost: u32,
+    /// Lanes
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self { m_cost: 65536, t_cost: 3, p_cost: 4 }
+    }
+}
+
+impl KdfParams {
+    /// Most memory (256 MiB) and iterations a stored header may ask for, so
+    /// a crafted blob cannot tie up the server
+    const MAX_M_COST: u32 = 256 * 1024;
+    const MAX_T_COST: u32 = 4;
+    const MAX_P_COST: u32 = 16;
+
+    fn derive_key(&self, passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+        if self.m_cost > Self::MAX_M_COST || self.t_cost > Self::MAX_T_COST || self.p_cost > Self::MAX_P_COST {
+            anyhow::bail!("Key derivation parameters {:?} exceed the supported maximum", self);
+        }
+        let params = argon2::Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| anyhow::anyhow!("Invalid key derivation parameters: {}", e))?;
+        let mut key = [0u8; 32];
+        Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+        Ok(key)
+    }
+}
 
 pub struct EncryptionService {
-    // This struct can hold configuration if needed in the future
+    algorithm: CipherAlgorithm,
 }
 
 impl EncryptionService {
     pub fn new() -> Self {
-        info!("Initializing encryption service (AES-256-GCM)");
-        Self {}
+        Self::with_algorithm(CipherAlgorithm::default())
+    }
+
+    /// Encrypt new content with `algorithm`. Content written with either
+    /// cipher can still be decrypted.
+    pub fn with_algorithm(algorithm: CipherAlgorithm) -> Self {
+        info!("Initializing encryption service ({})", algorithm.name());
+        Self { algorithm }
     }
 
-    /// Encrypt data with AES-256-GCM
+    /// Encrypt data with the configured cipher
     /// Returns (encrypted_data, base64_encoded_key)
     pub fn encrypt(&self, plaintext: &str) -> Result<(Vec<u8>, String)> {
         // Generate random 256-bit key
-        let key = Aes256Gcm::generate_key(&mut OsRng);
-        let cipher = Aes256Gcm::new(&key);
-
-        // Generate random 96-bit nonce (recommended for GCM)
-        let mut nonce_bytes = [0u8; 12];
-        OsRng.fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
-
-        // Encrypt
-        let ciphertext = cipher
-            .encrypt(nonce, plaintext.as_bytes())
-            .map_err(|e| anyhow::anyhow!("Encryption failed: {:?}", e))?;
+        let key_b64 = Self::generate_key();
+        let encrypted_data = self.encrypt_with_key(plaintext, &key_b64)?;
+        Ok((encrypted_data, key_b64))
+    }
 
-        // Combine nonce + ciphertext
-        let mut encrypted_data = nonce_bytes.to_vec();
-        encrypted_data.extend_from_slice(&ciphertext);
+    /// Encrypt data with an existing base64 key, e.g. to update an agreement
+    /// without re-issuing its key. A fresh nonce is used every time.
+    pub fn encrypt_with_key(&self, plaintext: &str, key_b64: &str) -> Result<Vec<u8>> {
+        self.encrypt_bytes_with_key(plaintext.as_bytes(), key_b64)
+    }
 
-        // Encode key as base64
-        let key_b64 = general_purpose::STANDARD.encode(key.as_slice());
+    /// `encrypt_with_key` for content that is not text
+    pub fn encrypt_bytes_with_key(&self, plaintext: &[u8], key_b64: &str) -> Result<Vec<u8>> {
+        // Random 96-bit nonce, as recommended for both ciphers
+        let encrypted_data = seal(self.algorithm, plaintext, key_b64, random_nonce())?;
 
         info!(
-            "Encrypted {} bytes → {} bytes (including nonce)",
+            "Encrypted {} bytes → {} bytes (including tag and nonce)",
             plaintext.len(),
             encrypted_data.len()
         );
 
-        Ok((encrypted_data, key_b64))
+        Ok(encrypted_data)
     }
 
-    /// Decrypt data with AES-256-GCM
+    /// Decrypt data written by either cipher, detected from the tag byte.
+    /// Untagged AES-256-GCM blobs (nonce + ciphertext) from before the tag
+    /// was added are still accepted.
     pub fn decrypt(&self, encrypted_data: &[u8], key_b64: &str) -> Result<String> {
-        // Decode base64 key
-        let key_bytes = general_purpose::STANDARD
-            .decode(key_b64)
-            .context("Invalid base64 key")?;
-
-        if key_bytes.len() != 32 {
-            anyhow::bail!("Invalid key length: expected 32 bytes, got {}", key_bytes.len());
-        }
-
-        let key = aes_gcm::Key::<Aes256Gcm>::from_slice(&key_bytes);
-        let cipher = Aes256Gcm::new(key);
-
-        // Extract nonce (first 12 bytes) and ciphertext
-        if encrypted_data.len() < 12 {
-            anyhow::bail!("Encrypted data too short");
-        }
-
-        let (nonce_bytes, ciphertext) = encrypted_data.split_at(12);
-        let nonce = Nonce::from_slice(nonce_bytes);
-
-        // Decrypt
-        let plaintext_bytes = cipher
-            .decrypt(nonce, ciphertext)
-            .map_err(|e| anyhow::anyhow!("Decryption failed - invalid key or corrupted data: {:?}", e))?;
+        let plaintext_bytes = open(encrypted_data, &decode_key(key_b64)?)?;
 
         let plaintext = String::from_utf8(plaintext_bytes)
             .context("Decrypted data is not valid UTF-8")?;
@@ -90,6 +546,69 @@ impl EncryptionService {
         Ok(plaintext)
     }
 
+    /// Encrypt with a key derived from `passphrase` by Argon2id, so the
+    /// content can be shared with a memorable phrase instead of a base64 key.
+    /// Returns tag + Argon2 parameters + salt + the sealed ciphertext.
+    pub fn encrypt_with_passphrase(&self, plaintext: &str, passphrase: &str) -> Result<Vec<u8>> {
+        self.encrypt_with_passphrase_params(plaintext, passphrase, KdfParams::default())
+    }
+
+    fn encrypt_with_passphrase_params(&self, plaintext: &str, passphrase: &str, params: KdfParams) -> Result<Vec<u8>> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = params.derive_key(passphrase, &salt)?;
+
+        let mut encrypted_data = Vec::with_capacity(PASSPHRASE_HEADER_LEN);
+        encrypted_data.push(PASSPHRASE_TAG);
+        for value in [params.m_cost, params.t_cost, params.p_cost] {
+            encrypted_data.extend_from_slice(&value.to_le_bytes());
+        }
+        encrypted_data.extend_from_slice(&salt);
+        encrypted_data.extend(seal_with_key(self.algorithm, plaintext.as_bytes(), &key, random_nonce())?);
+
+        info!("Encrypted {} bytes with a passphrase-derived key", plaintext.len());
+        Ok(encrypted_data)
+    }
+
+    pub fn decrypt_with_passphrase(&self, encrypted_data: &[u8], passphrase: &str) -> Result<String> {
+        if encrypted_data.len() <= PASSPHRASE_HEADER_LEN || encrypted_data[0] != PASSPHRASE_TAG {
+            anyhow::bail!("Not passphrase-encrypted data");
+        }
+        let (header, sealed) = encrypted_data.split_at(PASSPHRASE_HEADER_LEN);
+        let param = |i: usize| u32::from_le_bytes(header[1 + 4 * i..5 + 4 * i].try_into().expect("4 bytes"));
+        let params = KdfParams { m_cost: param(0), t_cost: param(1), p_cost: param(2) };
+        let key = params.derive_key(passphrase, &header[1 + 12..])?;
+
+        let plaintext = String::from_utf8(open(sealed, &key).context("Decryption failed - wrong passphrase or corrupted data")?)
+            .context("Decrypted data is not valid UTF-8")?;
+
+        info!("Decrypted {} bytes with a passphrase-derived key", encrypted_data.len());
+        Ok(plaintext)
+    }
+
+    /// Wrap `data_key` (a document's 256-bit key) with `master_key`. The
+    /// envelope is stored apart from the content, so rotating the master key
+    /// only re-wraps envelopes and never re-encrypts what is on IPFS.
+    /// Returns algorithm tag + nonce + wrapped key.
+    pub fn envelope_encrypt(&self, data_key: &[u8], master_key: &[u8]) -> Result<Vec<u8>> {
+        if data_key.len() != 32 || master_key.len() != 32 {
+            anyhow::bail!("Envelope keys must be 32 bytes, got {} and {}", data_key.len(), master_key.len());
+        }
+        seal_with_key(self.algorithm, data_key, master_key, random_nonce())
+    }
+
+    /// The document key inside an `envelope_encrypt` envelope
+    pub fn envelope_decrypt(&self, envelope: &[u8], master_key: &[u8]) -> Result<Vec<u8>> {
+        if master_key.len() != 32 {
+            anyhow::bail!("Invalid master key length: expected 32 bytes, got {}", master_key.len());
+        }
+        let data_key = open(envelope, master_key).context("Failed to unwrap key envelope - wrong master key?")?;
+        if data_key.len() != 32 {
+            anyhow::bail!("Key envelope holds {} bytes, expected a 32-byte key", data_key.len());
+        }
+        Ok(data_key)
+    }
+
     /// Generate a random encryption key (for testing/utilities)
     pub fn generate_key() -> String {
         let key = Aes256Gcm::generate_key(&mut OsRng);
@@ -97,6 +616,40 @@ impl EncryptionService {
     }
 }
 
+impl EncryptionServiceTrait for EncryptionService {
+    fn encrypt(&self, plaintext: &str) -> Result<(Vec<u8>, String)> {
+        EncryptionService::encrypt(self, plaintext)
+    }
+
+    fn encrypt_with_key(&self, plaintext: &str, key_b64: &str) -> Result<Vec<u8>> {
+        EncryptionService::encrypt_with_key(self, plaintext, key_b64)
+    }
+
+    fn encrypt_bytes_with_key(&self, data: &[u8], key_b64: &str) -> Result<Vec<u8>> {
+        EncryptionService::encrypt_bytes_with_key(self, data, key_b64)
+    }
+
+    fn decrypt(&self, encrypted_data: &[u8], key_b64: &str) -> Result<String> {
+        EncryptionService::decrypt(self, encrypted_data, key_b64)
+    }
+
+    fn encrypt_with_passphrase(&self, plaintext: &str, passphrase: &str) -> Result<Vec<u8>> {
+        EncryptionService::encrypt_with_passphrase(self, plaintext, passphrase)
+    }
+
+    fn decrypt_with_passphrase(&self, encrypted_data: &[u8], passphrase: &str) -> Result<String> {
+        EncryptionService::decrypt_with_passphrase(self, encrypted_data, passphrase)
+    }
+
+    fn envelope_encrypt(&self, data_key: &[u8], master_key: &[u8]) -> Result<Vec<u8>> {
+        EncryptionService::envelope_encrypt(self, data_key, master_key)
+    }
+
+    fn envelope_decrypt(&self, envelope: &[u8], master_key: &[u8]) -> Result<Vec<u8>> {
+        EncryptionService::envelope_decrypt(self, envelope, master_key)
+    }
+}
+
 impl Default for EncryptionService {
     fn default() -> Self {
         Self::new()
@@ -121,6 +674,22 @@ mod tests {
         assert_eq!(decrypted, plaintext);
     }
 
+    #[test]
+    fn test_encrypt_with_existing_key() {
+        let service = EncryptionService::new();
+        let key = EncryptionService::generate_key();
+
+        let first = service.encrypt_with_key("Amended", &key).unwrap();
+        let second = service.encrypt_with_key("Amended", &key).unwrap();
+        assert_ne!(first, second, "nonce must not be reused");
+        assert_eq!(service.decrypt(&second, &key).unwrap(), "Amended");
+
+        let pdf = service.encrypt_bytes_with_key(b"%PDF-1.7\xff", &key).unwrap();
+        assert_eq!(service.decrypt_bytes(&pdf, &key).unwrap(), b"%PDF-1.7\xff");
+
+        assert!(service.encrypt_with_key("x", "c2hvcnQ=").is_err());
+    }
+
     #[test]
     fn test_decrypt_with_wrong_key() {
         let service = EncryptionService::new();
@@ -134,6 +703,146 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_chacha20_poly1305() {
+        let chacha = EncryptionService::with_algorithm(CipherAlgorithm::ChaCha20Poly1305);
+        let aes = EncryptionService::new();
+        let plaintext = r#"{"title":"Kalki 2898 AD"}"#;
+
+        let (encrypted_data, key) = chacha.encrypt(plaintext).unwrap();
+        assert_eq!(encrypted_data[0], CipherAlgorithm::ChaCha20Poly1305.tag());
+        assert_eq!(chacha.decrypt(&encrypted_data, &key).unwrap(), plaintext);
+        // The algorithm comes from the blob, not the service
+        assert_eq!(aes.decrypt(&encrypted_data, &key).unwrap(), plaintext);
+
+        let (aes_data, aes_key) = aes.encrypt(plaintext).unwrap();
+        assert_eq!(aes_data[0], CipherAlgorithm::Aes256Gcm.tag());
+        assert_eq!(chacha.decrypt(&aes_data, &aes_key).unwrap(), plaintext);
+        assert!(chacha.decrypt(&encrypted_data, &aes_key).is_err());
+
+        assert_eq!(CipherAlgorithm::parse("chacha20_poly1305"), Some(CipherAlgorithm::ChaCha20Poly1305));
+        assert_eq!(CipherAlgorithm::parse("rot13"), None);
+    }
+
+    #[test]
+    fn test_decrypt_untagged_legacy_blob() {
+        let key = EncryptionService::generate_key();
+        let key_bytes = general_purpose::STANDARD.decode(&key).unwrap();
+        // Nonce starting with an algorithm tag byte, as legacy nonces may
+        let nonce = [CipherAlgorithm::ChaCha20Poly1305.tag(); NONCE_LEN];
+        let mut legacy = nonce.to_vec();
+        legacy.extend(CipherAlgorithm::Aes256Gcm.encrypt(&key_bytes, &nonce, b"Legacy").unwrap());
+
+        assert_eq!(EncryptionService::new().decrypt(&legacy, &key).unwrap(), "Legacy");
+    }
+
+    #[test]
+    fn test_passphrase_encryption() {
+        let service = EncryptionService::with_algorithm(CipherAlgorithm::ChaCha20Poly1305);
+        // Cheap parameters keep the test fast; they are read back from the header
+        let params = KdfParams { m_cost: 64, t_cost: 1, p_cost: 1 };
+        let encrypted_data = service
+            .encrypt_with_passphrase_params("Shared agreement", "correct horse battery staple", params)
+            .unwrap();
+
+        assert_eq!(encrypted_data[0], PASSPHRASE_TAG);
+        assert_eq!(encrypted_data[1..5], 64u32.to_le_bytes());
+        assert_eq!(
+            EncryptionService::new().decrypt_with_passphrase(&encrypted_data, "correct horse battery staple").unwrap(),
+            "Shared agreement"
+        );
+        assert!(service.decrypt_with_passphrase(&encrypted_data, "wrong horse").is_err());
+        assert!(service.decrypt(&encrypted_data, &EncryptionService::generate_key()).is_err());
+
+        // Unreasonable costs in a crafted header are refused before deriving
+        let mut crafted = encrypted_data.clone();
+        crafted[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(service.decrypt_with_passphrase(&crafted, "correct horse battery staple").is_err());
+        crafted[1..5].copy_from_slice(&(512 * 1024u32).to_le_bytes());
+        crafted[5..9].copy_from_slice(&1u32.to_le_bytes());
+        assert!(service.decrypt_with_passphrase(&crafted, "correct horse battery staple").is_err());
+    }
+
+    #[test]
+    fn test_envelope_encryption() {
+        let service = EncryptionService::new();
+        let master_key = decode_key(&EncryptionService::generate_key()).unwrap();
+        let (encrypted_data, data_key_b64) = service.encrypt("Bulk rekey me").unwrap();
+        let data_key = decode_key(&data_key_b64).unwrap();
+
+        let envelope = service.envelope_encrypt(&data_key, &master_key).unwrap();
+        assert_eq!(envelope.len(), 1 + NONCE_LEN + 32 + AUTH_TAG_LEN);
+        let unwrapped = service.envelope_decrypt(&envelope, &master_key).unwrap();
+        assert_eq!(unwrapped, data_key);
+        let unwrapped_b64 = general_purpose::STANDARD.encode(&unwrapped);
+        assert_eq!(service.decrypt(&encrypted_data, &unwrapped_b64).unwrap(), "Bulk rekey me");
+
+        // Rotating the master key re-wraps the envelope; the content is untouched
+        let new_master_key = decode_key(&EncryptionService::generate_key()).unwrap();
+        let rewrapped = service.envelope_encrypt(&unwrapped, &new_master_key).unwrap();
+        assert_eq!(service.envelope_decrypt(&rewrapped, &new_master_key).unwrap(), data_key);
+        assert!(service.envelope_decrypt(&rewrapped, &master_key).is_err());
+        assert!(service.envelope_encrypt(b"short", &master_key).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_fields() {
+        let service = EncryptionService::new();
+        let key = EncryptionService::generate_key();
+        let agreement = serde_json::json!({
+            "rights": {"territories": ["IN"], "term": {"years": 3}},
+            "financial": {"dealValue": 100_000_000, "currency": "INR"}
+        });
+
+        assert_eq!(missing_field_paths(&agreement, &["financial", "missing.field"]), vec!["missing.field"]);
+        assert!(service.encrypt_fields(&agreement, &["financial", "missing.field"], &key).is_err());
+
+        let protected = service.encrypt_fields(&agreement, &["financial", "rights.term.years"], &key).unwrap();
+        assert_eq!(protected["rights"]["territories"], serde_json::json!(["IN"]));
+        assert_eq!(protected["financial"]["_alg"], "aes256gcm");
+        assert!(protected["rights"]["term"]["years"][ENCRYPTED_FIELD_KEY].is_string());
+        assert!(!protected.to_string().contains("100000000"));
+
+        assert_eq!(service.decrypt_fields(&protected, &key).unwrap(), agreement);
+        assert!(service.decrypt_fields(&protected, &EncryptionService::generate_key()).is_err());
+        // Plain JSON is not taken for a field-encrypted document
+        assert!(service.decrypt_fields(&agreement, &key).is_err());
+
+        // The check value rejects a wrong key even when no field is encrypted
+        let unencrypted = service.encrypt_fields(&agreement, &[], &key).unwrap();
+        let stored = unencrypted.to_string().into_bytes();
+        assert!(service.open_document(&stored, &EncryptionService::generate_key()).is_err());
+        let document = service.open_document(&stored, &key).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&document.plaintext).unwrap(), agreement);
+
+        // A field's own key discloses that field and nothing else
+        let encrypted = general_purpose::STANDARD
+            .decode(protected["financial"][ENCRYPTED_FIELD_KEY].as_str().unwrap())
+            .unwrap();
+        let financial = service.decrypt(&encrypted, &field_key(&key, "financial").unwrap()).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&financial).unwrap(), agreement["financial"]);
+    }
+
+    #[tokio::test]
+    async fn test_rekey_keeps_field_encryption() {
+        let store = Arc::new(crate::memory_store::MemoryStore::new());
+        let service = EncryptionService::new();
+        let key = EncryptionService::generate_key();
+        let agreement = serde_json::json!({ "title": "Kalki 2898 AD", "financial": { "dealValue": 1000 } });
+        let protected = service.encrypt_fields(&agreement, &["financial"], &key).unwrap();
+        let cid = store.upload(protected.to_string().as_bytes()).await.unwrap();
+
+        let wrong_key = service.rekey_in_place(store.clone(), &cid, &EncryptionService::generate_key()).await;
+        assert!(wrong_key.unwrap_err().is::<InvalidKeyError>());
+
+        let result = service.rekey_in_place(store.clone(), &cid, &key).await.unwrap();
+        let stored = store.fetch(&result.new_cid).await.unwrap();
+        let document = service.open_document(&stored, &result.new_key).unwrap();
+        assert_eq!(document.encrypted_fields, vec!["financial"]);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&document.plaintext).unwrap(), agreement);
+        assert!(service.open_document(&stored, &key).is_err());
+    }
+
     #[test]
     fn test_decrypt_corrupted_data() {
         let service = EncryptionService::new();
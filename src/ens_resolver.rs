@@ -0,0 +1,175 @@
+// src/ens_resolver.rs - Resolve ENS names to Ethereum addresses
+use alloy::ens::ProviderEnsExt;
+use alloy::primitives::Address;
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Placeholder used when no wallet address is known
+pub const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+/// Longest an ENS lookup may take before the name is treated as unresolved,
+/// so a slow RPC provider cannot stall the parse
+const ENS_LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Resolves `*.eth` names through an Ethereum JSON-RPC provider.
+/// Without a provider only literal addresses are accepted.
+#[derive(Default)]
+pub struct EnsResolver {
+    provider: Option<DynProvider>,
+}
+
+/// Whether `value` is a `0x`-prefixed 20-byte hex address
+pub fn is_hex_address(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .map_or(false, |hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+pub fn is_ens_name(value: &str) -> bool {
+    let value = value.trim().to_lowercase();
+    value.ends_with(".eth") && value.len() > ".eth".len() && !value.contains(char::is_whitespace)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AddressType {
+    Hex,
+    Ens,
+    Unknown,
+}
+
+/// Format check of a wallet address or ENS name, made before any resolution
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletAddressValidation {
+    pub is_valid: bool,
+    pub address_type: AddressType,
+    /// EIP-55 mixed-case form, for hex addresses only
+    pub checksum_address: Option<String>,
+}
+
+/// EIP-55 mixed-case form of a hex address
+pub fn checksum_address(address: &str) -> Option<String> {
+    Address::from_str(address.trim()).ok().map(|address| address.to_checksum(None))
+}
+
+/// Validate a hex address (`0x` + 40 hex digits) or ENS name (`name.eth`).
+/// A hex address written in mixed case must match its EIP-55 checksum.
+pub fn validate_wallet_address(raw: &str) -> WalletAddressValidation {
+    let raw = raw.trim();
+    if is_hex_address(raw) {
+        let checksum = checksum_address(raw);
+        let hex = &raw[2..];
+        let mixed_case = hex.chars().any(|c| c.is_ascii_uppercase()) && hex.chars().any(|c| c.is_ascii_lowercase());
+        return WalletAddressValidation {
+            is_valid: checksum.is_some() && (!mixed_case || checksum.as_deref() == Some(raw)),
+            address_type: AddressType::Hex,
+            checksum_address: checksum,
+        };
+    }
+
+    let address_type = if raw.to_lowercase().ends_with(".eth") { AddressType::Ens } else { AddressType::Unknown };
+    WalletAddressValidation {
+        is_valid: address_type == AddressType::Ens && is_ens_name(raw),
+        address_type,
+        checksum_address: None,
+    }
+}
+
+impl EnsResolver {
+    pub fn new(rpc_url: Option<String>) -> Self {
+        let provider = rpc_url.and_then(|url| match url.parse() {
+            Ok(rpc_url) => {
+                info!("Initializing ENS resolver ({})", url);
+                Some(ProviderBuilder::new().connect_http(rpc_url).erased())
+            }
+            Err(e) => {
+                warn!("Invalid ETHEREUM_RPC_URL, ENS resolution disabled: {}", e);
+                None
+            }
+        });
+
+        Self { provider }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// Resolve a raw wallet value to a lowercase hex address. Literal addresses
+    /// are returned as-is; ENS names need a provider; anything else is `None`.
+    pub async fn resolve(&self, raw: &str) -> Option<String> {
+        let raw = raw.trim();
+        if is_hex_address(raw) {
+            return Some(raw.to_lowercase());
+        }
+        if !is_ens_name(raw) {
+            return None;
+        }
+
+        let provider = self.provider.as_ref()?;
+        match tokio::time::timeout(ENS_LOOKUP_TIMEOUT, self.lookup(provider, raw)).await {
+            Ok(Ok(address)) => {
+                info!("🔗 Resolved {} to {}", raw, address);
+                Some(address)
+            }
+            Ok(Err(e)) => {
+                warn!("ENS resolution failed for {}: {}", raw, e);
+                None
+            }
+            Err(_) => {
+                warn!("ENS resolution for {} timed out after {:?}", raw, ENS_LOOKUP_TIMEOUT);
+                None
+            }
+        }
+    }
+
+    async fn lookup(&self, provider: &DynProvider, name: &str) -> Result<String> {
+        let address = provider
+            .resolve_name(&name.to_lowercase())
+            .await
+            .context("ENS lookup failed")?;
+        Ok(format!("{:#x}", address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_without_provider() {
+        let resolver = EnsResolver::new(None);
+        let address = "0xAbC0000000000000000000000000000000000123";
+
+        assert_eq!(resolver.resolve(address).await.as_deref(), Some(address.to_lowercase().as_str()));
+        assert!(resolver.resolve("vyjayanthi.eth").await.is_none());
+        assert!(resolver.resolve("not an address").await.is_none());
+        assert!(is_ens_name("Company.ETH"));
+        assert!(!is_hex_address("0x123"));
+    }
+
+    #[test]
+    fn test_validate_wallet_address() {
+        // EIP-55 reference vector
+        let checksummed = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let lower = validate_wallet_address(&checksummed.to_lowercase());
+        assert!(lower.is_valid);
+        assert_eq!(lower.address_type, AddressType::Hex);
+        assert_eq!(lower.checksum_address.as_deref(), Some(checksummed));
+
+        assert!(validate_wallet_address(checksummed).is_valid);
+        assert!(!validate_wallet_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").is_valid);
+        assert!(!validate_wallet_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beae").is_valid);
+
+        let ens = validate_wallet_address("vyjayanthi.eth");
+        assert_eq!((ens.is_valid, ens.address_type, ens.checksum_address), (true, AddressType::Ens, None));
+        assert!(!validate_wallet_address("my wallet.eth").is_valid);
+        assert_eq!(validate_wallet_address("IBAN 1234").address_type, AddressType::Unknown);
+    }
+}
@@ -0,0 +1,255 @@
+// src/financial_report.rs - Financial summary of an agreement, as JSON or a one-page PDF
+use anyhow::{Context, Result};
+use printpdf::{BuiltinFont, Mm, PdfDocument};
+use serde::Serialize;
+
+use crate::models::{FlexibleDate, RightsAgreementJSON};
+
+/// Indicative USD value of one unit of each supported currency. Reports are
+/// for orientation, not accounting, so fixed reference rates are used.
+const USD_RATES: &[(&str, f64)] = &[
+    ("USD", 1.0),
+    ("INR", 0.012),
+    ("EUR", 1.08),
+    ("GBP", 1.27),
+    ("CNY", 0.14),
+    ("JPY", 0.0067),
+    ("AUD", 0.66),
+];
+
+/// LLM keys that may carry an advance or minimum guarantee, see `raw_llm_extras`
+const ADVANCE_KEYS: &[&str] = &["advance", "advance_amount", "recoupable_advance"];
+const MINIMUM_GUARANTEE_KEYS: &[&str] = &["minimum_guarantee", "mg", "minimum_guarantee_amount"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FinancialReport {
+    pub agreement_id: String,
+    pub title: String,
+    pub currency: String,
+    pub deal_value: u64,
+    pub platform_fee_percentage: f64,
+    pub platform_fee: u64,
+    pub net_to_rights_holder: u64,
+    pub payment_schedule: Vec<ScheduledPayment>,
+    /// Sum of the payment schedule
+    pub scheduled_total: u64,
+    /// Whether the schedule adds up to the deal value
+    pub schedule_balanced: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub advance: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum_guarantee: Option<u64>,
+    pub waterfall: Vec<WaterfallStep>,
+    /// `deal_value` at the indicative rate, `None` for unrecognised currencies
+    pub deal_value_usd: Option<f64>,
+}
+
+/// A payment milestone, or the upfront/on-delivery split when there are none
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledPayment {
+    pub name: String,
+    pub amount: u64,
+    /// ISO date when known, otherwise the date as written
+    pub due_date: String,
+}
+
+/// One step from the gross deal value down to what the rights holder receives
+#[derive(Debug, Clone, Serialize)]
+pub struct WaterfallStep {
+    pub step: String,
+    pub amount: i64,
+    pub running_total: u64,
+}
+
+/// A whole amount from a JSON number or a formatted string like "10,00,000"
+fn amount_from(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64().or_else(|| n.as_f64().map(|f| f as u64)),
+        serde_json::Value::String(s) => {
+            let digits: String = s.chars().filter(char::is_ascii_digit).collect();
+            digits.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+fn extra_amount(agreement: &RightsAgreementJSON, keys: &[&str]) -> Option<u64> {
+    let extras = agreement.raw_llm_extras.as_ref()?;
+    keys.iter().find_map(|key| extras.get(*key).and_then(amount_from))
+}
+
+fn usd_value(amount: u64, currency: &str) -> Option<f64> {
+    USD_RATES
+        .iter()
+        .find(|(code, _)| *code == currency)
+        .map(|(_, rate)| (amount as f64 * rate * 100.0).round() / 100.0)
+}
+
+fn schedule_date(raw: &str) -> String {
+    FlexibleDate::parse(raw).to_string()
+}
+
+impl FinancialReport {
+    pub fn from_agreement(agreement: &RightsAgreementJSON) -> Self {
+        let financial = &agreement.financial;
+
+        let payment_schedule = match financial.payment_structure.milestones.as_deref() {
+            Some(milestones) if !milestones.is_empty() => milestones
+                .iter()
+                .map(|m| ScheduledPayment {
+                    name: m.name.clone(),
+                    amount: m.amount,
+                    due_date: schedule_date(&m.due_date),
+                })
+                .collect(),
+            _ => {
+                let breakdown = &financial.payment_structure.breakdown;
+                let delivery_date = agreement
+                    .deliverables
+                    .as_ref()
+                    .map_or_else(|| "Unknown".to_string(), |d| schedule_date(&d.delivery_deadline));
+                vec![
+                    ScheduledPayment {
+                        name: "Upfront".to_string(),
+                        amount: breakdown.upfront,
+                        due_date: agreement.rights.term.start_date.to_string(),
+                    },
+                    ScheduledPayment {
+                        name: "On delivery".to_string(),
+                        amount: breakdown.on_delivery,
+                        due_date: delivery_date,
+                    },
+                ]
+            }
+        };
+        let scheduled_total = payment_schedule.iter().map(|p| p.amount).sum();
+
+        let waterfall = vec![
+            WaterfallStep {
+                step: "Gross deal value".to_string(),
+                amount: financial.deal_value as i64,
+                running_total: financial.deal_value,
+            },
+            WaterfallStep {
+                step: format!("Platform fee ({}%)", financial.platform_fee.percentage),
+                amount: -(financial.platform_fee.amount as i64),
+                running_total: financial.net_to_rights_holder,
+            },
+        ];
+
+        Self {
+            agreement_id: agreement.agreement_id.clone(),
+            title: agreement.content.title.clone(),
+            currency: financial.currency.clone(),
+            deal_value: financial.deal_value,
+            platform_fee_percentage: financial.platform_fee.percentage,
+            platform_fee: financial.platform_fee.amount,
+            net_to_rights_holder: financial.net_to_rights_holder,
+            scheduled_total,
+            schedule_balanced: scheduled_total == financial.deal_value,
+            payment_schedule,
+            advance: extra_amount(agreement, ADVANCE_KEYS),
+            minimum_guarantee: extra_amount(agreement, MINIMUM_GUARANTEE_KEYS),
+            waterfall,
+            deal_value_usd: usd_value(financial.deal_value, &financial.currency),
+        }
+    }
+
+    /// The report as text lines, as printed in the PDF
+    fn lines(&self) -> Vec<String> {
+        let money = |amount: u64| format!("{} {}", amount, self.currency);
+        let mut lines = vec![
+            format!("Agreement: {}", self.agreement_id),
+            format!("Title: {}", self.title),
+            String::new(),
+            format!("Deal value: {}", money(self.deal_value)),
+        ];
+        if let Some(usd) = self.deal_value_usd {
+            lines.push(format!("Deal value (USD, indicative): {:.2}", usd));
+        }
+        lines.push(format!("Platform fee ({}%): {}", self.platform_fee_percentage, money(self.platform_fee)));
+        lines.push(format!("Net to rights holder: {}", money(self.net_to_rights_holder)));
+        if let Some(advance) = self.advance {
+            lines.push(format!("Advance: {}", money(advance)));
+        }
+        if let Some(mg) = self.minimum_guarantee {
+            lines.push(format!("Minimum guarantee: {}", money(mg)));
+        }
+
+        lines.push(String::new());
+        lines.push("Payment schedule:".to_string());
+        for payment in &self.payment_schedule {
+            lines.push(format!("  {} - {} - due {}", payment.name, money(payment.amount), payment.due_date));
+        }
+        lines.push(format!(
+            "  Total: {}{}",
+            money(self.scheduled_total),
+            if self.schedule_balanced { "" } else { " (does not match the deal value)" }
+        ));
+
+        lines.push(String::new());
+        lines.push("Waterfall:".to_string());
+        for step in &self.waterfall {
+            lines.push(format!("  {}: {} -> {}", step.step, step.amount, money(step.running_total)));
+        }
+        lines
+    }
+
+    /// Render as a single A4 page. The built-in PDF fonts only cover ASCII
+    /// reliably, so other characters are printed as `?`.
+    pub fn to_pdf(&self) -> Result<Vec<u8>> {
+        let (doc, page, layer) = PdfDocument::new("Financial report", Mm(210.0), Mm(297.0), "Report");
+        let layer = doc.get_page(page).get_layer(layer);
+        let regular = doc.add_builtin_font(BuiltinFont::Helvetica).context("Failed to load PDF font")?;
+        let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).context("Failed to load PDF font")?;
+
+        layer.use_text("Financial Report", 18.0, Mm(20.0), Mm(275.0), &bold);
+        let mut y = 260.0;
+        for line in self.lines() {
+            let ascii: String = line.chars().map(|c| if c.is_ascii() { c } else { '?' }).collect();
+            layer.use_text(ascii, 10.0, Mm(20.0), Mm(y), &regular);
+            y -= 6.0;
+            if y < 15.0 {
+                break;
+            }
+        }
+
+        doc.save_to_bytes().context("Failed to render PDF")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_builder::JSONBuilder;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_report_schedule_and_extras() {
+        let llm_json = json!({
+            "title": "Kalki 2898 AD",
+            "licensor": "Vyjayanthi Movies",
+            "licensee": "Netflix India",
+            "territories": ["India"],
+            "media_types": ["SVOD"],
+            "deal_value": 100_000_000,
+            "currency": "INR",
+            "start_date": "2024-08-01",
+            "exclusivity": true,
+            "minimum_guarantee": "2,50,00,000"
+        });
+        let agreement = JSONBuilder::default().build_from_llm_json(&llm_json).await.unwrap();
+        let report = FinancialReport::from_agreement(&agreement);
+
+        assert_eq!(report.platform_fee, 2_500_000);
+        assert_eq!(report.payment_schedule.len(), 2);
+        assert_eq!(report.payment_schedule[0].due_date, "2024-08-01");
+        assert!(report.schedule_balanced);
+        assert_eq!(report.minimum_guarantee, Some(25_000_000));
+        assert_eq!(report.advance, None);
+        assert_eq!(report.deal_value_usd, Some(1_200_000.0));
+        assert_eq!(report.waterfall.last().unwrap().running_total, 97_500_000);
+
+        assert!(report.to_pdf().unwrap().starts_with(b"%PDF"));
+    }
+}
@@ -0,0 +1,335 @@
+// src/flat_agreement.rs - Single-row projection of an agreement for wide SQL tables
+use chrono::NaiveDate;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+#[cfg(test)]
+use schemars::schema::SingleOrVec;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::models::RightsAgreementJSON;
+
+/// Every scalar of `RightsAgreementJSON` as one column, named `<section>_<field>`,
+/// so agreements can be analysed with plain SQL instead of `jsonb` operators.
+/// Lists stay lists (`TEXT[]`); nested lists such as milestones become counts.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct RightsAgreementFlat {
+    pub agreement_id: String,
+
+    pub rights_holder_name: String,
+    pub rights_holder_wallet_address: String,
+
+    pub content_title: String,
+    pub content_original_title: String,
+    pub content_type: String,
+    pub content_language: String,
+    pub content_genres: Vec<String>,
+    pub content_duration_minutes: i64,
+    pub content_release_date: String,
+    pub content_director: String,
+    pub content_producer: String,
+    pub content_production_company: Option<String>,
+    pub content_production_country: Option<String>,
+    pub content_rating_cbfc: String,
+    pub content_rating_mpaa: Option<String>,
+
+    pub rights_territories: Vec<String>,
+    pub rights_media_types: Vec<String>,
+    /// Normalized media type codes, e.g. `SVOD`
+    pub rights_media_type_codes: Vec<String>,
+    pub rights_exclusive: bool,
+    pub rights_term_years: i64,
+    #[schemars(schema_with = "nullable_date")]
+    pub rights_term_start_date: Option<NaiveDate>,
+    #[schemars(schema_with = "nullable_date")]
+    pub rights_term_end_date: Option<NaiveDate>,
+
+    pub financial_deal_value: i64,
+    pub financial_currency: String,
+    pub financial_platform_fee_percentage: f64,
+    pub financial_platform_fee_amount: i64,
+    pub financial_net_to_rights_holder: i64,
+    pub financial_payment_type: String,
+    pub financial_upfront: i64,
+    pub financial_on_delivery: i64,
+    pub financial_milestone_count: i64,
+
+    pub licensor_name: Option<String>,
+    pub licensor_country: Option<String>,
+    pub licensor_contact_email: Option<String>,
+    pub licensor_signatory_name: Option<String>,
+    pub licensee_name: Option<String>,
+    pub licensee_country: Option<String>,
+    pub licensee_contact_email: Option<String>,
+    pub licensee_signatory_name: Option<String>,
+
+    #[schemars(schema_with = "nullable_date")]
+    pub deliverables_delivery_deadline: Option<NaiveDate>,
+    pub deliverables_status: Option<String>,
+
+    pub restrictions_territories_excluded: Vec<String>,
+    pub restrictions_platforms_excluded: Vec<String>,
+    pub restrictions_holdback_theatrical_days: Option<i64>,
+    pub restrictions_holdback_physical_media_days: Option<i64>,
+    pub restrictions_holdback_free_tv_days: Option<i64>,
+
+    pub special_terms_count: i64,
+
+    pub legal_governing_law: Option<String>,
+    pub legal_dispute_mechanism: Option<String>,
+
+    pub metadata_status: Option<String>,
+    pub metadata_version: Option<String>,
+    pub metadata_created_date: Option<String>,
+    pub metadata_tags: Vec<String>,
+}
+
+fn nullable_date(_: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(vec![InstanceType::String, InstanceType::Null].into()),
+        format: Some("date".to_string()),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// SCREAMING_SNAKE_CASE name of a serde enum variant
+fn variant_name<T: Serialize>(value: &T) -> Option<String> {
+    serde_json::to_value(value).ok()?.as_str().map(str::to_string)
+}
+
+impl From<&RightsAgreementJSON> for RightsAgreementFlat {
+    fn from(agreement: &RightsAgreementJSON) -> Self {
+        let content = &agreement.content;
+        let rights = &agreement.rights;
+        let financial = &agreement.financial;
+        let licensor = agreement.parties.as_ref().map(|p| &p.licensor);
+        let licensee = agreement.parties.as_ref().map(|p| &p.licensee);
+        let restrictions = agreement.restrictions.as_ref();
+        let holdback = restrictions.map(|r| r.holdback_period.to_days());
+        let metadata = agreement.metadata.as_ref();
+
+        Self {
+            agreement_id: agreement.agreement_id.clone(),
+
+            rights_holder_name: agreement.rights_holder.name.clone(),
+            rights_holder_wallet_address: agreement.rights_holder.wallet_address.clone(),
+
+            content_title: content.title.clone(),
+            content_original_title: content.original_title.clone(),
+            content_type: content.content_type.clone(),
+            content_language: content.language.clone(),
+            content_genres: content.genre.clone(),
+            content_duration_minutes: content.duration as i64,
+            content_release_date: content.release_date.clone(),
+            content_director: content.director.clone(),
+            content_producer: content.producer.clone(),
+            content_production_company: content.production_company.clone(),
+            content_production_country: content.production_country.clone(),
+            content_rating_cbfc: content.rating.cbfc.clone(),
+            content_rating_mpaa: content.rating.mpaa.clone(),
+
+            rights_territories: rights.territories.clone(),
+            rights_media_types: rights.media_types.clone(),
+            rights_media_type_codes: rights.media_types_normalized.iter().map(|m| m.as_str().to_string()).collect(),
+            rights_exclusive: rights.exclusivity,
+            rights_term_years: rights.term.years as i64,
+            rights_term_start_date: rights.term.start_date.date(),
+            rights_term_end_date: rights.term.end_date.date(),
+
+            financial_deal_value: financial.deal_value as i64,
+            financial_currency: financial.currency.clone(),
+            financial_platform_fee_percentage: financial.platform_fee.percentage,
+            financial_platform_fee_amount: financial.platform_fee.amount as i64,
+            financial_net_to_rights_holder: financial.net_to_rights_holder as i64,
+            financial_payment_type: financial.payment_structure.payment_type.clone(),
+            financial_upfront: financial.payment_structure.breakdown.upfront as i64,
+            financial_on_delivery: financial.payment_structure.breakdown.on_delivery as i64,
+            financial_milestone_count: financial.payment_structure.milestones.as_ref().map_or(0, |m| m.len() as i64),
+
+            licensor_name: licensor.map(|p| p.name.clone()),
+            licensor_country: licensor.map(|p| p.country.clone()),
+            licensor_contact_email: licensor.map(|p| p.contact_email.clone()),
+            licensor_signatory_name: licensor.map(|p| p.signatory_name.clone()),
+            licensee_name: licensee.map(|p| p.name.clone()),
+            licensee_country: licensee.map(|p| p.country.clone()),
+            licensee_contact_email: licensee.map(|p| p.contact_email.clone()),
+            licensee_signatory_name: licensee.map(|p| p.signatory_name.clone()),
+
+            deliverables_delivery_deadline: agreement.deliverables.as_ref().and_then(|d| d.deadline()),
+            deliverables_status: agreement.deliverables.as_ref().and_then(|d| d.status.as_ref()).and_then(variant_name),
+
+            restrictions_territories_excluded: restrictions.map(|r| r.territories_excluded.clone()).unwrap_or_default(),
+            restrictions_platforms_excluded: restrictions.map(|r| r.platforms_excluded.clone()).unwrap_or_default(),
+            restrictions_holdback_theatrical_days: holdback.as_ref().map(|h| h.theatrical as i64),
+            restrictions_holdback_physical_media_days: holdback.as_ref().map(|h| h.physical_media as i64),
+            restrictions_holdback_free_tv_days: holdback.as_ref().map(|h| h.free_tv as i64),
+
+            special_terms_count: agreement.special_terms.as_ref().map_or(0, |t| t.len() as i64),
+
+            legal_governing_law: agreement.legal_terms.as_ref().map(|l| l.governing_law.clone()),
+            legal_dispute_mechanism: agreement
+                .legal_terms
+                .as_ref()
+                .and_then(|l| variant_name(&l.dispute_resolution.mechanism)),
+
+            metadata_status: metadata.map(|m| m.status.clone()),
+            metadata_version: metadata.map(|m| m.version.clone()),
+            metadata_created_date: metadata.map(|m| m.created_date.clone()),
+            metadata_tags: metadata.map(|m| m.tags.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// PostgreSQL type for a column schema, and whether it admits NULL
+#[cfg(test)]
+fn sql_type(schema: &SchemaObject) -> (String, bool) {
+    let (instance_type, nullable) = match &schema.instance_type {
+        Some(SingleOrVec::Single(t)) => (**t, false),
+        Some(SingleOrVec::Vec(types)) => (
+            types.iter().copied().find(|t| *t != InstanceType::Null).unwrap_or(InstanceType::Null),
+            types.contains(&InstanceType::Null),
+        ),
+        None => (InstanceType::Object, true),
+    };
+
+    let sql = match instance_type {
+        InstanceType::Boolean => "BOOLEAN".to_string(),
+        InstanceType::Integer => "BIGINT".to_string(),
+        InstanceType::Number => "DOUBLE PRECISION".to_string(),
+        InstanceType::String if schema.format.as_deref() == Some("date") => "DATE".to_string(),
+        InstanceType::String => "TEXT".to_string(),
+        InstanceType::Array => {
+            let item = schema.array.as_ref().and_then(|a| match &a.items {
+                Some(SingleOrVec::Single(item)) => match item.as_ref() {
+                    Schema::Object(item) => Some(sql_type(item).0),
+                    Schema::Bool(_) => None,
+                },
+                _ => None,
+            });
+            format!("{}[]", item.unwrap_or_else(|| "TEXT".to_string()))
+        }
+        InstanceType::Object | InstanceType::Null => "JSONB".to_string(),
+    };
+    (sql, nullable)
+}
+
+/// Table the upload path records flat agreements in
+pub const FLAT_TABLE: &str = "parsed_agreements_flat";
+
+/// Column names of `RightsAgreementFlat`, in schema (alphabetical) order
+fn column_names() -> Vec<String> {
+    let root = schemars::schema_for!(RightsAgreementFlat);
+    let object = root.schema.object.as_ref().expect("flat agreement schema is an object");
+    object.properties.keys().cloned().collect()
+}
+
+/// `CREATE TABLE` statement for `RightsAgreementFlat`, generated from its schema
+/// so the table stays in step with the struct. `agreement_id` is the primary key;
+/// other columns follow in alphabetical order, which groups them by section.
+/// Tests compare it against `migrations/schema.sql`, which creates the table.
+#[cfg(test)]
+pub fn create_table_ddl(table: &str) -> String {
+    let root = schemars::schema_for!(RightsAgreementFlat);
+    let object = root.schema.object.as_ref().expect("flat agreement schema is an object");
+
+    let columns: Vec<String> = object
+        .properties
+        .iter()
+        .map(|(name, schema)| {
+            let (sql, nullable) = match schema {
+                Schema::Object(schema) => sql_type(schema),
+                Schema::Bool(_) => ("JSONB".to_string(), true),
+            };
+            if name == "agreement_id" {
+                format!("    {} {} PRIMARY KEY", name, sql)
+            } else if nullable {
+                format!("    {} {}", name, sql)
+            } else {
+                format!("    {} {} NOT NULL", name, sql)
+            }
+        })
+        .collect();
+
+    let (primary, rest): (Vec<_>, Vec<_>) = columns.into_iter().partition(|c| c.contains("PRIMARY KEY"));
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\n{}\n);",
+        table,
+        primary.into_iter().chain(rest).collect::<Vec<_>>().join(",\n")
+    )
+}
+
+/// Upsert of one `RightsAgreementFlat`, bound as JSON to `$1`. Postgres maps
+/// the JSON onto the table's columns, so only the `SET` list depends on the struct.
+pub fn upsert_sql(table: &str) -> String {
+    let updates: Vec<String> = column_names()
+        .into_iter()
+        .filter(|name| name != "agreement_id")
+        .map(|name| format!("{0} = EXCLUDED.{0}", name))
+        .collect();
+    format!(
+        "INSERT INTO {0} SELECT * FROM jsonb_populate_record(NULL::{0}, $1)\nON CONFLICT (agreement_id) DO UPDATE SET {1}",
+        table,
+        updates.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ddl_column_types() {
+        let ddl = create_table_ddl("agreements_flat");
+
+        assert!(ddl.starts_with("CREATE TABLE IF NOT EXISTS agreements_flat (\n    agreement_id TEXT PRIMARY KEY,"));
+        assert!(ddl.contains("    content_title TEXT NOT NULL,"));
+        assert!(ddl.contains("    content_production_company TEXT,"));
+        assert!(ddl.contains("    financial_deal_value BIGINT NOT NULL,"));
+        assert!(ddl.contains("    financial_platform_fee_percentage DOUBLE PRECISION NOT NULL,"));
+        assert!(ddl.contains("    rights_exclusive BOOLEAN NOT NULL,"));
+        assert!(ddl.contains("    rights_territories TEXT[] NOT NULL,"));
+        assert!(ddl.contains("    rights_term_end_date DATE,"));
+        assert!(ddl.contains("    restrictions_holdback_theatrical_days BIGINT,"));
+        assert!(ddl.ends_with("\n);"));
+
+        // The migration must be regenerated whenever the struct changes
+        let schema_sql = include_str!("../migrations/schema.sql");
+        assert!(schema_sql.contains(&create_table_ddl(FLAT_TABLE)));
+    }
+
+    #[test]
+    fn test_upsert_sql() {
+        let sql = upsert_sql("agreements_flat");
+
+        assert!(sql.starts_with("INSERT INTO agreements_flat SELECT * FROM jsonb_populate_record(NULL::agreements_flat, $1)"));
+        assert!(sql.contains("content_title = EXCLUDED.content_title"));
+        assert!(sql.contains("metadata_tags = EXCLUDED.metadata_tags"));
+        assert!(!sql.contains("agreement_id = EXCLUDED"));
+    }
+
+    #[tokio::test]
+    async fn test_flatten_built_agreement() {
+        let llm_json = serde_json::json!({
+            "title": "Kalki 2898 AD",
+            "licensor": "Vyjayanthi Movies",
+            "licensee": "Netflix",
+            "territory": ["India"],
+            "rights": ["SVOD"],
+            "total_fee": 1000000000,
+            "currency": "INR",
+            "term_end": "2031-06-26",
+        });
+        let agreement = crate::json_builder::JSONBuilder::default()
+            .build_from_llm_json(&llm_json)
+            .await
+            .unwrap();
+        let flat = RightsAgreementFlat::from(&agreement);
+
+        assert_eq!(flat.content_title, "Kalki 2898 AD");
+        assert_eq!(flat.financial_deal_value, 1_000_000_000);
+        assert_eq!(flat.rights_territories, vec!["India"]);
+        assert_eq!(flat.rights_term_end_date, NaiveDate::from_ymd_opt(2031, 6, 26));
+        assert_eq!(flat.licensee_name.as_deref(), Some("Netflix"));
+    }
+}
@@ -0,0 +1,202 @@
+// src/grpc.rs - gRPC server sharing AppState with the REST API
+use axum::body::Bytes;
+use axum::http::StatusCode;
+use tonic::{Code, Request, Response, Status, Streaming};
+use tracing::{error, info};
+
+use crate::api_keys::{API_KEY_HEADER, QUOTA_REMAINING_HEADER};
+use crate::{admit_parse, fetch_decrypted, process_upload, AppState, ParseUpload};
+
+pub mod proto {
+    tonic::include_proto!("rights_parser");
+}
+
+use proto::rights_parser_service_server::{RightsParserService, RightsParserServiceServer};
+
+pub struct GrpcService {
+    state: AppState,
+}
+
+pub fn server(state: AppState) -> RightsParserServiceServer<GrpcService> {
+    RightsParserServiceServer::new(GrpcService { state })
+}
+
+/// gRPC equivalent of an HTTP error status
+fn grpc_code(status: StatusCode) -> Code {
+    match status {
+        StatusCode::BAD_REQUEST | StatusCode::UNSUPPORTED_MEDIA_TYPE => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::UNPROCESSABLE_ENTITY => Code::FailedPrecondition,
+        StatusCode::PAYMENT_REQUIRED | StatusCode::PAYLOAD_TOO_LARGE | StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        _ => Code::Internal,
+    }
+}
+
+/// Convert a REST error response into a gRPC status, keeping its message
+async fn status_from_response(response: axum::response::Response) -> Status {
+    let code = grpc_code(response.status());
+    let message = axum::body::to_bytes(response.into_body(), 64 * 1024)
+        .await
+        .ok()
+        .and_then(|body| serde_json::from_slice::<serde_json::Value>(&body).ok())
+        .and_then(|body| body.get("message").and_then(|m| m.as_str()).map(str::to_string))
+        .unwrap_or_else(|| "Request failed".to_string());
+    Status::new(code, message)
+}
+
+fn non_empty(value: String) -> Option<String> {
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+#[tonic::async_trait]
+impl RightsParserService for GrpcService {
+    async fn parse_pdf(
+        &self,
+        request: Request<Streaming<proto::Chunk>>,
+    ) -> Result<Response<proto::ParseResponse>, Status> {
+        let start_time = std::time::Instant::now();
+        info!("📄 Received gRPC PDF stream");
+
+        // Same key and quota checks as POST /api/parse, from `x-api-key` metadata
+        let raw_key = request
+            .metadata()
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string);
+        let admitted = match admit_parse(&self.state, raw_key.as_deref()).await {
+            Ok(admitted) => admitted,
+            Err(response) => return Err(status_from_response(response).await),
+        };
+
+        let mut stream = request.into_inner();
+        let mut upload = ParseUpload {
+            api_key_id: admitted.as_ref().map(|(record, _)| record.id),
+            ..ParseUpload::default()
+        };
+        let mut pdf_bytes = Vec::new();
+        let mut first = true;
+
+        while let Some(chunk) = stream.message().await? {
+            if pdf_bytes.len() + chunk.data.len() > self.state.max_upload_bytes {
+                return Err(Status::resource_exhausted("PDF exceeds MAX_UPLOAD_BYTES"));
+            }
+
+            if first {
+                if let Some(file_name) = non_empty(chunk.file_name) {
+                    upload.file_name = file_name;
+                }
+                upload.agreement_type = non_empty(chunk.agreement_type);
+                upload.webhook_url = non_empty(chunk.webhook_url);
+                first = false;
+            }
+            pdf_bytes.extend_from_slice(&chunk.data);
+        }
+
+        if pdf_bytes.is_empty() {
+            return Err(Status::invalid_argument("No PDF data received"));
+        }
+        upload.file_bytes = Some(Bytes::from(pdf_bytes));
+
+        let parsed = match process_upload(&self.state, upload, start_time).await {
+            Ok(axum::Json(parsed)) => parsed,
+            Err(response) => return Err(status_from_response(response).await),
+        };
+
+        let mut response = Response::new(proto::ParseResponse {
+            ipfs_cid: parsed.ipfs_cid,
+            ipfs_url: parsed.ipfs_url,
+            encryption_key: parsed.encryption_key,
+            ipfs_gateway_url: parsed.ipfs_gateway_url,
+            pdf_sha256: parsed.pdf_sha256,
+            json_sha256: parsed.json_sha256,
+            days_until_deadline: parsed.days_until_deadline,
+            bundle_cid: parsed.bundle_cid,
+            file_name: parsed.metadata.file_name,
+            file_size: parsed.metadata.file_size,
+            model_used: parsed.metadata.model_used,
+            processing_time_ms: parsed.metadata.processing_time_ms,
+            agreement_type: parsed.metadata.agreement_type,
+            warnings: parsed.warnings,
+        });
+        if let Some((_, remaining)) = admitted {
+            response.metadata_mut().insert(QUOTA_REMAINING_HEADER, remaining.into());
+        }
+        Ok(response)
+    }
+
+    async fn decrypt(
+        &self,
+        request: Request<proto::DecryptRequest>,
+    ) -> Result<Response<proto::Agreement>, Status> {
+        let request = request.into_inner();
+        info!("🔓 gRPC decrypt for CID: {}", request.cid);
+
+        let json = fetch_decrypted(&self.state, &request.cid, &request.key)
+            .await
+            .map_err(|(status, axum::Json(body))| {
+                error!("gRPC decrypt failed: {}", body.message);
+                Status::new(grpc_code(status), body.message)
+            })?;
+
+        Ok(Response::new(proto::Agreement {
+            ipfs_cid: request.cid,
+            json,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::EncryptionService;
+    use axum::response::IntoResponse;
+
+    #[tokio::test]
+    async fn test_status_from_response_keeps_message() {
+        let response = crate::error_response(StatusCode::UNAUTHORIZED, "Decryption failed - invalid key").into_response();
+        let status = status_from_response(response).await;
+
+        assert_eq!(status.code(), Code::Unauthenticated);
+        assert_eq!(status.message(), "Decryption failed - invalid key");
+        assert_eq!(grpc_code(StatusCode::IM_A_TEAPOT), Code::Internal);
+    }
+
+    #[tokio::test]
+    async fn test_parse_requires_api_key() {
+        let mut state = AppState::in_memory();
+        state.require_api_key = true;
+
+        let status = admit_parse(&state, None).await.map(|_| ()).unwrap_err();
+        assert_eq!(status_from_response(status).await.code(), Code::Unauthenticated);
+
+        state.require_api_key = false;
+        assert!(admit_parse(&state, None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_from_memory_store() {
+        let state = AppState::in_memory();
+        let (encrypted, key) = state.encryption_service.encrypt(r#"{"title":"Kalki 2898 AD"}"#).unwrap();
+        let cid = state.ipfs_client.upload(&encrypted).await.unwrap();
+        let service = GrpcService { state };
+
+        let agreement = service
+            .decrypt(Request::new(proto::DecryptRequest { cid: cid.clone(), key }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(agreement.ipfs_cid, cid);
+        assert_eq!(agreement.json, r#"{"title":"Kalki 2898 AD"}"#);
+
+        let status = service
+            .decrypt(Request::new(proto::DecryptRequest { cid, key: EncryptionService::generate_key() }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+}
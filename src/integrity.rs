@@ -0,0 +1,67 @@
+// src/integrity.rs - Content hashes binding IPFS agreements to their source PDF
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// Hex-encoded SHA-256 of raw bytes
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Hash of an agreement's canonical JSON, excluding `metadata.jsonSha256`
+/// itself so the hash can be stored inside the document it covers
+pub fn json_commitment(agreement: &Value) -> String {
+    let mut canonical = agreement.clone();
+    if let Some(metadata) = canonical.get_mut("metadata").and_then(Value::as_object_mut) {
+        metadata.remove("jsonSha256");
+    }
+
+    sha256_hex(serde_json::to_string(&canonical).unwrap_or_default().as_bytes())
+}
+
+/// Record the source PDF hash (if given) and a fresh JSON commitment in
+/// `metadata`. Returns the JSON commitment.
+pub fn stamp_content_hashes(agreement: &mut Value, pdf_sha256: Option<&str>) -> String {
+    if let Some(root) = agreement.as_object_mut() {
+        let metadata = root
+            .entry("metadata")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let (Some(metadata), Some(pdf_sha256)) = (metadata.as_object_mut(), pdf_sha256) {
+            metadata.insert("pdfSha256".to_string(), Value::String(pdf_sha256.to_string()));
+        }
+    }
+
+    let json_sha256 = json_commitment(agreement);
+    if let Some(metadata) = agreement.get_mut("metadata").and_then(Value::as_object_mut) {
+        metadata.insert("jsonSha256".to_string(), Value::String(json_sha256.clone()));
+    }
+
+    json_sha256
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_stamped_hash_verifies() {
+        let mut agreement = json!({ "title": "Test", "licensor": "Company A" });
+        let json_sha256 = stamp_content_hashes(&mut agreement, Some("abc123"));
+
+        assert_eq!(agreement["metadata"]["pdfSha256"], "abc123");
+        assert_eq!(agreement["metadata"]["jsonSha256"], json_sha256.as_str());
+
+        // Survives a round trip through the stored string form
+        let stored: Value = serde_json::from_str(&agreement.to_string()).unwrap();
+        assert_eq!(json_commitment(&stored), json_sha256);
+    }
+
+    #[test]
+    fn test_tampering_changes_hash() {
+        let mut agreement = json!({ "title": "Test", "total_fee": 100 });
+        let json_sha256 = stamp_content_hashes(&mut agreement, None);
+
+        agreement["total_fee"] = json!(999);
+        assert_ne!(json_commitment(&agreement), json_sha256);
+    }
+}
@@ -1,29 +1,623 @@
-// src/ipfs_client.rs - IPFS Client with Pinata Support
+// src/ipfs_client.rs - IPFS Client with Pinata and web3.storage Support
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use reqwest::{Client, multipart};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{info, error, warn};
 
+use crate::llm_service::RetryConfig;
+
+/// Gateways tried in order when fetching pinned content, unless `IPFS_GATEWAYS` replaces them
+pub const DEFAULT_GATEWAYS: &[&str] = &[
+    "https://gateway.pinata.cloud",
+    "https://ipfs.io",
+    "https://cloudflare-ipfs.com",
+];
+
+const GATEWAY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Fetched by `/api/gateways/benchmark` when no CID is given (the IPFS website logo)
+pub const BENCHMARK_CID: &str = "QmZULkCELmmk5XNfCgTnCyFgAVxBRBXyDHGGMVoLFLiXEN";
+
+/// Consecutive timeouts after which a gateway is skipped for a while
+const GATEWAY_TIMEOUT_THRESHOLD: u32 = 3;
+
+/// First blackout; each further one without a success in between doubles it
+const GATEWAY_BASE_BLACKOUT: Duration = Duration::from_secs(60);
+
+/// Doublings after which the blackout stops growing (about an hour)
+const GATEWAY_MAX_BLACKOUT_DOUBLINGS: u32 = 6;
+
+/// Gateway web3.storage serves uploads through
+const W3S_GATEWAY: &str = "https://w3s.link";
+
+const W3S_API_URL: &str = "https://api.web3.storage";
+
+/// Where uploads are pinned, chosen with `IPFS_BACKEND`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpfsBackend {
+    Local,
+    Pinata,
+    Web3Storage,
+}
+
+impl IpfsBackend {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().replace(['-', '_', '.'], "").as_str() {
+            "local" => Some(Self::Local),
+            "pinata" => Some(Self::Pinata),
+            "web3storage" | "w3s" => Some(Self::Web3Storage),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Pinata => "pinata",
+            Self::Web3Storage => "web3storage",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct IPFSClient {
     client: Client,
     ipfs_url: String,
     pinata_jwt: Option<String>,
     use_pinata: bool,
+    web3storage: Option<Web3StorageClient>,
+    gateway_urls: Vec<String>,
+    /// Index into `gateway_urls` of the fastest gateway seen this session, tried first
+    fastest_gateway: Arc<AtomicUsize>,
+    gateway_stats: Arc<DashMap<String, GatewayStats>>,
+}
+
+/// Recent timeout history of one gateway
+#[derive(Debug, Clone, Default)]
+pub struct GatewayStats {
+    pub consecutive_timeouts: u32,
+    pub last_success: Option<Instant>,
+    /// Skipped until then; tried again afterwards
+    pub blacklisted_until: Option<Instant>,
+    /// Blackouts since the last success, which sets the next blackout's length
+    pub blackouts: u32,
+    /// How long the last successful fetch took
+    pub last_latency: Option<Duration>,
+}
+
+impl GatewayStats {
+    pub fn is_blacklisted(&self, now: Instant) -> bool {
+        self.blacklisted_until.is_some_and(|until| now < until)
+    }
+
+    /// Count a timeout, returning the blackout length if the gateway is now blacklisted
+    pub fn record_timeout(&mut self, now: Instant) -> Option<Duration> {
+        self.consecutive_timeouts += 1;
+        if self.consecutive_timeouts < GATEWAY_TIMEOUT_THRESHOLD {
+            return None;
+        }
+
+        let blackout = GATEWAY_BASE_BLACKOUT * 2u32.pow(self.blackouts.min(GATEWAY_MAX_BLACKOUT_DOUBLINGS));
+        self.blackouts += 1;
+        self.blacklisted_until = Some(now + blackout);
+        Some(blackout)
+    }
+
+    /// Reset after a successful fetch, returning whether the gateway had been blacklisted
+    pub fn record_success(&mut self, now: Instant) -> bool {
+        let recovered = self.blackouts > 0;
+        *self = Self {
+            last_success: Some(now),
+            ..Self::default()
+        };
+        recovered
+    }
+}
+
+/// Backoff between `retry_upload` attempts, and the attempts the API makes
+const UPLOAD_RETRY: RetryConfig = RetryConfig {
+    max_attempts: 3,
+    initial_delay_ms: 500,
+    multiplier: 2.0,
+    max_delay_ms: 30_000,
+};
+
+/// Share of each upload backoff added or taken away at random
+const UPLOAD_RETRY_JITTER: f64 = 0.25;
+
+/// The IPFS node or pinning service answered with a non-success status
+#[derive(Debug)]
+pub struct IpfsStatusError {
+    pub operation: &'static str,
+    pub status: reqwest::StatusCode,
+    pub body: String,
+}
+
+impl std::fmt::Display for IpfsStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed: {} - {}", self.operation, self.status, self.body)
+    }
+}
+
+impl std::error::Error for IpfsStatusError {}
+
+impl IpfsStatusError {
+    async fn from_response(operation: &'static str, response: reqwest::Response) -> Self {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        Self { operation, status, body }
+    }
+}
+
+/// 5xx responses and network failures may succeed on a retry; 4xx responses
+/// and unreadable replies will fail the same way again
+pub fn is_transient_upload_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<IpfsStatusError>()
+            .is_some_and(|e| e.status.is_server_error())
+            || cause
+                .downcast_ref::<reqwest::Error>()
+                .is_some_and(|e| e.is_timeout() || e.is_connect() || e.is_request())
+    })
+}
+
+fn is_timeout(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_timeout())
+}
+
+/// Content-addressed storage behind the API, so the IPFS node can be replaced
+/// by `MemoryStore` where no network is available
+#[async_trait]
+pub trait IPFSClientTrait: Send + Sync {
+    async fn upload(&self, data: &[u8]) -> Result<String>;
+
+    /// Upload named files as one directory and return the directory CID
+    async fn upload_directory(&self, files: &[(&str, &[u8])]) -> Result<String>;
+
+    /// Upload several payloads, returning `(file_name, cid)` pairs in input
+    /// order. Stores without a batch API upload them one at a time.
+    async fn batch_upload(&self, items: Vec<(&str, Vec<u8>)>) -> Result<Vec<(String, String)>> {
+        let mut cids = Vec::with_capacity(items.len());
+        for (name, data) in items {
+            let cid = self.upload(&data).await.with_context(|| format!("Failed to upload {}", name))?;
+            cids.push((name.to_string(), cid));
+        }
+        Ok(cids)
+    }
+
+    async fn fetch(&self, cid: &str) -> Result<Vec<u8>>;
+
+    async fn unpin(&self, cid: &str) -> Result<()>;
+
+    async fn health_check(&self) -> Result<bool>;
+
+    /// One page of pinned content, starting after the cursor of the previous page
+    async fn list_pins_paginated(&self, after: Option<String>, limit: usize) -> Result<PinPage>;
+
+    async fn check_exists(&self, cid: &str) -> Result<bool> {
+        Ok(self.fetch(cid).await.is_ok())
+    }
+
+    /// Gateways fetches read through, in configured order. Stores that do not
+    /// read through gateways have none.
+    fn gateway_urls(&self) -> &[String] {
+        &[]
+    }
+
+    /// Fetch `cid` through every gateway at once and time each. Stores that
+    /// do not read through gateways report none.
+    async fn benchmark_gateways(&self, cid: &str) -> Result<GatewayBenchmarkReport> {
+        Ok(GatewayBenchmarkReport { cid: cid.to_string(), results: Vec::new(), fastest: None })
+    }
+}
+
+/// One gateway's result in a benchmark run
+#[derive(Debug, Clone, Serialize)]
+pub struct GatewayBenchmark {
+    pub gateway: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GatewayBenchmarkReport {
+    pub cid: String,
+    /// In configured order
+    pub results: Vec<GatewayBenchmark>,
+    /// The gateway fetches now try first
+    pub fastest: Option<String>,
+}
+
+/// Largest page `list_pins_paginated` returns; Pinata rejects bigger pages
+pub const MAX_PIN_PAGE: usize = 1000;
+
+/// A pinned CID
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PinInfo {
+    pub cid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pin_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PinPage {
+    pub pins: Vec<PinInfo>,
+    /// Pass as `after` to get the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Page through pins ordered by CID, with the last CID of a page as the cursor
+pub fn paginate_by_cid(mut pins: Vec<PinInfo>, after: Option<&str>, limit: usize) -> PinPage {
+    pins.sort_by(|a, b| a.cid.cmp(&b.cid));
+    let start = after.map_or(0, |after| pins.partition_point(|p| p.cid.as_str() <= after));
+    let mut page: Vec<PinInfo> = pins.drain(start..).collect();
+
+    let next_cursor = (page.len() > limit).then(|| page[limit - 1].cid.clone());
+    page.truncate(limit);
+    PinPage { pins: page, next_cursor }
+}
+
+/// Placeholder for the CID in CDN gateway templates
+const CID_PLACEHOLDER: &str = "{cid}";
+
+/// Faster public gateways to link uploads through, as URL templates such as
+/// `https://{cid}.ipfs.dweb.link/` or `https://cloudflare-ipfs.com/ipfs/{cid}`
+#[derive(Debug, Clone, Default)]
+pub struct CdnGateways {
+    templates: Vec<String>,
+}
+
+impl CdnGateways {
+    /// Templates without a `{cid}` placeholder are taken as a path prefix
+    /// the CID is appended to. Blank entries and repeats are dropped.
+    pub fn new<I, S>(templates: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut normalized: Vec<String> = Vec::new();
+        for template in templates {
+            let template = template.as_ref().trim();
+            if template.is_empty() {
+                continue;
+            }
+            let template = if template.contains(CID_PLACEHOLDER) {
+                template.to_string()
+            } else {
+                format!("{}/{}", template.trim_end_matches('/'), CID_PLACEHOLDER)
+            };
+            if !normalized.contains(&template) {
+                normalized.push(template);
+            }
+        }
+        Self { templates: normalized }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    pub fn templates(&self) -> &[String] {
+        &self.templates
+    }
+
+    /// `cid` through every configured gateway, preferred first
+    pub fn urls(&self, cid: &str) -> Vec<String> {
+        self.templates.iter().map(|t| t.replace(CID_PLACEHOLDER, cid)).collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct PinLsResponse {
+    #[serde(rename = "Keys", default)]
+    keys: std::collections::HashMap<String, PinLsEntry>,
+}
+
+#[derive(Deserialize)]
+struct PinLsEntry {
+    #[serde(rename = "Type")]
+    pin_type: String,
+}
+
+#[derive(Deserialize)]
+struct PinataPinList {
+    count: usize,
+    #[serde(default)]
+    rows: Vec<PinataPin>,
+}
+
+#[derive(Deserialize)]
+struct PinataPin {
+    ipfs_pin_hash: String,
+    size: Option<u64>,
+    date_pinned: Option<String>,
+    metadata: Option<PinataPinMetadata>,
+}
+
+#[derive(Deserialize)]
+struct PinataPinMetadata {
+    name: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct IPFSAddResponse {
     #[serde(rename = "Hash")]
     hash: String,
+    #[serde(rename = "Name", default)]
+    name: String,
 }
 
+/// Folder name used when uploading a directory, since Pinata requires one
+const BUNDLE_DIR: &str = "bundle";
+
+/// Concurrent uploads when a batch has to be fanned out
+const BATCH_CONCURRENCY: usize = 5;
+
 #[derive(Deserialize)]
 struct PinataResponse {
     #[serde(rename = "IpfsHash")]
     ipfs_hash: String,
 }
 
+#[derive(Deserialize)]
+struct W3SUploadResponse {
+    cid: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct W3SUpload {
+    cid: String,
+    created: Option<String>,
+    dag_size: Option<u64>,
+    name: Option<String>,
+}
+
+/// Client for the web3.storage HTTP API, authenticated with a `W3S_TOKEN` API token
+#[derive(Clone)]
+pub struct Web3StorageClient {
+    client: Client,
+    token: String,
+    api_url: String,
+}
+
+impl Web3StorageClient {
+    pub fn new(token: String) -> Self {
+        Self {
+            client: Client::new(),
+            token,
+            api_url: W3S_API_URL.to_string(),
+        }
+    }
+
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    pub async fn upload(&self, data: &[u8]) -> Result<String> {
+        info!("Uploading {} bytes to web3.storage", data.len());
+
+        let response = self.client
+            .post(format!("{}/upload", self.api_url))
+            .bearer_auth(&self.token)
+            .header("X-Name", "encrypted.json")
+            .body(data.to_vec())
+            .send()
+            .await
+            .context("Failed to upload to web3.storage")?;
+
+        let result: W3SUploadResponse = Self::json(response, "web3.storage upload").await?;
+        info!("✅ Uploaded to web3.storage: {}", result.cid);
+        Ok(result.cid)
+    }
+
+    /// web3.storage wraps the parts of a multipart upload in a directory
+    pub async fn upload_directory(&self, files: &[(&str, &[u8])]) -> Result<String> {
+        if files.is_empty() {
+            anyhow::bail!("Cannot upload an empty directory");
+        }
+
+        info!("Uploading directory of {} files to web3.storage", files.len());
+
+        let form = files.iter().fold(multipart::Form::new(), |form, (name, data)| {
+            form.part("file", multipart::Part::bytes(data.to_vec()).file_name(name.to_string()))
+        });
+
+        let response = self.client
+            .post(format!("{}/upload", self.api_url))
+            .bearer_auth(&self.token)
+            .multipart(form)
+            .send()
+            .await
+            .context("Failed to upload directory to web3.storage")?;
+
+        let result: W3SUploadResponse = Self::json(response, "web3.storage directory upload").await?;
+        info!("✅ Uploaded directory to web3.storage: {}", result.cid);
+        Ok(result.cid)
+    }
+
+    /// web3.storage has no batch upload, so uploads are fanned out a few at a time
+    pub async fn batch_upload(&self, items: Vec<(&str, Vec<u8>)>) -> Result<Vec<(String, String)>> {
+        info!("Batch uploading {} files to web3.storage", items.len());
+
+        let cids: Vec<(String, String)> = stream::iter(items)
+            .map(|(name, data)| async move {
+                let cid = self.upload(&data).await
+                    .with_context(|| format!("Failed to upload {}", name))?;
+                Ok::<_, anyhow::Error>((name.to_string(), cid))
+            })
+            .buffered(BATCH_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        info!("✅ Batch uploaded {} files to web3.storage", cids.len());
+        Ok(cids)
+    }
+
+    pub async fn fetch(&self, cid: &str) -> Result<Vec<u8>> {
+        info!("Fetching {} from web3.storage gateway", cid);
+
+        let response = self.client
+            .get(format!("{}/ipfs/{}", W3S_GATEWAY, cid))
+            .timeout(GATEWAY_TIMEOUT)
+            .send()
+            .await
+            .context("Failed to fetch from web3.storage")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("web3.storage fetch failed: {}", response.status());
+        }
+
+        let data = response.bytes().await.context("Failed to read web3.storage response")?.to_vec();
+        info!("✅ Fetched {} bytes from web3.storage", data.len());
+        Ok(data)
+    }
+
+    /// Remove `cid` from the account's uploads
+    pub async fn unpin(&self, cid: &str) -> Result<()> {
+        info!("Removing {} from web3.storage", cid);
+
+        let response = self.client
+            .delete(format!("{}/user/uploads/{}", self.api_url, cid))
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("Failed to remove from web3.storage")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("web3.storage unpin failed: {} - {}", status, error_text);
+        }
+
+        info!("✅ Removed {} from web3.storage", cid);
+        Ok(())
+    }
+
+    /// Uploads newest first; the creation time of the last one is the cursor
+    pub async fn list_pins_paginated(&self, after: Option<String>, limit: usize) -> Result<PinPage> {
+        let limit = limit.clamp(1, MAX_PIN_PAGE);
+        let mut query = vec![("size", limit.to_string())];
+        if let Some(before) = after {
+            query.push(("before", before));
+        }
+
+        let response = self.client
+            .get(format!("{}/user/uploads", self.api_url))
+            .bearer_auth(&self.token)
+            .query(&query)
+            .send()
+            .await
+            .context("Failed to list uploads on web3.storage")?;
+
+        let uploads: Vec<W3SUpload> = Self::json(response, "web3.storage upload listing").await?;
+        let next_cursor = if uploads.len() < limit {
+            None
+        } else {
+            uploads.last().and_then(|u| u.created.clone())
+        };
+        let pins = uploads
+            .into_iter()
+            .map(|upload| PinInfo {
+                cid: upload.cid,
+                pin_type: None,
+                size: upload.dag_size,
+                pinned_at: upload.created,
+                name: upload.name,
+            })
+            .collect();
+        Ok(PinPage { pins, next_cursor })
+    }
+
+    /// Ask the status endpoint rather than downloading the content
+    pub async fn check_exists(&self, cid: &str) -> Result<bool> {
+        let response = self.client
+            .get(format!("{}/status/{}", self.api_url, cid))
+            .send()
+            .await
+            .context("Failed to check status on web3.storage")?;
+
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            status => anyhow::bail!("web3.storage status check failed: {}", status),
+        }
+    }
+
+    /// Whether the API is reachable and accepts the token
+    pub async fn health_check(&self) -> Result<bool> {
+        let response = self.client
+            .get(format!("{}/user/uploads", self.api_url))
+            .bearer_auth(&self.token)
+            .query(&[("size", "1")])
+            .send()
+            .await;
+
+        Ok(response.is_ok_and(|r| r.status().is_success()))
+    }
+
+    async fn json<T: serde::de::DeserializeOwned>(response: reqwest::Response, what: &'static str) -> Result<T> {
+        if !response.status().is_success() {
+            return Err(IpfsStatusError::from_response(what, response).await.into());
+        }
+        response.json().await.with_context(|| format!("Failed to parse {} response", what))
+    }
+}
+
+#[async_trait]
+impl IPFSClientTrait for Web3StorageClient {
+    async fn upload(&self, data: &[u8]) -> Result<String> {
+        Web3StorageClient::upload(self, data).await
+    }
+
+    async fn upload_directory(&self, files: &[(&str, &[u8])]) -> Result<String> {
+        Web3StorageClient::upload_directory(self, files).await
+    }
+
+    async fn batch_upload(&self, items: Vec<(&str, Vec<u8>)>) -> Result<Vec<(String, String)>> {
+        Web3StorageClient::batch_upload(self, items).await
+    }
+
+    async fn fetch(&self, cid: &str) -> Result<Vec<u8>> {
+        Web3StorageClient::fetch(self, cid).await
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        Web3StorageClient::unpin(self, cid).await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Web3StorageClient::health_check(self).await
+    }
+
+    async fn list_pins_paginated(&self, after: Option<String>, limit: usize) -> Result<PinPage> {
+        Web3StorageClient::list_pins_paginated(self, after, limit).await
+    }
+
+    async fn check_exists(&self, cid: &str) -> Result<bool> {
+        Web3StorageClient::check_exists(self, cid).await
+    }
+}
+
 impl IPFSClient {
     pub fn new(ipfs_url: String, pinata_jwt: Option<String>) -> Self {
         let use_pinata = pinata_jwt.is_some();
@@ -39,29 +633,276 @@ impl IPFSClient {
             ipfs_url,
             pinata_jwt,
             use_pinata,
+            web3storage: None,
+            gateway_urls: DEFAULT_GATEWAYS.iter().map(|g| g.to_string()).collect(),
+            fastest_gateway: Arc::default(),
+            gateway_stats: Arc::default(),
+        }
+    }
+
+    /// Replace the fetch gateways, in order of preference. Blank entries and
+    /// repeats are dropped; with none left the defaults are kept.
+    pub fn with_gateways<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut gateways: Vec<String> = Vec::new();
+        for url in urls {
+            let url = url.as_ref().trim().trim_end_matches('/');
+            if !url.is_empty() && !gateways.iter().any(|g| g == url) {
+                gateways.push(url.to_string());
+            }
+        }
+        if !gateways.is_empty() {
+            self.gateway_urls = gateways;
+            self.fastest_gateway = Arc::default();
+        }
+        self
+    }
+
+    pub fn gateway_urls(&self) -> &[String] {
+        &self.gateway_urls
+    }
+
+    /// Configured gateways with the fastest seen so far moved to the front
+    fn gateway_order(&self) -> Vec<&str> {
+        let fastest = self.fastest_gateway.load(Ordering::Relaxed);
+        let mut order: Vec<&str> = Vec::with_capacity(self.gateway_urls.len());
+        order.extend(self.gateway_urls.get(fastest).map(String::as_str));
+        order.extend(
+            self.gateway_urls
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != fastest)
+                .map(|(_, g)| g.as_str()),
+        );
+        order
+    }
+
+    fn last_latency(&self, gateway: &str) -> Option<Duration> {
+        self.gateway_stats.get(gateway).and_then(|stats| stats.last_latency)
+    }
+
+    /// Record a successful fetch, returning whether the gateway had been blacklisted
+    fn record_gateway_success(&self, gateway: &str, latency: Duration) -> bool {
+        let mut stats = self.gateway_stats.entry(gateway.to_string()).or_default();
+        let recovered = stats.record_success(Instant::now());
+        stats.last_latency = Some(latency);
+        recovered
+    }
+
+    /// Make `gateway` the first one tried if it beat the current fastest
+    fn note_latency(&self, gateway: &str, latency: Duration) {
+        let Some(index) = self.gateway_urls.iter().position(|g| g == gateway) else {
+            return;
+        };
+        let fastest = self.fastest_gateway.load(Ordering::Relaxed);
+        if index == fastest {
+            return;
+        }
+        let beaten = self
+            .gateway_urls
+            .get(fastest)
+            .and_then(|g| self.last_latency(g))
+            .is_none_or(|current| latency < current);
+        if beaten {
+            self.fastest_gateway.store(index, Ordering::Relaxed);
+        }
+    }
+
+    /// Time a fetch of `cid` from every configured gateway in parallel, and
+    /// make the quickest the first one tried from now on
+    pub async fn benchmark_gateways(&self, cid: &str) -> GatewayBenchmarkReport {
+        info!("⏱️  Benchmarking {} IPFS gateways with {}", self.gateway_urls.len(), cid);
+
+        let results: Vec<(GatewayBenchmark, Option<Duration>)> =
+            futures::future::join_all(self.gateway_urls.iter().map(|gateway| async move {
+                let started = Instant::now();
+                let fetched = self.fetch_from_gateway(&format!("{}/ipfs/{}", gateway, cid)).await;
+                let latency = started.elapsed();
+                match fetched {
+                    Ok(data) => {
+                        self.record_gateway_success(gateway, latency);
+                        let result = GatewayBenchmark {
+                            gateway: gateway.clone(),
+                            latency_ms: Some(latency.as_millis() as u64),
+                            bytes: Some(data.len()),
+                            error: None,
+                        };
+                        (result, Some(latency))
+                    }
+                    Err(e) => {
+                        if is_timeout(&e) {
+                            self.gateway_stats.entry(gateway.clone()).or_default().record_timeout(Instant::now());
+                        }
+                        let result = GatewayBenchmark {
+                            gateway: gateway.clone(),
+                            latency_ms: None,
+                            bytes: None,
+                            error: Some(format!("{:#}", e)),
+                        };
+                        (result, None)
+                    }
+                }
+            }))
+            .await;
+
+        let fastest = results
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, latency))| latency.map(|l| (i, l)))
+            .min_by_key(|(_, latency)| *latency)
+            .map(|(i, _)| i);
+        if let Some(index) = fastest {
+            self.fastest_gateway.store(index, Ordering::Relaxed);
+        }
+
+        GatewayBenchmarkReport {
+            cid: cid.to_string(),
+            results: results.into_iter().map(|(result, _)| result).collect(),
+            fastest: fastest.map(|i| self.gateway_urls[i].clone()),
+        }
+    }
+
+    /// Client for `backend`, failing when its credentials are missing. Without
+    /// an explicit backend, Pinata is used when a JWT is set and the local node otherwise.
+    pub fn for_backend(
+        backend: Option<IpfsBackend>,
+        ipfs_url: String,
+        pinata_jwt: Option<String>,
+        w3s_token: Option<String>,
+    ) -> Result<Self> {
+        match backend {
+            None => Ok(Self::new(ipfs_url, pinata_jwt)),
+            Some(IpfsBackend::Local) => Ok(Self::new(ipfs_url, None)),
+            Some(IpfsBackend::Pinata) => {
+                let jwt = pinata_jwt.context("IPFS_BACKEND=pinata requires PINATA_JWT")?;
+                Ok(Self::new(ipfs_url, Some(jwt)))
+            }
+            Some(IpfsBackend::Web3Storage) => {
+                let token = w3s_token.context("IPFS_BACKEND=web3storage requires W3S_TOKEN")?;
+                info!("Initializing IPFS client with web3.storage");
+                Ok(Self {
+                    web3storage: Some(Web3StorageClient::new(token)),
+                    ..Self::new(ipfs_url, None)
+                })
+            }
+        }
+    }
+
+    pub fn backend(&self) -> IpfsBackend {
+        if self.web3storage.is_some() {
+            IpfsBackend::Web3Storage
+        } else if self.use_pinata {
+            IpfsBackend::Pinata
+        } else {
+            IpfsBackend::Local
         }
     }
 
     /// Upload data to IPFS
     pub async fn upload(&self, data: &[u8]) -> Result<String> {
-        if self.use_pinata {
+        if let Some(w3s) = &self.web3storage {
+            w3s.upload(data).await
+        } else if self.use_pinata {
             self.upload_to_pinata(data).await
         } else {
             self.upload_to_local(data).await
         }
     }
 
+    /// `upload`, retrying 5xx responses and network failures with exponential
+    /// backoff and jitter. Once `max_attempts` are used up the last error is returned.
+    pub async fn retry_upload(&self, data: &[u8], max_attempts: u32) -> Result<String> {
+        let config = RetryConfig { max_attempts: max_attempts.max(1), ..UPLOAD_RETRY };
+        let mut attempt = 1;
+        loop {
+            match self.upload(data).await {
+                Ok(cid) => return Ok(cid),
+                Err(e) if attempt < config.max_attempts && is_transient_upload_error(&e) => {
+                    let delay = config.delay_with_jitter(attempt, UPLOAD_RETRY_JITTER);
+                    attempt += 1;
+                    warn!("🔁 {:#}; upload attempt {}/{} in {:?}", e, attempt, config.max_attempts, delay);
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Upload named files as a single UnixFS directory and return the directory CID
+    pub async fn upload_directory(&self, files: &[(&str, &[u8])]) -> Result<String> {
+        if files.is_empty() {
+            anyhow::bail!("Cannot upload an empty directory");
+        }
+
+        if let Some(w3s) = &self.web3storage {
+            w3s.upload_directory(files).await
+        } else if self.use_pinata {
+            self.upload_directory_to_pinata(files).await
+        } else {
+            self.upload_directory_to_local(files).await
+        }
+    }
+
+    /// Upload several payloads at once, returning `(file_name, cid)` pairs in input order.
+    /// The local node takes them in one request; Pinata and web3.storage have no
+    /// batch upload, so uploads are fanned out a few at a time.
+    pub async fn batch_upload(&self, items: Vec<(&str, Vec<u8>)>) -> Result<Vec<(String, String)>> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if let Some(w3s) = &self.web3storage {
+            w3s.batch_upload(items).await
+        } else if self.use_pinata {
+            self.batch_upload_to_pinata(items).await
+        } else {
+            self.batch_upload_to_local(items).await
+        }
+    }
+
     /// Fetch data from IPFS
     pub async fn fetch(&self, cid: &str) -> Result<Vec<u8>> {
-        if self.use_pinata {
+        if let Some(w3s) = &self.web3storage {
+            w3s.fetch(cid).await
+        } else if self.use_pinata {
             self.fetch_from_pinata(cid).await
         } else {
             self.fetch_from_local(cid).await
         }
     }
 
+    /// Unpin content so it can be garbage collected
+    pub async fn unpin(&self, cid: &str) -> Result<()> {
+        if let Some(w3s) = &self.web3storage {
+            w3s.unpin(cid).await
+        } else if self.use_pinata {
+            self.unpin_from_pinata(cid).await
+        } else {
+            self.unpin_from_local(cid).await
+        }
+    }
+
+    /// One page of pins. Pinata pages by offset, which becomes the cursor. The
+    /// local node's `pin/ls` has no paging, so its pins are fetched and paged by CID.
+    pub async fn list_pins_paginated(&self, after: Option<String>, limit: usize) -> Result<PinPage> {
+        let limit = limit.clamp(1, MAX_PIN_PAGE);
+        if let Some(w3s) = &self.web3storage {
+            w3s.list_pins_paginated(after, limit).await
+        } else if self.use_pinata {
+            self.list_pins_from_pinata(after, limit).await
+        } else {
+            self.list_pins_from_local(after, limit).await
+        }
+    }
+
     /// Check if content exists on IPFS
     pub async fn check_exists(&self, cid: &str) -> Result<bool> {
+        if let Some(w3s) = &self.web3storage {
+            return w3s.check_exists(cid).await;
+        }
         match self.fetch(cid).await {
             Ok(_) => Ok(true),
             Err(_) => Ok(false),
@@ -70,7 +911,9 @@ impl IPFSClient {
 
     /// Health check
     pub async fn health_check(&self) -> Result<bool> {
-        if self.use_pinata {
+        if let Some(w3s) = &self.web3storage {
+            w3s.health_check().await
+        } else if self.use_pinata {
             self.check_pinata_health().await
         } else {
             self.check_local_health().await
@@ -94,9 +937,7 @@ impl IPFSClient {
             .context("Failed to upload to IPFS")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!("IPFS upload failed: {} - {}", status, error_text);
+            return Err(IpfsStatusError::from_response("IPFS upload", response).await.into());
         }
 
         let result: IPFSAddResponse = response.json()
@@ -107,6 +948,67 @@ impl IPFSClient {
         Ok(result.hash)
     }
 
+    async fn upload_directory_to_local(&self, files: &[(&str, &[u8])]) -> Result<String> {
+        info!("Uploading directory of {} files to local IPFS node", files.len());
+
+        let form = files.iter().fold(multipart::Form::new(), |form, (name, data)| {
+            form.part("file", multipart::Part::bytes(data.to_vec()).file_name(name.to_string()))
+        });
+
+        let response = self.client
+            .post(format!("{}/api/v0/add?wrap-with-directory=true", self.ipfs_url))
+            .multipart(form)
+            .send()
+            .await
+            .context("Failed to upload directory to IPFS")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("IPFS directory upload failed: {} - {}", status, error_text);
+        }
+
+        let body = response.text().await.context("Failed to read IPFS response")?;
+        let entries = parse_add_entries(&body)?;
+
+        let directory = entries
+            .iter()
+            .find(|e| e.name.is_empty())
+            .or_else(|| entries.last())
+            .context("IPFS returned no directory entry")?;
+
+        info!("✅ Uploaded directory to IPFS: {}", directory.hash);
+        Ok(directory.hash.clone())
+    }
+
+    async fn batch_upload_to_local(&self, items: Vec<(&str, Vec<u8>)>) -> Result<Vec<(String, String)>> {
+        info!("Batch uploading {} files to local IPFS node", items.len());
+
+        let names: Vec<String> = items.iter().map(|(name, _)| name.to_string()).collect();
+        let form = items.into_iter().fold(multipart::Form::new(), |form, (name, data)| {
+            form.part("file", multipart::Part::bytes(data).file_name(name.to_string()))
+        });
+
+        let response = self.client
+            .post(format!("{}/api/v0/add?wrap-with-directory=true", self.ipfs_url))
+            .multipart(form)
+            .send()
+            .await
+            .context("Failed to batch upload to IPFS")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("IPFS batch upload failed: {} - {}", status, error_text);
+        }
+
+        let body = response.text().await.context("Failed to read IPFS response")?;
+        let cids = cids_by_name(&parse_add_entries(&body)?, &names)?;
+
+        info!("✅ Batch uploaded {} files to IPFS", cids.len());
+        Ok(cids)
+    }
+
     async fn fetch_from_local(&self, cid: &str) -> Result<Vec<u8>> {
         info!("Fetching {} from local IPFS node", cid);
 
@@ -129,6 +1031,53 @@ impl IPFSClient {
         Ok(data)
     }
 
+    async fn unpin_from_local(&self, cid: &str) -> Result<()> {
+        info!("Unpinning {} from local IPFS node", cid);
+
+        let response = self.client
+            .post(format!("{}/api/v0/pin/rm?arg={}", self.ipfs_url, cid))
+            .send()
+            .await
+            .context("Failed to unpin from IPFS")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("IPFS unpin failed: {} - {}", status, error_text);
+        }
+
+        info!("✅ Unpinned {}", cid);
+        Ok(())
+    }
+
+    async fn list_pins_from_local(&self, after: Option<String>, limit: usize) -> Result<PinPage> {
+        let response = self.client
+            .post(format!("{}/api/v0/pin/ls?type=recursive", self.ipfs_url))
+            .send()
+            .await
+            .context("Failed to list pins")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("IPFS pin listing failed: {} - {}", status, error_text);
+        }
+
+        let listing: PinLsResponse = response.json().await.context("Unexpected pin/ls response")?;
+        let pins = listing
+            .keys
+            .into_iter()
+            .map(|(cid, entry)| PinInfo {
+                cid,
+                pin_type: Some(entry.pin_type),
+                size: None,
+                pinned_at: None,
+                name: None,
+            })
+            .collect();
+        Ok(paginate_by_cid(pins, after.as_deref(), limit))
+    }
+
     async fn check_local_health(&self) -> Result<bool> {
         let response = self.client
             .post(format!("{}/api/v0/version", self.ipfs_url))
@@ -158,50 +1107,193 @@ impl IPFSClient {
             .await
             .context("Failed to upload to Pinata")?;
 
+        if !response.status().is_success() {
+            return Err(IpfsStatusError::from_response("Pinata upload", response).await.into());
+        }
+
+        let result: PinataResponse = response.json()
+            .await
+            .context("Failed to parse Pinata response")?;
+
+        info!("✅ Uploaded to Pinata: {}", result.ipfs_hash);
+        Ok(result.ipfs_hash)
+    }
+
+    async fn upload_directory_to_pinata(&self, files: &[(&str, &[u8])]) -> Result<String> {
+        let jwt = self.pinata_jwt.as_ref()
+            .context("Pinata JWT not configured")?;
+
+        info!("Uploading directory of {} files to Pinata", files.len());
+
+        // Pinata builds a directory from the shared path prefix of each file name
+        let form = files.iter().fold(multipart::Form::new(), |form, (name, data)| {
+            form.part("file", multipart::Part::bytes(data.to_vec())
+                .file_name(format!("{}/{}", BUNDLE_DIR, name)))
+        });
+
+        let response = self.client
+            .post("https://api.pinata.cloud/pinning/pinFileToIPFS")
+            .header("Authorization", format!("Bearer {}", jwt))
+            .multipart(form)
+            .send()
+            .await
+            .context("Failed to upload directory to Pinata")?;
+
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Pinata upload failed: {} - {}", status, error_text);
+            anyhow::bail!("Pinata directory upload failed: {} - {}", status, error_text);
         }
 
         let result: PinataResponse = response.json()
             .await
             .context("Failed to parse Pinata response")?;
 
-        info!("✅ Uploaded to Pinata: {}", result.ipfs_hash);
+        info!("✅ Uploaded directory to Pinata: {}", result.ipfs_hash);
         Ok(result.ipfs_hash)
     }
 
+    async fn unpin_from_pinata(&self, cid: &str) -> Result<()> {
+        let jwt = self.pinata_jwt.as_ref()
+            .context("Pinata JWT not configured")?;
+
+        info!("Unpinning {} from Pinata", cid);
+
+        let response = self.client
+            .delete(format!("https://api.pinata.cloud/pinning/unpin/{}", cid))
+            .header("Authorization", format!("Bearer {}", jwt))
+            .send()
+            .await
+            .context("Failed to unpin from Pinata")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Pinata unpin failed: {} - {}", status, error_text);
+        }
+
+        info!("✅ Unpinned {} from Pinata", cid);
+        Ok(())
+    }
+
+    async fn list_pins_from_pinata(&self, after: Option<String>, limit: usize) -> Result<PinPage> {
+        let jwt = self.pinata_jwt.as_ref()
+            .context("Pinata JWT not configured")?;
+        let offset: usize = match after {
+            Some(cursor) => cursor.parse().context("Invalid pin cursor")?,
+            None => 0,
+        };
+
+        let response = self.client
+            .get("https://api.pinata.cloud/data/pinList")
+            .header("Authorization", format!("Bearer {}", jwt))
+            .query(&[("status", "pinned".to_string()), ("pageLimit", limit.to_string()), ("pageOffset", offset.to_string())])
+            .send()
+            .await
+            .context("Failed to list pins on Pinata")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Pinata pin listing failed: {} - {}", status, error_text);
+        }
+
+        let listing: PinataPinList = response.json().await.context("Unexpected Pinata pinList response")?;
+        let next_offset = offset + listing.rows.len();
+        let next_cursor = (!listing.rows.is_empty() && next_offset < listing.count).then(|| next_offset.to_string());
+        let pins = listing
+            .rows
+            .into_iter()
+            .map(|pin| PinInfo {
+                cid: pin.ipfs_pin_hash,
+                pin_type: None,
+                size: pin.size,
+                pinned_at: pin.date_pinned,
+                name: pin.metadata.and_then(|m| m.name),
+            })
+            .collect();
+        Ok(PinPage { pins, next_cursor })
+    }
+
+    async fn batch_upload_to_pinata(&self, items: Vec<(&str, Vec<u8>)>) -> Result<Vec<(String, String)>> {
+        info!("Batch uploading {} files to Pinata", items.len());
+
+        let cids: Vec<(String, String)> = stream::iter(items)
+            .map(|(name, data)| async move {
+                let cid = self.upload_to_pinata(&data).await
+                    .with_context(|| format!("Failed to upload {}", name))?;
+                Ok::<_, anyhow::Error>((name.to_string(), cid))
+            })
+            .buffered(BATCH_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        info!("✅ Batch uploaded {} files to Pinata", cids.len());
+        Ok(cids)
+    }
+
     async fn fetch_from_pinata(&self, cid: &str) -> Result<Vec<u8>> {
         info!("Fetching {} from Pinata gateway", cid);
 
-        // Try Pinata gateway first, fallback to public gateway
-        let urls = vec![
-            format!("https://gateway.pinata.cloud/ipfs/{}", cid),
-            format!("https://ipfs.io/ipfs/{}", cid),
-            format!("https://cloudflare-ipfs.com/ipfs/{}", cid),
-        ];
+        // Fastest gateway first, then the rest in configured order
+        let gateways = self.gateway_order();
+        self.retry_on_gateway_timeout(cid, &gateways).await
+    }
 
-        for url in urls {
+    /// Fetch `cid` from the first gateway that answers. Gateways that keep
+    /// timing out are skipped for 1, 2, 4... minutes and then probed again.
+    pub async fn retry_on_gateway_timeout(&self, cid: &str, gateways: &[&str]) -> Result<Vec<u8>> {
+        let mut skipped = 0;
+        for gateway in gateways {
+            let blacklisted = self
+                .gateway_stats
+                .get(*gateway)
+                .is_some_and(|stats| stats.is_blacklisted(Instant::now()));
+            if blacklisted {
+                skipped += 1;
+                continue;
+            }
+
+            let url = format!("{}/ipfs/{}", gateway, cid);
+            let started = Instant::now();
             match self.fetch_from_gateway(&url).await {
                 Ok(data) => {
-                    info!("✅ Fetched {} bytes from gateway", data.len());
+                    let latency = started.elapsed();
+                    info!("✅ Fetched {} bytes from gateway in {}ms", data.len(), latency.as_millis());
+                    let recovered = self.record_gateway_success(gateway, latency);
+                    self.note_latency(gateway, latency);
+                    if recovered {
+                        warn!(gateway = %gateway, "IPFS gateway recovered");
+                    }
                     return Ok(data);
                 }
-                Err(e) => {
-                    warn!("Failed to fetch from {}: {}", url, e);
-                    continue;
+                Err(e) if is_timeout(&e) => {
+                    let mut stats = self.gateway_stats.entry(gateway.to_string()).or_default();
+                    let blackout = stats.record_timeout(Instant::now());
+                    warn!("Timed out fetching from {}", url);
+                    if let Some(blackout) = blackout {
+                        warn!(
+                            gateway = %gateway,
+                            consecutive_timeouts = stats.consecutive_timeouts,
+                            blackout_secs = blackout.as_secs(),
+                            "IPFS gateway blacklisted"
+                        );
+                    }
                 }
+                Err(e) => warn!("Failed to fetch from {}: {}", url, e),
             }
         }
 
+        if skipped == gateways.len() {
+            anyhow::bail!("All IPFS gateways are temporarily blacklisted after repeated timeouts");
+        }
         anyhow::bail!("Failed to fetch from all IPFS gateways")
     }
 
     async fn fetch_from_gateway(&self, url: &str) -> Result<Vec<u8>> {
         let response = self.client
             .get(url)
-            .timeout(std::time::Duration::from_secs(30))
+            .timeout(GATEWAY_TIMEOUT)
             .send()
             .await?;
 
@@ -228,10 +1320,96 @@ impl IPFSClient {
     }
 }
 
+#[async_trait]
+impl IPFSClientTrait for IPFSClient {
+    async fn upload(&self, data: &[u8]) -> Result<String> {
+        IPFSClient::retry_upload(self, data, UPLOAD_RETRY.max_attempts).await
+    }
+
+    async fn upload_directory(&self, files: &[(&str, &[u8])]) -> Result<String> {
+        IPFSClient::upload_directory(self, files).await
+    }
+
+    async fn batch_upload(&self, items: Vec<(&str, Vec<u8>)>) -> Result<Vec<(String, String)>> {
+        IPFSClient::batch_upload(self, items).await
+    }
+
+    async fn fetch(&self, cid: &str) -> Result<Vec<u8>> {
+        IPFSClient::fetch(self, cid).await
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        IPFSClient::unpin(self, cid).await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        IPFSClient::health_check(self).await
+    }
+
+    async fn list_pins_paginated(&self, after: Option<String>, limit: usize) -> Result<PinPage> {
+        IPFSClient::list_pins_paginated(self, after, limit).await
+    }
+
+    async fn check_exists(&self, cid: &str) -> Result<bool> {
+        IPFSClient::check_exists(self, cid).await
+    }
+
+    fn gateway_urls(&self) -> &[String] {
+        IPFSClient::gateway_urls(self)
+    }
+
+    async fn benchmark_gateways(&self, cid: &str) -> Result<GatewayBenchmarkReport> {
+        Ok(IPFSClient::benchmark_gateways(self, cid).await)
+    }
+}
+
+/// Parse an `add` response: one JSON object per line, each file and then the
+/// wrapping directory with an empty name
+fn parse_add_entries(body: &str) -> Result<Vec<IPFSAddResponse>> {
+    body.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()
+        .context("Failed to parse IPFS response")
+}
+
+/// Pair each uploaded file name with the CID the node reported for it
+fn cids_by_name(entries: &[IPFSAddResponse], names: &[String]) -> Result<Vec<(String, String)>> {
+    names
+        .iter()
+        .map(|name| {
+            let entry = entries
+                .iter()
+                .find(|e| &e.name == name)
+                .with_context(|| format!("IPFS returned no entry for {}", name))?;
+            Ok((name.clone(), entry.hash.clone()))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cdn_gateway_templates() {
+        let gateways = CdnGateways::new([
+            "https://cloudflare-ipfs.com/ipfs/",
+            " ",
+            "https://{cid}.ipfs.dweb.link/",
+            "https://cloudflare-ipfs.com/ipfs",
+        ]);
+
+        assert_eq!(
+            gateways.urls("bafkrei-test"),
+            vec![
+                "https://cloudflare-ipfs.com/ipfs/bafkrei-test",
+                "https://bafkrei-test.ipfs.dweb.link/",
+            ]
+        );
+        assert!(CdnGateways::new(Vec::<String>::new()).urls("bafkrei-test").is_empty());
+    }
+
     #[tokio::test]
     async fn test_local_ipfs_initialization() {
         let client = IPFSClient::new(
@@ -250,6 +1428,136 @@ mod tests {
         assert!(client.use_pinata);
     }
 
+    #[tokio::test]
+    async fn test_web3storage_initialization() {
+        let client = IPFSClient::for_backend(
+            IpfsBackend::parse("web3storage"),
+            "http://localhost:5001".to_string(),
+            None,
+            Some("test_token".to_string())
+        ).unwrap();
+        assert!(!client.use_pinata);
+        assert_eq!(client.backend(), IpfsBackend::Web3Storage);
+        assert_eq!(client.web3storage.as_ref().unwrap().api_url(), "https://api.web3.storage");
+
+        assert!(IPFSClient::for_backend(Some(IpfsBackend::Web3Storage), "http://localhost:5001".to_string(), None, None).is_err());
+        assert!(IPFSClient::for_backend(Some(IpfsBackend::Pinata), "http://localhost:5001".to_string(), None, None).is_err());
+        let local = IPFSClient::for_backend(Some(IpfsBackend::Local), "http://localhost:5001".to_string(), Some("test_jwt".to_string()), None).unwrap();
+        assert_eq!(local.backend(), IpfsBackend::Local);
+        assert_eq!(IpfsBackend::parse("Web3.Storage"), Some(IpfsBackend::Web3Storage));
+        assert_eq!(IpfsBackend::parse("s3"), None);
+    }
+
+    #[tokio::test]
+    async fn test_upload_empty_directory_fails() {
+        let client = IPFSClient::new(
+            "http://localhost:5001".to_string(),
+            None
+        );
+        assert!(client.upload_directory(&[]).await.is_err());
+    }
+
+    #[test]
+    fn test_paginate_by_cid() {
+        let pin = |cid: &str| PinInfo { cid: cid.to_string(), pin_type: None, size: None, pinned_at: None, name: None };
+        let pins = vec![pin("QmC"), pin("QmA"), pin("QmB")];
+
+        let first = paginate_by_cid(pins.clone(), None, 2);
+        assert_eq!(first.pins, vec![pin("QmA"), pin("QmB")]);
+        assert_eq!(first.next_cursor.as_deref(), Some("QmB"));
+
+        let last = paginate_by_cid(pins, first.next_cursor.as_deref(), 2);
+        assert_eq!(last.pins, vec![pin("QmC")]);
+        assert_eq!(last.next_cursor, None);
+    }
+
+    #[test]
+    fn test_gateway_blackout_doubles_until_success() {
+        let now = Instant::now();
+        let mut stats = GatewayStats::default();
+
+        assert_eq!(stats.record_timeout(now), None);
+        assert_eq!(stats.record_timeout(now), None);
+        assert_eq!(stats.record_timeout(now), Some(Duration::from_secs(60)));
+        assert!(stats.is_blacklisted(now));
+        assert!(!stats.is_blacklisted(now + Duration::from_secs(61)));
+
+        // The re-probe times out again
+        assert_eq!(stats.record_timeout(now), Some(Duration::from_secs(120)));
+        assert_eq!(stats.record_timeout(now), Some(Duration::from_secs(240)));
+
+        assert!(stats.record_success(now));
+        assert_eq!(stats.consecutive_timeouts, 0);
+        assert!(!stats.is_blacklisted(now));
+        assert!(!stats.record_success(now));
+    }
+
+    #[tokio::test]
+    async fn test_retry_upload_after_server_errors() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server.mock("POST", "/api/v0/add").with_status(503).expect(2).create_async().await;
+        let added = server
+            .mock("POST", "/api/v0/add")
+            .with_status(200)
+            .with_body(r#"{"Name":"encrypted.json","Hash":"QmRetried"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = IPFSClient::new(server.url(), None);
+        assert_eq!(client.retry_upload(b"{}", 3).await.unwrap(), "QmRetried");
+        unavailable.assert_async().await;
+        added.assert_async().await;
+
+        server.reset();
+        let rejected = server.mock("POST", "/api/v0/add").with_status(400).expect(1).create_async().await;
+        let err = client.retry_upload(b"{}", 3).await.unwrap_err();
+        assert!(!is_transient_upload_error(&err), "4xx must not be retried");
+        rejected.assert_async().await;
+
+        for retry in 1..=3 {
+            let delay = UPLOAD_RETRY.delay_with_jitter(retry, UPLOAD_RETRY_JITTER).as_millis();
+            let base = UPLOAD_RETRY.delay(retry).as_millis();
+            assert!(delay >= base * 3 / 4 && delay <= base * 5 / 4, "{}ms outside ±25% of {}ms", delay, base);
+        }
+    }
+
+    #[test]
+    fn test_configured_gateways_fastest_first() {
+        let client = IPFSClient::new("http://localhost:5001".to_string(), None)
+            .with_gateways(["https://a.example/", " ", "https://b.example", "https://a.example"]);
+        assert_eq!(client.gateway_urls(), ["https://a.example", "https://b.example"]);
+        assert_eq!(client.gateway_order(), vec!["https://a.example", "https://b.example"]);
+
+        client.record_gateway_success("https://a.example", Duration::from_millis(200));
+        client.record_gateway_success("https://b.example", Duration::from_millis(50));
+        client.note_latency("https://b.example", Duration::from_millis(50));
+        assert_eq!(client.gateway_order(), vec!["https://b.example", "https://a.example"]);
+
+        client.record_gateway_success("https://a.example", Duration::from_millis(100));
+        client.note_latency("https://a.example", Duration::from_millis(100));
+        assert_eq!(client.gateway_order()[0], "https://b.example");
+
+        let defaults = IPFSClient::new("http://localhost:5001".to_string(), None).with_gateways([""]);
+        assert_eq!(defaults.gateway_urls().len(), DEFAULT_GATEWAYS.len());
+    }
+
+    #[tokio::test]
+    async fn test_batch_upload_maps_names_to_cids() {
+        let client = IPFSClient::new("http://localhost:5001".to_string(), None);
+        assert!(client.batch_upload(Vec::new()).await.unwrap().is_empty());
+
+        let body = "{\"Name\":\"a.json\",\"Hash\":\"QmA\"}\n{\"Name\":\"b.json\",\"Hash\":\"QmB\"}\n{\"Name\":\"\",\"Hash\":\"QmDir\"}\n";
+        let entries = parse_add_entries(body).unwrap();
+        let names = vec!["b.json".to_string(), "a.json".to_string()];
+
+        assert_eq!(
+            cids_by_name(&entries, &names).unwrap(),
+            vec![("b.json".to_string(), "QmB".to_string()), ("a.json".to_string(), "QmA".to_string())]
+        );
+        assert!(cids_by_name(&entries, &["c.json".to_string()]).is_err());
+    }
+
     #[tokio::test]
     async fn test_fetch_from_public_gateway() {
         let client = IPFSClient::new(
@@ -1,49 +1,468 @@
 // src/json_builder.rs
 use anyhow::Result;
 use chrono::Utc;
-use tracing::info;
+use tracing::{info, warn};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::agreement_store;
+use crate::ens_resolver::{self, EnsResolver, ZERO_ADDRESS};
 use crate::models::*;
+use crate::normalization::{normalize_media_types, normalize_streaming_platforms};
+
+/// `Default` builds without ENS resolution or agreement ID de-duplication
+#[derive(Clone, Default)]
+pub struct JSONBuilder {
+    ens_resolver: Arc<EnsResolver>,
+    db: Option<PgPool>,
+}
+
+/// Highest `-NNN` suffix tried when de-duplicating an agreement ID
+const MAX_AGREEMENT_ID_SUFFIX: u32 = 999;
+
+/// `base` with a zero-padded sequence suffix, e.g. `RELIANCE-KALKI-2024-002`
+fn suffixed_agreement_id(base: &str, sequence: u32) -> String {
+    format!("{}-{:03}", base, sequence)
+}
+
+/// `ContentInfo.content_type` when nothing better is known
+const DEFAULT_CONTENT_TYPE: &str = "MOVIE";
+
+/// Keywords implying a content type, checked in order so that "web series"
+/// wins over "series"
+const CONTENT_TYPE_KEYWORDS: &[(&str, &str)] = &[
+    ("web series", "WEB_SERIES"),
+    ("live event", "LIVE_EVENT"),
+    ("short film", "SHORT_FILM"),
+    ("documentary", "DOCUMENTARY"),
+    ("animation", "ANIMATION"),
+    ("series", "TV_SERIES"),
+    ("episodes", "TV_SERIES"),
+    ("season", "TV_SERIES"),
+];
+
+/// Content type suggested by keywords in `text`, `MOVIE` if none match
+pub fn detect_content_type(text: &str) -> String {
+    let text = text.to_lowercase();
+    CONTENT_TYPE_KEYWORDS
+        .iter()
+        .find(|(keyword, _)| text.contains(keyword))
+        .map_or(DEFAULT_CONTENT_TYPE, |(_, content_type)| content_type)
+        .to_string()
+}
+
+/// The LLM's content type, unless it is missing or the generic default, in
+/// which case the title, genres and media types are searched for keywords
+fn resolve_content_type(parsed: &ParsedAgreement) -> String {
+    match parsed.content_type.as_deref().map(str::trim) {
+        Some(content_type) if !content_type.is_empty() && !content_type.eq_ignore_ascii_case(DEFAULT_CONTENT_TYPE) => {
+            content_type.to_string()
+        }
+        _ => {
+            let text = [vec![parsed.title.clone()], parsed.genre.clone(), parsed.media_types.clone()]
+                .concat()
+                .join(" ");
+            detect_content_type(&text)
+        }
+    }
+}
+
+/// Raw LLM keys accepted for each `ParsedAgreement` field, in order of preference.
+/// Covers the Modelfile's names plus variants models produce when they drift.
+const FIELD_ALIASES: &[(&str, &[&str])] = &[
+    ("title", &["title", "film_title", "content_title", "work_title"]),
+    ("licensor", &["licensor", "assignor", "rights_holder", "licensor_name"]),
+    ("licensee", &["licensee", "assignee", "distributor", "licensee_name"]),
+    ("licensor_address", &["licensor_address", "assignor_address"]),
+    ("licensee_address", &["licensee_address", "assignee_address"]),
+    ("wallet_address", &["wallet_address", "walletAddress", "ens_name"]),
+    ("territories", &["territories", "territory", "licensed_territories"]),
+    ("media_types", &["media_types", "rights", "media_rights", "rights_granted"]),
+    ("deal_value", &["deal_value", "total_fee", "license_fee", "consideration", "fee"]),
+    ("currency", &["currency", "fee_currency"]),
+    ("term_years", &["term_years", "term_in_years"]),
+    ("start_date", &["start_date", "term_start", "effective_date"]),
+    ("end_date", &["end_date", "term_end", "expiry_date"]),
+    ("exclusivity", &["exclusivity", "exclusive", "is_exclusive"]),
+    ("content_type", &["content_type", "type"]),
+    ("language", &["language", "original_language"]),
+    ("genre", &["genre", "genres"]),
+    ("director", &["director", "directed_by"]),
+    ("producer", &["producer", "produced_by"]),
+    ("production_company", &["production_company", "banner", "production_house"]),
+    ("production_country", &["production_country", "country_of_origin"]),
+    ("release_date", &["release_date", "theatrical_release_date"]),
+    ("duration", &["duration", "runtime", "runtime_minutes"]),
+    ("special_terms", &["special_terms", "special_clauses", "special_provisions"]),
+    ("delivery_deadline", &["delivery_deadline", "deliveryDeadline", "delivery_date"]),
+    ("agreement_id", &["agreementId", "agreement_id"]),
+    ("territories_excluded", &["territories_excluded", "excluded_territories", "territoriesExcluded"]),
+    ("platforms_excluded", &["platforms_excluded", "excluded_platforms", "platformsExcluded"]),
+    ("holdback_period", &["holdback_period", "holdbackPeriod", "holdbacks"]),
+    ("milestones", &["milestones", "payment_milestones", "payment_schedule", "installments", "instalments"]),
+];
+
+/// First non-null, non-empty value under any alias of `field`
+fn lookup<'a>(json: &'a serde_json::Value, field: &str) -> Option<&'a serde_json::Value> {
+    let aliases = FIELD_ALIASES
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, aliases)| *aliases)
+        .unwrap_or(&[]);
+
+    aliases.iter().filter_map(|key| json.get(*key)).find(|v| match v {
+        serde_json::Value::Null => false,
+        serde_json::Value::String(s) => !s.trim().is_empty(),
+        serde_json::Value::Array(a) => !a.is_empty(),
+        _ => true,
+    })
+}
+
+fn lookup_string(json: &serde_json::Value, field: &str) -> Option<String> {
+    match lookup(json, field)? {
+        serde_json::Value::String(s) => Some(s.trim().to_string()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// A list field, accepting a single string as a one-element list
+fn lookup_list(json: &serde_json::Value, field: &str) -> Vec<String> {
+    match lookup(json, field) {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.as_str())
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        Some(serde_json::Value::String(s)) => vec![s.trim().to_string()],
+        _ => Vec::new(),
+    }
+}
+
+/// Special terms as written, also accepting terms `merge_built_fields` has
+/// already classified
+fn lookup_special_terms(json: &serde_json::Value) -> Vec<String> {
+    match lookup(json, "special_terms") {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.as_str().or_else(|| item.get("description")?.as_str()))
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        _ => lookup_list(json, "special_terms"),
+    }
+}
+
+/// Copy what the builder derived from flat LLM output back into it: the
+/// inferred payment type, the classified special terms and, when it was
+/// recognised, the ISO 4217 currency code
+pub fn merge_built_fields(agreement: &RightsAgreementJSON, llm_json: &mut serde_json::Value) {
+    let Some(fields) = llm_json.as_object_mut() else {
+        return;
+    };
+
+    let payment_type = &agreement.financial.payment_structure.payment_type;
+    fields.insert("payment_type".to_string(), serde_json::json!(payment_type));
+    if let Some(special_terms) = &agreement.special_terms {
+        fields.insert("special_terms".to_string(), serde_json::json!(special_terms));
+    }
+    if Financial::normalize_currency(&agreement.financial.currency_raw).is_some() {
+        fields.insert("currency".to_string(), serde_json::json!(agreement.financial.currency));
+    }
+}
+
+/// A number field, accepting strings such as `"10,00,00,000"` or `"Rs. 1,000.50"`.
+/// Only the first number in the string counts and any fraction is dropped.
+fn lookup_u64(json: &serde_json::Value, field: &str) -> Option<u64> {
+    parse_u64(lookup(json, field)?)
+}
+
+/// Payment milestones that state a due date. Amounts and percentages are
+/// read as `lookup_u64` reads them, and default to 0 when missing.
+fn lookup_milestones(json: &serde_json::Value) -> Vec<Milestone> {
+    let Some(serde_json::Value::Array(items)) = lookup(json, "milestones") else {
+        return Vec::new();
+    };
+    let first = |item: &serde_json::Value, keys: &[&str]| keys.iter().find_map(|key| item.get(*key)).cloned();
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let due_date = first(item, &["due_date", "dueDate", "date"])?.as_str()?.trim().to_string();
+            let name = first(item, &["name", "milestone", "description"]);
+            Some(Milestone {
+                name: name.as_ref().and_then(|n| n.as_str()).unwrap_or(&due_date).trim().to_string(),
+                amount: first(item, &["amount", "value"]).as_ref().and_then(parse_u64).unwrap_or(0),
+                percentage: first(item, &["percentage", "percent"]).as_ref().and_then(parse_u64).unwrap_or(0) as u32,
+                due_date,
+            })
+        })
+        .collect()
+}
+
+/// Holdback periods in the unit the agreement states, days if it names none.
+/// `None` unless at least one period is a positive number.
+fn lookup_holdback(json: &serde_json::Value) -> Option<HoldbackPeriod> {
+    let holdback = lookup(json, "holdback_period")?;
+    let amount = |keys: &[&str]| keys.iter().find_map(|key| holdback.get(*key).and_then(parse_u64)).unwrap_or(0) as u32;
+    let unit = match holdback.get("unit").and_then(|u| u.as_str()).map(|u| u.trim().to_uppercase()) {
+        Some(unit) if unit.starts_with("WEEK") => HoldbackUnit::Weeks,
+        Some(unit) if unit.starts_with("MONTH") => HoldbackUnit::Months,
+        _ => HoldbackUnit::Days,
+    };
 
-pub struct JSONBuilder;
+    let period = HoldbackPeriod {
+        theatrical: amount(&["theatrical"]),
+        physical_media: amount(&["physical_media", "physicalMedia"]),
+        free_tv: amount(&["free_tv", "freeTV", "freeTv"]),
+        unit,
+    };
+    (period.theatrical > 0 || period.physical_media > 0 || period.free_tv > 0).then_some(period)
+}
+
+fn parse_u64(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64().or_else(|| n.as_f64().filter(|f| *f >= 0.0).map(|f| f as u64)),
+        serde_json::Value::String(s) => {
+            let number: String = s
+                .chars()
+                .skip_while(|c| !c.is_ascii_digit())
+                .take_while(|c| c.is_ascii_digit() || *c == ',' || *c == '.')
+                .filter(|c| *c != ',')
+                .collect();
+            number.split('.').next()?.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+fn lookup_bool(json: &serde_json::Value, field: &str) -> Option<bool> {
+    match lookup(json, field)? {
+        serde_json::Value::Bool(b) => Some(*b),
+        serde_json::Value::String(s) => match s.trim().to_lowercase().as_str() {
+            "true" | "yes" | "exclusive" => Some(true),
+            "false" | "no" | "non-exclusive" | "non_exclusive" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Keys that mean the licensee pays per sale or revenue. Matched whole, so
+/// `advance_payment` is not read as an advance against royalties.
+const ROYALTY_KEYS: &[&str] = &[
+    "royalty",
+    "royalties",
+    "royalty_rate",
+    "royalty_percentage",
+    "per_unit",
+    "per_unit_fee",
+    "revenue_share",
+    "revenue_share_percentage",
+    "advance",
+    "advance_against_royalties",
+];
+
+/// Keys holding a milestone or instalment schedule of fixed payments
+const MILESTONE_KEYS: &[&str] = &["milestones", "payment_milestones", "payment_schedule", "installments", "instalments"];
+
+/// Whether a value actually states something, rather than being null, zero or empty
+fn is_stated(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::Bool(b) => *b,
+        serde_json::Value::Number(n) => n.as_f64().map_or(true, |f| f != 0.0),
+        serde_json::Value::String(s) => !s.trim().is_empty(),
+        serde_json::Value::Array(a) => !a.is_empty(),
+        serde_json::Value::Object(o) => !o.is_empty(),
+    }
+}
+
+/// Whether any key at any depth matches `matches` with a stated value
+fn has_stated_key(json: &serde_json::Value, matches: &dyn Fn(&str) -> bool) -> bool {
+    match json {
+        serde_json::Value::Object(map) => map.iter().any(|(key, value)| {
+            (matches(&key.to_lowercase()) && is_stated(value)) || has_stated_key(value, matches)
+        }),
+        serde_json::Value::Array(items) => items.iter().any(|item| has_stated_key(item, matches)),
+        _ => false,
+    }
+}
+
+/// `"ROYALTY"` when the extracted terms only mention royalties, `"HYBRID"`
+/// when they also carry a flat fee or fixed milestones, otherwise `"FIXED"`
+pub fn infer_payment_type(llm_json: &serde_json::Value) -> String {
+    // `royaltyRate` and `royalty_rate` alike
+    let royalty = has_stated_key(llm_json, &|key| {
+        let key = key.replace('_', "");
+        ROYALTY_KEYS.iter().any(|k| k.replace('_', "") == key)
+    });
+    let fixed = lookup_u64(llm_json, "deal_value").unwrap_or(0) > 0
+        || has_stated_key(llm_json, &|key| MILESTONE_KEYS.contains(&key));
+
+    match (royalty, fixed) {
+        (true, true) => "HYBRID",
+        (true, false) => "ROYALTY",
+        _ => "FIXED",
+    }
+    .to_string()
+}
 
 impl JSONBuilder {
-    pub fn new() -> Self {
-        Self
+    pub fn new(ens_resolver: Arc<EnsResolver>) -> Self {
+        Self { ens_resolver, db: None }
+    }
+
+    /// Reserve generated agreement IDs in the database so they are unique
+    pub fn with_db(mut self, db: PgPool) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// `base` if it can be reserved, otherwise the first free `base-001`, `base-002`, ...
+    /// The database being unavailable is not fatal; `base` is used as-is.
+    pub async fn unique_agreement_id(&self, base: String) -> String {
+        let Some(db) = &self.db else {
+            return base;
+        };
+
+        let candidates = std::iter::once(base.clone())
+            .chain((1..=MAX_AGREEMENT_ID_SUFFIX).map(|n| suffixed_agreement_id(&base, n)));
+        for candidate in candidates {
+            match agreement_store::reserve_agreement_id(db, &candidate).await {
+                Ok(true) => {
+                    if candidate != base {
+                        info!("🆔 Agreement ID {} already taken, using {}", base, candidate);
+                    }
+                    return candidate;
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    warn!("Could not reserve agreement ID, using {}: {:#}", base, e);
+                    return base;
+                }
+            }
+        }
+
+        warn!("No free suffix for agreement ID {}", base);
+        base
+    }
+
+    /// Build the structured agreement straight from raw LLM output, looking each
+    /// field up under several alias keys instead of requiring `ParsedAgreement`'s names
+    pub async fn build_from_llm_json(&self, json: &serde_json::Value) -> Result<RightsAgreementJSON> {
+        if !json.is_object() {
+            anyhow::bail!("LLM output is not a JSON object");
+        }
+
+        let unknown = || "Unknown".to_string();
+        let parsed = ParsedAgreement {
+            title: lookup_string(json, "title").unwrap_or_else(unknown),
+            licensor: lookup_string(json, "licensor").unwrap_or_else(unknown),
+            licensee: lookup_string(json, "licensee").unwrap_or_else(unknown),
+            licensor_address: lookup_string(json, "licensor_address"),
+            licensee_address: lookup_string(json, "licensee_address"),
+            wallet_address: lookup_string(json, "wallet_address"),
+            territories: lookup_list(json, "territories"),
+            media_types: lookup_list(json, "media_types"),
+            deal_value: lookup_u64(json, "deal_value").unwrap_or(0),
+            currency: lookup_string(json, "currency").unwrap_or_default(),
+            term_years: lookup_u64(json, "term_years").map(|y| y as u32),
+            start_date: lookup_string(json, "start_date"),
+            end_date: lookup_string(json, "end_date"),
+            exclusivity: lookup_bool(json, "exclusivity").unwrap_or(false),
+            content_type: lookup_string(json, "content_type"),
+            language: lookup_string(json, "language"),
+            genre: lookup_list(json, "genre"),
+            director: lookup_string(json, "director"),
+            producer: lookup_string(json, "producer"),
+            production_company: lookup_string(json, "production_company"),
+            production_country: lookup_string(json, "production_country"),
+            release_date: lookup_string(json, "release_date"),
+            duration: lookup_u64(json, "duration").map(|d| d as u32),
+            special_terms: lookup_special_terms(json),
+            delivery_deadline: lookup_string(json, "delivery_deadline"),
+            agreement_id: lookup_string(json, "agreement_id"),
+            milestones: lookup_milestones(json),
+            holdback_period: lookup_holdback(json),
+            territories_excluded: lookup_list(json, "territories_excluded"),
+            platforms_excluded: lookup_list(json, "platforms_excluded"),
+        };
+
+        let mut agreement = self.build_agreement(&parsed).await?;
+        let extras = ParsedAgreement::merge_with_llm_json(parsed, json);
+        // The raw output may name royalty terms `ParsedAgreement` has no field for
+        agreement.financial.payment_structure.payment_type = infer_payment_type(&extras);
+        agreement.raw_llm_extras = Some(extras);
+        Ok(agreement)
     }
 
     pub async fn build_agreement(&self, parsed: &ParsedAgreement) -> Result<RightsAgreementJSON> {
         info!("🔨 Building JSON structure");
 
-        // Generate agreement ID
+        // Generate agreement ID unless the LLM assigned one; either is reserved
         let title = &parsed.title;
-        let agreement_id = format!("{}-{}-{}", 
+        let agreement_id = parsed.agreement_id.clone().unwrap_or_else(|| format!("{}-{}-{}", 
             parsed.licensor.replace(" ", "-").to_uppercase(),
             title.split_whitespace().next().unwrap_or("TITLE"),
             Utc::now().format("%Y")
-        );
+        ));
+        let agreement_id = self.unique_agreement_id(agreement_id).await;
 
         // Calculate financial details
         let platform_fee_percentage = 2.5;
         let platform_fee_amount = (parsed.deal_value as f64 * platform_fee_percentage / 100.0) as u64;
         let net_to_holder = parsed.deal_value - platform_fee_amount;
 
+        let mut warnings = Vec::new();
+
+        // Standardize currency to ISO 4217
+        let currency = match Financial::normalize_currency(&parsed.currency) {
+            Some(code) => code,
+            None => {
+                warn!("Unrecognised currency: {:?}", parsed.currency);
+                warnings.push(format!("Unrecognised currency '{}', stored as XXX", parsed.currency));
+                "XXX".to_string()
+            }
+        };
+
+        let wallet_address_raw = parsed.wallet_address.clone().unwrap_or_default();
+        if !wallet_address_raw.is_empty() && !ens_resolver::validate_wallet_address(&wallet_address_raw).is_valid {
+            warnings.push(format!("Wallet address '{}' is not a valid address or ENS name", wallet_address_raw));
+        }
+        let wallet_address_resolved = self.ens_resolver.resolve(&wallet_address_raw).await;
+        if !wallet_address_raw.is_empty() && wallet_address_resolved.is_none() {
+            warnings.push(format!("Wallet address '{}' could not be resolved", wallet_address_raw));
+        }
+        let wallet_address_checksum = wallet_address_resolved.as_deref().and_then(ens_resolver::checksum_address);
+
+        let has_restrictions = parsed.holdback_period.is_some()
+            || !parsed.territories_excluded.is_empty()
+            || !parsed.platforms_excluded.is_empty();
+
         // Build complete structure
         let agreement = RightsAgreementJSON {
             agreement_id,
             rights_holder: RightsHolder {
                 name: parsed.licensor.clone(),
-                wallet_address: "0x0000000000000000000000000000000000000000".to_string(), // To be filled
+                wallet_address: wallet_address_resolved.clone().unwrap_or_else(|| ZERO_ADDRESS.to_string()),
+                wallet_address_raw,
+                wallet_address_resolved,
+                wallet_address_checksum,
             },
             content: ContentInfo {
                 title: parsed.title.clone(),
                 original_title: parsed.title.clone(),
-                content_type: parsed.content_type.clone().unwrap_or_else(|| "MOVIE".to_string()),
+                content_type: resolve_content_type(parsed),
                 language: parsed.language.clone().unwrap_or_else(|| "Unknown".to_string()),
                 genre: parsed.genre.clone(),
                 duration: parsed.duration.unwrap_or(120),
                 release_date: parsed.release_date.clone().unwrap_or_else(|| "Unknown".to_string()),
                 director: parsed.director.clone().unwrap_or_else(|| "Unknown".to_string()),
                 producer: parsed.producer.clone().unwrap_or_else(|| "Unknown".to_string()),
+                production_company: parsed.production_company.clone(),
+                production_country: parsed.production_country.clone(),
                 rating: Rating {
                     cbfc: "U/A".to_string(),
                     mpaa: Some("PG-13".to_string()),
@@ -52,35 +471,39 @@ impl JSONBuilder {
             rights: Rights {
                 territories: parsed.territories.clone(),
                 media_types: parsed.media_types.clone(),
+                media_types_normalized: normalize_media_types(&parsed.media_types),
                 exclusivity: parsed.exclusivity,
                 term: Term {
                     years: parsed.term_years.unwrap_or(1),
-                    start_date: parsed.start_date.clone().unwrap_or_else(|| "Unknown".to_string()),
-                    end_date: parsed.end_date.clone().unwrap_or_else(|| "Unknown".to_string()),
+                    start_date: FlexibleDate::parse(parsed.start_date.as_deref().unwrap_or("Unknown")),
+                    end_date: FlexibleDate::parse(parsed.end_date.as_deref().unwrap_or("Unknown")),
                 },
             },
             financial: Financial {
                 deal_value: parsed.deal_value,
-                currency: parsed.currency.clone(),
+                currency,
+                currency_raw: parsed.currency.clone(),
                 platform_fee: PlatformFee {
                     percentage: platform_fee_percentage,
                     amount: platform_fee_amount,
                 },
                 net_to_rights_holder: net_to_holder,
                 payment_structure: PaymentStructure {
-                    payment_type: "FIXED".to_string(),
+                    payment_type: infer_payment_type(&serde_json::to_value(parsed).unwrap_or_default()),
                     breakdown: PaymentBreakdown {
                         upfront: parsed.deal_value / 2,
                         on_delivery: parsed.deal_value / 2,
                     },
-                    milestones: None,
+                    milestones: (!parsed.milestones.is_empty()).then(|| parsed.milestones.clone()),
                 },
             },
             parties: Some(Parties {
                 licensor: Party {
                     name: parsed.licensor.clone(),
                     registration_number: "TBD".to_string(),
-                    address: "TBD".to_string(),
+                    address: parsed.licensor_address.clone().unwrap_or_else(|| "TBD".to_string()),
+                    address_validated: false,
+                    address_country_code: None,
                     country: "TBD".to_string(),
                     contact_email: "contact@licensor.com".to_string(),
                     signatory_name: "TBD".to_string(),
@@ -89,7 +512,9 @@ impl JSONBuilder {
                 licensee: Party {
                     name: parsed.licensee.clone(),
                     registration_number: "TBD".to_string(),
-                    address: "TBD".to_string(),
+                    address: parsed.licensee_address.clone().unwrap_or_else(|| "TBD".to_string()),
+                    address_validated: false,
+                    address_country_code: None,
                     country: "TBD".to_string(),
                     contact_email: "contact@licensee.com".to_string(),
                     signatory_name: "TBD".to_string(),
@@ -113,7 +538,7 @@ impl JSONBuilder {
                 dubbing: vec![
                     "Hindi".to_string(),
                 ],
-                delivery_deadline: "2025-03-01".to_string(),
+                delivery_deadline: parsed.delivery_deadline.clone().unwrap_or_default(),
                 technical_specs: TechnicalSpecs {
                     video_codec: "H.265/HEVC".to_string(),
                     audio_codec: "AAC".to_string(),
@@ -121,31 +546,291 @@ impl JSONBuilder {
                     drm_required: true,
                     drm_type: "Widevine, PlayReady".to_string(),
                 },
+                status: None,
+                days_until_deadline: None,
             }),
-            restrictions: None,
-            special_terms: None,
+            restrictions: has_restrictions.then(|| Restrictions {
+                territories_excluded: parsed.territories_excluded.clone(),
+                platforms_excluded: parsed.platforms_excluded.clone(),
+                platforms_excluded_normalized: normalize_streaming_platforms(&parsed.platforms_excluded),
+                holdback_period: parsed.holdback_period.clone().unwrap_or(HoldbackPeriod {
+                    theatrical: 0,
+                    physical_media: 0,
+                    free_tv: 0,
+                    unit: HoldbackUnit::Days,
+                }),
+                content_rating: "Unknown".to_string(),
+                editing_rights: "Unknown".to_string(),
+                merchandising_rights: "Unknown".to_string(),
+            }),
+            special_terms: (!parsed.special_terms.is_empty())
+                .then(|| parsed.special_terms.iter().map(|term| SpecialTerm::parse(term)).collect()),
             legal_terms: Some(LegalTerms {
                 governing_law: "Laws of India".to_string(),
-                dispute_resolution: "Arbitration".to_string(),
+                dispute_resolution: DisputeResolutionClause::parse("Arbitration"),
+                dispute_resolution_raw: "Arbitration".to_string(),
                 confidentiality: "5 years".to_string(),
                 warranties: "Standard warranties apply".to_string(),
                 indemnification: "Mutual indemnification".to_string(),
                 forcemajeure: "Standard force majeure clause".to_string(),
             }),
+            obligations: Vec::new(),
             metadata: Some(Metadata {
-                created_date: Utc::now().format("%Y-%m-%d").to_string(),
-                last_modified: Utc::now().format("%Y-%m-%d").to_string(),
-                version: "1.0".to_string(),
-                status: "PENDING".to_string(),
-                blockchain: BlockchainInfo {
-                    network: "CBDC_TESTNET".to_string(),
-                    deployment_pending: true,
-                },
+                warnings,
+                ..Metadata::new()
             }),
+            raw_llm_extras: None,
         };
 
+        let mut agreement = agreement;
+        if let Some(deliverables) = agreement.deliverables.as_mut() {
+            deliverables.refresh_deadline(chrono::Utc::now().date_naive());
+        }
+
+        if let (Some(parties), Some(metadata)) = (agreement.parties.as_mut(), agreement.metadata.as_mut()) {
+            for (role, party) in [("Licensor", &mut parties.licensor), ("Licensee", &mut parties.licensee)] {
+                // Placeholder addresses are not worth a warning
+                let has_address = party.address != "TBD";
+                let issues = party.validate_address();
+                if has_address && !issues.is_empty() {
+                    metadata.warnings.push(format!("{} address: {}", role, issues.join("; ")));
+                }
+            }
+        }
+
+        if let (Some(warning), Some(metadata)) = (agreement.content.production_credit_warning(), agreement.metadata.as_mut()) {
+            warn!("{}", warning);
+            metadata.warnings.push(warning);
+        }
+
+        if let (Some(milestones), Some(metadata)) = (
+            agreement.financial.payment_structure.milestones.as_deref(),
+            agreement.metadata.as_mut(),
+        ) {
+            if let Err(errors) = validate_milestone_ordering(milestones) {
+                for error in errors {
+                    warn!("{}", error);
+                    metadata.warnings.push(error.to_string());
+                }
+            }
+        }
+
+        // Holdbacks must be plausible, and a platform cannot be both granted and excluded
+        if let (Some(restrictions), Some(metadata)) = (&agreement.restrictions, agreement.metadata.as_mut()) {
+            for warning in restrictions.holdback_period.validation_warnings() {
+                warn!("{}", warning);
+                metadata.warnings.push(warning);
+            }
+
+            for platform in restrictions.platform_conflicts(&agreement.rights) {
+                warn!("Platform {} is both granted and excluded", platform.as_str());
+                metadata.warnings.push(format!(
+                    "Platform '{}' appears in both rights.mediaTypes and restrictions.platformsExcluded",
+                    platform.as_str()
+                ));
+            }
+        }
+
         info!("✅ JSON structure built successfully");
 
         Ok(agreement)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::normalization::StreamingPlatform;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_agreement_id_suffix() {
+        assert_eq!(suffixed_agreement_id("RELIANCE-KALKI-2024", 2), "RELIANCE-KALKI-2024-002");
+        // Without a database the generated ID is kept
+        let id = JSONBuilder::default().unique_agreement_id("RELIANCE-KALKI-2024".to_string()).await;
+        assert_eq!(id, "RELIANCE-KALKI-2024");
+    }
+
+    #[test]
+    fn test_detect_content_type() {
+        assert_eq!(detect_content_type("Exclusive rights to the Web Series"), "WEB_SERIES");
+        assert_eq!(detect_content_type("all 10 episodes of Season 2"), "TV_SERIES");
+        assert_eq!(detect_content_type("a feature-length documentary"), "DOCUMENTARY");
+        assert_eq!(detect_content_type("Kalki 2898 AD"), "MOVIE");
+    }
+
+    #[tokio::test]
+    async fn test_build_from_llm_json_uses_aliases() {
+        let llm_json = json!({
+            "title": "Kalki 2898 AD",
+            "assignor": "Vyjayanthi Movies",
+            "licensee": "Netflix India",
+            "territory": "ENTIRE UNIVERSE",
+            "rights": ["SVOD", "AVOD"],
+            "total_fee": "10,00,00,000",
+            "currency": "INR",
+            "term_start": "2024-08-01",
+            "term_end": "2029-07-31",
+            "exclusivity": "Exclusive",
+            "original_language": "Telugu",
+            "director": null,
+            "sublicensing_right": true
+        });
+
+        let agreement = JSONBuilder::default().build_from_llm_json(&llm_json).await.unwrap();
+
+        assert_eq!(agreement.rights_holder.name, "Vyjayanthi Movies");
+        assert_eq!(agreement.rights.territories, vec!["ENTIRE UNIVERSE"]);
+        assert_eq!(agreement.rights.media_types.len(), 2);
+        assert_eq!(agreement.financial.deal_value, 100_000_000);
+        assert_eq!(agreement.rights.term.end_date, "2029-07-31");
+        assert!(agreement.rights.exclusivity);
+        assert_eq!(agreement.content.language, "Telugu");
+        assert_eq!(agreement.content.director, "Unknown");
+        assert_eq!(agreement.content.content_type, "MOVIE");
+
+        // Keys without a struct field are kept; parsed values win over raw ones
+        let extras = agreement.raw_llm_extras.unwrap();
+        assert_eq!(extras["sublicensing_right"], true);
+        assert_eq!(extras["deal_value"], 100_000_000);
+        assert_eq!(extras["total_fee"], "10,00,00,000");
+
+        assert!(JSONBuilder::default().build_from_llm_json(&json!([])).await.is_err());
+
+        // Fractions are dropped rather than read as extra digits
+        let agreement = JSONBuilder::default()
+            .build_from_llm_json(&json!({"total_fee": "Rs. 1,000.50", "agreementId": "VM-KALKI-2024"}))
+            .await
+            .unwrap();
+        assert_eq!(agreement.financial.deal_value, 1000);
+        assert_eq!(agreement.agreement_id, "VM-KALKI-2024");
+    }
+
+    #[tokio::test]
+    async fn test_delivery_deadline_only_when_stated() {
+        let builder = JSONBuilder::default();
+
+        let agreement = builder.build_from_llm_json(&json!({"title": "Kalki 2898 AD"})).await.unwrap();
+        let deliverables = agreement.deliverables.unwrap();
+        assert_eq!(deliverables.delivery_deadline, "");
+        assert_eq!(deliverables.deadline(), None);
+        assert_eq!(deliverables.status, None);
+
+        let agreement = builder
+            .build_from_llm_json(&json!({"title": "Kalki 2898 AD", "delivery_deadline": "1st March 2025"}))
+            .await
+            .unwrap();
+        assert_eq!(agreement.deliverables.unwrap().deadline(), chrono::NaiveDate::from_ymd_opt(2025, 3, 1));
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_milestones_warn() {
+        let llm_json = json!({
+            "title": "Kalki 2898 AD",
+            "total_fee": 100_000_000,
+            "payment_milestones": [
+                {"name": "Delivery", "amount": "5,00,00,000", "due_date": "2025-03-01", "percentage": 50},
+                {"name": "Signing", "amount": 50_000_000, "due_date": "1 January 2025", "percentage": 50}
+            ]
+        });
+
+        let agreement = JSONBuilder::default().build_from_llm_json(&llm_json).await.unwrap();
+        let milestones = agreement.financial.payment_structure.milestones.as_deref().unwrap();
+        assert_eq!(milestones[0].amount, 50_000_000);
+        assert_eq!(milestones[1].due_date, "1 January 2025");
+        let warnings = agreement.metadata.unwrap().warnings;
+        assert!(warnings.contains(&"Milestone 'Signing' is listed after 'Delivery' but falls due before it".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_implausible_holdback_warns() {
+        let builder = JSONBuilder::default();
+        let llm_json = json!({
+            "title": "Kalki 2898 AD",
+            "holdback_period": {"theatrical": 90, "physical_media": null, "free_tv": 18, "unit": "MONTHS"}
+        });
+        let agreement = builder.build_from_llm_json(&llm_json).await.unwrap();
+        let holdback = &agreement.restrictions.unwrap().holdback_period;
+        assert_eq!(holdback.unit, HoldbackUnit::Months);
+        assert_eq!(holdback.to_days().free_tv, 540);
+        assert!(agreement.metadata.unwrap().warnings.iter().any(|w| w.starts_with("Theatrical holdback of 2700 days")));
+
+        // Stated in weeks, 12 weeks is a plausible 84 days
+        let llm_json = json!({"title": "Kalki 2898 AD", "holdback_period": {"theatrical": 12, "unit": "weeks"}});
+        let agreement = builder.build_from_llm_json(&llm_json).await.unwrap();
+        assert!(!agreement.metadata.unwrap().warnings.iter().any(|w| w.starts_with("Theatrical holdback")));
+
+        let llm_json = json!({"title": "Kalki 2898 AD", "holdback_period": {"theatrical": null}});
+        assert!(builder.build_from_llm_json(&llm_json).await.unwrap().restrictions.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_excluded_platform_conflicts() {
+        let llm_json = json!({
+            "title": "Kalki 2898 AD",
+            "rights": ["SVOD on Netflix", "AVOD on YouTube"],
+            "platforms_excluded": ["Netflix India", "Amazon Prime"],
+            "territories_excluded": ["Pakistan"]
+        });
+
+        let agreement = JSONBuilder::default().build_from_llm_json(&llm_json).await.unwrap();
+        let restrictions = agreement.restrictions.unwrap();
+        assert_eq!(
+            restrictions.platforms_excluded_normalized,
+            vec![StreamingPlatform::Netflix, StreamingPlatform::AmazonPrimeVideo]
+        );
+        assert_eq!(restrictions.territories_excluded, vec!["Pakistan"]);
+        assert_eq!(restrictions.holdback_period.theatrical, 0);
+        let warnings = agreement.metadata.unwrap().warnings;
+        assert!(warnings.iter().any(|w| w.starts_with("Platform 'Netflix' appears in both")), "{:?}", warnings);
+        assert!(!warnings.iter().any(|w| w.contains("Amazon")));
+    }
+
+    #[test]
+    fn test_infer_payment_type() {
+        assert_eq!(infer_payment_type(&json!({"deal_value": 100_000_000})), "FIXED");
+        assert_eq!(infer_payment_type(&json!({"deal_value": 0, "royalty_rate": "12%"})), "ROYALTY");
+        assert_eq!(
+            infer_payment_type(&json!({"total_fee": "5,00,000", "payment_terms": {"revenue_share": 0.3}})),
+            "HYBRID"
+        );
+        assert_eq!(
+            infer_payment_type(&json!({"milestones": [{"amount": 10}], "advance": 50_000})),
+            "HYBRID"
+        );
+        // Keys present but empty say nothing about how the deal is paid
+        assert_eq!(infer_payment_type(&json!({"royalty_rate": null, "advance": 0})), "FIXED");
+        // Keys are matched whole, in snake or camel case
+        assert_eq!(infer_payment_type(&json!({"deal_value": 500_000, "advance_payment": 100_000})), "FIXED");
+        assert_eq!(infer_payment_type(&json!({"royaltyRate": "10%"})), "ROYALTY");
+    }
+
+    #[tokio::test]
+    async fn test_merge_built_fields() {
+        let mut llm_json = json!({
+            "title": "Kalki 2898 AD",
+            "total_fee": 50_000_000,
+            "currency": "Rupees",
+            "royalty_rate": "5%",
+            "special_terms": ["Licensee has a first look at sequels until 31 December 2027"]
+        });
+        let builder = JSONBuilder::default();
+        let agreement = builder.build_from_llm_json(&llm_json).await.unwrap();
+        merge_built_fields(&agreement, &mut llm_json);
+
+        assert_eq!(llm_json["payment_type"], "HYBRID");
+        assert_eq!(llm_json["currency"], "INR");
+        assert_eq!(llm_json["special_terms"][0]["category"], "FIRST_LOOK");
+
+        // Merged output builds the same agreement again
+        let rebuilt = builder.build_from_llm_json(&llm_json).await.unwrap();
+        assert_eq!(rebuilt.special_terms, agreement.special_terms);
+        assert_eq!(rebuilt.financial.currency, "INR");
+
+        // An unrecognised currency is left as written
+        let mut llm_json = json!({"currency": "Doubloons"});
+        let agreement = builder.build_from_llm_json(&llm_json).await.unwrap();
+        merge_built_fields(&agreement, &mut llm_json);
+        assert_eq!(llm_json["currency"], "Doubloons");
+    }
+}
@@ -0,0 +1,283 @@
+// src/json_fields.rs - Dot-notation field selection over agreement JSON
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// Top-level keys holding financial terms, in both the structured agreement
+/// format and the flat format returned by the LLM
+pub const FINANCIAL_FIELDS: &[&str] = &["financial", "total_fee", "payment_terms"];
+
+/// Keep only the listed dot-notation paths (e.g. `parties.licensor.name`).
+/// Paths that pass through an array are applied to every element.
+pub fn filter_json_fields(value: &Value, paths: &[&str]) -> Value {
+    let split: Vec<Vec<&str>> = paths
+        .iter()
+        .map(|p| p.split('.').filter(|s| !s.is_empty()).collect::<Vec<_>>())
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    filter_segments(value, &split)
+}
+
+fn filter_segments(value: &Value, paths: &[Vec<&str>]) -> Value {
+    // A path that ends here selects the whole subtree
+    if paths.iter().any(|p| p.is_empty()) {
+        return value.clone();
+    }
+
+    match value {
+        Value::Object(map) => {
+            let mut out = Map::new();
+            for (key, child) in map {
+                let rest: Vec<Vec<&str>> = paths
+                    .iter()
+                    .filter(|p| p[0] == key)
+                    .map(|p| p[1..].to_vec())
+                    .collect();
+                if !rest.is_empty() {
+                    out.insert(key.clone(), filter_segments(child, &rest));
+                }
+            }
+            Value::Object(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|i| filter_segments(i, paths)).collect()),
+        // Path continues past a scalar, so nothing matches
+        _ => Value::Null,
+    }
+}
+
+/// Remove top-level keys from a JSON object
+pub fn redact_fields(value: &mut Value, keys: &[&str]) {
+    if let Value::Object(map) = value {
+        for key in keys {
+            map.remove(*key);
+        }
+    }
+}
+
+/// Key casing used when returning agreement JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonNamingStrategy {
+    #[default]
+    CamelCase,
+    SnakeCase,
+    PascalCase,
+}
+
+impl JsonNamingStrategy {
+    /// Re-case a single key, which may itself be camelCase or snake_case
+    pub fn apply(&self, key: &str) -> String {
+        let words = split_words(key);
+
+        match self {
+            JsonNamingStrategy::SnakeCase => words.join("_"),
+            JsonNamingStrategy::CamelCase | JsonNamingStrategy::PascalCase => {
+                let mut out = String::with_capacity(key.len());
+                for (i, word) in words.iter().enumerate() {
+                    if i == 0 && *self == JsonNamingStrategy::CamelCase {
+                        out.push_str(word);
+                    } else {
+                        let mut chars = word.chars();
+                        if let Some(first) = chars.next() {
+                            out.extend(first.to_uppercase());
+                            out.push_str(chars.as_str());
+                        }
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Lower-case words of a camelCase, PascalCase or snake_case key.
+/// Digits stay attached to the preceding word (`pdfSha256` -> `pdf`, `sha256`).
+fn split_words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map_or(false, |n| n.is_lowercase());
+            // Break on `aB`/`1B`, and before the last capital of an acronym (`IPFSUrl`)
+            if !prev.is_uppercase() || next_is_lower {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+
+        current.extend(c.to_lowercase());
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Recursively rename every object key using `strategy`
+pub fn rename_keys(value: &Value, strategy: JsonNamingStrategy) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (strategy.apply(k), rename_keys(v, strategy)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|i| rename_keys(i, strategy)).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Flatten JSON into `(dot.path, value)` pairs. Arrays are comma-joined,
+/// with arrays of objects joined per sub-path (`milestones.name`).
+pub fn flatten_to_rows(value: &Value) -> Vec<(String, String)> {
+    let mut rows: Vec<(String, Vec<String>)> = Vec::new();
+    flatten_into(value, "", &mut rows);
+
+    rows.into_iter()
+        .map(|(path, values)| (path, values.join(", ")))
+        .collect()
+}
+
+fn flatten_into(value: &Value, path: &str, rows: &mut Vec<(String, Vec<String>)>) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                flatten_into(child, &join(key), rows);
+            }
+        }
+        Value::Array(items) => {
+            if items.is_empty() {
+                push_row(rows, path, None);
+            }
+            for item in items {
+                flatten_into(item, path, rows);
+            }
+        }
+        Value::Null => push_row(rows, path, None),
+        Value::String(s) => push_row(rows, path, Some(s.clone())),
+        other => push_row(rows, path, Some(other.to_string())),
+    }
+}
+
+fn push_row(rows: &mut Vec<(String, Vec<String>)>, path: &str, value: Option<String>) {
+    let row = match rows.iter_mut().find(|(p, _)| p == path) {
+        Some(row) => row,
+        None => {
+            rows.push((path.to_string(), Vec::new()));
+            rows.last_mut().unwrap()
+        }
+    };
+    row.1.extend(value);
+}
+
+/// True if a flattened row path is selected by one of the field paths
+pub fn path_selected(row_path: &str, fields: &[&str]) -> bool {
+    fields.iter().any(|f| {
+        row_path == *f || (row_path.starts_with(f) && row_path[f.len()..].starts_with('.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_filter_nested_paths() {
+        let value = json!({
+            "content": { "title": "Kalki 2898 AD", "language": "Telugu" },
+            "parties": {
+                "licensor": { "name": "Vyjayanthi Movies", "address": "Hyderabad" },
+                "licensee": { "name": "Zee" }
+            },
+            "financial": { "dealValue": 100 }
+        });
+
+        let filtered = filter_json_fields(&value, &["content", "parties.licensor.name"]);
+
+        assert_eq!(
+            filtered,
+            json!({
+                "content": { "title": "Kalki 2898 AD", "language": "Telugu" },
+                "parties": { "licensor": { "name": "Vyjayanthi Movies" } }
+            })
+        );
+    }
+
+    #[test]
+    fn test_filter_through_arrays() {
+        let value = json!({
+            "milestones": [
+                { "name": "Signing", "amount": 10 },
+                { "name": "Delivery", "amount": 20 }
+            ]
+        });
+
+        let filtered = filter_json_fields(&value, &["milestones.name"]);
+
+        assert_eq!(
+            filtered,
+            json!({ "milestones": [{ "name": "Signing" }, { "name": "Delivery" }] })
+        );
+    }
+
+    #[test]
+    fn test_redact_financial_fields() {
+        let mut value = json!({ "title": "Test", "total_fee": 100, "financial": {} });
+        redact_fields(&mut value, FINANCIAL_FIELDS);
+        assert_eq!(value, json!({ "title": "Test" }));
+    }
+
+    #[test]
+    fn test_naming_strategies() {
+        assert_eq!(JsonNamingStrategy::SnakeCase.apply("pdfSha256"), "pdf_sha256");
+        assert_eq!(JsonNamingStrategy::SnakeCase.apply("agreementId"), "agreement_id");
+        assert_eq!(JsonNamingStrategy::SnakeCase.apply("IPFSUrl"), "ipfs_url");
+        assert_eq!(JsonNamingStrategy::PascalCase.apply("rightsHolder"), "RightsHolder");
+        assert_eq!(JsonNamingStrategy::CamelCase.apply("total_fee"), "totalFee");
+
+        let value = json!({ "rightsHolder": { "companyName": "X" }, "milestones": [{ "dueDate": "2025" }] });
+        assert_eq!(
+            rename_keys(&value, JsonNamingStrategy::SnakeCase),
+            json!({ "rights_holder": { "company_name": "X" }, "milestones": [{ "due_date": "2025" }] })
+        );
+    }
+
+    #[test]
+    fn test_flatten_to_rows() {
+        let value = json!({
+            "content": { "title": "Kalki 2898 AD", "genre": ["Sci-Fi", "Action"] },
+            "milestones": [{ "name": "Signing", "amount": 10 }, { "name": "Delivery", "amount": 20 }],
+            "exclusive": true
+        });
+
+        let rows = flatten_to_rows(&value);
+        let get = |p: &str| rows.iter().find(|(path, _)| path == p).map(|(_, v)| v.as_str());
+
+        assert_eq!(get("content.title"), Some("Kalki 2898 AD"));
+        assert_eq!(get("content.genre"), Some("Sci-Fi, Action"));
+        assert_eq!(get("milestones.name"), Some("Signing, Delivery"));
+        assert_eq!(get("milestones.amount"), Some("10, 20"));
+        assert_eq!(get("exclusive"), Some("true"));
+
+        assert!(path_selected("content.title", &["content"]));
+        assert!(!path_selected("contentType", &["content"]));
+    }
+}
@@ -0,0 +1,131 @@
+// src/log_sampling.rs - Per-request sampling of INFO and lower log lines
+use axum::body::Body;
+use axum::http::Request;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Level, Metadata, Span, Subscriber};
+use tracing_subscriber::layer::{Context, Filter};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Span field carrying the sampling decision for a request
+const SAMPLED_FIELD: &str = "sampled";
+
+/// Decides once per request whether its INFO, DEBUG and TRACE events are logged.
+/// WARN and ERROR are always logged, as is anything outside a request span.
+///
+/// Used both to create the request span (`request_span`) and as a per-layer
+/// filter on the log output layer.
+#[derive(Debug, Clone, Copy)]
+pub struct LogSampler {
+    sample_rate: f64,
+}
+
+/// Sampling decision stored in a request span's extensions
+struct Sampled(bool);
+
+impl LogSampler {
+    /// `sample_rate` is the fraction of requests to log, e.g. `0.1` for 10%
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            sample_rate: sample_rate.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    pub fn sample(&self) -> bool {
+        self.sample_rate >= 1.0 || rand::random::<f64>() < self.sample_rate
+    }
+
+    /// Root span for one HTTP request, carrying its sampling decision
+    pub fn request_span(&self, request: &Request<Body>) -> Span {
+        tracing::info_span!(
+            "request",
+            method = %request.method(),
+            uri = %request.uri(),
+            sampled = self.sample(),
+        )
+    }
+}
+
+struct SampledVisitor(Option<bool>);
+
+impl Visit for SampledVisitor {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        if field.name() == SAMPLED_FIELD {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+impl<S> Filter<S> for LogSampler
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        // Spans always pass so their decisions can be looked up; WARN and ERROR
+        // are the least verbose levels and are never sampled out
+        if !meta.is_event() || *meta.level() <= Level::WARN {
+            return true;
+        }
+
+        let Some(current) = cx.lookup_current() else {
+            return true;
+        };
+        current
+            .scope()
+            .find_map(|span| span.extensions().get::<Sampled>().map(|sampled| sampled.0))
+            .unwrap_or(true)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = SampledVisitor(None);
+        attrs.record(&mut visitor);
+
+        if let (Some(sampled), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(Sampled(sampled));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Layer;
+
+    struct CountingLayer(Arc<AtomicUsize>);
+
+    impl<S: Subscriber> Layer<S> for CountingLayer {
+        fn on_event(&self, _event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_unsampled_request_keeps_only_warnings() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let subscriber = tracing_subscriber::registry()
+            .with(CountingLayer(count.clone()).with_filter(LogSampler::new(0.0)));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", sampled = false);
+            span.in_scope(|| {
+                tracing::info!("dropped");
+                tracing::info_span!("extract").in_scope(|| tracing::debug!("dropped"));
+                tracing::warn!("kept");
+            });
+            tracing::info!("kept, outside any request");
+        });
+
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        assert!(LogSampler::new(1.0).sample());
+        assert!(!LogSampler::new(-1.0).sample());
+    }
+}
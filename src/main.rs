@@ -1,32 +1,80 @@
 // src/main.rs - Fixed version without await in closures
 mod models;
+mod normalization;
 mod pdf_extractor;
+mod ocr_preprocessing;
 mod llm_service;
 mod json_builder;
 mod encryption;
 mod ipfs_client;
+mod agreement_store;
+mod json_fields;
+mod integrity;
+mod agreement_diff;
+mod templates;
+mod worker;
+mod address_validation;
+mod ens_resolver;
+mod notifier;
+mod pdf_download;
+mod s3_storage;
+mod grpc;
+mod log_sampling;
+mod tmdb;
+#[cfg(test)]
+mod memory_store;
+mod api_keys;
+mod webhooks;
+mod flat_agreement;
+mod financial_report;
+mod agreement_comparator;
+mod blockchain;
+mod docx_extractor;
 
 use axum::{
-    body::Bytes,
-    extract::{Multipart, Path, Query, State},
-    http::StatusCode,
-    response::{IntoResponse, Json},
-    routing::{get, post},
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Extension, Multipart, Path, Query, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+    routing::{delete, get, post},
     Router,
 };
+use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::fs;
+use tokio::sync::{Mutex, Semaphore};
+use tokio_util::sync::CancellationToken;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::{error, info, warn};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
-use crate::pdf_extractor::PDFExtractor;
-use crate::llm_service::LLMService;
+use crate::docx_extractor::DocxExtractor;
+use crate::pdf_extractor::{PDFExtractor, PagedText, PdfDocumentMeta, TextExtractor};
+use crate::llm_service::{LLMService, OllamaModel, ParseOptions, RetryConfig};
+use crate::ens_resolver::EnsResolver;
 use crate::json_builder::JSONBuilder;
-use crate::encryption::EncryptionService;
-use crate::ipfs_client::IPFSClient;
+use crate::notifier::{AgreementSummary, EmailNotifier, NotificationEvent, SmtpConfig};
+use crate::s3_storage::{S3Config, S3Storage};
+use crate::tmdb::TmdbClient;
+use crate::webhooks::{AgreementParsedEvent, WebhookEmitter, WebhookEvent, WebhookSubscription};
+use crate::api_keys::{ApiKeyRecord, KeyQuota, QuotaRegistry, API_KEY_HEADER, QUOTA_REMAINING_HEADER};
+use crate::encryption::{CipherAlgorithm, EncryptionService, EncryptionServiceTrait, RekeyResult};
+use crate::financial_report::FinancialReport;
+use crate::agreement_comparator::{AgreementComparator, AgreementFingerprint, SimilarAgreement};
+use crate::blockchain::BlockchainClient;
+use crate::ipfs_client::{CdnGateways, GatewayBenchmarkReport, IPFSClient, IPFSClientTrait, IpfsBackend, PinPage};
+use crate::agreement_store::{AgreementRecord, AgreementStatistics, Cursor, NewAgreementRecord};
+use crate::json_fields::JsonNamingStrategy;
+use crate::log_sampling::LogSampler;
+use crate::templates::{AgreementTemplate, TemplateRegistry, DEFAULT_TEMPLATE};
+use crate::models::{BlockchainInfo, Deliverables, Financial, FlexibleDate, MergeStrategy, Metadata, Obligation, ReviewCostEstimate, Rights, RightsAgreementJSON};
 
 // Response structures
 #[derive(Serialize, Deserialize)]
@@ -35,9 +83,92 @@ struct ParseResponse {
     ipfs_url: String,
     encryption_key: String,
     ipfs_gateway_url: String,
+    /// The upload through the first configured CDN gateway
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cdn_url: Option<String>,
+    /// The upload through the other configured CDN gateways, to try if `cdn_url` is slow
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    alternative_gateways: Vec<String>,
+    pdf_sha256: String,
+    json_sha256: String,
+    /// Base64 `encryption_key` wrapped with the server's master key, when one
+    /// is configured. Kept apart from the content so keys can be rotated by
+    /// re-wrapping envelopes alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_envelope: Option<String>,
+    /// Author, creator and dates embedded in the uploaded PDF
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pdf_metadata: Option<PdfDocumentMeta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    days_until_deadline: Option<i64>,
+    /// Directory CID holding the source document and agreement, both
+    /// encrypted with `encryption_key`, and a manifest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundle_cid: Option<String>,
+    /// Non-fatal issues hit while processing, each `code: message`
+    #[serde(default)]
+    warnings: Vec<String>,
+    /// Required agreement fields the LLM left out and was asked for again
+    #[serde(default)]
+    validation_warnings: Vec<String>,
+    /// Previously parsed agreements this upload may duplicate
+    #[serde(default)]
+    similar_agreements: Vec<SimilarAgreement>,
     metadata: FileMetadata,
 }
 
+/// Collects non-fatal pipeline issues for `ParseResponse.warnings`. The code is
+/// a stable snake_case identifier clients can match on; the message is for people.
+#[derive(Debug, Default)]
+struct ParseWarnings(Vec<String>);
+
+impl ParseWarnings {
+    fn push(&mut self, code: &str, message: impl std::fmt::Display) {
+        warn!("⚠️  {}: {}", code, message);
+        self.0.push(format!("{}: {}", code, message));
+    }
+}
+
+/// Fields an agreement is expected to have, as (name, flat pointer, structured pointer)
+const COMPLETENESS_FIELDS: [(&str, &str, &str); 8] = [
+    ("title", "/title", "/content/title"),
+    ("licensor", "/licensor", "/parties/licensor/name"),
+    ("licensee", "/licensee", "/parties/licensee/name"),
+    ("territory", "/territory", "/rights/territories"),
+    ("rights", "/rights", "/rights/mediaTypes"),
+    ("term start", "/term_start", "/rights/term/startDate"),
+    ("term end", "/term_end", "/rights/term/endDate"),
+    ("deal value", "/total_fee", "/financial/dealValue"),
+];
+
+/// Share of `COMPLETENESS_FIELDS` below which a parse is flagged for review
+const MIN_COMPLETENESS_SCORE: f64 = 0.6;
+
+/// Model confidence below which a field is flagged for review
+const LOW_CONFIDENCE_THRESHOLD: f32 = 0.5;
+
+/// Fraction of expected fields the LLM filled in, and the names of those it missed
+fn completeness(agreement: &serde_json::Value) -> (f64, Vec<&'static str>) {
+    let missing: Vec<&str> = COMPLETENESS_FIELDS
+        .iter()
+        .filter(|(_, flat, structured)| {
+            // Structured first: the flat `/rights` pointer would match the structured rights object
+            let value = agreement.pointer(structured).or_else(|| agreement.pointer(flat));
+            match value {
+                None | Some(serde_json::Value::Null) => true,
+                Some(serde_json::Value::String(s)) => s.trim().is_empty() || s.eq_ignore_ascii_case("unknown"),
+                Some(serde_json::Value::Array(a)) => a.is_empty(),
+                Some(serde_json::Value::Number(n)) => n.as_f64() == Some(0.0),
+                _ => false,
+            }
+        })
+        .map(|(name, _, _)| *name)
+        .collect();
+
+    let score = 1.0 - missing.len() as f64 / COMPLETENESS_FIELDS.len() as f64;
+    (score, missing)
+}
+
 #[derive(Serialize, Deserialize)]
 struct FileMetadata {
     file_name: String,
@@ -45,11 +176,400 @@ struct FileMetadata {
     processed_at: String,
     model_used: String,
     processing_time_ms: u64,
+    /// `pdf`, `pdftotext` or `ocr` for PDFs, `docx` for Word documents, `provided` for direct mode
+    extraction_method: String,
+    /// Template the agreement was parsed with, e.g. `film` or `music`
+    agreement_type: String,
+}
+
+/// Fields collected from a `POST /api/parse` multipart upload
+struct ParseUpload {
+    file_bytes: Option<Bytes>,
+    file_name: String,
+    /// Content type the client sent with the file part
+    content_type: Option<String>,
+    priority: Option<String>,
+    webhook_url: Option<String>,
+    password: Option<String>,
+    /// Pre-extracted text to parse instead of the PDF (direct mode)
+    extracted_text: Option<String>,
+    /// Skips type detection when set
+    agreement_type: Option<String>,
+    /// `https://` URL to download the PDF from instead of uploading it
+    pdf_url: Option<String>,
+    /// Fill missing content fields from TMDb
+    enrich: bool,
+    /// Ask the model to rate its confidence in each extracted field
+    confidence: bool,
+    /// Dot paths to encrypt individually, leaving the rest of the agreement
+    /// readable. Empty means the whole document is encrypted.
+    encrypt_fields: Vec<String>,
+    /// Receives estimated LLM progress in percent, see `parse_stream_handler`
+    progress: Option<tokio::sync::mpsc::Sender<u32>>,
+    /// API key the upload was made with; only its webhooks hear about the result
+    api_key_id: Option<uuid::Uuid>,
+}
+
+#[derive(Deserialize, Default)]
+struct ParseQuery {
+    #[serde(default)]
+    enrich: bool,
+    #[serde(default)]
+    confidence: bool,
+}
+
+impl Default for ParseUpload {
+    fn default() -> Self {
+        Self {
+            file_bytes: None,
+            file_name: String::from("document.pdf"),
+            content_type: None,
+            priority: None,
+            webhook_url: None,
+            password: None,
+            extracted_text: None,
+            agreement_type: None,
+            pdf_url: None,
+            enrich: false,
+            confidence: false,
+            encrypt_fields: Vec::new(),
+            progress: None,
+            api_key_id: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct UploadUrlResponse {
+    upload_url: String,
+    s3_key: String,
+    method: &'static str,
+    content_type: &'static str,
+    expires_in_secs: u64,
+}
+
+#[derive(Deserialize)]
+struct ParseFromS3Request {
+    s3_key: String,
+    webhook_url: Option<String>,
+}
+
+#[derive(Serialize)]
+struct QueuedJobResponse {
+    job_id: uuid::Uuid,
+    status: &'static str,
 }
 
 #[derive(Deserialize)]
 struct DecryptQuery {
+    #[serde(default)]
+    key: String,
+    /// Decrypt content encrypted with a passphrase instead of a `key`
+    passphrase: Option<String>,
+    /// Comma-separated dot-notation paths to return, e.g. `content,parties.licensor.name`
+    fields: Option<String>,
+    #[serde(default)]
+    include_financial: bool,
+    /// Key casing of the returned JSON
+    naming: Option<JsonNamingStrategy>,
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    key: String,
+    format: Option<String>,
+    /// Comma-separated dot-notation paths to export, as for decrypt
+    fields: Option<String>,
+    #[serde(default)]
+    include_financial: bool,
+}
+
+#[derive(Deserialize)]
+struct FinancialReportQuery {
+    key: String,
+    /// `json` (default) or `pdf`
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MergeRequest {
+    cids: Vec<String>,
+    keys: Vec<String>,
+    strategy: MergeStrategy,
+    /// Key casing of the returned agreement; the stored copy stays camelCase
+    #[serde(default)]
+    naming: JsonNamingStrategy,
+}
+
+#[derive(Deserialize)]
+struct ConflictCheckRequest {
+    cids: Vec<String>,
+    keys: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ConflictCheckResponse {
+    has_conflict: bool,
+    overlap_period: Option<String>,
+    overlapping_territories: Vec<String>,
+}
+
+/// A newly encrypted agreement and where it was uploaded
+#[derive(Serialize)]
+struct StoredAgreementResponse {
+    ipfs_cid: String,
+    ipfs_url: String,
+    ipfs_gateway_url: String,
+    encryption_key: String,
+    /// Rendered with the requested `JsonNamingStrategy`
+    agreement: Box<serde_json::value::RawValue>,
+}
+
+#[derive(Deserialize)]
+struct RenewalRequest {
+    key: String,
+    extension_years: u32,
+    new_deal_value: Option<u64>,
+    /// Key casing of the returned agreement; the stored copy stays camelCase
+    #[serde(default)]
+    naming: JsonNamingStrategy,
+    effective_date: String,
+}
+
+#[derive(Deserialize)]
+struct VerifyQuery {
+    key: String,
+    pdf_hash: String,
+}
+
+#[derive(Serialize, Default)]
+struct VerificationChecks {
+    ipfs_retrievable: bool,
+    key_valid: bool,
+    pdf_hash_match: bool,
+    json_hash_match: bool,
+}
+
+#[derive(Serialize)]
+struct VerifyResponse {
+    verified: bool,
+    checks: VerificationChecks,
+}
+
+#[derive(Deserialize)]
+struct BlockchainExportRequest {
+    key: String,
+    /// Defaults to the rights holder's resolved wallet address
+    licensor_address: Option<String>,
+    licensee_address: String,
+}
+
+#[derive(Serialize)]
+struct BlockchainExportResponse {
+    /// The CID whose content hash was registered on-chain
+    anchored_cid: String,
+    /// The agreement re-uploaded with the transaction recorded in its metadata
+    ipfs_cid: String,
+    ipfs_url: String,
+    ipfs_gateway_url: String,
+    encryption_key: String,
+    blockchain: BlockchainInfo,
+}
+
+#[derive(Deserialize)]
+struct TagsRequest {
+    key: String,
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RegisterWebhookRequest {
+    url: String,
+    events: Vec<WebhookEvent>,
+}
+
+#[derive(Deserialize)]
+struct RekeyRequest {
+    key: String,
+}
+
+#[derive(Deserialize)]
+struct ShareRequest {
+    key: String,
+    passphrase: String,
+}
+
+#[derive(Serialize)]
+struct ShareResponse {
+    /// Copy of the agreement that opens with the passphrase
+    shared_cid: String,
+    ipfs_url: String,
+    ipfs_gateway_url: String,
+}
+
+#[derive(Serialize)]
+struct RekeyResponse {
+    #[serde(flatten)]
+    result: RekeyResult,
+    ipfs_url: String,
+    ipfs_gateway_url: String,
+    /// When the rotation was recorded in `key_rotations`; absent if recording failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rotated_at: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TagsResponse {
+    ipfs_cid: String,
+    ipfs_url: String,
+    ipfs_gateway_url: String,
+    encryption_key: String,
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct NotificationRequest {
+    cid: String,
+    key: String,
+    event_type: String,
+    recipient_emails: Vec<String>,
+    message: Option<String>,
+}
+
+#[derive(Serialize)]
+struct NotificationQueuedResponse {
+    ipfs_cid: String,
+    event_type: String,
+    recipients: usize,
+    status: &'static str,
+}
+
+#[derive(Deserialize)]
+struct BulkStatusRequest {
+    cids: Vec<String>,
+    keys: Vec<String>,
+    new_status: String,
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BulkStatusResponse {
+    job_id: uuid::Uuid,
+}
+
+#[derive(Deserialize)]
+struct ReviewCostQuery {
+    key: String,
+    rate: f64,
+}
+
+#[derive(Serialize)]
+struct ReviewCostResponse {
+    ipfs_cid: String,
+    hourly_rate: f64,
+    #[serde(flatten)]
+    estimate: ReviewCostEstimate,
+}
+
+#[derive(Deserialize)]
+struct SummaryQuery {
+    key: String,
+}
+
+#[derive(Serialize)]
+struct PartyContactResponse {
+    role: String,
+    name: String,
+    email: Option<String>,
+    signatory_name: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SummaryResponse {
+    ipfs_cid: String,
+    summary: String,
+    word_count: usize,
+}
+
+#[derive(Deserialize)]
+struct ListAgreementsQuery {
+    after: Option<String>,
+    limit: Option<i64>,
+    tag: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ChainOfTitleResponse {
+    chain: Vec<agreement_store::ChainLink>,
+}
+
+#[derive(Deserialize)]
+struct ObligationsQuery {
     key: String,
+    /// `licensor` or `licensee`; all obligations when omitted
+    party: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ObligationsResponse {
+    ipfs_cid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    party: Option<String>,
+    obligations: Vec<Obligation>,
+}
+
+#[derive(Deserialize)]
+struct ListPinsQuery {
+    after: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct BenchmarkQuery {
+    cid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExpiringQuery {
+    days: Option<i32>,
+    status: Option<String>,
+    after: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct ExpiringAgreementsResponse {
+    data: Vec<agreement_store::ExpiringAgreement>,
+    next_cursor: Option<String>,
+    has_more: bool,
+}
+
+#[derive(Serialize)]
+struct ListAgreementsResponse {
+    data: Vec<AgreementRecord>,
+    next_cursor: Option<String>,
+    has_more: bool,
+}
+
+#[derive(Serialize)]
+struct ModelSummary {
+    #[serde(flatten)]
+    model: OllamaModel,
+    compatible: bool,
+    is_active: bool,
+}
+
+#[derive(Serialize)]
+struct ModelsResponse {
+    current_model: String,
+    available_models: Vec<ModelSummary>,
+}
+
+#[derive(Serialize)]
+struct TemplateDetail<'a> {
+    #[serde(flatten)]
+    template: &'a AgreementTemplate,
+    prompt_prefix: Option<&'a str>,
 }
 
 #[derive(Serialize)]
@@ -57,6 +577,9 @@ struct HealthResponse {
     status: String,
     timestamp: String,
     services: ServiceHealth,
+    /// Gateways IPFS reads go through, in configured order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ipfs_gateways: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -78,19 +601,130 @@ struct AppState {
     pdf_extractor: Arc<PDFExtractor>,
     llm_service: Arc<LLMService>,
     json_builder: Arc<JSONBuilder>,
-    encryption_service: Arc<EncryptionService>,
-    ipfs_client: Arc<IPFSClient>,
+    ens_resolver: Arc<EnsResolver>,
+    email_notifier: Arc<EmailNotifier>,
+    encryption_service: Arc<dyn EncryptionServiceTrait>,
+    ipfs_client: Arc<dyn IPFSClientTrait>,
+    db: PgPool,
+    upload_field_names: Arc<Vec<String>>,
+    llm_semaphore: Arc<Semaphore>,
+    allow_direct_mode: bool,
+    template_registry: Arc<TemplateRegistry>,
+    statistics_cache: Arc<Mutex<Option<(std::time::Instant, AgreementStatistics)>>>,
+    bulk_jobs: worker::BulkJobRegistry,
+    enable_clause_analysis: bool,
+    enable_obligation_extraction: bool,
+    enable_entity_hints: bool,
+    max_upload_bytes: usize,
+    s3_storage: Option<Arc<S3Storage>>,
+    blockchain_client: Option<Arc<BlockchainClient>>,
+    tmdb_client: Option<Arc<TmdbClient>>,
+    /// `MASTER_ENCRYPTION_KEY`, which wraps each document key into `ParseResponse.key_envelope`
+    master_encryption_key: Option<Arc<Vec<u8>>>,
+    require_api_key: bool,
+    key_quotas: QuotaRegistry,
+    webhooks: Arc<WebhookEmitter>,
+    pdf_reports: bool,
+    cdn_gateways: CdnGateways,
+    /// Cancelled on SIGTERM; new HTTP requests are then refused
+    shutdown_token: CancellationToken,
+    /// HTTP requests currently being handled
+    active_requests: Arc<AtomicU64>,
+    /// Bounds concurrent passphrase key derivations, see `run_key_derivation`
+    kdf_semaphore: Arc<Semaphore>,
+}
+
+/// Passphrase key derivations allowed at once; each may use up to 256 MiB
+const MAX_CONCURRENT_KEY_DERIVATIONS: usize = 2;
+
+/// Shortest passphrase `/api/agreements/:cid/share` accepts
+const MIN_PASSPHRASE_CHARS: usize = 12;
+
+/// Default for SHUTDOWN_TIMEOUT_SECS, long enough for a 70B LLM call to finish
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 120;
+
+/// How often the drain checks whether in-flight requests have finished
+const DRAIN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+impl AppState {
+    /// Refuse new requests and wait up to `timeout` for in-flight ones to
+    /// finish. Returns how many were still running when the wait ended.
+    async fn shutdown(&self, timeout: std::time::Duration) -> u64 {
+        self.shutdown_token.cancel();
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let active = self.active_requests.load(Ordering::SeqCst);
+            if active == 0 || tokio::time::Instant::now() >= deadline {
+                return active;
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+impl AppState {
+    /// State backed by `MemoryStore` and `MemoryEncryptionService`, needing no
+    /// IPFS node or Ollama. The database pool connects lazily, so only handlers
+    /// that touch the database need a real one.
+    fn in_memory() -> Self {
+        let ens_resolver = Arc::new(EnsResolver::default());
+        let db = PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/rights_parser_test")
+            .expect("valid test database URL");
+        Self {
+            pdf_extractor: Arc::new(PDFExtractor::default()),
+            llm_service: Arc::new(LLMService::new("http://localhost:11434".to_string(), "rights-parser".to_string())),
+            json_builder: Arc::new(JSONBuilder::new(ens_resolver.clone())),
+            ens_resolver,
+            email_notifier: Arc::new(EmailNotifier::new(None)),
+            encryption_service: Arc::new(memory_store::MemoryEncryptionService::new()),
+            ipfs_client: Arc::new(memory_store::MemoryStore::new()),
+            webhooks: Arc::new(WebhookEmitter::new(db.clone())),
+            db,
+            upload_field_names: Arc::new(vec!["file".to_string()]),
+            llm_semaphore: Arc::new(Semaphore::new(1)),
+            allow_direct_mode: true,
+            template_registry: Arc::new(TemplateRegistry::new()),
+            statistics_cache: Arc::new(Mutex::new(None)),
+            bulk_jobs: Arc::new(Mutex::new(std::collections::HashMap::new())),
+            enable_clause_analysis: false,
+            enable_obligation_extraction: false,
+            enable_entity_hints: false,
+            max_upload_bytes: 25 * 1024 * 1024,
+            s3_storage: None,
+            blockchain_client: None,
+            tmdb_client: None,
+            master_encryption_key: None,
+            require_api_key: false,
+            key_quotas: QuotaRegistry::default(),
+            pdf_reports: false,
+            cdn_gateways: CdnGateways::default(),
+            shutdown_token: CancellationToken::new(),
+            active_requests: Arc::new(AtomicU64::new(0)),
+            kdf_semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_KEY_DERIVATIONS)),
+        }
+    }
 }
 
+/// How long `/api/agreements/statistics` results are reused
+const STATISTICS_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
 #[tokio::main]
 async fn main() {
-    // Initialize tracing
+    // Initialize tracing. Sampling is set up first so it applies from the first line.
+    let log_sample_rate: f64 = std::env::var("LOG_SAMPLE_RATE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1.0);
+    let log_sampler = LogSampler::new(log_sample_rate);
+
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "rights_agreement_parser=info,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_filter(log_sampler))
         .init();
 
     info!("🚀 Starting Rights Parser API Server");
@@ -98,47 +732,382 @@ async fn main() {
     // Load configuration from environment
     let ollama_url = std::env::var("OLLAMA_URL")
         .unwrap_or_else(|_| "http://localhost:11434".to_string());
+    // Tried in order when a model is out of memory or overloaded
+    let ollama_models: Vec<String> = std::env::var("OLLAMA_MODELS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
     let ollama_model = std::env::var("OLLAMA_MODEL")
-        .unwrap_or_else(|_| "rights-parser".to_string());
+        .ok()
+        .or_else(|| ollama_models.first().cloned())
+        .unwrap_or_else(|| "rights-parser".to_string());
     let ipfs_url = std::env::var("IPFS_URL")
         .unwrap_or_else(|_| "http://localhost:5001".to_string());
     let pinata_jwt = std::env::var("PINATA_JWT").ok();
+    let w3s_token = std::env::var("W3S_TOKEN").ok();
+    let ipfs_backend = std::env::var("IPFS_BACKEND")
+        .ok()
+        .map(|v| IpfsBackend::parse(&v).expect("IPFS_BACKEND must be local, pinata or web3storage"));
+    let ipfs_gateways: Vec<String> = std::env::var("IPFS_GATEWAYS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|g| g.trim().to_string())
+        .filter(|g| !g.is_empty())
+        .collect();
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "postgresql://localhost:5432/rights_parser".to_string());
+    let upload_field_names: Vec<String> = std::env::var("UPLOAD_FIELD_NAMES")
+        .unwrap_or_else(|_| "file,document,pdf,upload".to_string())
+        .split(',')
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .collect();
+    let max_concurrent_llm_requests = std::env::var("MAX_CONCURRENT_LLM_REQUESTS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(2);
+    let shutdown_timeout_secs = std::env::var("SHUTDOWN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS);
+    let max_upload_bytes = std::env::var("MAX_UPLOAD_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(25 * 1024 * 1024);
+    let agreement_type_models = std::env::var("AGREEMENT_TYPE_MODELS").unwrap_or_default();
+    let delivery_notice_days = std::env::var("DELIVERY_NOTICE_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i32>().ok())
+        .unwrap_or(14);
+    let allow_direct_mode = std::env::var("ALLOW_DIRECT_MODE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    let enable_clause_analysis = std::env::var("ENABLE_CLAUSE_ANALYSIS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    let enable_obligation_extraction = std::env::var("ENABLE_OBLIGATION_EXTRACTION")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    let enable_entity_hints = std::env::var("ENABLE_ENTITY_HINTS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    let require_api_key = std::env::var("REQUIRE_API_KEY")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    let temp_dir = std::env::var("TEMP_DIR")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| pdf_extractor::DEFAULT_TEMP_DIR.to_string());
+    let default_retry = RetryConfig::default();
+    let llm_retry = RetryConfig {
+        max_attempts: std::env::var("LLM_RETRY_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(default_retry.max_attempts),
+        initial_delay_ms: std::env::var("LLM_RETRY_INITIAL_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default_retry.initial_delay_ms),
+        multiplier: std::env::var("LLM_RETRY_MULTIPLIER")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|m: &f64| *m >= 1.0)
+            .unwrap_or(default_retry.multiplier),
+        max_delay_ms: std::env::var("LLM_RETRY_MAX_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default_retry.max_delay_ms),
+    };
+    let max_prompt_tokens = std::env::var("LLM_MAX_PROMPT_TOKENS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(llm_service::DEFAULT_MAX_PROMPT_TOKENS);
+    let cipher_algorithm = std::env::var("ENCRYPTION_ALGORITHM")
+        .ok()
+        .and_then(|v| CipherAlgorithm::parse(&v))
+        .unwrap_or_default();
+    let llm_cache_size = std::env::var("LLM_CACHE_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(llm_service::DEFAULT_CACHE_SIZE);
+    let llm_cache_ttl_seconds = std::env::var("LLM_CACHE_TTL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(llm_service::DEFAULT_CACHE_TTL_SECONDS);
+    let running_element_threshold = std::env::var("RUNNING_ELEMENT_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|t| (0.0..=1.0).contains(t))
+        .unwrap_or(pdf_extractor::DEFAULT_RUNNING_ELEMENT_THRESHOLD);
+    let pdf_reports = std::env::var("PDF_REPORTS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    // CDN_GATEWAY_TEMPLATE is a comma-separated list of `{cid}` templates
+    let mut cdn_templates: Vec<String> = std::env::var("CLOUDFLARE_IPFS_GATEWAY").into_iter().collect();
+    cdn_templates.extend(std::env::var("CDN_GATEWAY_TEMPLATE").unwrap_or_default().split(',').map(str::to_string));
+    let cdn_gateways = CdnGateways::new(cdn_templates);
+    let ethereum_rpc_url = std::env::var("ETHEREUM_RPC_URL").ok().filter(|v| !v.is_empty());
+    let registry_contract_address = std::env::var("RIGHTS_REGISTRY_CONTRACT_ADDRESS").ok().filter(|v| !v.is_empty());
+    let deployer_private_key = std::env::var("DEPLOYER_PRIVATE_KEY").ok().filter(|v| !v.is_empty());
+    let tmdb_api_key = std::env::var("TMDB_API_KEY").ok().filter(|v| !v.is_empty());
+    // Wraps each document key so keys can later be rotated server-side
+    let master_encryption_key = std::env::var("MASTER_ENCRYPTION_KEY")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .map(|key| Arc::new(encryption::decode_key(key.trim()).expect("MASTER_ENCRYPTION_KEY must be a base64 256-bit key")));
+    let smtp_config = std::env::var("SMTP_HOST").ok().filter(|h| !h.is_empty()).map(|host| SmtpConfig {
+        host,
+        port: std::env::var("SMTP_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(587),
+        user: std::env::var("SMTP_USER").ok(),
+        password: std::env::var("SMTP_PASSWORD").ok(),
+        from: std::env::var("SMTP_FROM").ok(),
+        allowed_recipients: std::env::var("NOTIFY_ALLOWED_RECIPIENTS")
+            .map(|list| list.split(',').map(|r| r.trim().to_string()).filter(|r| !r.is_empty()).collect())
+            .unwrap_or_default(),
+    });
+    // All four variables are required for presigned uploads
+    let s3_config = match (
+        std::env::var("AWS_BUCKET"),
+        std::env::var("AWS_REGION"),
+        std::env::var("AWS_ACCESS_KEY_ID"),
+        std::env::var("AWS_SECRET_ACCESS_KEY"),
+    ) {
+        (Ok(bucket), Ok(region), Ok(access_key_id), Ok(secret_access_key)) => Some(S3Config {
+            bucket,
+            region,
+            access_key_id,
+            secret_access_key,
+        }),
+        _ => None,
+    };
+    let grpc_port = std::env::var("PORT_GRPC")
+        .unwrap_or_else(|_| "9090".to_string())
+        .parse::<u16>()
+        .unwrap_or(9090);
     let server_port = std::env::var("PORT")
         .unwrap_or_else(|_| "8080".to_string())
         .parse::<u16>()
         .unwrap_or(8080);
 
+    let ipfs = IPFSClient::for_backend(ipfs_backend, ipfs_url.clone(), pinata_jwt.clone(), w3s_token)
+        .expect("Invalid IPFS backend configuration")
+        .with_gateways(ipfs_gateways);
+
     info!("⚙️  Configuration:");
     info!("   Ollama URL: {}", ollama_url);
     info!("   Ollama Model: {}", ollama_model);
+    info!("   Model fallbacks: {}", if ollama_models.is_empty() { "None".to_string() } else { ollama_models.join(", ") });
     info!("   IPFS URL: {}", ipfs_url);
     info!("   Pinata: {}", if pinata_jwt.is_some() { "Enabled" } else { "Disabled" });
+    info!("   IPFS backend: {}", ipfs.backend().name());
+    info!("   IPFS gateways: {}", ipfs.gateway_urls().join(", "));
+    info!("   Upload fields: {}", upload_field_names.join(", "));
+    info!("   Max concurrent LLM requests: {}", max_concurrent_llm_requests);
+    info!("   Max upload size: {} bytes", max_upload_bytes);
+    info!("   Shutdown drain timeout: {}s", shutdown_timeout_secs);
+    if !agreement_type_models.is_empty() {
+        info!("   Agreement type models: {}", agreement_type_models);
+    }
+    info!("   Delivery notice: {} days", delivery_notice_days);
+    info!("   Direct mode: {}", if allow_direct_mode { "Enabled" } else { "Disabled" });
+    info!("   Clause analysis: {}", if enable_clause_analysis { "Enabled" } else { "Disabled" });
+    info!("   Obligation extraction: {}", if enable_obligation_extraction { "Enabled" } else { "Disabled" });
+    info!("   Entity hints: {}", if enable_entity_hints { "Enabled" } else { "Disabled" });
+    info!("   API keys: {}", if require_api_key { "Required" } else { "Optional" });
+    info!("   PDF reports: {}", if pdf_reports { "Enabled" } else { "Disabled" });
+    info!("   Temp directory: {}", temp_dir);
+    info!("   LLM prompt budget: {} tokens", max_prompt_tokens);
+    info!("   Cipher: {}", cipher_algorithm.name());
+    if llm_cache_size > 0 {
+        info!("   LLM parse cache: {} entries for {}s", llm_cache_size, llm_cache_ttl_seconds);
+    } else {
+        info!("   LLM parse cache: Disabled");
+    }
+    info!(
+        "   LLM retries: {} attempts, {}ms x{} up to {}ms",
+        llm_retry.max_attempts, llm_retry.initial_delay_ms, llm_retry.multiplier, llm_retry.max_delay_ms
+    );
+    info!("   Running header/footer threshold: {:.0}% of pages", running_element_threshold * 100.0);
+    if cdn_gateways.is_empty() {
+        info!("   CDN gateways: None");
+    } else {
+        info!("   CDN gateways: {}", cdn_gateways.templates().join(", "));
+    }
+    info!("   ENS resolution: {}", if ethereum_rpc_url.is_some() { "Enabled" } else { "Disabled" });
+    match (&ethereum_rpc_url, &registry_contract_address, &deployer_private_key) {
+        (Some(_), Some(contract), Some(_)) => info!("   Blockchain export: {}", contract),
+        _ => info!("   Blockchain export: Disabled"),
+    }
+    info!("   TMDb enrichment: {}", if tmdb_api_key.is_some() { "Enabled" } else { "Disabled" });
+    info!("   Key envelopes: {}", if master_encryption_key.is_some() { "Enabled" } else { "Disabled" });
+    match &s3_config {
+        Some(s3) => info!("   S3 uploads: {} ({})", s3.bucket, s3.region),
+        None => info!("   S3 uploads: Disabled"),
+    }
+    match &smtp_config {
+        Some(smtp) => info!("   SMTP: {}:{}", smtp.host, smtp.port),
+        None => info!("   SMTP: Disabled"),
+    }
+    info!("   Log sample rate: {}", log_sampler.sample_rate());
     info!("   Port: {}", server_port);
+    info!("   gRPC port: {}", grpc_port);
 
     // Initialize services
-    let pdf_extractor = Arc::new(PDFExtractor::new());
-    let llm_service = Arc::new(LLMService::new(ollama_url.clone(), ollama_model.clone()));
-    let json_builder = Arc::new(JSONBuilder::new());
-    let encryption_service = Arc::new(EncryptionService::new());
-    let ipfs_client = Arc::new(IPFSClient::new(ipfs_url, pinata_jwt));
+    let pdf_extractor = Arc::new(
+        PDFExtractor::with_temp_dir(&temp_dir)
+            .expect("TEMP_DIR must be a writable directory")
+            .with_running_element_threshold(running_element_threshold),
+    );
+    let llm_service = Arc::new(
+        LLMService::new(ollama_url.clone(), ollama_model.clone())
+            .with_retry_config(llm_retry)
+            .with_max_prompt_tokens(max_prompt_tokens)
+            .with_cache(llm_cache_size, llm_cache_ttl_seconds)
+            .with_fallback_models(ollama_models),
+    );
+    let blockchain_client = match (&ethereum_rpc_url, registry_contract_address, deployer_private_key) {
+        (Some(rpc_url), Some(contract), Some(key)) => Some(Arc::new(
+            BlockchainClient::new(rpc_url, &contract, &key).expect("Invalid blockchain export configuration"),
+        )),
+        _ => None,
+    };
+    let ens_resolver = Arc::new(EnsResolver::new(ethereum_rpc_url));
+    let email_notifier = Arc::new(EmailNotifier::new(smtp_config));
+    let s3_storage = s3_config.map(|config| Arc::new(S3Storage::new(config)));
+    let tmdb_client = tmdb_api_key.map(|key| Arc::new(TmdbClient::new(key)));
+    let encryption_service: Arc<dyn EncryptionServiceTrait> = Arc::new(EncryptionService::with_algorithm(cipher_algorithm));
+    let ipfs_client: Arc<dyn IPFSClientTrait> = Arc::new(ipfs);
+
+    // Connect lazily so the API still starts while the database is unavailable
+    let db = PgPoolOptions::new()
+        .max_connections(5)
+        .connect_lazy(&database_url)
+        .expect("Invalid DATABASE_URL");
+    let json_builder = Arc::new(JSONBuilder::new(ens_resolver.clone()).with_db(db.clone()));
+
+    let webhooks = Arc::new(WebhookEmitter::new(db.clone()));
 
     let state = AppState {
         pdf_extractor,
         llm_service,
         json_builder,
+        ens_resolver,
+        email_notifier,
         encryption_service,
         ipfs_client,
+        db,
+        upload_field_names: Arc::new(upload_field_names),
+        llm_semaphore: Arc::new(Semaphore::new(max_concurrent_llm_requests)),
+        allow_direct_mode,
+        template_registry: Arc::new(TemplateRegistry::new().with_model_overrides(&agreement_type_models)),
+        statistics_cache: Arc::new(Mutex::new(None)),
+        bulk_jobs: Arc::new(Mutex::new(std::collections::HashMap::new())),
+        enable_clause_analysis,
+        enable_obligation_extraction,
+        enable_entity_hints,
+        max_upload_bytes,
+        s3_storage,
+        blockchain_client,
+        tmdb_client,
+        master_encryption_key,
+        require_api_key,
+        key_quotas: QuotaRegistry::default(),
+        webhooks,
+        pdf_reports,
+        cdn_gateways,
+        shutdown_token: CancellationToken::new(),
+        active_requests: Arc::new(AtomicU64::new(0)),
+        kdf_semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_KEY_DERIVATIONS)),
     };
+    let shutdown_state = state.clone();
+
+    tokio::spawn(worker::start_delivery_monitor(state.clone(), delivery_notice_days));
+    tokio::spawn(worker::start_temp_file_cleanup(state.clone()));
+    // S3 uploads are the only source of queued jobs
+    if state.s3_storage.is_some() {
+        tokio::spawn(worker::start_worker(state.clone()));
+    }
+
+    // gRPC runs on its own port and shares the same state
+    let grpc_addr = std::net::SocketAddr::from(([0, 0, 0, 0], grpc_port));
+    let grpc_service = grpc::server(state.clone());
+    tokio::spawn(async move {
+        info!("✅ gRPC server listening on {}", grpc_addr);
+        if let Err(e) = tonic::transport::Server::builder()
+            .add_service(grpc_service)
+            .serve(grpc_addr)
+            .await
+        {
+            error!("gRPC server failed: {}", e);
+        }
+    });
+
+    // Parsing endpoints count against the caller's daily API key quota
+    let parse_routes = Router::new()
+        .route("/api/parse", post(parse_pdf_handler))
+        .route("/api/parse/stream", post(parse_stream_handler))
+        .route("/api/parse/from-s3", post(parse_from_s3_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), enforce_parse_quota));
+
+    // Always need a valid X-API-Key; each caller only sees its own webhooks.
+    // Blockchain exports spend the deployer's gas, so they are keyed too.
+    let keyed_routes = Router::new()
+        .route("/api/webhooks", post(register_webhook_handler).get(list_webhooks_handler))
+        .route("/api/webhooks/:id", delete(delete_webhook_handler))
+        .route("/api/agreements/:cid/export-blockchain", post(export_blockchain_handler))
+        .route("/api/agreements/:cid/notify", post(notify_agreement_handler))
+        .route("/api/agreements/bulk-status-update", post(bulk_status_update_handler))
+        .route("/api/agreements/bulk-status-update/:job_id", get(bulk_status_job_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), authenticate_api_key));
 
     // Build router
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/api/parse", post(parse_pdf_handler))
+        .route("/api/models", get(list_models_handler))
+        .route("/api/cache/stats", get(cache_stats_handler))
+        .merge(parse_routes)
+        .route("/api/parse/request-upload-url", post(request_upload_url_handler))
+        .route("/api/parse/templates", get(list_templates_handler))
+        .route("/api/parse/templates/:name", get(get_template_handler))
         .route("/api/decrypt/:cid", get(decrypt_handler))
+        .route("/api/agreements/:cid/decrypt-stream", get(decrypt_stream_handler))
         .route("/api/status/:cid", get(status_handler))
+        .route("/api/pins", get(list_pins_handler))
+        .route("/api/gateways/benchmark", get(benchmark_gateways_handler))
+        .route("/api/agreements", get(list_agreements_handler))
+        .route("/api/agreements/merge", post(merge_agreements_handler))
+        .route("/api/agreements/check-conflict", post(check_conflict_handler))
+        .route("/api/agreements/expiring", get(list_expiring_handler))
+        .route("/api/agreements/statistics", get(statistics_handler))
+        .route("/api/agreements/:cid/renew", post(renew_agreement_handler))
+        .route("/api/agreements/:cid/verify", get(verify_agreement_handler))
+        .route("/api/agreements/:cid/export", get(export_agreement_handler))
+        .route("/api/agreements/:cid/financial-report", get(financial_report_handler))
+        .route("/api/agreements/:cid/tags", post(tag_agreement_handler))
+        .route("/api/agreements/:cid/share", post(share_agreement_handler))
+        .route("/api/rotate-key/:cid", post(rekey_agreement_handler))
+        .route("/api/agreements/:cid/summary", get(summary_handler))
+        .route("/api/agreements/:cid/parties/:role/contact", get(party_contact_handler))
+        .route("/api/agreements/:cid/review-cost", get(review_cost_handler))
+        .route("/api/agreements/:cid/chain-of-title", get(chain_of_title_handler))
+        .route("/api/agreements/:cid/amendments/:number", get(amendment_version_handler))
+        .route("/api/agreements/:cid/obligations", get(obligations_handler))
+        .merge(keyed_routes)
+        .layer(middleware::from_fn_with_state(state.clone(), track_active_requests))
         .with_state(state)
+        .layer(DefaultBodyLimit::max(max_upload_bytes))
         .layer(CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any))
-        .layer(TraceLayer::new_for_http());
+        .layer(TraceLayer::new_for_http().make_span_with(move |request: &axum::http::Request<Body>| {
+            log_sampler.request_span(request)
+        }));
 
     // Start server
     let addr = format!("0.0.0.0:{}", server_port);
@@ -148,14 +1117,94 @@ async fn main() {
 
     info!("✅ Server listening on http://{}", addr);
     info!("📖 API Documentation:");
-    info!("   POST /api/parse - Upload and parse PDF");
-    info!("   GET  /api/decrypt/:cid?key=... - Decrypt and view result");
+    info!("   POST /api/parse?enrich=true - Upload and parse PDF (or pass pdf_url), optionally enriched from TMDb");
+    info!("   POST /api/parse?confidence=true - Also score the model's confidence in each extracted field");
+    info!("   POST /api/parse (encrypt_fields[]=financial) - Encrypt only the listed fields, leaving the rest readable");
+    info!("   POST /api/parse/stream - /api/parse as server-sent events with LLM progress");
+    info!("   (parse endpoints accept X-API-Key and report X-Quota-Remaining)");
+    info!("   POST /api/parse/request-upload-url - Presigned S3 URL for large uploads");
+    info!("   POST /api/parse/from-s3 - Queue an uploaded S3 object for parsing");
+    info!("   GET  /api/parse/templates - List supported agreement types");
+    info!("   GET  /api/parse/templates/:name - Agreement type details and prompt");
+    info!("   GET  /api/decrypt/:cid?key=...&fields=...&include_financial=true&naming=snake_case - Decrypt and view result");
+    info!("   GET  /api/decrypt/:cid?passphrase=... - Decrypt content encrypted with a passphrase");
+    info!("   GET  /api/agreements/:cid/decrypt-stream?key=... - Decrypt as a chunked JSON stream");
     info!("   GET  /api/status/:cid - Check IPFS status");
+    info!("   GET  /api/pins?limit=100&after=... - List pinned CIDs a page at a time");
+    info!("   GET  /api/gateways/benchmark?cid=... - Time a fetch through each IPFS gateway");
+    info!("   GET  /api/agreements?after=...&limit=...&tag=... - List parsed agreements");
+    info!("   GET  /api/agreements/expiring?days=30&status=Active - Agreements expiring soon");
+    info!("   GET  /api/agreements/statistics - Portfolio statistics (cached 5 min)");
+    info!("   POST /api/agreements/bulk-status-update - Set the status of up to 500 agreements in the background (X-API-Key)");
+    info!("   GET  /api/agreements/bulk-status-update/:job_id - Bulk status job progress (X-API-Key)");
+    info!("   POST /api/agreements/merge - Merge rights from several agreements");
+    info!("   POST /api/agreements/check-conflict - Check two agreements for overlapping terms and territories");
+    info!("   POST /api/agreements/:cid/renew - Renew an agreement for a new term");
+    info!("   GET  /api/agreements/:cid/verify?key=...&pdf_hash=... - Verify content integrity");
+    info!("   GET  /api/agreements/:cid/export?format=csv&key=...&fields=... - Export as CSV");
+    info!("   GET  /api/agreements/:cid/financial-report?key=...&format=pdf - Financial report as JSON or PDF");
+    info!("   POST /api/agreements/:cid/tags - Replace an agreement's tags");
+    info!("   POST /api/agreements/:cid/share - Store a copy that opens with a passphrase");
+    info!("   POST /api/rotate-key/:cid - Re-encrypt any stored content with a fresh key");
+    info!("   POST /api/agreements/:cid/export-blockchain - Anchor an agreement in the rights registry contract (X-API-Key, once per CID)");
+    info!("   POST /api/agreements/:cid/notify - Email the agreement's parties about an event (X-API-Key)");
+    info!("   GET  /api/agreements/:cid/summary?key=... - Plain-English summary");
+    info!("   GET  /api/agreements/:cid/parties/:role/contact?key=... - Licensor or licensee contact details");
+    info!("   GET  /api/agreements/:cid/review-cost?rate=450&key=... - Estimate legal review cost");
+    info!("   GET  /api/agreements/:cid/chain-of-title - Versions this agreement was derived from");
+    info!("   GET  /api/agreements/:cid/amendments/:number?key=... - The agreement as of an amendment, 0 for the original");
+    info!("   GET  /api/agreements/:cid/obligations?key=...&party=licensee - Duties each party must perform");
+    info!("   POST /api/webhooks - Register a webhook for agreement.parsed events");
+    info!("   GET  /api/webhooks - List the caller's webhooks");
+    info!("   DELETE /api/webhooks/:id - Remove one of the caller's webhooks");
+    info!("   (webhook endpoints require X-API-Key; webhooks only receive that key's agreements)");
+    info!("   GET  /api/models - List models available on Ollama");
+    info!("   GET  /api/cache/stats - LLM parse cache hits, misses and entries");
     info!("   GET  /health - Health check");
+    info!("   gRPC rights_parser.RightsParserService/ParsePDF, /Decrypt on port {}", grpc_port);
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server failed to start");
+    tokio::select! {
+        result = axum::serve(listener, app) => result.expect("Server failed to start"),
+        _ = shutdown_signal() => {
+            let timeout = std::time::Duration::from_secs(shutdown_timeout_secs);
+            info!("🛑 Shutting down, waiting up to {}s for in-flight requests", shutdown_timeout_secs);
+            match shutdown_state.shutdown(timeout).await {
+                0 => info!("✅ All requests finished, exiting"),
+                aborted => warn!("Drain timed out, aborting {} in-flight requests", aborted),
+            }
+        }
+    }
+}
+
+/// Resolves on SIGTERM, or Ctrl+C when running in a terminal
+async fn shutdown_signal() {
+    let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("Failed to install SIGTERM handler");
+    tokio::select! {
+        _ = sigterm.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+}
+
+/// Decrements the active request count when a request finishes or is dropped
+struct ActiveRequestGuard(Arc<AtomicU64>);
+
+impl Drop for ActiveRequestGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Count in-flight requests for the shutdown drain, refusing new ones with
+/// 503 once shutdown has begun
+async fn track_active_requests(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if state.shutdown_token.is_cancelled() {
+        return error_response(StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down").into_response();
+    }
+
+    state.active_requests.fetch_add(1, Ordering::SeqCst);
+    let _guard = ActiveRequestGuard(state.active_requests.clone());
+    next.run(request).await
 }
 
 async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
@@ -180,104 +1229,655 @@ async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
             ollama: ollama_healthy,
             ipfs: ipfs_healthy,
         },
+        ipfs_gateways: state.ipfs_client.gateway_urls().to_vec(),
     })
 }
 
-async fn parse_pdf_handler(
+async fn list_models_handler(
     State(state): State<AppState>,
-    mut multipart: Multipart,
-) -> Result<Json<ParseResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let start_time = std::time::Instant::now();
-    
-    info!("📄 Received PDF parsing request");
-
-    // Extract PDF from multipart
-    let mut pdf_bytes: Option<Bytes> = None;
-    let mut file_name = String::from("document.pdf");
+) -> Result<Json<ModelsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!("🧠 Listing Ollama models");
 
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
-        error!("Failed to read multipart field: {}", e);
-        error_response(StatusCode::BAD_REQUEST, "Invalid multipart data")
-    })? {
-        let name = field.name().unwrap_or("").to_string();
+    let models = state.llm_service.list_models().await.map_err(|e| {
+        error!("Failed to list Ollama models: {}", e);
+        error_response(StatusCode::BAD_GATEWAY, "Failed to list models from Ollama")
+    })?;
 
-        if name == "file" {
-            file_name = field
-                .file_name()
-                .unwrap_or("document.pdf")
-                .to_string();
-            
-            pdf_bytes = Some(field.bytes().await.map_err(|e| {
-                error!("Failed to read file bytes: {}", e);
-                error_response(StatusCode::BAD_REQUEST, "Failed to read file")
-            })?);
-        }
-    }
+    let registry = state.llm_service.registry();
+    let available_models = models
+        .into_iter()
+        .map(|model| ModelSummary {
+            compatible: registry.is_compatible(&model.name),
+            is_active: state.llm_service.is_active_model(&model.name),
+            model,
+        })
+        .collect();
 
-    let pdf_bytes = pdf_bytes.ok_or_else(|| {
-        error!("No file provided in request");
-        error_response(StatusCode::BAD_REQUEST, "No file provided")
+    Ok(Json(ModelsResponse {
+        current_model: state.llm_service.model_name().to_string(),
+        available_models,
+    }))
+}
+
+async fn cache_stats_handler(State(state): State<AppState>) -> Json<llm_service::CacheStats> {
+    Json(state.llm_service.cache_stats())
+}
+
+async fn list_templates_handler(State(state): State<AppState>) -> Json<Vec<AgreementTemplate>> {
+    Json(state.template_registry.templates().to_vec())
+}
+
+async fn get_template_handler(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let registry = &state.template_registry;
+    let template = registry.get(&name).ok_or_else(|| {
+        error_response(StatusCode::NOT_FOUND, &format!("Unknown agreement template: {}", name))
     })?;
 
-    let file_size = pdf_bytes.len() as u64;
-    info!("📖 Processing PDF: {} ({} bytes)", file_name, file_size);
+    Ok(Json(TemplateDetail {
+        template,
+        prompt_prefix: registry.prompt_for(template).map(|p| p.prefix.as_str()),
+    })
+    .into_response())
+}
 
-    // Save to temporary file
-    let temp_path = format!("/tmp/{}-{}", 
-        chrono::Utc::now().timestamp(), 
-        file_name
-    );
-    
-    fs::write(&temp_path, &pdf_bytes)
-        .await
-        .map_err(|e| {
-            error!("Failed to write temp file: {}", e);
-            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save file")
+// Top-level agreement fields are recorded on this span once the LLM returns,
+// for both the flat Modelfile output and the structured schema. Objects are
+// reduced to party names and roles: PAN, GSTIN and addresses are deliberately
+// left out of logs.
+async fn parse_pdf_handler(
+    State(state): State<AppState>,
+    api_key: Option<Extension<ApiKeyRecord>>,
+    Query(query): Query<ParseQuery>,
+    multipart: Multipart,
+) -> Result<Json<ParseResponse>, Response> {
+    let start_time = std::time::Instant::now();
+
+    info!("📄 Received PDF parsing request");
+
+    let mut upload = read_parse_upload(&state, query, multipart).await?;
+    upload.api_key_id = api_key.map(|Extension(key)| key.id);
+    process_upload(&state, upload, start_time).await
+}
+
+/// `POST /api/parse/stream` - `/api/parse` as server-sent events. `progress`
+/// events carry the LLM's estimated percent complete while it writes, then a
+/// single `result` event carries the `ParseResponse` or an `error` event the
+/// error body.
+async fn parse_stream_handler(
+    State(state): State<AppState>,
+    api_key: Option<Extension<ApiKeyRecord>>,
+    Query(query): Query<ParseQuery>,
+    multipart: Multipart,
+) -> Result<Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>>, Response> {
+    let start_time = std::time::Instant::now();
+    info!("📄 Received streaming PDF parsing request");
+
+    let mut upload = read_parse_upload(&state, query, multipart).await?;
+    upload.api_key_id = api_key.map(|Extension(key)| key.id);
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::channel::<u32>(16);
+    upload.progress = Some(progress_tx);
+
+    let (events_tx, events_rx) = tokio::sync::mpsc::channel::<Event>(16);
+    // The middleware's guard drops once the stream is returned, so the task
+    // holds its own and shutdown waits for the parse to finish
+    state.active_requests.fetch_add(1, Ordering::SeqCst);
+    let guard = ActiveRequestGuard(state.active_requests.clone());
+    tokio::spawn(async move {
+        let _guard = guard;
+        let forward_progress = async {
+            while let Some(percent) = progress_rx.recv().await {
+                let _ = events_tx.send(Event::default().event("progress").data(percent.to_string())).await;
+            }
+        };
+        // Progress is drained before the result since process_upload drops
+        // its sender when it returns
+        let (result, ()) = tokio::join!(process_upload(&state, upload, start_time), forward_progress);
+
+        let event = match result {
+            Ok(Json(response)) => Event::default().event("result").json_data(&response).unwrap_or_else(|e| {
+                error!("Failed to serialize parse response: {}", e);
+                Event::default().event("error").data("Failed to serialize parse response")
+            }),
+            Err(response) => {
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap_or_default();
+                Event::default().event("error").data(String::from_utf8_lossy(&body))
+            }
+        };
+        let _ = events_tx.send(event).await;
+    });
+
+    let events = futures::stream::unfold(events_rx, |mut events_rx| async move {
+        events_rx.recv().await.map(|event| (Ok(event), events_rx))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Collect the file and optional fields of a `/api/parse` multipart body
+async fn read_parse_upload(state: &AppState, query: ParseQuery, mut multipart: Multipart) -> Result<ParseUpload, Response> {
+    // Extract PDF and optional metadata fields from multipart
+    let mut upload = ParseUpload {
+        enrich: query.enrich,
+        confidence: query.confidence,
+        ..ParseUpload::default()
+    };
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        error!("Failed to read multipart field: {}", e);
+        error_response(StatusCode::BAD_REQUEST, "Invalid multipart data").into_response()
+    })? {
+        let name = field.name().unwrap_or("").to_string();
+
+        if state.upload_field_names.iter().any(|n| *n == name) {
+            upload.file_name = field
+                .file_name()
+                .unwrap_or("document.pdf")
+                .to_string();
+            upload.content_type = field.content_type().map(str::to_string);
+
+            upload.file_bytes = Some(field.bytes().await.map_err(|e| {
+                error!("Failed to read file bytes: {}", e);
+                error_response(StatusCode::BAD_REQUEST, "Failed to read file").into_response()
+            })?);
+            continue;
+        }
+
+        // Repeated as `encrypt_fields[]`, or one comma-separated value
+        if name == "encrypt_fields[]" || name == "encrypt_fields" {
+            let value = field.text().await.map_err(|e| {
+                error!("Failed to read multipart field {}: {}", name, e);
+                error_response(StatusCode::BAD_REQUEST, "Invalid multipart data").into_response()
+            })?;
+            upload.encrypt_fields.extend(value.split(',').map(str::trim).filter(|p| !p.is_empty()).map(str::to_string));
+            continue;
+        }
+
+        let slot = match name.as_str() {
+            "priority" => &mut upload.priority,
+            "webhook_url" => &mut upload.webhook_url,
+            "password" => &mut upload.password,
+            "extracted_text" => &mut upload.extracted_text,
+            "agreement_type" => &mut upload.agreement_type,
+            "pdf_url" => &mut upload.pdf_url,
+            _ => continue,
+        };
+
+        let value = field.text().await.map_err(|e| {
+            error!("Failed to read multipart field {}: {}", name, e);
+            error_response(StatusCode::BAD_REQUEST, "Invalid multipart data").into_response()
         })?;
+        *slot = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+    }
 
-    // Extract text from PDF
-    info!("🔍 Extracting text from PDF");
-    let pdf_text = match state.pdf_extractor.extract_text(&pdf_bytes).await {
-        Ok(text) => text,
-        Err(e) => {
-            error!("PDF extraction failed: {}", e);
-            let _ = fs::remove_file(&temp_path).await; // Cleanup without await in map_err
-            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to extract text from PDF"));
+    Ok(upload)
+}
+
+/// Run a complete upload through extraction, the LLM, encryption and IPFS.
+/// Shared by the multipart endpoint and the gRPC `ParsePDF` stream.
+#[tracing::instrument(
+    name = "parse_pdf",
+    skip_all,
+    fields(
+        file_name = tracing::field::Empty,
+        ipfs_cid = tracing::field::Empty,
+        title = tracing::field::Empty,
+        licensor = tracing::field::Empty,
+        licensee = tracing::field::Empty,
+        assignor = tracing::field::Empty,
+        assignee = tracing::field::Empty,
+        territory = tracing::field::Empty,
+        languages = tracing::field::Empty,
+        rights = tracing::field::Empty,
+        total_fee = tracing::field::Empty,
+        currency = tracing::field::Empty,
+        payment_terms = tracing::field::Empty,
+        term_start = tracing::field::Empty,
+        term_end = tracing::field::Empty,
+        term_years = tracing::field::Empty,
+        exclusivity = tracing::field::Empty,
+        content_type = tracing::field::Empty,
+        original_language = tracing::field::Empty,
+        release_date = tracing::field::Empty,
+        director = tracing::field::Empty,
+        producer = tracing::field::Empty,
+        governing_law = tracing::field::Empty,
+        dispute_resolution = tracing::field::Empty,
+        agreementId = tracing::field::Empty,
+        rightsHolder = tracing::field::Empty,
+        content = tracing::field::Empty,
+        financial = tracing::field::Empty,
+        parties = tracing::field::Empty,
+        legalTerms = tracing::field::Empty,
+        llm_raw_json = tracing::field::Empty,
+    )
+)]
+async fn process_upload(
+    state: &AppState,
+    mut upload: ParseUpload,
+    start_time: std::time::Instant,
+) -> Result<Json<ParseResponse>, Response> {
+    if upload.enrich && state.tmdb_client.is_none() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "enrich=true requires TMDB_API_KEY to be configured").into_response());
+    }
+    let type_override = match upload.agreement_type.as_deref() {
+        Some(name) => Some(state.template_registry.get(name).ok_or_else(|| {
+            error_response(StatusCode::BAD_REQUEST, &format!("Unknown agreement_type: {}", name)).into_response()
+        })?),
+        None => None,
+    };
+
+    let provided_text = upload.extracted_text.take();
+    if provided_text.is_some() && !state.allow_direct_mode {
+        warn!("extracted_text supplied but direct mode is disabled");
+        return Err(error_response(StatusCode::FORBIDDEN, "Direct text mode is disabled").into_response());
+    }
+
+    if let Some(raw_url) = upload.pdf_url.take() {
+        if upload.file_bytes.is_some() {
+            return Err(error_response(StatusCode::BAD_REQUEST, "Provide either a file or pdf_url, not both").into_response());
+        }
+        let download_error = |e: pdf_download::DownloadError| {
+            warn!("{}", e);
+            error_response(e.status(), &e.to_string()).into_response()
+        };
+
+        let url = pdf_download::validate_url(&raw_url).map_err(download_error)?;
+        let client = pdf_download::public_client(&url).await.map_err(download_error)?;
+        let bytes = pdf_download::download_pdf(&client, &url, state.max_upload_bytes)
+            .await
+            .map_err(download_error)?;
+        upload.file_name = pdf_download::file_name(&url);
+        upload.file_bytes = Some(Bytes::from(bytes));
+    }
+
+    // Direct mode may send text alone, in which case the text is the source document
+    let has_pdf = upload.file_bytes.is_some();
+    let pdf_bytes = match (upload.file_bytes.take(), &provided_text) {
+        (Some(bytes), _) => bytes,
+        (None, Some(text)) => {
+            upload.file_name = String::from("extracted_text.txt");
+            Bytes::from(text.clone())
+        }
+        (None, None) => {
+            error!("No file provided in request (accepted fields: {:?})", state.upload_field_names);
+            return Err(error_response(StatusCode::BAD_REQUEST, "No file provided").into_response());
         }
     };
+    let file_name = std::mem::take(&mut upload.file_name);
+    tracing::Span::current().record("file_name", file_name.as_str());
+    let is_docx = has_pdf && docx_extractor::is_docx(&file_name, upload.content_type.as_deref(), &pdf_bytes);
+    let source_name = match (has_pdf, is_docx) {
+        (true, false) => "original.pdf.enc",
+        (true, true) => "original.docx.enc",
+        (false, _) => "original.txt.enc",
+    };
+
+    if let Some(priority) = &upload.priority {
+        info!("   Priority: {}", priority);
+    }
+    if let Some(webhook_url) = &upload.webhook_url {
+        info!("   Webhook: {}", webhook_url);
+    }
+    if upload.password.is_some() {
+        info!("   Password supplied for encrypted PDF");
+    }
+
+    let file_size = pdf_bytes.len() as u64;
+    info!("📖 Processing PDF: {} ({} bytes)", file_name, file_size);
+    let mut warnings = ParseWarnings::default();
 
-    if pdf_text.len() < 100 {
+    // Extract text from PDF, unless the caller already did
+    let (paged_text, extraction_method) = if let Some(text) = provided_text {
+        info!("📝 Using provided text, skipping PDF extraction");
+        (PagedText::unpaged(text), "provided")
+    } else if is_docx {
+        info!("🔍 Extracting text from Word document");
+        match DocxExtractor.extract_text(&pdf_bytes, upload.password.as_deref()).await {
+            Ok(text) => (PagedText::unpaged(text), "docx"),
+            Err(e) => {
+                warn!("Word extraction failed: {:#}", e);
+                return Err(error_response(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    &format!("Failed to extract text from Word document: {:#}", e),
+                ).into_response());
+            }
+        }
+    } else {
+        info!("🔍 Extracting text from PDF");
+        match state.pdf_extractor.extract_text_with_quality(&pdf_bytes, upload.password.as_deref()).await {
+            Ok((text, quality, method)) => {
+                if quality.is_garbled() {
+                    warnings.push(
+                        "unicode_decode_errors",
+                        format!(
+                            "{:.1}% of extracted characters could not be decoded, check the text against the PDF",
+                            quality.unicode_error_ratio * 100.0
+                        ),
+                    );
+                }
+                (text, method.as_str())
+            }
+            Err(e) if pdf_extractor::is_temp_file_timeout(&e) => {
+                error!("PDF extraction timed out: {:#}", e);
+                return Err(coded_error_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "filesystem_timeout",
+                    "Temporary storage is too slow to extract text, try again later",
+                ).into_response());
+            }
+            Err(e) => {
+                error!("PDF extraction failed: {}", e);
+                return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to extract text from PDF").into_response());
+            }
+        }
+    };
+
+    let pdf_text = &paged_text.full_text;
+    if pdf_text.len() < pdf_extractor::MIN_TEXT_CHARS {
         warn!("Extracted text too short: {} chars", pdf_text.len());
-        let _ = fs::remove_file(&temp_path).await;
-        return Err(error_response(StatusCode::BAD_REQUEST, "Could not extract sufficient text from PDF"));
+        return Err(error_response(StatusCode::BAD_REQUEST, "Could not extract sufficient text from PDF").into_response());
     }
 
     info!("✅ Extracted {} characters from PDF", pdf_text.len());
 
-    // Parse with LLM
-    info!("🤖 Calling LLM for parsing");
-    let json_string = match state.llm_service.parse_agreement(&pdf_text).await {
-        Ok(json) => json,
+    // Bilingual agreements repeat each section; send the English version only
+    // Page markers let the model cite the pages each clause came from
+    let llm_text = state
+        .pdf_extractor
+        .select_llm_text(&state.pdf_extractor.format_with_page_markers(&paged_text));
+
+    // Metadata and tables both read the PDF's object tree, so it is loaded once
+    let document = if has_pdf && !is_docx {
+        pdf_extractor::load_document(&pdf_bytes)
+            .map_err(|e| warn!("Failed to load PDF for metadata and tables: {:#}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    // Embedded metadata lets callers cross-check parties against the PDF's author
+    let pdf_metadata = document.as_ref().and_then(|doc| match state.pdf_extractor.extract_metadata(doc) {
+        Ok(meta) => {
+            info!("📇 PDF metadata: {} pages, author {:?}, creator {:?}", meta.page_count, meta.author, meta.creator);
+            Some(meta)
+        }
         Err(e) => {
-            error!("LLM parsing failed: {}", e);
-            let _ = fs::remove_file(&temp_path).await;
-            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("LLM parsing failed: {}", e)));
+            warn!("Failed to read PDF metadata: {:#}", e);
+            None
+        }
+    });
+
+    // clean_text flattens table columns, so tables also go to the model as markdown
+    let tables = match (extraction_method, &document) {
+        ("pdf" | "pdftotext", Some(doc)) => state.pdf_extractor.extract_and_print_tables(doc),
+        _ => Vec::new(),
+    };
+
+    // Repeat uploads of the same document reuse the parse before any LLM call
+    let cache_key = llm_service::parse_cache_key(&pdf_bytes, type_override.map(|t| t.name.as_str()));
+    let cached = state.llm_service.cached_parse(&cache_key);
+
+    // Parse with LLM - reject rather than queue when the model is saturated
+    let llm_permit = match state.llm_semaphore.clone().try_acquire_owned() {
+        Ok(permit) => permit,
+        Err(_) => {
+            warn!("LLM concurrency limit reached, rejecting request");
+            return Err(too_many_requests_response(30));
+        }
+    };
+
+    // The text reaches the model delimited as data; flag it so the result gets a second look
+    for injection in llm_service::detect_prompt_injections(&llm_text) {
+        warnings.push(
+            "prompt_injection_suspected",
+            format!("Contract text reads like an LLM instruction: {:?}", injection.matched),
+        );
+    }
+
+    let registry = &state.template_registry;
+    let (template, json_string, model_used, validation_warnings) = if let Some((parsed, agreement_type)) = cached {
+        info!("♻️  Using cached parse of this document");
+        let template = agreement_type
+            .as_deref()
+            .and_then(|name| registry.get(name))
+            .or_else(|| registry.get(DEFAULT_TEMPLATE))
+            .expect("default template");
+        (template, parsed.json, parsed.model_used, parsed.validation_warnings)
+    } else {
+        // Route to the prompt and model tuned for this kind of agreement
+        let template = match type_override {
+            Some(template) => {
+                info!("🏷️  Agreement type set by request: {}", template.name);
+                template
+            }
+            None => match state.llm_service.classify_agreement_type(&llm_text, &registry.names()).await {
+                Ok((name, confidence)) => {
+                    info!("🏷️  Detected agreement type: {} (confidence {:.2})", name, confidence);
+                    registry.get(&name).or_else(|| registry.get(DEFAULT_TEMPLATE)).expect("default template")
+                }
+                Err(e) => {
+                    warnings.push(
+                        "agreement_type_fallback",
+                        format!("Agreement type detection failed, used {}: {}", DEFAULT_TEMPLATE, e),
+                    );
+                    registry.get(DEFAULT_TEMPLATE).expect("default template")
+                }
+            },
+        };
+        let model_config = registry.model_for(template);
+        let model_used = model_config
+            .model
+            .clone()
+            .unwrap_or_else(|| state.llm_service.model_name().to_string());
+
+        // Hints only help the main extraction, so a failed pass is not an error
+        let entity_hints = if state.enable_entity_hints {
+            info!("🔎 Extracting named entities");
+            state.llm_service.extract_named_entities(&llm_text).await.map_err(|e| {
+                warnings.push("entity_hints_failed", format!("Named entity extraction failed: {}", e));
+            }).ok()
+        } else {
+            None
+        };
+        let parse_options = ParseOptions {
+            prompt_prefix: registry.prompt_for(template).map(|p| p.prefix.as_str()),
+            model: Some(&model_used),
+            entity_hints: entity_hints.as_ref(),
+            tables: &tables,
+            on_retry: None,
+            progress: upload.progress.clone(),
+        };
+
+        info!("🤖 Calling LLM for parsing");
+        let parsed = match state.llm_service.parse_agreement(&llm_text, &parse_options).await {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                error!("LLM parsing failed: {}", e);
+                return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("LLM parsing failed: {}", e)).into_response());
+            }
+        };
+        if parsed.model_used != model_used {
+            warnings.push(
+                "model_fallback",
+                format!("{} was unavailable, parsed with {}", model_used, parsed.model_used),
+            );
         }
+        state.llm_service.cache_parse(cache_key, &parsed, Some(&template.name));
+        (template, parsed.json, parsed.model_used, parsed.validation_warnings)
+    };
+
+    // Risk flags are advisory, so a failed analysis does not fail the parse
+    let unusual_clauses = if state.enable_clause_analysis {
+        info!("⚖️  Analysing clauses for legal risk");
+        state.llm_service.detect_unusual_clauses(&llm_text).await.unwrap_or_else(|e| {
+            warnings.push("clause_analysis_failed", format!("Clause analysis failed: {}", e));
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+    let obligations = if state.enable_obligation_extraction {
+        info!("📋 Extracting obligations");
+        state.llm_service.extract_obligations(&llm_text).await.unwrap_or_else(|e| {
+            warnings.push("obligation_extraction_failed", format!("Obligation extraction failed: {}", e));
+            Vec::new()
+        })
+    } else {
+        Vec::new()
     };
-    
+    let confidence = if upload.confidence {
+        info!("🎯 Rating extraction confidence");
+        state.llm_service.rate_confidence(&llm_text, &json_string, &model_used).await.map_err(|e| {
+            warnings.push("confidence_scoring_failed", format!("Confidence scoring failed: {}", e));
+        }).ok()
+    } else {
+        None
+    };
+    drop(llm_permit);
+
     // LLM already returns JSON - use it directly!
     info!("✅ Got JSON from LLM ({} bytes)", json_string.len());
+    record_extracted_fields(&tracing::Span::current(), &json_string);
+
+    // Bind the agreement to its source PDF so parties can verify it later
+    let pdf_sha256 = integrity::sha256_hex(&pdf_bytes);
+    let mut agreement_value: serde_json::Value = serde_json::from_str(&json_string).unwrap_or_else(|e| {
+        warnings.push("llm_partial_json", format!("LLM output is not valid JSON: {}", e));
+        serde_json::Value::default()
+    });
+    if let Some(raw) = resolve_wallet_address(&state.ens_resolver, &mut agreement_value).await {
+        warnings.push("wallet_unresolved", format!("Wallet address '{}' could not be resolved", raw));
+    }
+    if let Some(tmdb) = state.tmdb_client.as_deref().filter(|_| upload.enrich) {
+        enrich_from_tmdb(tmdb, &mut agreement_value, &mut warnings).await;
+    }
+    // Every agreement ID is reserved so no two uploads share one. The Modelfile's
+    // flat output carries no ID, so the builder generates one from it and its
+    // normalized fields are merged back in.
+    let schema_agreement_id = agreement_value.get("agreementId").and_then(|id| id.as_str()).map(str::to_string);
+    match schema_agreement_id {
+        Some(agreement_id) => {
+            let agreement_id = state.json_builder.unique_agreement_id(agreement_id).await;
+            if let Some(agreement) = agreement_value.as_object_mut() {
+                agreement.insert("agreementId".to_string(), serde_json::json!(agreement_id));
+            }
+        }
+        None if agreement_value.is_object() => match state.json_builder.build_from_llm_json(&agreement_value).await {
+            Ok(built) => {
+                json_builder::merge_built_fields(&built, &mut agreement_value);
+                if let Some(agreement) = agreement_value.as_object_mut() {
+                    agreement.remove("agreement_id");
+                    agreement.insert("agreementId".to_string(), serde_json::json!(built.agreement_id));
+                }
+            }
+            Err(e) => warnings.push("agreement_id_unassigned", format!("Could not assign an agreement ID: {}", e)),
+        },
+        None => {}
+    }
+    let (completeness_score, missing_fields) = completeness(&agreement_value);
+    if completeness_score < MIN_COMPLETENESS_SCORE {
+        warnings.push(
+            "low_completeness_score",
+            format!("Completeness score {:.2}, missing {}", completeness_score, missing_fields.join(", ")),
+        );
+    }
+    // Validation notes from the structured schema
+    if let Some(notes) = agreement_value.pointer("/metadata/warnings").and_then(|w| w.as_array()) {
+        for note in notes.iter().filter_map(|n| n.as_str()) {
+            warnings.push("agreement_validation", note);
+        }
+    }
+    // Free-form JSON mode may cite pages at the top level rather than in metadata
+    if let Some(source_pages) = agreement_value.as_object_mut().and_then(|a| a.remove("source_pages")) {
+        match valid_source_pages(source_pages, paged_text.pages.len()) {
+            Some(source_pages) => {
+                if let Some(metadata) = metadata_object(&mut agreement_value) {
+                    metadata.entry("sourcePages").or_insert(serde_json::json!(source_pages));
+                }
+            }
+            None => warnings.push("source_pages_invalid", "Page citations did not match the document's pages and were dropped"),
+        }
+    }
+    if !unusual_clauses.is_empty() {
+        if let Some(metadata) = metadata_object(&mut agreement_value) {
+            metadata.insert("unusualClauses".to_string(), serde_json::json!(unusual_clauses));
+        }
+    }
+    if let Some(confidence) = &confidence {
+        let low = confidence.low_confidence(LOW_CONFIDENCE_THRESHOLD);
+        if !low.is_empty() {
+            warnings.push("low_confidence_fields", format!("Review {}: the model was unsure of them", low.join(", ")));
+        }
+        if let Some(metadata) = metadata_object(&mut agreement_value) {
+            metadata.insert("confidenceScores".to_string(), serde_json::json!(confidence.scores));
+        }
+    }
+    if !obligations.is_empty() {
+        if let Some(agreement) = agreement_value.as_object_mut() {
+            agreement.insert("obligations".to_string(), serde_json::json!(obligations));
+        }
+    }
+    let json_sha256 = integrity::stamp_content_hashes(&mut agreement_value, Some(&pdf_sha256));
+
+    let producer = json_str(&agreement_value, &["/producer", "/content/producer"]);
+    let production_company = json_str(&agreement_value, &["/production_company", "/content/productionCompany"]);
+    if producer.is_some() && producer.map(str::trim) == production_company.map(str::trim) {
+        warnings.push(
+            "producer_company_conflated",
+            "Producer and production company are identical, the LLM may have conflated them",
+        );
+    }
+    let json_string = agreement_value.to_string();
 
-    // Encrypt JSON
-    info!("🔐 Encrypting JSON");
-    let (encrypted_data, encryption_key) = match state.encryption_service.encrypt(&json_string) {
+    // Encrypt JSON, or only the requested fields for partial disclosure
+    let encrypted = if upload.encrypt_fields.is_empty() {
+        info!("🔐 Encrypting JSON");
+        state.encryption_service.encrypt(&json_string)
+    } else {
+        info!("🔐 Encrypting fields: {}", upload.encrypt_fields.join(", "));
+        let paths: Vec<&str> = upload.encrypt_fields.iter().map(String::as_str).collect();
+        let missing = encryption::missing_field_paths(&agreement_value, &paths);
+        if !missing.is_empty() {
+            return Err(error_response(
+                StatusCode::BAD_REQUEST,
+                &format!("encrypt_fields not found in the parsed agreement: {}", missing.join(", ")),
+            ).into_response());
+        }
+        let key = EncryptionService::generate_key();
+        state
+            .encryption_service
+            .encrypt_fields(&agreement_value, &paths, &key)
+            .map(|protected| (protected.to_string().into_bytes(), key))
+    };
+    let (encrypted_data, encryption_key) = match encrypted {
         Ok(result) => result,
         Err(e) => {
             error!("Encryption failed: {}", e);
-            let _ = fs::remove_file(&temp_path).await;
-            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Encryption failed"));
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Encryption failed").into_response());
+        }
+    };
+    // The source PDF goes into the bundle encrypted with the same key
+    let encrypted_source = match state.encryption_service.encrypt_bytes_with_key(&pdf_bytes, &encryption_key) {
+        Ok(data) => data,
+        Err(e) => {
+            error!("Source encryption failed: {}", e);
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, "Encryption failed").into_response());
         }
     };
+    let key_envelope = match state.master_encryption_key.as_deref() {
+        Some(master_key) => match encryption::decode_key(&encryption_key)
+            .and_then(|data_key| state.encryption_service.envelope_encrypt(&data_key, master_key))
+        {
+            Ok(envelope) => Some(general_purpose::STANDARD.encode(envelope)),
+            Err(e) => {
+                warnings.push("key_envelope_failed", format!("Failed to wrap the document key: {}", e));
+                None
+            }
+        },
+        None => None,
+    };
 
     // Upload to IPFS
     info!("📤 Uploading to IPFS");
@@ -285,34 +1885,229 @@ async fn parse_pdf_handler(
         Ok(cid) => cid,
         Err(e) => {
             error!("IPFS upload failed: {}", e);
-            let _ = fs::remove_file(&temp_path).await;
-            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("IPFS upload failed: {}", e)));
+            return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("IPFS upload failed: {}", e)).into_response());
         }
     };
 
-    // Cleanup
-    let _ = fs::remove_file(&temp_path).await;
+    // Bundle the source PDF with its agreement so both resolve from one CID
+    let manifest = serde_json::json!({
+        "version": 1,
+        "agreement_cid": ipfs_cid,
+        "files": {
+            source_name: {
+                "sha256": integrity::sha256_hex(&encrypted_source),
+                "size": encrypted_source.len(),
+                "encrypted": true,
+            },
+            "agreement.json.enc": {
+                "sha256": integrity::sha256_hex(&encrypted_data),
+                "size": encrypted_data.len(),
+                "encrypted": true,
+                // Empty when the whole document is encrypted
+                "encrypted_fields": upload.encrypt_fields,
+            },
+        },
+        "json_sha256": json_sha256,
+        "created_at": chrono::Utc::now().to_rfc3339(),
+    })
+    .to_string();
+
+    info!("📦 Uploading PDF bundle to IPFS");
+    let bundle_cid = match state
+        .ipfs_client
+        .upload_directory(&[
+            (source_name, &encrypted_source[..]),
+            ("agreement.json.enc", &encrypted_data[..]),
+            ("manifest.json", manifest.as_bytes()),
+        ])
+        .await
+    {
+        Ok(cid) => Some(cid),
+        Err(e) => {
+            warnings.push("bundle_upload_failed", format!("Bundle upload failed, returning agreement CID only: {}", e));
+            None
+        }
+    };
 
     let processing_time = start_time.elapsed().as_millis() as u64;
-    
+
     info!("✅ Successfully processed PDF in {}ms", processing_time);
     info!("📍 IPFS CID: {}", ipfs_cid);
+    tracing::Span::current().record("ipfs_cid", ipfs_cid.as_str());
+
+    let delivery_deadline = json_str(&agreement_value, &["/deliverables/deliveryDeadline", "/delivery_deadline"])
+        .and_then(Deliverables::parse_deadline);
+    let days_until_deadline = delivery_deadline
+        .map(|d| (d - chrono::Utc::now().date_naive()).num_days());
+
+    let raw_end_date = json_str(&agreement_value, &["/term_end", "/rights/term/endDate"]);
+    let end_date = raw_end_date.and_then(|d| FlexibleDate::parse(d).date());
+    if let (Some(raw), None) = (raw_end_date.filter(|d| !d.trim().is_empty() && *d != "Unknown"), end_date) {
+        warnings.push("date_normalization_fallback", format!("Term end date '{}' could not be normalized", raw));
+    }
+
+    let start_date = json_str(&agreement_value, &["/term_start", "/rights/term/startDate"])
+        .and_then(|d| FlexibleDate::parse(d).date());
+
+    // Checked before recording, so the upload does not match itself
+    let structured = serde_json::from_value::<RightsAgreementJSON>(agreement_value.clone()).ok();
+    let similar = match &structured {
+        Some(agreement) => AgreementComparator::find_similar(agreement, &state.db).await,
+        None => {
+            let fingerprint = AgreementFingerprint {
+                licensor: json_str(&agreement_value, &["/licensor"]),
+                licensee: json_str(&agreement_value, &["/licensee"]),
+                content_title: json_str(&agreement_value, &["/title"]),
+                start_date,
+            };
+            AgreementComparator::find_similar_to(&fingerprint, &state.db).await
+        }
+    };
+    let similar_agreements = similar.unwrap_or_else(|e| {
+        warnings.push("duplicate_check_failed", format!("Could not check for similar agreements: {:#}", e));
+        Vec::new()
+    });
+    if !similar_agreements.is_empty() {
+        info!("🔁 {} similar agreement(s) already recorded", similar_agreements.len());
+    }
+
+    // Record the agreement - the upload already succeeded, so a failure here is not fatal
+    let currency = json_str(&agreement_value, &["/currency", "/financial/currency"]).and_then(Financial::currency_code);
+    let record = NewAgreementRecord {
+        ipfs_cid: &ipfs_cid,
+        agreement_id: json_str(&agreement_value, &["/agreementId"]),
+        title: json_str(&agreement_value, &["/title", "/content/title"]),
+        licensor: json_str(&agreement_value, &["/licensor", "/parties/licensor/name"]),
+        licensee: json_str(&agreement_value, &["/licensee", "/parties/licensee/name"]),
+        file_name: &file_name,
+        file_size: file_size as i64,
+        processing_time_ms: processing_time as i64,
+        model_used: &model_used,
+        webhook_url: upload.webhook_url.as_deref(),
+        delivery_deadline,
+        start_date,
+        end_date,
+        status: json_str(&agreement_value, &["/metadata/status"]),
+        agreement_type: Some(&template.name),
+        deal_value: ["/total_fee", "/financial/dealValue"]
+            .iter()
+            .find_map(|p| agreement_value.pointer(p).and_then(|v| v.as_i64().or_else(|| v.as_f64().map(|f| f as i64)))),
+        currency: currency.as_deref(),
+        territories: json_territories(&agreement_value),
+    };
+    if let Err(e) = agreement_store::insert_agreement(&state.db, &record).await {
+        warnings.push("database_record_failed", format!("Failed to record agreement in database: {}", e));
+    }
+    // Agreements the builder could not structure have no flat projection
+    if let Some(agreement) = &structured {
+        if let Err(e) = agreement_store::upsert_flat_agreement(&state.db, agreement).await {
+            warn!("{:#}", e);
+        }
+    }
+    let version = json_str(&agreement_value, &["/metadata/version"]);
+    if let Err(e) = agreement_store::record_version(&state.db, &ipfs_cid, None, version, record.status).await {
+        warn!("{:#}", e);
+    }
+    state.webhooks.agreement_parsed(upload.api_key_id, &AgreementParsedEvent {
+        cid: &ipfs_cid,
+        agreement_id: record.agreement_id,
+        licensor: record.licensor,
+        deal_value: record.deal_value,
+    });
 
+    let mut cdn_urls = state.cdn_gateways.urls(&ipfs_cid).into_iter();
     Ok(Json(ParseResponse {
         ipfs_cid: ipfs_cid.clone(),
         ipfs_url: format!("ipfs://{}", ipfs_cid),
         ipfs_gateway_url: format!("https://ipfs.io/ipfs/{}", ipfs_cid),
+        cdn_url: cdn_urls.next(),
+        alternative_gateways: cdn_urls.collect(),
         encryption_key,
+        pdf_sha256,
+        json_sha256,
+        key_envelope,
+        pdf_metadata,
+        days_until_deadline,
+        bundle_cid,
+        warnings: warnings.0,
+        validation_warnings,
+        similar_agreements,
         metadata: FileMetadata {
             file_name,
             file_size,
             processed_at: chrono::Utc::now().to_rfc3339(),
-            model_used: "llama3.3:70b-instruct-q4_K_M".to_string(),
             processing_time_ms: processing_time,
+            extraction_method: extraction_method.to_string(),
+            agreement_type: template.name.clone(),
+            model_used,
         },
     }))
 }
 
+fn s3_storage(state: &AppState) -> Result<&S3Storage, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .s3_storage
+        .as_deref()
+        .ok_or_else(|| error_response(StatusCode::SERVICE_UNAVAILABLE, "S3 uploads are not configured"))
+}
+
+async fn request_upload_url_handler(
+    State(state): State<AppState>,
+) -> Result<Json<UploadUrlResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let storage = s3_storage(&state)?;
+    let s3_key = S3Storage::new_upload_key();
+
+    let upload_url = storage.presigned_upload_url(&s3_key).await.map_err(|e| {
+        error!("Failed to presign upload: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create upload URL")
+    })?;
+
+    info!("🪣 Issued upload URL for {}", s3_key);
+
+    Ok(Json(UploadUrlResponse {
+        upload_url,
+        s3_key,
+        method: "PUT",
+        content_type: "application/pdf",
+        expires_in_secs: s3_storage::PRESIGNED_URL_TTL.as_secs(),
+    }))
+}
+
+async fn parse_from_s3_handler(
+    State(state): State<AppState>,
+    api_key: Option<Extension<ApiKeyRecord>>,
+    Json(request): Json<ParseFromS3Request>,
+) -> Result<(StatusCode, Json<QueuedJobResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let storage = s3_storage(&state)?;
+    if !S3Storage::is_upload_key(&request.s3_key) {
+        return Err(error_response(StatusCode::BAD_REQUEST, "s3_key was not issued by this service"));
+    }
+
+    let file_size = match storage.object_size(&request.s3_key).await {
+        Ok(Some(size)) => size,
+        Ok(None) => return Err(error_response(StatusCode::NOT_FOUND, "Nothing has been uploaded to s3_key yet")),
+        Err(e) => {
+            error!("S3 lookup failed: {}", e);
+            return Err(error_response(StatusCode::BAD_GATEWAY, "Failed to reach S3"));
+        }
+    };
+    if file_size as usize > state.max_upload_bytes {
+        return Err(error_response(StatusCode::PAYLOAD_TOO_LARGE, "Uploaded file exceeds MAX_UPLOAD_BYTES"));
+    }
+
+    let file_name = request.s3_key.rsplit('/').next().unwrap_or("document.pdf");
+    let file_path = format!("s3://{}/{}", storage.bucket(), request.s3_key);
+    let api_key_hash = api_key.as_ref().map(|Extension(key)| key.key_hash.as_str());
+    let job_id = worker::enqueue_job(&state.db, file_name, &file_path, file_size, api_key_hash, request.webhook_url.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Failed to queue job: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to queue job")
+        })?;
+
+    Ok((StatusCode::ACCEPTED, Json(QueuedJobResponse { job_id, status: "pending" })))
+}
+
 async fn decrypt_handler(
     State(state): State<AppState>,
     Path(cid): Path<String>,
@@ -320,31 +2115,78 @@ async fn decrypt_handler(
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
     info!("🔓 Decrypting IPFS content: {}", cid);
 
-    // Fetch from IPFS
-    let encrypted_data = state.ipfs_client.fetch(&cid)
-        .await
-        .map_err(|e| {
-            error!("IPFS fetch failed: {}", e);
-            error_response(StatusCode::NOT_FOUND, &format!("Failed to fetch from IPFS: {}", e))
-        })?;
+    let json_value = decrypted_view(&state, &cid, &params).await?;
 
-    // Decrypt
-    let json_string = state.encryption_service.decrypt(&encrypted_data, &params.key)
-        .map_err(|e| {
-            error!("Decryption failed: {}", e);
-            error_response(StatusCode::UNAUTHORIZED, "Decryption failed - invalid key")
-        })?;
+    info!("✅ Successfully decrypted content");
+
+    Ok(Json(json_value))
+}
+
+/// Same as `decrypt_handler`, but the JSON is serialized straight into a chunked
+/// response body instead of being rendered to one buffer first
+async fn decrypt_stream_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Query(params): Query<DecryptQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    info!("🔓 Streaming decrypted IPFS content: {}", cid);
+
+    let json_value = decrypted_view(&state, &cid, &params).await?;
+
+    // The serializer writes synchronously, so it runs on a blocking thread and
+    // feeds a pipe whose read half becomes the body stream
+    let (reader, writer) = tokio::io::duplex(64 * 1024);
+    tokio::task::spawn_blocking(move || {
+        let writer = std::io::BufWriter::new(tokio_util::io::SyncIoBridge::new(writer));
+        let mut serializer = serde_json::Serializer::new(writer);
+        let result = serde::Serialize::serialize(&json_value, &mut serializer)
+            .map_err(std::io::Error::from)
+            .and_then(|_| std::io::Write::flush(&mut serializer.into_inner()));
+        if let Err(e) = result {
+            // Headers are already sent, so the client sees a truncated body
+            error!("Streaming {} failed: {}", cid, e);
+        }
+    });
+
+    let body = Body::from_stream(tokio_util::io::ReaderStream::new(reader));
+    Ok(([(header::CONTENT_TYPE, "application/json")], body).into_response())
+}
+
+/// Fetch, decrypt and parse an agreement, then apply the redaction, field
+/// selection and key naming requested in `params`
+async fn decrypted_view(
+    state: &AppState,
+    cid: &str,
+    params: &DecryptQuery,
+) -> Result<serde_json::Value, (StatusCode, Json<ErrorResponse>)> {
+    let json_string = match params.passphrase.as_deref() {
+        Some(passphrase) => fetch_decrypted_with_passphrase(state, cid, passphrase).await?,
+        None => fetch_decrypted(state, cid, &params.key).await?,
+    };
 
     // Parse JSON
-    let json_value: serde_json::Value = serde_json::from_str(&json_string)
+    let mut json_value: serde_json::Value = serde_json::from_str(&json_string)
         .map_err(|e| {
             error!("JSON parsing failed: {}", e);
             error_response(StatusCode::INTERNAL_SERVER_ERROR, "Invalid JSON data")
         })?;
 
-    info!("✅ Successfully decrypted content");
+    // Financial terms are only returned on explicit request
+    if !params.include_financial {
+        json_fields::redact_fields(&mut json_value, json_fields::FINANCIAL_FIELDS);
+    }
 
-    Ok(Json(json_value))
+    if let Some(fields) = params.fields.as_deref() {
+        let paths: Vec<&str> = fields.split(',').map(str::trim).collect();
+        json_value = json_fields::filter_json_fields(&json_value, &paths);
+    }
+
+    // Field paths above use the stored names, so rename last
+    if let Some(naming) = params.naming {
+        json_value = json_fields::rename_keys(&json_value, naming);
+    }
+
+    Ok(json_value)
 }
 
 async fn status_handler(
@@ -365,13 +2207,1602 @@ async fn status_handler(
     })))
 }
 
-fn error_response(status: StatusCode, message: &str) -> (StatusCode, Json<ErrorResponse>) {
-    (
-        status,
-        Json(ErrorResponse {
-            error: status.to_string(),
-            message: message.to_string(),
-            timestamp: chrono::Utc::now().to_rfc3339(),
-        }),
-    )
+/// Fetch ciphertext from IPFS and decrypt it
+async fn fetch_decrypted(
+    state: &AppState,
+    cid: &str,
+    key: &str,
+) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    // Fetch from IPFS
+    let encrypted_data = state.ipfs_client.fetch(cid)
+        .await
+        .map_err(|e| {
+            error!("IPFS fetch failed: {}", e);
+            error_response(StatusCode::NOT_FOUND, &format!("Failed to fetch from IPFS: {}", e))
+        })?;
+
+    state
+        .encryption_service
+        .open_document(&encrypted_data, key)
+        .map(|document| document.plaintext)
+        .map_err(|e| {
+            error!("Decryption failed: {}", e);
+            error_response(StatusCode::UNAUTHORIZED, "Decryption failed - invalid key")
+        })
+}
+
+/// `fetch_decrypted` for content encrypted with a passphrase. Key derivation
+/// is deliberately slow, so it runs on a blocking thread.
+async fn fetch_decrypted_with_passphrase(
+    state: &AppState,
+    cid: &str,
+    passphrase: &str,
+) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    let encrypted_data = state.ipfs_client.fetch(cid)
+        .await
+        .map_err(|e| {
+            error!("IPFS fetch failed: {}", e);
+            error_response(StatusCode::NOT_FOUND, &format!("Failed to fetch from IPFS: {}", e))
+        })?;
+
+    let encryption_service = state.encryption_service.clone();
+    let passphrase = passphrase.to_string();
+    run_key_derivation(state, move || encryption_service.decrypt_with_passphrase(&encrypted_data, &passphrase))
+        .await?
+        .map_err(|e| {
+            error!("Decryption failed: {}", e);
+            error_response(StatusCode::UNAUTHORIZED, "Decryption failed - invalid passphrase")
+        })
+}
+
+/// Run a passphrase operation on a blocking thread, as key derivation is
+/// deliberately slow. Answers 429 while `MAX_CONCURRENT_KEY_DERIVATIONS`
+/// are already running rather than queueing more memory-hungry work.
+async fn run_key_derivation<T: Send + 'static>(
+    state: &AppState,
+    operation: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> Result<anyhow::Result<T>, (StatusCode, Json<ErrorResponse>)> {
+    let permit = state.kdf_semaphore.clone().try_acquire_owned().map_err(|_| {
+        warn!("Key derivation limit reached, rejecting request");
+        error_response(StatusCode::TOO_MANY_REQUESTS, "Too many passphrase operations in progress, retry later")
+    })?;
+
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        operation()
+    })
+    .await
+    .map_err(|e| {
+        error!("Key derivation task failed: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Key derivation failed")
+    })
+}
+
+/// Fetch and decrypt an agreement stored in the structured `RightsAgreementJSON` format
+async fn fetch_agreement(
+    state: &AppState,
+    cid: &str,
+    key: &str,
+) -> Result<RightsAgreementJSON, (StatusCode, Json<ErrorResponse>)> {
+    let json_string = fetch_decrypted(state, cid, key).await?;
+    let value: serde_json::Value = serde_json::from_str(&json_string).map_err(|e| {
+        error!("Agreement {} is not valid JSON: {}", cid, e);
+        error_response(StatusCode::UNPROCESSABLE_ENTITY, &format!("Agreement {} is not valid JSON", cid))
+    })?;
+    if let Ok(agreement) = RightsAgreementJSON::deserialize(&value) {
+        return Ok(agreement);
+    }
+
+    // Flat LLM output, as stored by uploads. A builder without a database
+    // normalizes it without reserving another agreement ID.
+    JSONBuilder::default().build_from_llm_json(&value).await.map_err(|e| {
+        error!("Agreement {} could not be normalized: {}", cid, e);
+        error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            &format!("Agreement {} is not in a recognised agreement format", cid),
+        )
+    })
+}
+
+/// Re-stamp the JSON commitment of modified agreement JSON, encrypt it with a
+/// fresh key and upload it to IPFS. Returns `(cid, key)`.
+async fn store_agreement_value(
+    state: &AppState,
+    agreement_value: &mut serde_json::Value,
+) -> Result<(String, String), (StatusCode, Json<ErrorResponse>)> {
+    // Content changed, so the JSON commitment must be recomputed
+    integrity::stamp_content_hashes(agreement_value, None);
+    let json_string = agreement_value.to_string();
+
+    let (encrypted_data, encryption_key) = state.encryption_service.encrypt(&json_string)
+        .map_err(|e| {
+            error!("Encryption failed: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Encryption failed")
+        })?;
+
+    let ipfs_cid = state.ipfs_client.upload(&encrypted_data)
+        .await
+        .map_err(|e| {
+            error!("IPFS upload failed: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("IPFS upload failed: {}", e))
+        })?;
+
+    info!("📍 Stored agreement at IPFS CID: {}", ipfs_cid);
+
+    Ok((ipfs_cid, encryption_key))
+}
+
+/// Encrypt an agreement with a fresh key and upload it to IPFS, returning it
+/// with keys in `naming` casing
+async fn store_agreement(
+    state: &AppState,
+    agreement: RightsAgreementJSON,
+    naming: JsonNamingStrategy,
+) -> Result<StoredAgreementResponse, (StatusCode, Json<ErrorResponse>)> {
+    let serialize_error = |e: serde_json::Error| {
+        error!("Failed to serialize agreement: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to serialize agreement")
+    };
+
+    // Readers deserialize the stored copy, so it is always camelCase
+    let stored_json = agreement.serialize_with_strategy(JsonNamingStrategy::CamelCase).map_err(serialize_error)?;
+    let mut agreement_value: serde_json::Value = serde_json::from_str(&stored_json).map_err(serialize_error)?;
+
+    let (ipfs_cid, encryption_key) = store_agreement_value(state, &mut agreement_value).await?;
+    let agreement: RightsAgreementJSON = serde_json::from_value(agreement_value).map_err(|e| {
+        error!("Failed to re-read stamped agreement: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to serialize agreement")
+    })?;
+    let agreement = agreement
+        .serialize_with_strategy(naming)
+        .and_then(serde_json::value::RawValue::from_string)
+        .map_err(serialize_error)?;
+
+    Ok(StoredAgreementResponse {
+        ipfs_url: format!("ipfs://{}", ipfs_cid),
+        ipfs_gateway_url: format!("https://ipfs.io/ipfs/{}", ipfs_cid),
+        ipfs_cid,
+        encryption_key,
+        agreement,
+    })
+}
+
+async fn merge_agreements_handler(
+    State(state): State<AppState>,
+    Json(request): Json<MergeRequest>,
+) -> Result<Json<StoredAgreementResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!("🔀 Merging {} agreements", request.cids.len());
+
+    if request.cids.len() < 2 || request.cids.len() != request.keys.len() {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "Provide at least two CIDs with one key per CID",
+        ));
+    }
+
+    let mut agreements = Vec::with_capacity(request.cids.len());
+    for (cid, key) in request.cids.iter().zip(&request.keys) {
+        agreements.push(fetch_agreement(&state, cid, key).await?);
+    }
+
+    let mut merged = agreements.remove(0);
+    let others: Vec<Rights> = agreements.into_iter().map(|a| a.rights).collect();
+    merged.rights = merged.rights.merge(&others, &request.strategy);
+
+    let metadata = merged.metadata.get_or_insert_with(Metadata::new);
+    metadata.last_modified = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    metadata.source_cids = request.cids.clone();
+
+    let stored = store_agreement(&state, merged, request.naming).await?;
+
+    info!("✅ Merged agreements into {}", stored.ipfs_cid);
+
+    Ok(Json(stored))
+}
+
+async fn check_conflict_handler(
+    State(state): State<AppState>,
+    Json(request): Json<ConflictCheckRequest>,
+) -> Result<Json<ConflictCheckResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if request.cids.len() != 2 || request.keys.len() != 2 {
+        return Err(error_response(StatusCode::BAD_REQUEST, "Provide exactly two CIDs with one key per CID"));
+    }
+    info!("⚔️  Checking {} and {} for conflicts", request.cids[0], request.cids[1]);
+
+    let first = fetch_agreement(&state, &request.cids[0], &request.keys[0]).await?;
+    let second = fetch_agreement(&state, &request.cids[1], &request.keys[1]).await?;
+
+    let overlap_period = Rights::compute_term_overlap(&first.rights.term, &second.rights.term);
+    let overlapping_territories = first.rights.overlapping_territories(&second.rights);
+
+    Ok(Json(ConflictCheckResponse {
+        has_conflict: overlap_period.is_some() && first.rights.has_territory_overlap(&second.rights),
+        overlap_period: overlap_period.map(|p| p.to_string()),
+        overlapping_territories,
+    }))
+}
+
+async fn renew_agreement_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Json(request): Json<RenewalRequest>,
+) -> Result<Json<StoredAgreementResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!("🔁 Renewing agreement {} for {} years", cid, request.extension_years);
+
+    let parse_date = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok();
+
+    if request.extension_years == 0 {
+        return Err(error_response(StatusCode::BAD_REQUEST, "extension_years must be at least 1"));
+    }
+    let effective_date = parse_date(&request.effective_date).ok_or_else(|| {
+        error_response(StatusCode::BAD_REQUEST, "effective_date must be YYYY-MM-DD")
+    })?;
+
+    let mut renewal = fetch_agreement(&state, &cid, &request.key).await?;
+
+    // The renewal picks up where the original term ends
+    let start = renewal.rights.term.end_date.date().unwrap_or(effective_date);
+    let end = start
+        .checked_add_months(chrono::Months::new(request.extension_years * 12))
+        .ok_or_else(|| error_response(StatusCode::BAD_REQUEST, "Renewal term is out of range"))?;
+
+    renewal.rights.term.start_date = FlexibleDate::from_date(start);
+    renewal.rights.term.end_date = FlexibleDate::from_date(end);
+    renewal.rights.term.years = request.extension_years;
+
+    if let Some(deal_value) = request.new_deal_value {
+        renewal.financial.set_deal_value(deal_value);
+    }
+
+    let metadata = renewal.metadata.get_or_insert_with(Metadata::new);
+    let major = metadata
+        .version
+        .split('.')
+        .next()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(1);
+    metadata.version = format!("{}.0", major + 1);
+    metadata.last_modified = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    metadata.status = "Active".to_string();
+    metadata.previous_version_cid = Some(cid.clone());
+    metadata.effective_date = Some(request.effective_date.clone());
+    let (version, status) = (metadata.version.clone(), metadata.status.clone());
+
+    let stored = store_agreement(&state, renewal, request.naming).await?;
+    record_successor(&state, &stored.ipfs_cid, &cid, Some(&version), Some(&status)).await;
+
+    info!("✅ Renewed {} as {}", cid, stored.ipfs_cid);
+
+    Ok(Json(stored))
+}
+
+async fn verify_agreement_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Query(params): Query<VerifyQuery>,
+) -> Json<VerifyResponse> {
+    info!("🔎 Verifying agreement {}", cid);
+
+    let mut checks = VerificationChecks::default();
+
+    let agreement = match state.ipfs_client.fetch(&cid).await {
+        Ok(encrypted_data) => {
+            checks.ipfs_retrievable = true;
+            state.encryption_service.open_document(&encrypted_data, &params.key).ok().map(|document| document.plaintext)
+        }
+        Err(e) => {
+            warn!("Verification fetch failed for {}: {}", cid, e);
+            None
+        }
+    };
+
+    if let Some(json_string) = agreement {
+        checks.key_valid = true;
+
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&json_string) {
+            let stored_pdf_hash = json_str(&value, &["/metadata/pdfSha256"]);
+            checks.pdf_hash_match = stored_pdf_hash
+                .map(|h| h.eq_ignore_ascii_case(params.pdf_hash.trim()))
+                .unwrap_or(false);
+
+            let stored_json_hash = json_str(&value, &["/metadata/jsonSha256"]);
+            checks.json_hash_match = stored_json_hash == Some(integrity::json_commitment(&value).as_str());
+        }
+    }
+
+    let verified = checks.ipfs_retrievable
+        && checks.key_valid
+        && checks.pdf_hash_match
+        && checks.json_hash_match;
+
+    if verified {
+        info!("✅ Agreement {} verified", cid);
+    } else {
+        warn!("⚠️  Agreement {} failed verification", cid);
+    }
+
+    Json(VerifyResponse { verified, checks })
+}
+
+async fn export_agreement_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Query(params): Query<ExportQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let format = params.format.as_deref().unwrap_or("csv");
+    if !format.eq_ignore_ascii_case("csv") {
+        return Err(error_response(StatusCode::BAD_REQUEST, "Unsupported export format, expected csv"));
+    }
+
+    info!("📤 Exporting agreement {} as CSV", cid);
+
+    let agreement = fetch_agreement(&state, &cid, &params.key).await?;
+    let fields: Option<Vec<&str>> = params
+        .fields
+        .as_deref()
+        .map(|f| f.split(',').map(str::trim).filter(|f| !f.is_empty()).collect());
+
+    let rows: Vec<_> = agreement
+        .to_csv_rows()
+        .into_iter()
+        .filter(|(path, _)| {
+            params.include_financial || !json_fields::path_selected(path, json_fields::FINANCIAL_FIELDS)
+        })
+        .filter(|(path, _)| fields.as_ref().map_or(true, |f| json_fields::path_selected(path, f)))
+        .collect();
+
+    // Field paths as the header row, values as a single record
+    let columns: Vec<String> = rows.iter().map(|(path, _)| csv_field(path)).collect();
+    let record: Vec<String> = rows.iter().map(|(_, value)| csv_field(value)).collect();
+    let csv = format!("{}\r\n{}\r\n", columns.join(","), record.join(","));
+
+    let disposition = format!("attachment; filename=\"{}.csv\"", cid);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        csv,
+    )
+        .into_response())
+}
+
+/// Deal value, fees, payment schedule and waterfall of an agreement.
+/// `format=pdf` needs PDF_REPORTS=true.
+async fn financial_report_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Query(params): Query<FinancialReportQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let pdf = match params.format.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("json") => false,
+        Some("pdf") if state.pdf_reports => true,
+        Some("pdf") => return Err(error_response(StatusCode::NOT_FOUND, "PDF reports are disabled")),
+        Some(_) => return Err(error_response(StatusCode::BAD_REQUEST, "Unsupported report format, expected json or pdf")),
+    };
+
+    info!("💰 Financial report for {}{}", cid, if pdf { " (PDF)" } else { "" });
+
+    let agreement = fetch_agreement(&state, &cid, &params.key).await?;
+    let report = FinancialReport::from_agreement(&agreement);
+    if !report.schedule_balanced {
+        warn!(
+            "Payment schedule of {} totals {} but the deal value is {}",
+            cid, report.scheduled_total, report.deal_value
+        );
+    }
+
+    if !pdf {
+        return Ok(Json(report).into_response());
+    }
+
+    let bytes = report.to_pdf().map_err(|e| {
+        error!("Failed to render financial report for {}: {:#}", cid, e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to render PDF report")
+    })?;
+    let disposition = format!("attachment; filename=\"{}-financial-report.pdf\"", cid);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+async fn register_webhook_handler(
+    State(state): State<AppState>,
+    Extension(api_key): Extension<ApiKeyRecord>,
+    Json(request): Json<RegisterWebhookRequest>,
+) -> Result<(StatusCode, Json<WebhookSubscription>), (StatusCode, Json<ErrorResponse>)> {
+    let url = pdf_download::validate_url(&request.url)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, &e.to_string()))?;
+    // Deliveries check again, in case the name is later pointed elsewhere
+    pdf_download::public_client(&url)
+        .await
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, &e.to_string()))?;
+    if request.events.is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "events must not be empty"));
+    }
+
+    let mut events = request.events;
+    events.sort_by_key(|e| e.as_str());
+    events.dedup();
+    let subscription = state.webhooks.register(api_key.id, url.as_str(), &events).await.map_err(|e| {
+        error!("{:#}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to register webhook")
+    })?;
+
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+async fn list_webhooks_handler(
+    State(state): State<AppState>,
+    Extension(api_key): Extension<ApiKeyRecord>,
+) -> Result<Json<Vec<WebhookSubscription>>, (StatusCode, Json<ErrorResponse>)> {
+    let subscriptions = state.webhooks.list(api_key.id).await.map_err(|e| {
+        error!("{:#}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to list webhooks")
+    })?;
+    Ok(Json(subscriptions))
+}
+
+async fn delete_webhook_handler(
+    State(state): State<AppState>,
+    Extension(api_key): Extension<ApiKeyRecord>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let deleted = state.webhooks.delete(api_key.id, id).await.map_err(|e| {
+        error!("{:#}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to delete webhook")
+    })?;
+    if !deleted {
+        return Err(error_response(StatusCode::NOT_FOUND, "Webhook not found"));
+    }
+    info!("🪝 Deleted webhook {}", id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn tag_agreement_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Json(request): Json<TagsRequest>,
+) -> Result<Json<TagsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let tags = normalization::normalize_tags(&request.tags);
+    info!("🏷️  Tagging agreement {} with {:?}", cid, tags);
+
+    // Works on the stored JSON directly so LLM-format agreements can be tagged too
+    let json_string = fetch_decrypted(&state, &cid, &request.key).await?;
+    let mut agreement_value: serde_json::Value = serde_json::from_str(&json_string).map_err(|e| {
+        error!("JSON parsing failed: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Invalid JSON data")
+    })?;
+
+    let metadata = metadata_object(&mut agreement_value)
+        .ok_or_else(|| error_response(StatusCode::UNPROCESSABLE_ENTITY, "Agreement JSON is not an object"))?;
+    metadata.insert("tags".to_string(), serde_json::json!(tags));
+    metadata.insert("previousVersionCid".to_string(), serde_json::json!(cid));
+
+    let (ipfs_cid, encryption_key) = store_agreement_value(&state, &mut agreement_value).await?;
+    record_successor(&state, &ipfs_cid, &cid, None, None).await;
+
+    match agreement_store::update_tags(&state.db, &cid, &ipfs_cid, &tags).await {
+        Ok(true) => {}
+        Ok(false) => warn!("No database record for {}, tags only stored on IPFS", cid),
+        Err(e) => warn!("Failed to update tags in database: {}", e),
+    }
+
+    info!("✅ Tagged {} as {}", cid, ipfs_cid);
+
+    Ok(Json(TagsResponse {
+        ipfs_url: format!("ipfs://{}", ipfs_cid),
+        ipfs_gateway_url: format!("https://ipfs.io/ipfs/{}", ipfs_cid),
+        ipfs_cid,
+        encryption_key,
+        tags,
+    }))
+}
+
+async fn export_blockchain_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Json(request): Json<BlockchainExportRequest>,
+) -> Result<Json<BlockchainExportResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let client = state
+        .blockchain_client
+        .clone()
+        .ok_or_else(|| error_response(StatusCode::SERVICE_UNAVAILABLE, "Blockchain export is not configured"))?;
+    info!("⛓️  Anchoring agreement {} on-chain", cid);
+
+    let json_string = fetch_decrypted(&state, &cid, &request.key).await?;
+    let mut agreement_value: serde_json::Value = serde_json::from_str(&json_string).map_err(|e| {
+        error!("JSON parsing failed: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Invalid JSON data")
+    })?;
+
+    let licensor_raw = request
+        .licensor_address
+        .as_deref()
+        .or_else(|| json_str(&agreement_value, &["/rightsHolder/walletAddressResolved", "/wallet_address_resolved"]))
+        .ok_or_else(|| error_response(StatusCode::BAD_REQUEST, "Agreement has no licensor wallet, provide licensor_address"))?
+        .to_string();
+    let mut addresses = Vec::with_capacity(2);
+    for (role, raw) in [("licensor", licensor_raw.as_str()), ("licensee", request.licensee_address.as_str())] {
+        let address = state.ens_resolver.resolve(raw).await.ok_or_else(|| {
+            error_response(StatusCode::BAD_REQUEST, &format!("Could not resolve {} address '{}'", role, raw))
+        })?;
+        addresses.push(address);
+    }
+
+    // Recomputed rather than read from metadata.jsonSha256, which may be missing or stale
+    let content_hash = blockchain::content_hash_bytes(&integrity::json_commitment(&agreement_value)).map_err(|e| {
+        error!("Invalid content hash for {}: {:#}", cid, e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to hash agreement")
+    })?;
+
+    // An anchored copy carries its transaction; registering it again would
+    // record the same agreement twice
+    if let Some(tx_hash) = agreement_value.pointer("/metadata/blockchain/txHash").and_then(|t| t.as_str()) {
+        return Err(error_response(
+            StatusCode::CONFLICT,
+            &format!("Agreement {} is already anchored in transaction {}", cid, tx_hash),
+        ));
+    }
+    reserve_blockchain_export(&state, &cid).await?;
+
+    let receipt = match client.register_agreement(&cid, &content_hash, &addresses[0], &addresses[1]).await {
+        Ok(receipt) => receipt,
+        Err(e) => {
+            error!("Blockchain export failed for {}: {:#}", cid, e);
+            let message = match e.downcast_ref::<blockchain::UnconfirmedTransaction>() {
+                // The transaction may still be mined, so the claim is kept
+                Some(unconfirmed) => {
+                    if let Err(e) = agreement_store::record_blockchain_export(&state.db, &cid, &unconfirmed.tx_hash, None).await {
+                        error!("Failed to record unconfirmed export of {}: {}", cid, e);
+                    }
+                    format!("{}; the export will not be retried", unconfirmed)
+                }
+                None => {
+                    if let Err(e) = agreement_store::release_blockchain_export(&state.db, &cid).await {
+                        error!("Failed to release export claim on {}: {}", cid, e);
+                    }
+                    "Failed to register agreement on-chain".to_string()
+                }
+            };
+            return Err(error_response(StatusCode::BAD_GATEWAY, &message));
+        }
+    };
+
+    // Recorded straight away so a failure below cannot free the claim on a mined export
+    let tx_hash = receipt.transaction_hash.to_string();
+    if let Err(e) = agreement_store::record_blockchain_export(&state.db, &cid, &tx_hash, None).await {
+        error!("Failed to record blockchain export of {}: {}", cid, e);
+    }
+    let contract_address = client.contract_address();
+    let metadata = metadata_object(&mut agreement_value)
+        .ok_or_else(|| error_response(StatusCode::UNPROCESSABLE_ENTITY, "Agreement JSON is not an object"))?;
+    let network = metadata
+        .get("blockchain")
+        .and_then(|b| b.get("network"))
+        .and_then(|n| n.as_str())
+        .unwrap_or("CBDC_TESTNET")
+        .to_string();
+    let blockchain = BlockchainInfo {
+        network,
+        deployment_pending: false,
+        tx_hash: Some(tx_hash.clone()),
+        block_number: receipt.block_number,
+        contract_address: Some(contract_address.clone()),
+    };
+    metadata.insert("blockchain".to_string(), serde_json::json!(blockchain));
+    metadata.insert("previousVersionCid".to_string(), serde_json::json!(cid));
+
+    let (ipfs_cid, encryption_key) = store_agreement_value(&state, &mut agreement_value).await?;
+    record_successor(&state, &ipfs_cid, &cid, None, None).await;
+
+    let anchored =
+        agreement_store::update_blockchain_anchor(&state.db, &cid, &ipfs_cid, &tx_hash, receipt.block_number, &contract_address);
+    match anchored.await {
+        Ok(true) => {}
+        Ok(false) => warn!("No database record for {}, anchor only stored on IPFS", cid),
+        Err(e) => warn!("Failed to record blockchain anchor in database: {}", e),
+    }
+    if let Err(e) = agreement_store::record_blockchain_export(&state.db, &cid, &tx_hash, Some(&ipfs_cid)).await {
+        warn!("Failed to record blockchain export of {}: {}", cid, e);
+    }
+
+    info!("✅ Anchored {} in transaction {}", cid, tx_hash);
+
+    Ok(Json(BlockchainExportResponse {
+        anchored_cid: cid,
+        ipfs_url: format!("ipfs://{}", ipfs_cid),
+        ipfs_gateway_url: format!("https://ipfs.io/ipfs/{}", ipfs_cid),
+        ipfs_cid,
+        encryption_key,
+        blockchain,
+    }))
+}
+
+/// Claim `cid` for an on-chain export, answering 409 if it was already exported
+/// or an export is in progress
+async fn reserve_blockchain_export(state: &AppState, cid: &str) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let reserved = agreement_store::reserve_blockchain_export(&state.db, cid).await.map_err(|e| {
+        error!("Failed to reserve blockchain export of {}: {}", cid, e);
+        error_response(StatusCode::SERVICE_UNAVAILABLE, "Could not check for an earlier export, try again later")
+    })?;
+    if reserved {
+        return Ok(());
+    }
+
+    let message = match agreement_store::blockchain_export_tx(&state.db, cid).await {
+        Ok(Some(tx_hash)) => format!("Agreement {} was already exported in transaction {}", cid, tx_hash),
+        _ => format!("Agreement {} is already being exported", cid),
+    };
+    Err(error_response(StatusCode::CONFLICT, &message))
+}
+
+/// Store a copy of the agreement encrypted with a passphrase, for parties
+/// who open it with `GET /api/decrypt/:cid?passphrase=...` instead of a key
+async fn share_agreement_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Json(request): Json<ShareRequest>,
+) -> Result<Json<ShareResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if request.passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("passphrase must be at least {} characters", MIN_PASSPHRASE_CHARS),
+        ));
+    }
+
+    info!("🔗 Sharing {} with a passphrase", cid);
+    let json_string = fetch_decrypted(&state, &cid, &request.key).await?;
+
+    let encryption_service = state.encryption_service.clone();
+    let passphrase = request.passphrase;
+    let encrypted_data = run_key_derivation(&state, move || encryption_service.encrypt_with_passphrase(&json_string, &passphrase))
+        .await?
+        .map_err(|e| {
+            error!("Passphrase encryption failed: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Encryption failed")
+        })?;
+
+    let shared_cid = state.ipfs_client.upload(&encrypted_data).await.map_err(|e| {
+        error!("IPFS upload failed: {}", e);
+        error_response(StatusCode::BAD_GATEWAY, &format!("IPFS upload failed: {}", e))
+    })?;
+
+    info!("✅ Shared {} as {}", cid, shared_cid);
+
+    Ok(Json(ShareResponse {
+        ipfs_url: format!("ipfs://{}", shared_cid),
+        ipfs_gateway_url: format!("https://ipfs.io/ipfs/{}", shared_cid),
+        shared_cid,
+    }))
+}
+
+/// Re-encrypt the content at `cid` under a fresh key. The plaintext stays
+/// inside `rekey_in_place` and is never returned or stored.
+async fn rekey_agreement_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Json(request): Json<RekeyRequest>,
+) -> Result<Json<RekeyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let mut result = state
+        .encryption_service
+        .rekey_in_place(state.ipfs_client.clone(), &cid, &request.key)
+        .await
+        .map_err(|e| {
+            error!("Rekey failed for {}: {:#}", cid, e);
+            if e.is::<encryption::InvalidKeyError>() {
+                error_response(StatusCode::UNAUTHORIZED, "Decryption failed - invalid key")
+            } else {
+                error_response(StatusCode::BAD_GATEWAY, "Key rotation failed; the agreement is unchanged")
+            }
+        })?;
+
+    // The old CID stays pinned until the database points at the new one
+    match agreement_store::update_cid(&state.db, &cid, &result.new_cid).await {
+        Ok(true) => {}
+        Ok(false) => warn!("No database record for {}, rekey only stored on IPFS", cid),
+        Err(e) => {
+            error!("Failed to point database record at {}: {}", result.new_cid, e);
+            if result.new_cid != cid {
+                if let Err(e) = state.ipfs_client.unpin(&result.new_cid).await {
+                    warn!("Failed to unpin abandoned rekey upload {}: {}", result.new_cid, e);
+                }
+            }
+            return Err(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Key rotation could not be recorded; the agreement is unchanged",
+            ));
+        }
+    }
+    result.old_cid_unpinned = encryption::unpin_replaced(state.ipfs_client.clone(), &cid, &result.new_cid).await;
+    if result.new_cid != cid {
+        record_successor(&state, &result.new_cid, &cid, None, None).await;
+    }
+    let rotated_at = agreement_store::record_key_rotation(&state.db, &cid, &result.new_cid)
+        .await
+        .map_err(|e| error!("Failed to record key rotation {} → {}: {}", cid, result.new_cid, e))
+        .ok();
+
+    Ok(Json(RekeyResponse {
+        ipfs_url: format!("ipfs://{}", result.new_cid),
+        ipfs_gateway_url: format!("https://ipfs.io/ipfs/{}", result.new_cid),
+        rotated_at: rotated_at.map(|at| at.to_rfc3339()),
+        result,
+    }))
+}
+
+async fn notify_agreement_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Json(request): Json<NotificationRequest>,
+) -> Result<(StatusCode, Json<NotificationQueuedResponse>), (StatusCode, Json<ErrorResponse>)> {
+    if request.cid != cid {
+        return Err(error_response(StatusCode::BAD_REQUEST, "cid in body does not match the URL"));
+    }
+    let event = NotificationEvent::parse(&request.event_type).ok_or_else(|| {
+        error_response(StatusCode::BAD_REQUEST, "event_type must be one of: executed, amended, expiring")
+    })?;
+    if request.recipient_emails.is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "recipient_emails must not be empty"));
+    }
+    let recipients = request
+        .recipient_emails
+        .iter()
+        .map(|email| {
+            email.trim().parse::<lettre::message::Mailbox>().map_err(|_| {
+                error_response(StatusCode::BAD_REQUEST, &format!("Invalid email address: {}", email))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if !state.email_notifier.is_enabled() {
+        return Err(error_response(StatusCode::SERVICE_UNAVAILABLE, "Email notifications are not configured"));
+    }
+
+    // Read the template fields from whichever shape the agreement was stored in
+    let json_string = fetch_decrypted(&state, &cid, &request.key).await?;
+    let agreement_value: serde_json::Value = serde_json::from_str(&json_string).map_err(|e| {
+        error!("JSON parsing failed: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Invalid JSON data")
+    })?;
+
+    // Only the agreement's own party contacts and configured addresses can be mailed
+    let contacts: Vec<&str> = ["/parties/licensor/contactEmail", "/parties/licensee/contactEmail"]
+        .iter()
+        .filter_map(|p| json_str(&agreement_value, &[p]))
+        .collect();
+    if let Some(recipient) = notifier::unlisted_recipient(&recipients, &contacts, state.email_notifier.allowed_recipients()) {
+        warn!("Refused notification for {} to unlisted recipient {}", cid, recipient);
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            &format!("{} is not a contact on this agreement", recipient.email),
+        ));
+    }
+
+    info!("📧 Notifying {} recipients that {} was {}", recipients.len(), cid, request.event_type);
+    let summary = AgreementSummary {
+        title: json_str(&agreement_value, &["/title", "/content/title"]).unwrap_or("Untitled agreement"),
+        licensor: json_str(&agreement_value, &["/licensor", "/parties/licensor/name", "/rightsHolder/name"]).unwrap_or("Unknown"),
+        licensee: json_str(&agreement_value, &["/licensee", "/parties/licensee/name"]).unwrap_or("Unknown"),
+    };
+    let (subject, body) = notifier::render_email(event, &summary, &cid, request.message.as_deref());
+
+    // Deliver in the background so the caller does not wait on SMTP
+    let notifier = state.email_notifier.clone();
+    let recipient_count = recipients.len();
+    let queued_cid = cid.clone();
+    tokio::spawn(async move {
+        match notifier.send(&recipients, &subject, &body).await {
+            Ok(sent) => info!("📧 Sent {}/{} notifications for {}", sent, recipients.len(), queued_cid),
+            Err(e) => error!("Notification for {} failed: {}", queued_cid, e),
+        }
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(NotificationQueuedResponse {
+            ipfs_cid: cid,
+            event_type: request.event_type.to_lowercase(),
+            recipients: recipient_count,
+            status: "queued",
+        }),
+    ))
+}
+
+async fn list_expiring_handler(
+    State(state): State<AppState>,
+    Query(params): Query<ExpiringQuery>,
+) -> Result<Json<ExpiringAgreementsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let days = params.days.unwrap_or(30);
+    if !(0..=3650).contains(&days) {
+        return Err(error_response(StatusCode::BAD_REQUEST, "days must be between 0 and 3650"));
+    }
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+
+    let cursor = match params.after.as_deref() {
+        Some(token) => Some(agreement_store::ExpiryCursor::decode(token).map_err(|e| {
+            warn!("Invalid pagination cursor: {}", e);
+            error_response(StatusCode::BAD_REQUEST, "Invalid cursor")
+        })?),
+        None => None,
+    };
+
+    info!("⏳ Listing agreements expiring within {} days", days);
+
+    let page = agreement_store::list_expiring(&state.db, days, params.status.as_deref(), cursor.as_ref(), limit)
+        .await
+        .map_err(|e| {
+            error!("Failed to list expiring agreements: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to list expiring agreements")
+        })?;
+
+    Ok(Json(ExpiringAgreementsResponse {
+        data: page.data,
+        next_cursor: page.next_cursor,
+        has_more: page.has_more,
+    }))
+}
+
+async fn statistics_handler(
+    State(state): State<AppState>,
+) -> Result<Json<AgreementStatistics>, (StatusCode, Json<ErrorResponse>)> {
+    // Hold the lock while refreshing so concurrent callers share one scan
+    let mut cache = state.statistics_cache.lock().await;
+    if let Some((computed_at, statistics)) = cache.as_ref() {
+        if computed_at.elapsed() < STATISTICS_CACHE_TTL {
+            return Ok(Json(statistics.clone()));
+        }
+    }
+
+    info!("📊 Computing agreement statistics");
+
+    let statistics = agreement_store::agreement_statistics(&state.db).await.map_err(|e| {
+        error!("Failed to compute statistics: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to compute statistics")
+    })?;
+    *cache = Some((std::time::Instant::now(), statistics.clone()));
+
+    Ok(Json(statistics))
+}
+
+async fn bulk_status_update_handler(
+    State(state): State<AppState>,
+    Json(request): Json<BulkStatusRequest>,
+) -> Result<(StatusCode, Json<BulkStatusResponse>), (StatusCode, Json<ErrorResponse>)> {
+    if request.cids.is_empty() || request.cids.len() != request.keys.len() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "Provide at least one CID with one key per CID"));
+    }
+    if request.cids.len() > worker::MAX_BULK_AGREEMENTS {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("A bulk job accepts at most {} agreements", worker::MAX_BULK_AGREEMENTS),
+        ));
+    }
+    let new_status = request.new_status.trim().to_string();
+    if new_status.is_empty() || new_status.len() > 20 {
+        return Err(error_response(StatusCode::BAD_REQUEST, "new_status must be 1-20 characters"));
+    }
+
+    let job_id = uuid::Uuid::new_v4();
+    let agreements: Vec<(String, String)> = request.cids.into_iter().zip(request.keys).collect();
+    info!("📦 Queued bulk job {} for {} agreements", job_id, agreements.len());
+
+    {
+        let mut bulk_jobs = state.bulk_jobs.lock().await;
+        worker::prune_bulk_jobs(&mut bulk_jobs, worker::BULK_JOB_TTL);
+        bulk_jobs.insert(job_id, worker::BulkJobProgress::new(job_id, &new_status, agreements.len()));
+    }
+
+    // Shutdown waits for the job as it would for a request still in flight
+    state.active_requests.fetch_add(1, Ordering::SeqCst);
+    let guard = ActiveRequestGuard(state.active_requests.clone());
+    let job = worker::run_bulk_status_update(
+        state.clone(),
+        state.bulk_jobs.clone(),
+        job_id,
+        agreements,
+        new_status,
+        request.reason,
+    );
+    tokio::spawn(async move {
+        let _guard = guard;
+        job.await
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(BulkStatusResponse { job_id })))
+}
+
+async fn bulk_status_job_handler(
+    State(state): State<AppState>,
+    Path(job_id): Path<uuid::Uuid>,
+) -> Result<Json<worker::BulkJobProgress>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .bulk_jobs
+        .lock()
+        .await
+        .get(&job_id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "Unknown job id"))
+}
+
+async fn summary_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Query(params): Query<SummaryQuery>,
+) -> Result<Json<SummaryResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!("📝 Summarizing agreement {}", cid);
+
+    let agreement = fetch_agreement(&state, &cid, &params.key).await?;
+
+    // Same limit as parsing - reject rather than queue when the model is saturated
+    let _permit = state.llm_semaphore.clone().try_acquire_owned().map_err(|_| {
+        warn!("LLM concurrency limit reached, rejecting summary request");
+        error_response(StatusCode::TOO_MANY_REQUESTS, "LLM concurrency limit reached, retry later")
+    })?;
+    let summary = state.llm_service.generate_plain_english_summary(&agreement).await.map_err(|e| {
+        error!("Summary generation failed: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("Summary generation failed: {}", e))
+    })?;
+
+    Ok(Json(SummaryResponse {
+        ipfs_cid: cid,
+        word_count: summary.split_whitespace().count(),
+        summary,
+    }))
+}
+
+/// Contact details for one party, without returning the rest of the agreement.
+/// Every lookup is recorded in `audit_events`.
+async fn party_contact_handler(
+    State(state): State<AppState>,
+    Path((cid, role)): Path<(String, String)>,
+    Query(params): Query<SummaryQuery>,
+) -> Result<Json<PartyContactResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let role = role.to_lowercase();
+    if role != "licensor" && role != "licensee" {
+        return Err(error_response(StatusCode::BAD_REQUEST, "role must be licensor or licensee"));
+    }
+    info!("📇 Contact lookup for {} of {}", role, cid);
+
+    let json_string = fetch_decrypted(&state, &cid, &params.key).await?;
+    // Only the party is kept; the rest of the document is dropped straight away
+    let party = serde_json::from_str::<serde_json::Value>(&json_string)
+        .ok()
+        .and_then(|agreement| {
+            agreement
+                .pointer(&format!("/parties/{}", role))
+                .cloned()
+                // The flat LLM format only records party names
+                .or_else(|| agreement.get(&role).filter(|v| v.is_string()).map(|name| serde_json::json!({ "name": name })))
+        })
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, &format!("Agreement has no {}", role)))?;
+    drop(json_string);
+
+    let field = |key: &str| party.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    let contact = PartyContactResponse {
+        name: field("name").unwrap_or_default(),
+        email: field("contactEmail"),
+        signatory_name: field("signatoryName"),
+        role,
+    };
+
+    let details = serde_json::json!({ "role": contact.role });
+    if let Err(e) = agreement_store::record_audit_event(&state.db, "contact_lookup", &cid, &details).await {
+        warn!("{:#}", e);
+    }
+
+    Ok(Json(contact))
+}
+
+/// Predecessors followed when building a chain of title
+const MAX_CHAIN_DEPTH: i32 = 20;
+
+/// Record `cid` as derived from `predecessor`. Lineage is best-effort, so
+/// failures only log.
+async fn record_successor(state: &AppState, cid: &str, predecessor: &str, version: Option<&str>, status: Option<&str>) {
+    if let Err(e) = agreement_store::record_version(&state.db, cid, Some(predecessor), version, status).await {
+        warn!("{:#}", e);
+    }
+}
+
+/// The agreement as it stood after amendment `number`, rebuilt from the
+/// `metadata.amendments` history of the stored version
+async fn amendment_version_handler(
+    State(state): State<AppState>,
+    Path((cid, number)): Path<(String, usize)>,
+    Query(params): Query<SummaryQuery>,
+) -> Result<Json<RightsAgreementJSON>, (StatusCode, Json<ErrorResponse>)> {
+    info!("🕰️  Rebuilding amendment {} of {}", number, cid);
+
+    let agreement = fetch_agreement(&state, &cid, &params.key).await?;
+    let recorded = agreement.metadata.as_ref().map_or(0, |m| m.amendments.len());
+    if number > recorded {
+        return Err(error_response(
+            StatusCode::NOT_FOUND,
+            &format!("Amendment {} does not exist ({} recorded)", number, recorded),
+        ));
+    }
+
+    agreement_diff::version_at(&agreement, number).map(Json).map_err(|e| {
+        warn!("Failed to rebuild amendment {} of {}: {:#}", number, cid, e);
+        error_response(StatusCode::UNPROCESSABLE_ENTITY, &format!("{:#}", e))
+    })
+}
+
+async fn chain_of_title_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+) -> Result<Json<ChainOfTitleResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!("📜 Chain of title for {}", cid);
+
+    let chain = agreement_store::chain_of_title(&state.db, &cid, MAX_CHAIN_DEPTH)
+        .await
+        .map_err(|e| {
+            error!("Failed to load chain of title for {}: {:#}", cid, e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to load chain of title")
+        })?;
+    if chain.is_empty() {
+        return Err(error_response(StatusCode::NOT_FOUND, &format!("No version history for {}", cid)));
+    }
+
+    Ok(Json(ChainOfTitleResponse { chain }))
+}
+
+async fn obligations_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Query(params): Query<ObligationsQuery>,
+) -> Result<Json<ObligationsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let party = params.party.as_deref().map(str::to_lowercase);
+    if party.as_deref().is_some_and(|p| p != "licensor" && p != "licensee") {
+        return Err(error_response(StatusCode::BAD_REQUEST, "party must be licensor or licensee"));
+    }
+    info!("📋 Obligations of {}", cid);
+
+    let json_string = fetch_decrypted(&state, &cid, &params.key).await?;
+    let agreement: serde_json::Value = serde_json::from_str(&json_string).map_err(|e| {
+        error!("JSON parsing failed: {}", e);
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, "Invalid JSON data")
+    })?;
+    let mut obligations: Vec<Obligation> = agreement
+        .get("obligations")
+        .cloned()
+        .and_then(|o| serde_json::from_value(o).ok())
+        .unwrap_or_default();
+
+    if let Some(role) = party.as_deref() {
+        let name = json_str(
+            &agreement,
+            &[&format!("/parties/{}/name", role), &format!("/{}", role)],
+        );
+        obligations.retain(|o| o.is_owed_by(role, name));
+    }
+
+    Ok(Json(ObligationsResponse {
+        ipfs_cid: cid,
+        party,
+        obligations,
+    }))
+}
+
+async fn review_cost_handler(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    Query(params): Query<ReviewCostQuery>,
+) -> Result<Json<ReviewCostResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !params.rate.is_finite() || params.rate <= 0.0 {
+        return Err(error_response(StatusCode::BAD_REQUEST, "rate must be a positive hourly rate"));
+    }
+
+    info!("⚖️  Estimating review cost for {} at {}/h", cid, params.rate);
+
+    let agreement = fetch_agreement(&state, &cid, &params.key).await?;
+
+    Ok(Json(ReviewCostResponse {
+        ipfs_cid: cid,
+        hourly_rate: params.rate,
+        estimate: agreement.estimate_legal_review_cost(params.rate),
+    }))
+}
+
+async fn list_agreements_handler(
+    State(state): State<AppState>,
+    Query(params): Query<ListAgreementsQuery>,
+) -> Result<Json<ListAgreementsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+
+    let cursor = match params.after.as_deref() {
+        Some(token) => Some(Cursor::decode(token).map_err(|e| {
+            warn!("Invalid pagination cursor: {}", e);
+            error_response(StatusCode::BAD_REQUEST, "Invalid cursor")
+        })?),
+        None => None,
+    };
+
+    info!("📚 Listing agreements (limit {})", limit);
+
+    let page = agreement_store::list_agreements(&state.db, cursor.as_ref(), params.tag.as_deref(), limit)
+        .await
+        .map_err(|e| {
+            error!("Failed to list agreements: {}", e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to list agreements")
+        })?;
+
+    Ok(Json(ListAgreementsResponse {
+        data: page.data,
+        next_cursor: page.next_cursor,
+        has_more: page.has_more,
+    }))
+}
+
+async fn list_pins_handler(
+    State(state): State<AppState>,
+    Query(params): Query<ListPinsQuery>,
+) -> Result<Json<PinPage>, (StatusCode, Json<ErrorResponse>)> {
+    let limit = params.limit.unwrap_or(100).clamp(1, ipfs_client::MAX_PIN_PAGE);
+    info!("📌 Listing pins (limit {})", limit);
+
+    let page = state.ipfs_client.list_pins_paginated(params.after, limit).await.map_err(|e| {
+        error!("Failed to list pins: {:#}", e);
+        error_response(StatusCode::BAD_GATEWAY, "Failed to list pins")
+    })?;
+
+    Ok(Json(page))
+}
+
+async fn benchmark_gateways_handler(
+    State(state): State<AppState>,
+    Query(params): Query<BenchmarkQuery>,
+) -> Result<Json<GatewayBenchmarkReport>, (StatusCode, Json<ErrorResponse>)> {
+    let cid = params.cid.unwrap_or_else(|| ipfs_client::BENCHMARK_CID.to_string());
+
+    let report = state.ipfs_client.benchmark_gateways(&cid).await.map_err(|e| {
+        error!("Failed to benchmark gateways: {:#}", e);
+        error_response(StatusCode::BAD_GATEWAY, "Failed to benchmark gateways")
+    })?;
+    info!("⏱️  Fastest gateway: {}", report.fastest.as_deref().unwrap_or("none"));
+
+    Ok(Json(report))
+}
+
+/// Longest raw LLM output recorded on the parse span at debug level
+const LLM_RAW_JSON_SPAN_CHARS: usize = 2000;
+
+/// Record each top-level field of the LLM output on `span`. Fields the span
+/// does not declare are ignored by `tracing`.
+fn record_extracted_fields(span: &tracing::Span, json_string: &str) {
+    if let Ok(serde_json::Value::Object(fields)) = serde_json::from_str(json_string) {
+        for (key, value) in &fields {
+            match value {
+                serde_json::Value::Null => {}
+                serde_json::Value::String(s) => {
+                    span.record(key.as_str(), s.as_str());
+                }
+                serde_json::Value::Bool(b) => {
+                    span.record(key.as_str(), *b);
+                }
+                serde_json::Value::Number(n) => match n.as_i64() {
+                    Some(i) => {
+                        span.record(key.as_str(), i);
+                    }
+                    None => {
+                        span.record(key.as_str(), n.as_f64().unwrap_or_default());
+                    }
+                },
+                other => {
+                    if let Some(names) = span_names(other) {
+                        span.record(key.as_str(), names.as_str());
+                    }
+                }
+            }
+        }
+    }
+
+    if tracing::enabled!(tracing::Level::DEBUG) {
+        let raw: String = json_string.chars().take(LLM_RAW_JSON_SPAN_CHARS).collect();
+        span.record("llm_raw_json", raw.as_str());
+    }
+}
+
+/// Span value for an object or array field: the `name` of a party-like
+/// object, `role: name` for each party in an object of them, and the scalar
+/// items of a list. Nothing else is recorded, so addresses, registration
+/// numbers and contact details stay out of the span.
+fn span_names(value: &serde_json::Value) -> Option<String> {
+    let names: Vec<String> = match value {
+        serde_json::Value::Object(fields) => match fields.get("name").and_then(|n| n.as_str()) {
+            Some(name) => vec![name.to_string()],
+            None => fields
+                .iter()
+                .filter_map(|(role, party)| Some(format!("{}: {}", role, party.get("name")?.as_str()?)))
+                .collect(),
+        },
+        serde_json::Value::Array(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                serde_json::Value::Bool(b) => Some(b.to_string()),
+                serde_json::Value::Object(fields) => fields.get("name")?.as_str().map(str::to_string),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+/// The agreement's `metadata` object, created if missing. `None` if the
+/// agreement JSON is not an object.
+fn metadata_object(agreement: &mut serde_json::Value) -> Option<&mut serde_json::Map<String, serde_json::Value>> {
+    agreement
+        .as_object_mut()?
+        .entry("metadata")
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+        .as_object_mut()
+}
+
+/// LLM page citations, if they map clause names to pages that exist in a
+/// `page_count`-page document
+fn valid_source_pages(value: serde_json::Value, page_count: usize) -> Option<std::collections::BTreeMap<String, Vec<u32>>> {
+    let source_pages: std::collections::BTreeMap<String, Vec<u32>> = serde_json::from_value(value).ok()?;
+    let pages = 1..=page_count as u32;
+    source_pages.values().flatten().all(|page| pages.contains(page)).then_some(source_pages)
+}
+
+/// First string value found at any of the given JSON pointers
+fn json_str<'a>(value: &'a serde_json::Value, pointers: &[&str]) -> Option<&'a str> {
+    pointers
+        .iter()
+        .find_map(|p| value.pointer(p).and_then(|v| v.as_str()))
+}
+
+/// Territories as uppercase codes, from the flat `territory` string or the
+/// structured `rights.territories` list
+fn json_territories(agreement: &serde_json::Value) -> Vec<String> {
+    let raw: Vec<&str> = match agreement.pointer("/rights/territories").and_then(|v| v.as_array()) {
+        Some(list) => list.iter().filter_map(|t| t.as_str()).collect(),
+        None => json_str(agreement, &["/territory"])
+            .map(|t| t.split(',').collect())
+            .unwrap_or_default(),
+    };
+
+    let mut territories: Vec<String> = raw
+        .into_iter()
+        .map(|t| t.trim().to_uppercase().replace(' ', "_"))
+        .filter(|t| !t.is_empty())
+        .collect();
+    territories.dedup();
+    territories
+}
+
+/// Record the resolved address next to an extracted wallet address or ENS name,
+/// in whichever shape (flat or structured) the LLM returned
+/// Returns the raw value if one was given but could not be resolved.
+async fn resolve_wallet_address(resolver: &EnsResolver, agreement: &mut serde_json::Value) -> Option<String> {
+    let structured = agreement.pointer("/rightsHolder").is_some();
+    let raw = match json_str(agreement, &["/wallet_address", "/rightsHolder/walletAddressRaw", "/rightsHolder/walletAddress"]) {
+        Some(raw) if !raw.trim().is_empty() && raw != ens_resolver::ZERO_ADDRESS => raw.to_string(),
+        _ => return None,
+    };
+    let validation = ens_resolver::validate_wallet_address(&raw);
+    if !validation.is_valid {
+        warn!("Wallet address {:?} is not a valid {:?} address", raw, validation.address_type);
+    }
+    let resolved = resolver.resolve(&raw).await;
+
+    let (target, raw_key, resolved_key, checksum_key) = if structured {
+        (agreement.pointer_mut("/rightsHolder"), "walletAddressRaw", "walletAddressResolved", "walletAddressChecksum")
+    } else {
+        (Some(&mut *agreement), "wallet_address_raw", "wallet_address_resolved", "wallet_address_checksum")
+    };
+    if let Some(serde_json::Value::Object(map)) = target {
+        map.insert(raw_key.to_string(), serde_json::json!(raw));
+        if let Some(address) = &resolved {
+            map.insert(resolved_key.to_string(), serde_json::json!(address));
+            if let Some(checksum) = ens_resolver::checksum_address(address) {
+                map.insert(checksum_key.to_string(), serde_json::json!(checksum));
+            }
+            if structured {
+                map.insert("walletAddress".to_string(), serde_json::json!(address));
+            }
+        }
+    }
+    resolved.is_none().then_some(raw)
+}
+
+/// Fill content fields the LLM left empty from TMDb. Enrichment is best-effort,
+/// so lookup failures are only reported as warnings.
+async fn enrich_from_tmdb(tmdb: &TmdbClient, agreement: &mut serde_json::Value, warnings: &mut ParseWarnings) {
+    let Some(title) = json_str(agreement, &["/title", "/content/title"]).map(str::to_string) else {
+        warnings.push("enrichment_skipped", "Enrichment requested but the agreement has no title");
+        return;
+    };
+
+    let movie = match tmdb.find_movie(&title).await {
+        Ok(Some(movie)) => movie,
+        Ok(None) => {
+            warnings.push("enrichment_no_match", format!("No TMDb match for '{}'", title));
+            return;
+        }
+        Err(e) => {
+            warnings.push("enrichment_failed", format!("TMDb enrichment failed: {:#}", e));
+            return;
+        }
+    };
+
+    let filled = tmdb::apply_enrichment(agreement, &movie);
+    if filled.is_empty() {
+        return;
+    }
+    info!("🎬 Filled {} from TMDb", filled.join(", "));
+    if let Some(metadata) = metadata_object(agreement) {
+        metadata.insert("enrichmentSource".to_string(), serde_json::json!(tmdb::ENRICHMENT_SOURCE));
+    }
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The caller's `X-API-Key`, if one was sent
+fn api_key_header(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Look up a raw API key, answering 401 for unknown keys
+async fn authenticate_key(state: &AppState, raw_key: &str) -> Result<ApiKeyRecord, Response> {
+    match api_keys::validate_key(&state.db, raw_key).await {
+        Ok(Some(record)) => Ok(record),
+        Ok(None) => {
+            warn!("Rejected unknown or inactive API key");
+            Err(error_response(StatusCode::UNAUTHORIZED, "Invalid API key").into_response())
+        }
+        Err(e) => {
+            error!("API key lookup failed: {}", e);
+            Err(error_response(StatusCode::SERVICE_UNAVAILABLE, "API key validation unavailable").into_response())
+        }
+    }
+}
+
+/// Reject requests without a valid `X-API-Key`, whatever REQUIRE_API_KEY
+/// says. Handlers behind it get the caller's `ApiKeyRecord` as an extension.
+async fn authenticate_api_key(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let Some(raw_key) = api_key_header(&request) else {
+        return error_response(StatusCode::UNAUTHORIZED, "Missing X-API-Key header").into_response();
+    };
+    let record = match authenticate_key(&state, &raw_key).await {
+        Ok(record) => record,
+        Err(response) => return response,
+    };
+
+    request.extensions_mut().insert(record);
+    next.run(request).await
+}
+
+/// Validate the caller's `X-API-Key` and count the request against its daily
+/// parse quota. Requests without a key pass unmetered unless REQUIRE_API_KEY is set.
+async fn enforce_parse_quota(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let raw_key = api_key_header(&request);
+    let (record, remaining) = match admit_parse(&state, raw_key.as_deref()).await {
+        Ok(Some(admitted)) => admitted,
+        Ok(None) => return next.run(request).await,
+        Err(response) => return response,
+    };
+
+    request.extensions_mut().insert(record);
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert(QUOTA_REMAINING_HEADER, HeaderValue::from(remaining));
+    response
+}
+
+/// The checks behind `enforce_parse_quota`, shared with the gRPC `ParsePDF`.
+/// Returns the caller's key and its parses left today, or None for an
+/// unmetered request without a key.
+async fn admit_parse(state: &AppState, raw_key: Option<&str>) -> Result<Option<(ApiKeyRecord, u32)>, Response> {
+    let Some(raw_key) = raw_key else {
+        if state.require_api_key {
+            return Err(error_response(StatusCode::UNAUTHORIZED, "Missing X-API-Key header").into_response());
+        }
+        return Ok(None);
+    };
+
+    let record = authenticate_key(state, raw_key).await?;
+
+    let today = chrono::Utc::now().date_naive();
+    let daily_parses = record.daily_parse_quota.max(0) as u32;
+    let remaining = {
+        let mut quota = state
+            .key_quotas
+            .entry(api_keys::hash_key(raw_key))
+            .or_insert_with(|| KeyQuota::new(daily_parses, today));
+        // Pick up quota changes made in the database since the entry was created
+        quota.daily_parses = daily_parses;
+        quota.try_consume(today)
+    };
+
+    let Some(remaining) = remaining else {
+        warn!("📉 Daily parse quota exhausted for key {}", record.key_prefix);
+        let mut response = error_response(StatusCode::PAYMENT_REQUIRED, "Daily parse quota exhausted").into_response();
+        response
+            .headers_mut()
+            .insert(QUOTA_REMAINING_HEADER, HeaderValue::from(0u32));
+        return Err(response);
+    };
+
+    let pool = state.db.clone();
+    let key_id = record.id;
+    tokio::spawn(async move {
+        if let Err(e) = api_keys::touch_key(&pool, key_id).await {
+            warn!("{}", e);
+        }
+    });
+
+    Ok(Some((record, remaining)))
+}
+
+/// 429 response telling the client when to retry
+fn too_many_requests_response(retry_after_secs: u64) -> Response {
+    let mut response = error_response(
+        StatusCode::TOO_MANY_REQUESTS,
+        "Too many concurrent parsing requests, please retry later",
+    )
+    .into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
+
+fn error_response(status: StatusCode, message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    coded_error_response(status, &status.to_string(), message)
+}
+
+/// `error_response` with a machine-readable `error` code in place of the
+/// status text, for failures clients are expected to handle specifically
+fn coded_error_response(status: StatusCode, code: &str, message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            error: code.to_string(),
+            message: message.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_verify_field_encrypted_agreement() {
+        let state = AppState::in_memory();
+        let key = memory_store::MemoryEncryptionService::KEY;
+        let mut agreement = serde_json::json!({ "title": "Kalki 2898 AD", "financial": { "dealValue": 1000 } });
+        integrity::stamp_content_hashes(&mut agreement, Some("abc123"));
+        let protected = state.encryption_service.encrypt_fields(&agreement, &["financial"], key).unwrap();
+        let cid = state.ipfs_client.upload(protected.to_string().as_bytes()).await.unwrap();
+
+        let verify = |key: &str| {
+            let query = VerifyQuery { key: key.to_string(), pdf_hash: "ABC123".to_string() };
+            verify_agreement_handler(State(state.clone()), Path(cid.clone()), Query(query))
+        };
+        assert!(verify(key).await.0.verified);
+
+        let wrong = verify(&EncryptionService::generate_key()).await.0;
+        assert!(!wrong.verified);
+        assert!(!wrong.checks.key_valid);
+    }
+
+    #[test]
+    fn test_extracted_fields_leave_out_party_details() {
+        use std::sync::{Arc, Mutex};
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+
+        struct Recorded(Arc<Mutex<Vec<String>>>);
+
+        impl tracing::field::Visit for Recorded {
+            fn record_debug(&mut self, _field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                self.0.lock().unwrap().push(format!("{:?}", value));
+            }
+        }
+
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Recorded {
+            fn on_record(&self, _span: &tracing::span::Id, values: &tracing::span::Record<'_>, _ctx: Context<'_, S>) {
+                values.record(&mut Recorded(self.0.clone()));
+            }
+        }
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_subscriber::filter::LevelFilter::INFO)
+            .with(Recorded(recorded.clone()));
+        let agreement = serde_json::json!({
+            "title": "Kalki 2898 AD",
+            "rightsHolder": { "name": "Vyjayanthi Movies", "walletAddress": "0x0000000000000000000000000000000000000000" },
+            "parties": {
+                "licensor": { "name": "Vyjayanthi Movies", "registrationNumber": "AABCV1234F", "address": "Road No. 10, Jubilee Hills, Hyderabad 500033", "contactEmail": "legal@vyjayanthi.example" },
+                "licensee": { "name": "Netflix India", "registrationNumber": "27AAICN1234A1Z5", "address": "Bandra Kurla Complex, Mumbai 400051", "contactEmail": "deals@netflix.example" }
+            },
+            "territory": ["India", "Nepal"]
+        });
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "parse_pdf",
+                title = tracing::field::Empty,
+                rightsHolder = tracing::field::Empty,
+                parties = tracing::field::Empty,
+                territory = tracing::field::Empty,
+            );
+            record_extracted_fields(&span, &agreement.to_string());
+        });
+
+        let values = recorded.lock().unwrap().join("\n");
+        assert!(values.contains("licensor: Vyjayanthi Movies"));
+        assert!(values.contains("licensee: Netflix India"));
+        assert!(values.contains("India, Nepal"));
+        for detail in ["Jubilee Hills", "Bandra Kurla", "AABCV1234F", "27AAICN1234A1Z5", "legal@vyjayanthi.example", "0x0000"] {
+            assert!(!values.contains(detail), "span recorded {}", detail);
+        }
+    }
+
+    #[test]
+    fn test_valid_source_pages() {
+        let cited = serde_json::json!({ "territories": [2], "payment": [3, 4] });
+        assert_eq!(valid_source_pages(cited.clone(), 4).unwrap()["payment"], vec![3, 4]);
+        assert!(valid_source_pages(cited, 3).is_none());
+        assert!(valid_source_pages(serde_json::json!({ "term": [0] }), 4).is_none());
+        assert!(valid_source_pages(serde_json::json!(["page 2"]), 4).is_none());
+    }
+
+    #[test]
+    fn test_coded_error_response() {
+        let (status, Json(body)) = coded_error_response(StatusCode::SERVICE_UNAVAILABLE, "filesystem_timeout", "Try again later");
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body.error, "filesystem_timeout");
+        assert_eq!(error_response(StatusCode::NOT_FOUND, "Unknown job id").1.error, "404 Not Found");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_flat_agreement() {
+        let state = AppState::in_memory();
+        let key = memory_store::MemoryEncryptionService::KEY;
+        let flat = serde_json::json!({
+            "agreementId": "VM-KALKI-2024",
+            "title": "Kalki 2898 AD",
+            "licensor": "Vyjayanthi Movies",
+            "territory": "India",
+            "total_fee": 100_000_000,
+            "currency": "INR"
+        });
+        let encrypted = state.encryption_service.encrypt_with_key(&flat.to_string(), key).unwrap();
+        let cid = state.ipfs_client.upload(&encrypted).await.unwrap();
+
+        let Ok(agreement) = fetch_agreement(&state, &cid, key).await else {
+            panic!("flat agreement was not normalized");
+        };
+        assert_eq!(agreement.agreement_id, "VM-KALKI-2024");
+        assert_eq!(agreement.rights_holder.name, "Vyjayanthi Movies");
+        assert_eq!(agreement.financial.deal_value, 100_000_000);
+    }
 }
\ No newline at end of file
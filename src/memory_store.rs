@@ -0,0 +1,230 @@
+// src/memory_store.rs - In-memory IPFS and encryption for tests
+//
+// Neither type touches the network, so an `AppState` built from them works in
+// unit tests without an IPFS node. Compiled for tests only.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::encryption::{self, CipherAlgorithm, EncryptionService, EncryptionServiceTrait};
+use crate::ipfs_client::{self, IPFSClientTrait, PinInfo, PinPage};
+
+/// CIDv1 prefix for a raw block with a SHA-256 multihash
+const RAW_SHA256_CID_PREFIX: [u8; 4] = [0x01, 0x55, 0x12, 0x20];
+
+/// Content-addressed store held in a `HashMap`
+#[derive(Default)]
+pub struct MemoryStore {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+/// The CIDv1 (raw codec) IPFS would assign to `data` as a single block
+pub fn cid_for(data: &[u8]) -> String {
+    let mut bytes = RAW_SHA256_CID_PREFIX.to_vec();
+    bytes.extend_from_slice(&Sha256::digest(data));
+    format!("b{}", base32_lower(&bytes))
+}
+
+/// RFC 4648 base32, lowercase and unpadded, as used by multibase `b`
+fn base32_lower(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+    let mut out = String::with_capacity(bytes.len() * 8 / 5 + 1);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.lock().expect("memory store poisoned").len()
+    }
+
+    fn put(&self, data: &[u8]) -> String {
+        let cid = cid_for(data);
+        self.objects
+            .lock()
+            .expect("memory store poisoned")
+            .insert(cid.clone(), data.to_vec());
+        cid
+    }
+}
+
+#[async_trait]
+impl IPFSClientTrait for MemoryStore {
+    async fn upload(&self, data: &[u8]) -> Result<String> {
+        Ok(self.put(data))
+    }
+
+    /// Stores each file, then a JSON listing of names to CIDs as the directory
+    async fn upload_directory(&self, files: &[(&str, &[u8])]) -> Result<String> {
+        if files.is_empty() {
+            anyhow::bail!("Cannot upload an empty directory");
+        }
+
+        let listing: serde_json::Map<String, serde_json::Value> = files
+            .iter()
+            .map(|(name, data)| (name.to_string(), serde_json::json!(self.put(data))))
+            .collect();
+        Ok(self.put(serde_json::Value::Object(listing).to_string().as_bytes()))
+    }
+
+    async fn fetch(&self, cid: &str) -> Result<Vec<u8>> {
+        self.objects
+            .lock()
+            .expect("memory store poisoned")
+            .get(cid)
+            .cloned()
+            .with_context(|| format!("{} not found in memory store", cid))
+    }
+
+    async fn unpin(&self, cid: &str) -> Result<()> {
+        self.objects
+            .lock()
+            .expect("memory store poisoned")
+            .remove(cid)
+            .map(|_| ())
+            .with_context(|| format!("{} not found in memory store", cid))
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    async fn list_pins_paginated(&self, after: Option<String>, limit: usize) -> Result<PinPage> {
+        let pins = self
+            .objects
+            .lock()
+            .expect("memory store poisoned")
+            .iter()
+            .map(|(cid, data)| PinInfo {
+                cid: cid.clone(),
+                pin_type: Some("recursive".to_string()),
+                size: Some(data.len() as u64),
+                pinned_at: None,
+                name: None,
+            })
+            .collect();
+        Ok(ipfs_client::paginate_by_cid(pins, after.as_deref(), limit.clamp(1, ipfs_client::MAX_PIN_PAGE)))
+    }
+}
+
+/// AES-256-GCM with a fixed key and a nonce derived from the plaintext, so the
+/// same input always produces the same ciphertext. Never use outside tests:
+/// the key is public and nonces repeat for repeated plaintexts.
+#[derive(Default)]
+pub struct MemoryEncryptionService;
+
+impl MemoryEncryptionService {
+    /// Base64 key returned for every encryption
+    pub const KEY: &'static str = "cmlnaHRzLXBhcnNlci1tZW1vcnktdGVzdC1rZXkhISE=";
+
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl EncryptionServiceTrait for MemoryEncryptionService {
+    fn encrypt(&self, plaintext: &str) -> Result<(Vec<u8>, String)> {
+        Ok((self.encrypt_with_key(plaintext, Self::KEY)?, Self::KEY.to_string()))
+    }
+
+    fn encrypt_with_key(&self, plaintext: &str, key_b64: &str) -> Result<Vec<u8>> {
+        self.encrypt_bytes_with_key(plaintext.as_bytes(), key_b64)
+    }
+
+    fn encrypt_bytes_with_key(&self, plaintext: &[u8], key_b64: &str) -> Result<Vec<u8>> {
+        let digest = Sha256::new()
+            .chain_update(key_b64.as_bytes())
+            .chain_update(plaintext)
+            .finalize();
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&digest[..12]);
+        encryption::seal(CipherAlgorithm::default(), plaintext, key_b64, nonce)
+    }
+
+    fn decrypt(&self, encrypted_data: &[u8], key_b64: &str) -> Result<String> {
+        EncryptionService::new().decrypt(encrypted_data, key_b64)
+    }
+
+    fn encrypt_with_passphrase(&self, plaintext: &str, passphrase: &str) -> Result<Vec<u8>> {
+        EncryptionService::new().encrypt_with_passphrase(plaintext, passphrase)
+    }
+
+    fn decrypt_with_passphrase(&self, encrypted_data: &[u8], passphrase: &str) -> Result<String> {
+        EncryptionService::new().decrypt_with_passphrase(encrypted_data, passphrase)
+    }
+
+    fn envelope_encrypt(&self, data_key: &[u8], master_key: &[u8]) -> Result<Vec<u8>> {
+        EncryptionService::new().envelope_encrypt(data_key, master_key)
+    }
+
+    fn envelope_decrypt(&self, envelope: &[u8], master_key: &[u8]) -> Result<Vec<u8>> {
+        EncryptionService::new().envelope_decrypt(envelope, master_key)
+    }
+
+    fn new_key(&self) -> String {
+        Self::KEY.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_cid_matches_ipfs() {
+        // `ipfs add --cid-version 1 --raw-leaves` of an empty file
+        assert_eq!(cid_for(b""), "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku");
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_and_encryption() {
+        let store = Arc::new(MemoryStore::new());
+        let encryption = MemoryEncryptionService::new();
+
+        let (first, key) = encryption.encrypt(r#"{"title":"Kalki"}"#).unwrap();
+        let (second, _) = encryption.encrypt(r#"{"title":"Kalki"}"#).unwrap();
+        assert_eq!(first, second, "output must be deterministic");
+
+        let cid = store.upload(&first).await.unwrap();
+        assert_eq!(cid, store.upload(&second).await.unwrap());
+        let fetched = store.fetch(&cid).await.unwrap();
+        assert_eq!(encryption.decrypt(&fetched, &key).unwrap(), r#"{"title":"Kalki"}"#);
+
+        // Rekeying with the deterministic key yields the same CID, which must stay pinned
+        let result = encryption.rekey_in_place(store.clone(), &cid, &key).await.unwrap();
+        assert_eq!(result.new_cid, cid);
+        assert!(!crate::encryption::unpin_replaced(store.clone(), &cid, &result.new_cid).await);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_upload() {
+        let store = MemoryStore::new();
+        let items = vec![("kalki.json.enc", b"kalki".to_vec()), ("salaar.json.enc", b"salaar".to_vec())];
+
+        let cids = store.batch_upload(items).await.unwrap();
+        assert_eq!(cids.len(), 2);
+        assert_eq!(cids[0], ("kalki.json.enc".to_string(), cid_for(b"kalki")));
+        assert_eq!(store.fetch(&cids[1].1).await.unwrap(), b"salaar");
+    }
+}
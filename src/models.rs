@@ -1,7 +1,14 @@
 // src/models.rs
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::address_validation;
+use crate::agreement_diff::AgreementDiff;
+use crate::json_fields::{self, JsonNamingStrategy};
+use crate::normalization::{self, MediaTypeCode, StreamingPlatform};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RightsAgreementJSON {
     pub agreement_id: String,
@@ -15,22 +22,102 @@ pub struct RightsAgreementJSON {
     pub deliverables: Option<Deliverables>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub restrictions: Option<Restrictions>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub special_terms: Option<Vec<String>>,
+    /// Parsed clauses; older documents store each clause as a plain string
+    #[serde(default, deserialize_with = "deserialize_special_terms", skip_serializing_if = "Option::is_none")]
+    pub special_terms: Option<Vec<SpecialTerm>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub legal_terms: Option<LegalTerms>,
+    /// Duties either party must perform, e.g. delivering masters or reporting sales
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub obligations: Vec<Obligation>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<Metadata>,
+    /// The parsed fields plus any extra keys the LLM returned, kept for debugging
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_llm_extras: Option<serde_json::Value>,
+}
+
+/// One `(field_path, value)` pair of a flattened agreement
+pub type CsvRow = (String, String);
+
+impl RightsAgreementJSON {
+    /// Flatten the agreement into dot-notation field paths for spreadsheet export
+    pub fn to_csv_rows(&self) -> Vec<CsvRow> {
+        serde_json::to_value(self)
+            .map(|value| json_fields::flatten_to_rows(&value))
+            .unwrap_or_default()
+    }
+
+    /// Serialize with keys in the requested casing instead of the default camelCase
+    pub fn serialize_with_strategy(&self, strategy: JsonNamingStrategy) -> serde_json::Result<String> {
+        let value = serde_json::to_value(self)?;
+        serde_json::to_string(&json_fields::rename_keys(&value, strategy))
+    }
+
+    /// Rough cost of a legal review at `hourly_rate`, based on how many
+    /// territories, special terms and amendments the agreement has
+    pub fn estimate_legal_review_cost(&self, hourly_rate: f64) -> ReviewCostEstimate {
+        let territories = self.rights.territories.len();
+        let special_terms = self.special_terms.as_ref().map_or(0, Vec::len);
+        let amendments = self.metadata.as_ref().map_or(0, |m| m.amendments.len());
+
+        let mut complexity_factors = Vec::new();
+        if territories > 0 {
+            complexity_factors.push(format!("{} territories", territories));
+        }
+        if special_terms > 0 {
+            complexity_factors.push(format!("{} special terms", special_terms));
+        }
+        if self.rights.exclusivity {
+            complexity_factors.push("Exclusive grant".to_string());
+        }
+        if amendments > 0 {
+            complexity_factors.push(format!("{} amendments", amendments));
+        }
+
+        let estimated_hours = territories as f64 * 0.5
+            + special_terms as f64 * 0.25
+            + if self.rights.exclusivity { 1.0 } else { 0.0 }
+            + amendments as f64 * 0.75;
+
+        ReviewCostEstimate {
+            estimated_hours,
+            cost: (estimated_hours * hourly_rate * 100.0).round() / 100.0,
+            complexity_score: (estimated_hours / COMPLEX_REVIEW_HOURS).min(1.0),
+            complexity_factors,
+        }
+    }
+}
+
+/// Review hours at which an agreement counts as maximally complex
+const COMPLEX_REVIEW_HOURS: f64 = 10.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewCostEstimate {
+    pub estimated_hours: f64,
+    pub cost: f64,
+    /// 0.0 for a trivial agreement up to 1.0 at `COMPLEX_REVIEW_HOURS` or more
+    pub complexity_score: f64,
+    pub complexity_factors: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RightsHolder {
     pub name: String,
     pub wallet_address: String,
+    /// Wallet address or ENS name exactly as it appears in the agreement
+    #[serde(default)]
+    pub wallet_address_raw: String,
+    /// Hex address `wallet_address_raw` resolved to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_address_resolved: Option<String>,
+    /// EIP-55 checksummed form of `wallet_address_resolved`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_address_checksum: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentInfo {
     pub title: String,
@@ -42,51 +129,360 @@ pub struct ContentInfo {
     pub duration: u32,
     pub release_date: String,
     pub director: String,
+    /// Individual producer credit
     pub producer: String,
+    /// Entity that produced the content, distinct from the individual producer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub production_company: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub production_country: Option<String>,
     pub rating: Rating,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ContentInfo {
+    /// Warn when the production company repeats the producer, which usually
+    /// means the LLM put the company name in both fields
+    pub fn production_credit_warning(&self) -> Option<String> {
+        let company = self.production_company.as_deref()?.trim();
+        if !company.is_empty() && company.eq_ignore_ascii_case(self.producer.trim()) {
+            Some(format!(
+                "Producer and production company are both '{}'; the producer should be a person",
+                company
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Rating {
     pub cbfc: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mpaa: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Rights {
     pub territories: Vec<String>,
     pub media_types: Vec<String>,
+    /// `media_types` mapped to canonical codes
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    pub media_types_normalized: Vec<MediaTypeCode>,
     pub exclusivity: bool,
     pub term: Term,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SetStrategy {
+    Union,
+    Intersection,
+}
+
+/// How to combine rights when merging several agreements
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MergeStrategy {
+    pub territories: SetStrategy,
+    pub media_types: SetStrategy,
+    #[serde(default)]
+    pub use_earliest_start: bool,
+    #[serde(default)]
+    pub use_latest_end: bool,
+}
+
+impl Rights {
+    /// Combine these rights with `others` according to `strategy`.
+    /// The result is exclusive only if every input is exclusive.
+    pub fn merge(&self, others: &[Rights], strategy: &MergeStrategy) -> Rights {
+        let all: Vec<&Rights> = std::iter::once(self).chain(others.iter()).collect();
+
+        let territories = combine(all.iter().map(|r| &r.territories), strategy.territories);
+        let media_types = combine(all.iter().map(|r| &r.media_types), strategy.media_types);
+        let exclusivity = all.iter().all(|r| r.exclusivity);
+
+        let mut term = self.term.clone();
+        if strategy.use_earliest_start {
+            if let Some(start) = all.iter().filter_map(|r| r.term.start_date.date()).min() {
+                term.start_date = FlexibleDate::from_date(start);
+            }
+        }
+        if strategy.use_latest_end {
+            if let Some(end) = all.iter().filter_map(|r| r.term.end_date.date()).max() {
+                term.end_date = FlexibleDate::from_date(end);
+            }
+        }
+        if let (Some(start), Some(end)) = (term.start_date.date(), term.end_date.date()) {
+            term.years = end.years_since(start).unwrap_or(term.years);
+        }
+
+        Rights {
+            territories,
+            media_types_normalized: normalization::normalize_media_types(&media_types),
+            media_types,
+            exclusivity,
+            term,
+        }
+    }
+
+    /// The days both terms are in force, `None` if they do not overlap or
+    /// either term's dates could not be parsed
+    pub fn compute_term_overlap(self_term: &Term, other_term: &Term) -> Option<DateRange> {
+        let (a, b) = (self_term.date_range()?, other_term.date_range()?);
+        let overlap = DateRange {
+            start: a.start.max(b.start),
+            end: a.end.min(b.end),
+        };
+        (overlap.start <= overlap.end).then_some(overlap)
+    }
+
+    pub fn has_territory_overlap(&self, other: &Rights) -> bool {
+        !self.overlapping_territories(other).is_empty()
+    }
+
+    /// Territories granted by both, compared case-insensitively. A worldwide
+    /// grant overlaps every territory of the other.
+    pub fn overlapping_territories(&self, other: &Rights) -> Vec<String> {
+        let key = |t: &str| t.trim().to_uppercase().replace('_', " ");
+        let worldwide = |r: &Rights| r.territories.iter().any(|t| WORLDWIDE_TERRITORIES.contains(&key(t).as_str()));
+
+        match (worldwide(self), worldwide(other)) {
+            (true, _) => other.territories.clone(),
+            (false, true) => self.territories.clone(),
+            (false, false) => self
+                .territories
+                .iter()
+                .filter(|t| other.territories.iter().any(|o| key(o) == key(t)))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Territory names that grant the whole world
+const WORLDWIDE_TERRITORIES: &[&str] = &["WORLDWIDE", "WORLD", "GLOBAL", "UNIVERSE", "ENTIRE UNIVERSE", "ALL TERRITORIES"];
+
+/// An inclusive span of days
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DateRange {
+    pub start: chrono::NaiveDate,
+    pub end: chrono::NaiveDate,
+}
+
+impl std::fmt::Display for DateRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} to {}", self.start.format("%Y-%m-%d"), self.end.format("%Y-%m-%d"))
+    }
+}
+
+/// Union or intersection of string lists, keeping first-seen order
+fn combine<'a>(lists: impl Iterator<Item = &'a Vec<String>>, strategy: SetStrategy) -> Vec<String> {
+    let lists: Vec<&Vec<String>> = lists.collect();
+    let mut result: Vec<String> = Vec::new();
+
+    for item in lists.iter().flat_map(|l| l.iter()) {
+        if result.contains(item) {
+            continue;
+        }
+        let keep = match strategy {
+            SetStrategy::Union => true,
+            SetStrategy::Intersection => lists.iter().all(|l| l.contains(item)),
+        };
+        if keep {
+            result.push(item.clone());
+        }
+    }
+
+    result
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Term {
     pub years: u32,
-    pub start_date: String,
-    pub end_date: String,
+    #[serde(deserialize_with = "deserialize_flexible_date")]
+    #[schemars(with = "String")]
+    pub start_date: FlexibleDate,
+    #[serde(deserialize_with = "deserialize_flexible_date")]
+    #[schemars(with = "String")]
+    pub end_date: FlexibleDate,
+}
+
+impl Term {
+    /// Start to end date, taking the end as `years` after the start when it is
+    /// missing or unparseable
+    pub fn date_range(&self) -> Option<DateRange> {
+        let start = self.start_date.date()?;
+        let end = match self.end_date.date() {
+            Some(end) => end,
+            None if self.years > 0 => start.checked_add_months(chrono::Months::new(self.years * 12))?.pred_opt()?,
+            None => return None,
+        };
+        Some(DateRange { start, end })
+    }
+}
+
+/// Date formats the LLM is known to emit, tried in order. Day-first is tried
+/// before month-first, as in the agreements this service parses.
+const TERM_DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%d/%m/%Y",
+    "%m/%d/%Y",
+    "%d-%m-%Y",
+    "%d.%m.%Y",
+    "%B %d, %Y",
+    "%B %d %Y",
+    "%d %B %Y",
+    "%d %B, %Y",
+    "%b %d, %Y",
+    "%d %b %Y",
+];
+
+/// "1st", "2nd", "3rd", "4th" -> "1", "2", "3", "4"
+static ORDINAL_SUFFIX: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"(?i)\b(\d{1,2})(?:st|nd|rd|th)\b").expect("valid ordinal regex"));
+
+/// A date as written in the agreement, plus its parsed value when it is
+/// recognisable. Serializes as `YYYY-MM-DD` when parsed, else as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlexibleDate {
+    pub raw: String,
+    pub normalized: Option<chrono::NaiveDate>,
+}
+
+impl FlexibleDate {
+    /// Parse free text such as "January 1, 2025", "01/01/2025" or "1st January 2025"
+    pub fn parse(raw: &str) -> Self {
+        let cleaned = ORDINAL_SUFFIX.replace_all(raw.trim(), "$1");
+
+        Self {
+            raw: raw.to_string(),
+            normalized: TERM_DATE_FORMATS
+                .iter()
+                .find_map(|f| chrono::NaiveDate::parse_from_str(&cleaned, f).ok()),
+        }
+    }
+
+    pub fn from_date(date: chrono::NaiveDate) -> Self {
+        Self {
+            raw: date.format("%Y-%m-%d").to_string(),
+            normalized: Some(date),
+        }
+    }
+
+    pub fn date(&self) -> Option<chrono::NaiveDate> {
+        self.normalized
+    }
+}
+
+impl std::fmt::Display for FlexibleDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.normalized {
+            Some(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            None => f.write_str(&self.raw),
+        }
+    }
+}
+
+impl PartialEq<&str> for FlexibleDate {
+    fn eq(&self, other: &&str) -> bool {
+        self.to_string() == *other
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Serialize for FlexibleDate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Accept any date string, normalizing it where possible and keeping it as-is otherwise
+pub fn deserialize_flexible_date<'de, D>(deserializer: D) -> Result<FlexibleDate, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    Ok(FlexibleDate::parse(&raw))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Financial {
     pub deal_value: u64,
+    /// ISO 4217 code, or "XXX" when the raw value could not be recognised
     pub currency: String,
+    /// Currency exactly as extracted from the agreement
+    #[serde(default)]
+    pub currency_raw: String,
     pub platform_fee: PlatformFee,
     pub net_to_rights_holder: u64,
     pub payment_structure: PaymentStructure,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Lower-case aliases for each supported ISO 4217 code
+const CURRENCY_ALIASES: &[(&str, &[&str])] = &[
+    ("INR", &["inr", "₹", "rs", "rs.", "rupee", "rupees", "indian rupee", "indian rupees"]),
+    ("USD", &["usd", "$", "us$", "dollar", "dollars", "us dollar", "us dollars", "united states dollars"]),
+    ("EUR", &["eur", "€", "euro", "euros"]),
+    ("GBP", &["gbp", "£", "pound", "pounds", "pound sterling", "pounds sterling", "sterling", "british pounds"]),
+    ("CNY", &["cny", "rmb", "yuan", "renminbi", "chinese yuan", "元"]),
+    ("JPY", &["jpy", "yen", "japanese yen", "円"]),
+    ("AUD", &["aud", "a$", "au$", "australian dollar", "australian dollars"]),
+];
+
+impl Financial {
+    /// Change the deal value, rescaling the platform fee and payment breakdown.
+    /// Milestones are dropped since their amounts and dates no longer apply.
+    pub fn set_deal_value(&mut self, deal_value: u64) {
+        let scale = |amount: u64| {
+            if self.deal_value == 0 {
+                0
+            } else {
+                (amount as f64 * deal_value as f64 / self.deal_value as f64) as u64
+            }
+        };
+
+        let upfront = scale(self.payment_structure.breakdown.upfront);
+        self.payment_structure.breakdown = PaymentBreakdown {
+            upfront,
+            on_delivery: deal_value.saturating_sub(upfront),
+        };
+        self.payment_structure.milestones = None;
+
+        self.platform_fee.amount = (deal_value as f64 * self.platform_fee.percentage / 100.0) as u64;
+        self.net_to_rights_holder = deal_value.saturating_sub(self.platform_fee.amount);
+        self.deal_value = deal_value;
+    }
+
+    /// Map a currency as written in a contract ("Indian Rupees", "₹", "Rs.") to its ISO 4217 code
+    pub fn normalize_currency(raw: &str) -> Option<String> {
+        let key = raw.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+
+        CURRENCY_ALIASES
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&key.as_str()))
+            .map(|(code, _)| code.to_string())
+    }
+
+    /// `normalize_currency`, falling back to anything already shaped like an
+    /// ISO 4217 code (three letters), as stored in `parsed_agreements.currency`
+    pub fn currency_code(raw: &str) -> Option<String> {
+        let trimmed = raw.trim();
+        Self::normalize_currency(trimmed).or_else(|| {
+            (trimmed.len() == 3 && trimmed.chars().all(|c| c.is_ascii_alphabetic())).then(|| trimmed.to_ascii_uppercase())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PlatformFee {
     pub percentage: f64,
     pub amount: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PaymentStructure {
     #[serde(rename = "type")]
@@ -96,14 +492,14 @@ pub struct PaymentStructure {
     pub milestones: Option<Vec<Milestone>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PaymentBreakdown {
     pub upfront: u64,
     #[serde(rename = "onDelivery")]
     pub on_delivery: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Milestone {
     pub name: String,
@@ -112,36 +508,175 @@ pub struct Milestone {
     pub percentage: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A problem with the order of payment milestones
+#[derive(Debug, Clone, PartialEq)]
+pub enum MilestoneOrderError {
+    UnparseableDate { milestone: String, due_date: String },
+    OutOfOrder { milestone: String, after: String },
+    DuplicateDate { date: chrono::NaiveDate, milestones: Vec<String> },
+    DecreasingPercentage { milestone: String, percentage: u32, previous: u32 },
+}
+
+impl std::fmt::Display for MilestoneOrderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnparseableDate { milestone, due_date } => {
+                write!(f, "Milestone '{}' has an unrecognised due date '{}'", milestone, due_date)
+            }
+            Self::OutOfOrder { milestone, after } => {
+                write!(f, "Milestone '{}' is listed after '{}' but falls due before it", milestone, after)
+            }
+            Self::DuplicateDate { date, milestones } => {
+                write!(f, "Milestones {} share the due date {}", milestones.join(", "), date)
+            }
+            Self::DecreasingPercentage { milestone, percentage, previous } => write!(
+                f,
+                "Milestone '{}' is {}%, less than the {}% of the milestone before it",
+                milestone, percentage, previous
+            ),
+        }
+    }
+}
+
+/// Check that milestones are listed in due-date order, that no two fall due on
+/// the same day and that percentages never decrease over time. Milestones
+/// whose dates cannot be parsed are reported and left out of the other checks.
+pub fn validate_milestone_ordering(milestones: &[Milestone]) -> Result<(), Vec<MilestoneOrderError>> {
+    let mut errors = Vec::new();
+
+    let mut dated: Vec<(chrono::NaiveDate, &Milestone)> = Vec::new();
+    for milestone in milestones {
+        match FlexibleDate::parse(&milestone.due_date).date() {
+            Some(date) => dated.push((date, milestone)),
+            None => errors.push(MilestoneOrderError::UnparseableDate {
+                milestone: milestone.name.clone(),
+                due_date: milestone.due_date.clone(),
+            }),
+        }
+    }
+
+    for pair in dated.windows(2) {
+        if pair[1].0 < pair[0].0 {
+            errors.push(MilestoneOrderError::OutOfOrder {
+                milestone: pair[1].1.name.clone(),
+                after: pair[0].1.name.clone(),
+            });
+        }
+    }
+
+    // Stable sort, so milestones on the same date keep their listed order
+    dated.sort_by_key(|(date, _)| *date);
+
+    for group in dated.chunk_by(|a, b| a.0 == b.0).filter(|g| g.len() > 1) {
+        errors.push(MilestoneOrderError::DuplicateDate {
+            date: group[0].0,
+            milestones: group.iter().map(|(_, m)| m.name.clone()).collect(),
+        });
+    }
+
+    for pair in dated.windows(2) {
+        if pair[1].1.percentage < pair[0].1.percentage {
+            errors.push(MilestoneOrderError::DecreasingPercentage {
+                milestone: pair[1].1.name.clone(),
+                percentage: pair[1].1.percentage,
+                previous: pair[0].1.percentage,
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Parties {
     pub licensor: Party,
     pub licensee: Party,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Party {
     pub name: String,
     pub registration_number: String,
     pub address: String,
+    /// Whether `address` passed the offline country, postal code and city checks
+    #[serde(default)]
+    pub address_validated: bool,
+    /// ISO 3166-1 alpha-2 code detected in `address`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_country_code: Option<String>,
     pub country: String,
     pub contact_email: String,
     pub signatory_name: String,
     pub signatory_title: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Party {
+    /// Validate `address`, recording the result and returning any issues found
+    pub fn validate_address(&mut self) -> Vec<String> {
+        let result = address_validation::validate_address(&self.address);
+        self.address_validated = result.validated;
+        self.address_country_code = result.country_code;
+        result.issues
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Deliverables {
     pub video_formats: Vec<String>,
     pub audio_formats: Vec<String>,
     pub subtitles: Vec<String>,
     pub dubbing: Vec<String>,
+    /// Deadline as written in the agreement; empty when it states none
     pub delivery_deadline: String,
     pub technical_specs: TechnicalSpecs,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<DeliverableStatus>,
+    /// Days from when the agreement was built until the deadline; negative once overdue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days_until_deadline: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeliverableStatus {
+    Pending,
+    Overdue,
+}
+
+impl Deliverables {
+    /// Parse a free-text deadline ("2025-03-01", "1 March 2025", "01/03/2025")
+    pub fn parse_deadline(raw: &str) -> Option<chrono::NaiveDate> {
+        FlexibleDate::parse(raw).date()
+    }
+
+    pub fn deadline(&self) -> Option<chrono::NaiveDate> {
+        Self::parse_deadline(&self.delivery_deadline)
+    }
+
+    pub fn days_until_deadline(&self, today: chrono::NaiveDate) -> Option<i64> {
+        self.deadline().map(|d| (d - today).num_days())
+    }
+
+    /// Recompute `status` and `days_until_deadline` as of `today`
+    pub fn refresh_deadline(&mut self, today: chrono::NaiveDate) {
+        self.days_until_deadline = self.days_until_deadline(today);
+        self.status = self.days_until_deadline.map(|days| {
+            if days < 0 {
+                DeliverableStatus::Overdue
+            } else {
+                DeliverableStatus::Pending
+            }
+        });
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TechnicalSpecs {
     pub video_codec: String,
@@ -151,38 +686,431 @@ pub struct TechnicalSpecs {
     pub drm_type: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Restrictions {
     pub territories_excluded: Vec<String>,
     pub platforms_excluded: Vec<String>,
+    /// `platforms_excluded` mapped to known platforms
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    pub platforms_excluded_normalized: Vec<StreamingPlatform>,
     pub holdback_period: HoldbackPeriod,
     pub content_rating: String,
     pub editing_rights: String,
     pub merchandising_rights: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Restrictions {
+    /// Known platforms that are both granted in `rights.media_types` and excluded here
+    pub fn platform_conflicts(&self, rights: &Rights) -> Vec<StreamingPlatform> {
+        let granted = normalization::normalize_streaming_platforms(&rights.media_types);
+        let excluded = normalization::normalize_streaming_platforms(&self.platforms_excluded);
+
+        excluded
+            .into_iter()
+            .filter(|p| p.is_known() && granted.contains(p))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HoldbackPeriod {
     pub theatrical: u32,
     #[serde(rename = "physicalMedia")]
     pub physical_media: u32,
     #[serde(rename = "freeTV")]
     pub free_tv: u32,
+    /// Unit the periods above are expressed in, exactly as stated in the agreement
+    #[serde(default)]
+    pub unit: HoldbackUnit,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum HoldbackUnit {
+    #[default]
+    #[serde(alias = "days", alias = "DAYS")]
+    Days,
+    #[serde(alias = "weeks", alias = "WEEKS")]
+    Weeks,
+    #[serde(alias = "months", alias = "MONTHS")]
+    Months,
+}
+
+impl HoldbackUnit {
+    /// `amount` of this unit in days, counting a month as 30 days
+    pub fn to_days(&self, amount: u32) -> u32 {
+        match self {
+            Self::Days => amount,
+            Self::Weeks => amount.saturating_mul(7),
+            Self::Months => amount.saturating_mul(30),
+        }
+    }
+}
+
+/// Theatrical holdbacks outside this range (in days) are probably a unit mix-up.
+/// The industry norm is 90 days.
+const THEATRICAL_HOLDBACK_DAYS: std::ops::RangeInclusive<u32> = 30..=1825;
+
+impl HoldbackPeriod {
+    /// The same periods converted to days
+    pub fn to_days(&self) -> HoldbackPeriod {
+        HoldbackPeriod {
+            theatrical: self.unit.to_days(self.theatrical),
+            physical_media: self.unit.to_days(self.physical_media),
+            free_tv: self.unit.to_days(self.free_tv),
+            unit: HoldbackUnit::Days,
+        }
+    }
+
+    /// Warnings for implausible periods. A theatrical holdback of 0 means none
+    /// was given and is not flagged.
+    pub fn validation_warnings(&self) -> Vec<String> {
+        let theatrical = self.to_days().theatrical;
+        if theatrical == 0 || THEATRICAL_HOLDBACK_DAYS.contains(&theatrical) {
+            return Vec::new();
+        }
+
+        let qualifier = if theatrical < *THEATRICAL_HOLDBACK_DAYS.start() {
+            "suspiciously short"
+        } else {
+            "unusually long"
+        };
+        vec![format!(
+            "Theatrical holdback of {} days is {} (typically 90 days) - check the unit",
+            theatrical, qualifier
+        )]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LegalTerms {
     pub governing_law: String,
-    pub dispute_resolution: String,
+    /// Parsed clause; older documents store the clause as a plain string
+    #[serde(deserialize_with = "deserialize_dispute_resolution")]
+    pub dispute_resolution: DisputeResolutionClause,
+    /// Clause exactly as extracted from the agreement
+    #[serde(default)]
+    pub dispute_resolution_raw: String,
     pub confidentiality: String,
     pub warranties: String,
     pub indemnification: String,
     pub forcemajeure: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DisputeMechanism {
+    Arbitration,
+    Mediation,
+    Litigation,
+    ExpertDetermination,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DisputeResolutionClause {
+    pub mechanism: DisputeMechanism,
+    /// Seat of arbitration or court location
+    pub venue: Option<String>,
+    pub arbitration_rules: Option<String>,
+    pub number_of_arbitrators: Option<u32>,
+    pub language: Option<String>,
+}
+
+// Institutional rules, matched case-insensitively against the clause
+const ARBITRATION_RULES: &[(&str, &str)] = &[
+    ("arbitration and conciliation act", "Arbitration and Conciliation Act, 1996"),
+    ("uncitral", "UNCITRAL"),
+    ("lcia", "LCIA"),
+    ("siac", "SIAC"),
+    ("hkiac", "HKIAC"),
+    ("icc", "ICC"),
+    ("wipo", "WIPO"),
+    ("jams", "JAMS"),
+    ("aaa", "AAA"),
+];
+
+/// "London seat", "seated in London", "seat ... shall be London", "Mumbai Courts",
+/// "courts of Delhi". A leading article is matched outside the capture, so
+/// "the Mumbai Courts" gives "Mumbai".
+static VENUE_PATTERNS: LazyLock<Vec<regex::Regex>> = LazyLock::new(|| {
+    [
+        r"(?i)\b(?:(?:the|an?)\s+)?([A-Z][\w ]*?)\s+seat\b",
+        r"(?i)\bseat(?:ed)?\s+(?:of arbitration\s+)?(?:shall be\s+)?(?:in|at)?\s*(?:(?:the|an?)\s+)?([A-Z][\w ]*?)(?:[,.;]|$)",
+        r"\b(?:[Tt]he\s+)?([A-Z][\w ]*?)\s+[Cc]ourts?\b",
+        r"(?i)\bcourts?\s+(?:of|at|in)\s+(?:the\s+)?([A-Z][\w ]*?)(?:[,.;]|$)",
+        r"(?i)\b(?:held|conducted|venue)\s+(?:in|at|shall be)\s+(?:the\s+)?([A-Z][\w ]*?)(?:[,.;]|$)",
+    ]
+    .iter()
+    .map(|p| regex::Regex::new(p).expect("valid venue regex"))
+    .collect()
+});
+
+/// `ARBITRATION_RULES` keys as whole words, so "icc" does not match "Piccadilly"
+static ARBITRATION_RULE_PATTERNS: LazyLock<Vec<(regex::Regex, &'static str)>> = LazyLock::new(|| {
+    ARBITRATION_RULES
+        .iter()
+        .map(|(key, name)| {
+            let re = regex::Regex::new(&format!(r"\b{}\b", regex::escape(key))).expect("valid arbitration rules regex");
+            (re, *name)
+        })
+        .collect()
+});
+
+static EXPERT_WORD: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"\bexpert\b").expect("valid expert regex"));
+
+/// "three arbitrators", "panel of five"
+static ARBITRATOR_COUNT: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"\b(one|two|three|five|\d+)\s+arbitrators\b|panel of (three|five|\d+)")
+        .expect("valid arbitrator count regex")
+});
+
+/// "in the English language", "language of arbitration shall be Hindi"
+static ARBITRATION_LANGUAGE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"(?i)\b(?:in the\s+(\w+)\s+language|language\s+(?:of (?:the )?arbitration\s+)?shall be\s+(\w+))")
+        .expect("valid arbitration language regex")
+});
+
+impl DisputeResolutionClause {
+    /// Parse a free-text clause such as "ICC Arbitration, London seat" or "Mumbai Courts"
+    pub fn parse(raw: &str) -> Self {
+        let lower = raw.to_lowercase();
+
+        let mechanism = if lower.contains("arbitra") {
+            DisputeMechanism::Arbitration
+        } else if lower.contains("mediat") {
+            DisputeMechanism::Mediation
+        } else if lower.contains("expert determination") || EXPERT_WORD.is_match(&lower) {
+            DisputeMechanism::ExpertDetermination
+        } else {
+            DisputeMechanism::Litigation
+        };
+
+        let arbitration_rules = ARBITRATION_RULE_PATTERNS
+            .iter()
+            .find(|(re, _)| re.is_match(&lower))
+            .map(|(_, name)| name.to_string());
+
+        let number_of_arbitrators = if lower.contains("sole arbitrator") || lower.contains("single arbitrator") {
+            Some(1)
+        } else {
+            ARBITRATOR_COUNT
+                .captures(&lower)
+                .and_then(|c| c.get(1).or_else(|| c.get(2)))
+                .and_then(|m| match m.as_str() {
+                    "one" => Some(1),
+                    "two" => Some(2),
+                    "three" => Some(3),
+                    "five" => Some(5),
+                    n => n.parse().ok(),
+                })
+        };
+
+        let venue = VENUE_PATTERNS.iter().find_map(|re| {
+            re.captures(raw)?
+                .get(1)
+                .map(|m| m.as_str().trim().to_string())
+                .filter(|v| !v.is_empty() && !ARBITRATION_RULES.iter().any(|(k, _)| v.eq_ignore_ascii_case(k)))
+        });
+
+        let language = ARBITRATION_LANGUAGE
+            .captures(raw)
+            .and_then(|c| c.get(1).or_else(|| c.get(2)))
+            .map(|m| m.as_str().to_string());
+
+        Self {
+            mechanism,
+            venue,
+            arbitration_rules,
+            number_of_arbitrators,
+            language,
+        }
+    }
+}
+
+/// Accept either the structured clause or the legacy free-text string
+fn deserialize_dispute_resolution<'de, D>(deserializer: D) -> Result<DisputeResolutionClause, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Clause {
+        Structured(DisputeResolutionClause),
+        Raw(String),
+    }
+
+    Ok(match Clause::deserialize(deserializer)? {
+        Clause::Structured(clause) => clause,
+        Clause::Raw(raw) => DisputeResolutionClause::parse(&raw),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SpecialTermCategory {
+    MostFavored,
+    MatchingRight,
+    FirstLook,
+    OutputDeal,
+    PutPicture,
+    TurnaroundRight,
+    /// Label of an unrecognised clause, e.g. "Dubbing" from "Dubbing: ..."
+    Other(String),
+}
+
+/// When a special term applies, if the clause says
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TermDateRange {
+    #[serde(default, deserialize_with = "deserialize_optional_flexible_date")]
+    #[schemars(with = "Option<String>")]
+    pub start: Option<FlexibleDate>,
+    #[serde(default, deserialize_with = "deserialize_optional_flexible_date")]
+    #[schemars(with = "Option<String>")]
+    pub end: Option<FlexibleDate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecialTerm {
+    pub category: SpecialTermCategory,
+    /// Clause as written in the agreement
+    pub description: String,
+    /// "Licensor" and/or "Licensee"; empty when the clause names neither
+    #[serde(default)]
+    pub applies_to_parties: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_dates: Option<TermDateRange>,
+}
+
+// Deal structures, matched case-insensitively against the clause
+const SPECIAL_TERM_KEYWORDS: &[(&[&str], SpecialTermCategory)] = &[
+    (&["most favored", "most favoured", "most-favored", "most-favoured", "mfn"], SpecialTermCategory::MostFavored),
+    (&["matching right", "right to match", "right of first refusal", "first refusal"], SpecialTermCategory::MatchingRight),
+    (&["first look", "first-look"], SpecialTermCategory::FirstLook),
+    (&["output deal", "output agreement", "output arrangement"], SpecialTermCategory::OutputDeal),
+    (&["put picture", "put-picture", "pay or play"], SpecialTermCategory::PutPicture),
+    (&["turnaround"], SpecialTermCategory::TurnaroundRight),
+];
+
+/// One whole-word alternation of `SPECIAL_TERM_KEYWORDS` per category
+static SPECIAL_TERM_PATTERNS: LazyLock<Vec<(regex::Regex, SpecialTermCategory)>> = LazyLock::new(|| {
+    SPECIAL_TERM_KEYWORDS
+        .iter()
+        .map(|(keywords, category)| {
+            let alternation = keywords.iter().map(|k| regex::escape(k)).collect::<Vec<_>>().join("|");
+            let re = regex::Regex::new(&format!(r"\b(?:{})\b", alternation)).expect("valid special term regex");
+            (re, category.clone())
+        })
+        .collect()
+});
+
+/// Parties a special term can name, as whole words of the lowercased clause
+static SPECIAL_TERM_PARTIES: LazyLock<Vec<(regex::Regex, &'static str)>> = LazyLock::new(|| {
+    [("Licensor", r"\blicensor\b"), ("Licensee", r"\blicensee\b")]
+        .into_iter()
+        .map(|(party, pattern)| (regex::Regex::new(pattern).expect("valid party regex"), party))
+        .collect()
+});
+
+/// A date, optionally after a word marking it as the end of the term
+static SPECIAL_TERM_DATES: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"(?i)\b(until|till|through|up to|expiring)?\s*(\d{4}-\d{2}-\d{2}|\d{1,2}(?:st|nd|rd|th)?\s+[a-z]+,?\s+\d{4}|[a-z]+\s+\d{1,2},\s+\d{4}|\d{1,2}/\d{1,2}/\d{4})",
+    )
+    .expect("valid special term date regex")
+});
+
+impl SpecialTerm {
+    /// Classify a free-text clause such as "Licensee has a first look at sequels
+    /// until 31 December 2027" by its keywords
+    pub fn parse(raw: &str) -> Self {
+        let description = raw.trim().to_string();
+        let lower = description.to_lowercase();
+
+        let category = SPECIAL_TERM_PATTERNS
+            .iter()
+            .find(|(re, _)| re.is_match(&lower))
+            .map(|(_, category)| category.clone())
+            .unwrap_or_else(|| {
+                // "Dubbing: ..." keeps "Dubbing" as the label
+                let label = description
+                    .split_once(':')
+                    .map(|(label, _)| label.trim())
+                    .filter(|label| !label.is_empty() && label.split_whitespace().count() <= 4)
+                    .unwrap_or("");
+                SpecialTermCategory::Other(label.to_string())
+            });
+
+        let applies_to_parties = SPECIAL_TERM_PARTIES
+            .iter()
+            .filter(|(re, _)| re.is_match(&lower))
+            .map(|(_, party)| party.to_string())
+            .collect();
+
+        Self {
+            category,
+            effective_dates: Self::parse_dates(&description),
+            description,
+            applies_to_parties,
+        }
+    }
+
+    /// The first two recognisable dates as start and end. A lone date after
+    /// "until", "till" or "through" is the end.
+    fn parse_dates(text: &str) -> Option<TermDateRange> {
+        let found: Vec<(bool, FlexibleDate)> = SPECIAL_TERM_DATES
+            .captures_iter(text)
+            .filter_map(|c| {
+                let date = FlexibleDate::parse(c.get(2)?.as_str());
+                date.date().map(|_| (c.get(1).is_some(), date))
+            })
+            .take(2)
+            .collect();
+
+        match found.as_slice() {
+            [] => None,
+            [(true, end)] => Some(TermDateRange { start: None, end: Some(end.clone()) }),
+            [(_, start)] => Some(TermDateRange { start: Some(start.clone()), end: None }),
+            [(_, start), (_, end), ..] => Some(TermDateRange { start: Some(start.clone()), end: Some(end.clone()) }),
+        }
+    }
+}
+
+/// Accept structured special terms, legacy free-text strings, or a mix
+fn deserialize_special_terms<'de, D>(deserializer: D) -> Result<Option<Vec<SpecialTerm>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Term {
+        Structured(SpecialTerm),
+        Raw(String),
+    }
+
+    Ok(Option::<Vec<Term>>::deserialize(deserializer)?.map(|terms| {
+        terms
+            .into_iter()
+            .map(|term| match term {
+                Term::Structured(term) => term,
+                Term::Raw(raw) => SpecialTerm::parse(&raw),
+            })
+            .collect()
+    }))
+}
+
+fn deserialize_optional_flexible_date<'de, D>(deserializer: D) -> Result<Option<FlexibleDate>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<String>::deserialize(deserializer)?.map(|raw| FlexibleDate::parse(&raw)))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Metadata {
     pub created_date: String,
@@ -190,21 +1118,188 @@ pub struct Metadata {
     pub version: String,
     pub status: String,
     pub blockchain: BlockchainInfo,
+    /// CIDs of the agreements this one was merged from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_cids: Vec<String>,
+    /// Non-fatal validation issues found while building the agreement
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// User-defined labels, e.g. client, content category or risk level
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Clauses flagged as deviating from standard industry practice
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unusual_clauses: Vec<UnusualClause>,
+    /// External database content fields were filled in from, e.g. `tmdb`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enrichment_source: Option<String>,
+    /// Amendment history, oldest first, as diffs against the previous version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub amendments: Vec<AgreementDiff>,
+    /// CID of the version this document was derived from by a renewal or edit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_version_cid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_date: Option<String>,
+    /// Hex SHA-256 of the source PDF
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pdf_sha256: Option<String>,
+    /// Hex SHA-256 of this document's JSON, computed without this field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_sha256: Option<String>,
+    /// PDF pages each extracted clause was found on, e.g. `"territories": [2]`
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub source_pages: std::collections::BTreeMap<String, Vec<u32>>,
+    /// The model's own 0.0-1.0 confidence in each top-level field it extracted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence_scores: Option<std::collections::HashMap<String, f32>>,
+}
+
+impl Metadata {
+    /// Metadata for a freshly created, not yet deployed agreement
+    pub fn new() -> Self {
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        Self {
+            created_date: today.clone(),
+            last_modified: today,
+            version: "1.0".to_string(),
+            status: "PENDING".to_string(),
+            blockchain: BlockchainInfo {
+                network: "CBDC_TESTNET".to_string(),
+                deployment_pending: true,
+                tx_hash: None,
+                block_number: None,
+                contract_address: None,
+            },
+            source_cids: Vec::new(),
+            warnings: Vec::new(),
+            tags: Vec::new(),
+            unusual_clauses: Vec::new(),
+            enrichment_source: None,
+            amendments: Vec::new(),
+            previous_version_cid: None,
+            effective_date: None,
+            pdf_sha256: None,
+            json_sha256: None,
+            source_pages: Default::default(),
+            confidence_scores: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+pub enum Severity {
+    #[serde(alias = "low", alias = "LOW")]
+    Low,
+    #[serde(alias = "medium", alias = "MEDIUM")]
+    Medium,
+    #[serde(alias = "high", alias = "HIGH")]
+    High,
+}
+
+/// A clause that may carry legal risk because it departs from common terms
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UnusualClause {
+    /// Section number or heading the clause appears under
+    pub section: String,
+    /// Verbatim text of the clause
+    pub quote: String,
+    pub concern: String,
+    pub severity: Severity,
+}
+
+/// Something a party must do under the agreement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Obligation {
+    /// `Licensor`, `Licensee` or the name of the party that must perform
+    pub obligor: String,
+    pub description: String,
+    /// When it is due, as written in the agreement
+    #[serde(default)]
+    pub deadline: Option<String>,
+    #[serde(default)]
+    pub consequence_of_breach: Option<String>,
+    /// Whether it repeats, e.g. quarterly sales reports
+    #[serde(default)]
+    pub is_recurring: bool,
+}
+
+impl Obligation {
+    /// Whether `role` (`licensor` or `licensee`) must perform this, matching
+    /// the obligor against both the role and the party's name
+    pub fn is_owed_by(&self, role: &str, party_name: Option<&str>) -> bool {
+        let obligor = self.obligor.trim().to_lowercase();
+        let party_name = party_name.map(|n| n.trim().to_lowercase()).filter(|n| !n.is_empty());
+        obligor.split_whitespace().any(|word| word == role.to_lowercase())
+            || party_name.is_some_and(|name| obligor == name)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BlockchainInfo {
     pub network: String,
     pub deployment_pending: bool,
+    /// `registerAgreement` transaction anchoring this agreement's predecessor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Registry contract the agreement was anchored in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<String>,
+}
+
+/// Per-field confidence the model reported for an extraction
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParsedConfidence {
+    pub scores: std::collections::HashMap<String, f32>,
+}
+
+impl ParsedConfidence {
+    /// Scores from a `{"field": 0.8, ...}` object. Non-numeric values are
+    /// dropped and the rest clamped to 0.0-1.0.
+    pub fn from_llm_json(json: &serde_json::Value) -> Self {
+        let scores = json
+            .as_object()
+            .map(|fields| {
+                fields
+                    .iter()
+                    .filter_map(|(field, score)| Some((field.clone(), (score.as_f64()? as f32).clamp(0.0, 1.0))))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { scores }
+    }
+
+    /// Fields scored below `threshold`, alphabetically, for human review
+    pub fn low_confidence(&self, threshold: f32) -> Vec<&str> {
+        let mut fields: Vec<&str> = self
+            .scores
+            .iter()
+            .filter(|(_, score)| **score < threshold)
+            .map(|(field, _)| field.as_str())
+            .collect();
+        fields.sort_unstable();
+        fields
+    }
 }
 
 // LLM Response Structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParsedAgreement {
     pub title: String,
     pub licensor: String,
     pub licensee: String,
+    #[serde(default)]
+    pub licensor_address: Option<String>,
+    #[serde(default)]
+    pub licensee_address: Option<String>,
+    /// Wallet address or ENS name, only when stated in the agreement
+    #[serde(default)]
+    pub wallet_address: Option<String>,
     pub territories: Vec<String>,
     pub media_types: Vec<String>,
     pub deal_value: u64,
@@ -218,6 +1313,380 @@ pub struct ParsedAgreement {
     pub genre: Vec<String>,
     pub director: Option<String>,
     pub producer: Option<String>,
+    #[serde(default)]
+    pub production_company: Option<String>,
+    #[serde(default)]
+    pub production_country: Option<String>,
     pub release_date: Option<String>,
     pub duration: Option<u32>,
-}
\ No newline at end of file
+    /// Special clauses as written, classified when the agreement is built
+    #[serde(default)]
+    pub special_terms: Vec<String>,
+    /// Delivery deadline as written, only when stated in the agreement
+    #[serde(default)]
+    pub delivery_deadline: Option<String>,
+    /// ID the LLM assigned, if any; generated from the licensor and title otherwise
+    #[serde(default)]
+    pub agreement_id: Option<String>,
+    /// Payment milestones in the order the agreement lists them
+    #[serde(default)]
+    pub milestones: Vec<Milestone>,
+    /// Holdback periods, only when the agreement states at least one
+    #[serde(default)]
+    pub holdback_period: Option<HoldbackPeriod>,
+    #[serde(default)]
+    pub territories_excluded: Vec<String>,
+    /// Platforms the rights may not be exploited on, as written
+    #[serde(default)]
+    pub platforms_excluded: Vec<String>,
+}
+
+impl ParsedAgreement {
+    /// `parsed` as JSON, plus every top-level key of `llm_json` it does not
+    /// already have, e.g. a `sublicensing_right` the struct has no field for
+    pub fn merge_with_llm_json(parsed: ParsedAgreement, llm_json: &serde_json::Value) -> serde_json::Value {
+        let mut merged = serde_json::to_value(parsed).unwrap_or_default();
+        if let (Some(merged), Some(extra)) = (merged.as_object_mut(), llm_json.as_object()) {
+            for (key, value) in extra {
+                merged.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_with_strategy() {
+        let agreement: RightsAgreementJSON = serde_json::from_value(serde_json::json!({
+            "agreementId": "AGR-1",
+            "rightsHolder": { "name": "Vyjayanthi Movies", "walletAddress": "" },
+            "content": {
+                "title": "Kalki 2898 AD", "originalTitle": "Kalki 2898 AD", "type": "Film",
+                "language": "Telugu", "genre": ["Sci-Fi"], "duration": 180,
+                "releaseDate": "2024-06-27", "director": "Nag Ashwin", "producer": "C. Aswani Dutt",
+                "rating": { "cbfc": "UA" }
+            },
+            "rights": {
+                "territories": ["India"], "mediaTypes": ["SVOD"], "exclusivity": true,
+                "term": { "years": 5, "startDate": "2024-08-01", "endDate": "2029-07-31" }
+            },
+            "financial": {
+                "dealValue": 1000000, "currency": "INR",
+                "platformFee": { "percentage": 0.0, "amount": 0 },
+                "netToRightsHolder": 1000000,
+                "paymentStructure": { "type": "fixed", "breakdown": { "upfront": 0, "onDelivery": 1000000 } }
+            }
+        }))
+        .unwrap();
+        let render = |strategy| -> serde_json::Value {
+            serde_json::from_str(&agreement.serialize_with_strategy(strategy).unwrap()).unwrap()
+        };
+
+        let camel = render(JsonNamingStrategy::CamelCase);
+        assert_eq!(camel["rightsHolder"]["name"], "Vyjayanthi Movies");
+        assert_eq!(camel["financial"]["paymentStructure"]["breakdown"]["onDelivery"], 1000000);
+
+        let snake = render(JsonNamingStrategy::SnakeCase);
+        assert_eq!(snake["agreement_id"], "AGR-1");
+        assert_eq!(snake["financial"]["payment_structure"]["breakdown"]["on_delivery"], 1000000);
+        assert!(snake.get("rightsHolder").is_none());
+
+        let pascal = render(JsonNamingStrategy::PascalCase);
+        assert_eq!(pascal["RightsHolder"]["WalletAddress"], "");
+        assert_eq!(pascal["Rights"]["Term"]["StartDate"], "2024-08-01");
+    }
+
+    #[test]
+    fn test_validate_milestone_ordering() {
+        let milestone = |name: &str, due_date: &str, percentage: u32| Milestone {
+            name: name.to_string(),
+            amount: 0,
+            due_date: due_date.to_string(),
+            percentage,
+        };
+
+        let ordered = [milestone("Signing", "2024-08-01", 25), milestone("Delivery", "1st March 2025", 75)];
+        assert!(validate_milestone_ordering(&ordered).is_ok());
+
+        let reversed = [
+            milestone("Delivery", "2025-03-01", 75),
+            milestone("Signing", "2024-08-01", 25),
+            milestone("Launch", "2025-03-01", 80),
+            milestone("Bonus", "on release", 10),
+        ];
+        let errors = validate_milestone_ordering(&reversed).unwrap_err();
+        assert!(errors.contains(&MilestoneOrderError::OutOfOrder {
+            milestone: "Signing".to_string(),
+            after: "Delivery".to_string(),
+        }));
+        assert!(errors.iter().any(|e| matches!(e, MilestoneOrderError::DuplicateDate { milestones, .. } if milestones.len() == 2)));
+        assert!(errors.iter().any(|e| matches!(e, MilestoneOrderError::UnparseableDate { .. })));
+        assert!(!errors.iter().any(|e| matches!(e, MilestoneOrderError::DecreasingPercentage { .. })));
+    }
+
+    #[test]
+    fn test_term_dates_are_normalized() {
+        let term: Term = serde_json::from_str(
+            r#"{"years": 1, "startDate": "1st January 2025", "endDate": "sometime next year"}"#,
+        )
+        .unwrap();
+        assert_eq!(term.start_date.date(), chrono::NaiveDate::from_ymd_opt(2025, 1, 1));
+        assert_eq!(term.end_date.date(), None);
+
+        let json = serde_json::to_value(&term).unwrap();
+        assert_eq!(json["startDate"], "2025-01-01");
+        assert_eq!(json["endDate"], "sometime next year");
+
+        for raw in ["January 1, 2025", "01/01/2025", "2025-01-01", "1 Jan 2025"] {
+            assert_eq!(FlexibleDate::parse(raw), "2025-01-01", "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_holdback_units() {
+        let holdback: HoldbackPeriod =
+            serde_json::from_str(r#"{"theatrical": 3, "physicalMedia": 2, "freeTV": 18, "unit": "months"}"#).unwrap();
+        let days = holdback.to_days();
+        assert_eq!((days.theatrical, days.physical_media, days.free_tv), (90, 60, 540));
+        assert!(holdback.validation_warnings().is_empty());
+
+        // Without a unit the values are taken as days
+        let holdback: HoldbackPeriod =
+            serde_json::from_str(r#"{"theatrical": 3, "physicalMedia": 0, "freeTV": 0}"#).unwrap();
+        assert_eq!(holdback.unit, HoldbackUnit::Days);
+        assert!(holdback.validation_warnings()[0].contains("suspiciously short"));
+        assert_eq!(HoldbackUnit::Weeks.to_days(2_000), 14_000);
+    }
+
+    #[test]
+    fn test_estimate_legal_review_cost() {
+        let mut agreement: RightsAgreementJSON = serde_json::from_value(serde_json::json!({
+            "agreementId": "AGR-1",
+            "rightsHolder": { "name": "Vyjayanthi Movies", "walletAddress": "" },
+            "content": {
+                "title": "Kalki 2898 AD", "originalTitle": "Kalki 2898 AD", "type": "Film",
+                "language": "Telugu", "genre": [], "duration": 180, "releaseDate": "2024-06-27",
+                "director": "Nag Ashwin", "producer": "C. Aswani Dutt", "rating": { "cbfc": "UA" }
+            },
+            "rights": {
+                "territories": ["India", "Nepal"], "mediaTypes": ["SVOD"], "exclusivity": true,
+                "term": { "years": 5, "startDate": "2024-08-01", "endDate": "2029-07-31" }
+            },
+            "financial": {
+                "dealValue": 0, "currency": "INR", "platformFee": { "percentage": 0.0, "amount": 0 },
+                "netToRightsHolder": 0,
+                "paymentStructure": { "type": "fixed", "breakdown": { "upfront": 0, "onDelivery": 0 } }
+            },
+            "specialTerms": ["Dubbed versions included", "No sublicensing"]
+        }))
+        .unwrap();
+        agreement.metadata = Some(Metadata {
+            amendments: vec![AgreementDiff::default()],
+            ..Metadata::new()
+        });
+
+        // 2 × 0.5 + 2 × 0.25 + 1.0 exclusive + 1 × 0.75
+        let estimate = agreement.estimate_legal_review_cost(450.0);
+        assert_eq!(estimate.estimated_hours, 3.25);
+        assert_eq!(estimate.cost, 1462.5);
+        assert_eq!(estimate.complexity_score, 0.325);
+        assert_eq!(estimate.complexity_factors.len(), 4);
+    }
+
+    fn rights(territories: &[&str], start: &str, end: &str) -> Rights {
+        Rights {
+            territories: territories.iter().map(|t| t.to_string()).collect(),
+            media_types: vec!["SVOD".to_string()],
+            media_types_normalized: vec![MediaTypeCode::Svod],
+            exclusivity: true,
+            term: Term {
+                years: 1,
+                start_date: FlexibleDate::parse(start),
+                end_date: FlexibleDate::parse(end),
+            },
+        }
+    }
+
+    #[test]
+    fn test_delivery_deadline() {
+        assert_eq!(
+            Deliverables::parse_deadline("1 March 2025"),
+            chrono::NaiveDate::from_ymd_opt(2025, 3, 1)
+        );
+        assert_eq!(Deliverables::parse_deadline("TBD"), None);
+        assert_eq!(Deliverables::parse_deadline(""), None);
+
+        let mut deliverables = Deliverables {
+            video_formats: vec![],
+            audio_formats: vec![],
+            subtitles: vec![],
+            dubbing: vec![],
+            delivery_deadline: "2025-03-01".to_string(),
+            technical_specs: TechnicalSpecs {
+                video_codec: String::new(),
+                audio_codec: String::new(),
+                container_format: String::new(),
+                drm_required: false,
+                drm_type: String::new(),
+            },
+            status: None,
+            days_until_deadline: None,
+        };
+
+        deliverables.refresh_deadline(chrono::NaiveDate::from_ymd_opt(2025, 2, 15).unwrap());
+        assert_eq!(deliverables.days_until_deadline, Some(14));
+        assert_eq!(deliverables.status, Some(DeliverableStatus::Pending));
+
+        deliverables.refresh_deadline(chrono::NaiveDate::from_ymd_opt(2025, 3, 2).unwrap());
+        assert_eq!(deliverables.status, Some(DeliverableStatus::Overdue));
+    }
+
+    #[test]
+    fn test_parse_dispute_resolution() {
+        let clause = DisputeResolutionClause::parse("ICC Arbitration, London seat");
+        assert_eq!(clause.mechanism, DisputeMechanism::Arbitration);
+        assert_eq!(clause.arbitration_rules.as_deref(), Some("ICC"));
+        assert_eq!(clause.venue.as_deref(), Some("London"));
+
+        let clause = DisputeResolutionClause::parse("Mumbai Courts");
+        assert_eq!(clause.mechanism, DisputeMechanism::Litigation);
+        assert_eq!(clause.venue.as_deref(), Some("Mumbai"));
+        // Articles are not part of the venue
+        let clause = DisputeResolutionClause::parse("The Mumbai Courts shall have exclusive jurisdiction");
+        assert_eq!(clause.venue.as_deref(), Some("Mumbai"));
+        let clause = DisputeResolutionClause::parse("LCIA arbitration seated in the Netherlands");
+        assert_eq!(clause.venue.as_deref(), Some("Netherlands"));
+
+        let clause = DisputeResolutionClause::parse(
+            "Arbitration by a sole arbitrator under the SIAC Rules, seated in Singapore, conducted in the English language",
+        );
+        assert_eq!(clause.number_of_arbitrators, Some(1));
+        assert_eq!(clause.arbitration_rules.as_deref(), Some("SIAC"));
+        assert_eq!(clause.venue.as_deref(), Some("Singapore"));
+        assert_eq!(clause.language.as_deref(), Some("English"));
+
+        // Stored documents with the old string field still load
+        let legal: LegalTerms = serde_json::from_value(serde_json::json!({
+            "governingLaw": "Laws of India", "disputeResolution": "Arbitration",
+            "confidentiality": "", "warranties": "", "indemnification": "", "forcemajeure": ""
+        }))
+        .unwrap();
+        assert_eq!(legal.dispute_resolution.mechanism, DisputeMechanism::Arbitration);
+    }
+
+    #[test]
+    fn test_parse_special_terms() {
+        let first_look = SpecialTerm::parse("Licensee shall have a first look at any sequel until 31st December 2027");
+        assert_eq!(first_look.category, SpecialTermCategory::FirstLook);
+        assert_eq!(first_look.applies_to_parties, vec!["Licensee"]);
+        let dates = first_look.effective_dates.unwrap();
+        assert_eq!(dates.start, None);
+        assert_eq!(dates.end.unwrap(), "2027-12-31");
+
+        let mfn = SpecialTerm::parse("MFN: Licensor will match any better terms offered between 2024-01-01 and 2025-12-31");
+        assert_eq!(mfn.category, SpecialTermCategory::MostFavored);
+        assert_eq!(mfn.effective_dates.unwrap().start.unwrap(), "2024-01-01");
+
+        assert_eq!(
+            SpecialTerm::parse("Dubbing: Telugu and Hindi versions").category,
+            SpecialTermCategory::Other("Dubbing".to_string())
+        );
+
+        // Legacy string lists still load
+        let terms: Vec<SpecialTerm> = deserialize_special_terms(serde_json::json!(["Turnaround after 18 months"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(terms[0].category, SpecialTermCategory::TurnaroundRight);
+    }
+
+    #[test]
+    fn test_production_credit_warning() {
+        let mut content: ContentInfo = serde_json::from_value(serde_json::json!({
+            "title": "Kalki 2898 AD", "originalTitle": "Kalki 2898 AD", "type": "MOVIE",
+            "language": "Telugu", "genre": [], "duration": 180, "releaseDate": "2024-06-27",
+            "director": "Nag Ashwin", "producer": "Vyjayanthi Movies",
+            "productionCompany": "vyjayanthi movies ", "rating": { "cbfc": "UA" }
+        }))
+        .unwrap();
+        assert!(content.production_credit_warning().is_some());
+
+        content.producer = "C. Aswani Dutt".to_string();
+        assert!(content.production_credit_warning().is_none());
+    }
+
+    #[test]
+    fn test_normalize_currency() {
+        assert_eq!(Financial::normalize_currency("Indian Rupees").as_deref(), Some("INR"));
+        assert_eq!(Financial::normalize_currency("₹").as_deref(), Some("INR"));
+        assert_eq!(Financial::normalize_currency("Rs.").as_deref(), Some("INR"));
+        assert_eq!(Financial::normalize_currency(" usd ").as_deref(), Some("USD"));
+        assert_eq!(Financial::normalize_currency("Pounds  Sterling").as_deref(), Some("GBP"));
+        assert_eq!(Financial::normalize_currency("doubloons"), None);
+
+        assert_eq!(Financial::currency_code("sgd").as_deref(), Some("SGD"));
+        assert_eq!(Financial::currency_code("Indian Rupees").as_deref(), Some("INR"));
+        assert_eq!(Financial::currency_code("Singapore Dollars"), None);
+    }
+
+    #[test]
+    fn test_merge_rights() {
+        let a = rights(&["IN", "US"], "2025-01-01", "2026-01-01");
+        let b = rights(&["US", "UK"], "2024-06-01", "2028-06-01");
+
+        let strategy = MergeStrategy {
+            territories: SetStrategy::Union,
+            media_types: SetStrategy::Intersection,
+            use_earliest_start: true,
+            use_latest_end: true,
+        };
+        let merged = a.merge(&[b.clone()], &strategy);
+
+        assert_eq!(merged.territories, vec!["IN", "US", "UK"]);
+        assert_eq!(merged.media_types, vec!["SVOD"]);
+        assert_eq!(merged.term.start_date, "2024-06-01");
+        assert_eq!(merged.term.end_date, "2028-06-01");
+        assert_eq!(merged.term.years, 4);
+
+        let strategy = MergeStrategy {
+            territories: SetStrategy::Intersection,
+            ..strategy
+        };
+        assert_eq!(a.merge(&[b], &strategy).territories, vec!["US"]);
+    }
+
+    #[test]
+    fn test_parsed_confidence_from_llm_json() {
+        let confidence = ParsedConfidence::from_llm_json(&serde_json::json!({
+            "deal_value": 0.3,
+            "title": 1.0,
+            "term_years": 1.7,
+            "territories": "high",
+            "licensee": 0.45
+        }));
+        assert_eq!(confidence.scores.len(), 4);
+        assert_eq!(confidence.scores["term_years"], 1.0);
+        assert_eq!(confidence.low_confidence(0.5), vec!["deal_value", "licensee"]);
+        assert!(ParsedConfidence::from_llm_json(&serde_json::json!([0.5])).scores.is_empty());
+    }
+
+    #[test]
+    fn test_term_and_territory_overlap() {
+        let a = rights(&["IN", "US"], "2025-01-01", "2026-01-01");
+        let b = rights(&["us", "UK"], "2025-06-01", "");
+        let c = rights(&["Entire Universe"], "2026-01-02", "2027-01-01");
+
+        // b has no end date, so its one-year term runs to 2026-05-31
+        let overlap = Rights::compute_term_overlap(&a.term, &b.term).unwrap();
+        assert_eq!(overlap.to_string(), "2025-06-01 to 2026-01-01");
+        assert_eq!(Rights::compute_term_overlap(&b.term, &c.term).unwrap().end.to_string(), "2026-05-31");
+        assert!(Rights::compute_term_overlap(&a.term, &c.term).is_none());
+
+        assert_eq!(a.overlapping_territories(&b), vec!["US"]);
+        assert_eq!(c.overlapping_territories(&b), vec!["us", "UK"]);
+        assert!(!a.has_territory_overlap(&rights(&["FR"], "2025-01-01", "2026-01-01")));
+    }
+}
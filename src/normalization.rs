@@ -0,0 +1,271 @@
+// src/normalization.rs - Map free-text rights vocabulary to canonical codes
+use serde::{Deserialize, Serialize};
+
+/// Canonical media exploitation windows
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
+pub enum MediaTypeCode {
+    Theatrical,
+    Svod,
+    Avod,
+    Tvod,
+    FreeTV,
+    PayTV,
+    PhysicalMedia,
+    AirlineInFlight,
+    HotelInRoom,
+    Educational,
+    Other(String),
+}
+
+impl MediaTypeCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            MediaTypeCode::Theatrical => "THEATRICAL",
+            MediaTypeCode::Svod => "SVOD",
+            MediaTypeCode::Avod => "AVOD",
+            MediaTypeCode::Tvod => "TVOD",
+            MediaTypeCode::FreeTV => "FREE_TV",
+            MediaTypeCode::PayTV => "PAY_TV",
+            MediaTypeCode::PhysicalMedia => "PHYSICAL_MEDIA",
+            MediaTypeCode::AirlineInFlight => "AIRLINE_IN_FLIGHT",
+            MediaTypeCode::HotelInRoom => "HOTEL_IN_ROOM",
+            MediaTypeCode::Educational => "EDUCATIONAL",
+            MediaTypeCode::Other(raw) => raw,
+        }
+    }
+}
+
+impl From<MediaTypeCode> for String {
+    fn from(code: MediaTypeCode) -> Self {
+        code.as_str().to_string()
+    }
+}
+
+impl From<String> for MediaTypeCode {
+    fn from(s: String) -> Self {
+        normalize_media_type(&s).unwrap_or(MediaTypeCode::Other(s))
+    }
+}
+
+// Checked in order, so more specific phrases come before generic ones
+const MEDIA_TYPE_KEYWORDS: &[(&[&str], MediaTypeCode)] = &[
+    (&["avod", "ad supported", "advertising video on demand", "youtube", "free streaming"], MediaTypeCode::Avod),
+    (&["tvod", "transactional video on demand", "electronic sell through", "est", "download to own", "rental", "itunes"], MediaTypeCode::Tvod),
+    (&["svod", "subscription video on demand", "streaming", "ott", "online vod", "vod", "netflix", "amazon prime", "prime video", "disney+", "hotstar"], MediaTypeCode::Svod),
+    (&["airline", "in flight", "inflight"], MediaTypeCode::AirlineInFlight),
+    (&["hotel", "in room", "motel"], MediaTypeCode::HotelInRoom),
+    (&["educational", "education", "schools"], MediaTypeCode::Educational),
+    (&["pay tv", "pay television", "cable", "satellite", "dth", "pay per view", "ppv"], MediaTypeCode::PayTV),
+    (&["free tv", "free to air", "fta", "free television", "terrestrial"], MediaTypeCode::FreeTV),
+    (&["dvd", "blu ray", "bluray", "home video", "physical media", "vhs"], MediaTypeCode::PhysicalMedia),
+    (&["theatrical", "theatre", "theater", "cinema"], MediaTypeCode::Theatrical),
+];
+
+/// Lower-case, treat `_`/`-` as spaces and collapse whitespace
+fn canonical(s: &str) -> String {
+    s.to_lowercase()
+        .replace(['_', '-'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// True if `phrase` occurs in `text` on word boundaries
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    let padded = format!(" {} ", text);
+    padded.contains(&format!(" {} ", phrase))
+}
+
+/// Map a media right as written ("Netflix", "Online VOD", "Cable") to its
+/// canonical code. Unrecognised values are kept as `Other`; blank input is `None`.
+pub fn normalize_media_type(s: &str) -> Option<MediaTypeCode> {
+    let key = canonical(s);
+    if key.is_empty() {
+        return None;
+    }
+
+    let code = MEDIA_TYPE_KEYWORDS
+        .iter()
+        .find(|(keywords, _)| keywords.iter().any(|k| contains_phrase(&key, k)))
+        .map(|(_, code)| code.clone())
+        .unwrap_or_else(|| MediaTypeCode::Other(s.trim().to_string()));
+
+    Some(code)
+}
+
+/// Normalize and de-duplicate a list of media rights
+pub fn normalize_media_types(values: &[String]) -> Vec<MediaTypeCode> {
+    let mut codes: Vec<MediaTypeCode> = Vec::new();
+    for code in values.iter().filter_map(|v| normalize_media_type(v)) {
+        if !codes.contains(&code) {
+            codes.push(code);
+        }
+    }
+    codes
+}
+
+/// Known streaming services referenced in platform grants and exclusions
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
+pub enum StreamingPlatform {
+    Netflix,
+    AmazonPrimeVideo,
+    DisneyPlus,
+    Hotstar,
+    AppleTvPlus,
+    Max,
+    Hulu,
+    Peacock,
+    ParamountPlus,
+    YouTube,
+    Zee5,
+    SonyLiv,
+    JioCinema,
+    Other(String),
+}
+
+impl StreamingPlatform {
+    pub fn as_str(&self) -> &str {
+        match self {
+            StreamingPlatform::Netflix => "Netflix",
+            StreamingPlatform::AmazonPrimeVideo => "Amazon Prime Video",
+            StreamingPlatform::DisneyPlus => "Disney+",
+            StreamingPlatform::Hotstar => "Hotstar",
+            StreamingPlatform::AppleTvPlus => "Apple TV+",
+            StreamingPlatform::Max => "Max",
+            StreamingPlatform::Hulu => "Hulu",
+            StreamingPlatform::Peacock => "Peacock",
+            StreamingPlatform::ParamountPlus => "Paramount+",
+            StreamingPlatform::YouTube => "YouTube",
+            StreamingPlatform::Zee5 => "ZEE5",
+            StreamingPlatform::SonyLiv => "SonyLIV",
+            StreamingPlatform::JioCinema => "JioCinema",
+            StreamingPlatform::Other(raw) => raw,
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        !matches!(self, StreamingPlatform::Other(_))
+    }
+}
+
+impl From<StreamingPlatform> for String {
+    fn from(platform: StreamingPlatform) -> Self {
+        platform.as_str().to_string()
+    }
+}
+
+impl From<String> for StreamingPlatform {
+    fn from(s: String) -> Self {
+        normalize_streaming_platform(&s)
+    }
+}
+
+// Hotstar is checked before Disney+ so "Disney+ Hotstar" maps to Hotstar
+const PLATFORM_KEYWORDS: &[(&[&str], StreamingPlatform)] = &[
+    (&["netflix"], StreamingPlatform::Netflix),
+    (&["amazon", "prime video", "amazon prime"], StreamingPlatform::AmazonPrimeVideo),
+    (&["hotstar", "jiohotstar"], StreamingPlatform::Hotstar),
+    (&["disney+", "disney plus", "disneyplus"], StreamingPlatform::DisneyPlus),
+    (&["apple tv+", "apple tv plus", "apple tv"], StreamingPlatform::AppleTvPlus),
+    (&["hbo max", "hbo", "max"], StreamingPlatform::Max),
+    (&["hulu"], StreamingPlatform::Hulu),
+    (&["peacock"], StreamingPlatform::Peacock),
+    (&["paramount+", "paramount plus"], StreamingPlatform::ParamountPlus),
+    (&["youtube"], StreamingPlatform::YouTube),
+    (&["zee5", "zee 5"], StreamingPlatform::Zee5),
+    (&["sonyliv", "sony liv"], StreamingPlatform::SonyLiv),
+    (&["jiocinema", "jio cinema"], StreamingPlatform::JioCinema),
+];
+
+/// Map a platform name as written ("Amazon Prime", "Disney Plus") to a known platform
+pub fn normalize_streaming_platform(s: &str) -> StreamingPlatform {
+    let key = canonical(s);
+
+    PLATFORM_KEYWORDS
+        .iter()
+        .find(|(keywords, _)| keywords.iter().any(|k| contains_phrase(&key, k)))
+        .map(|(_, platform)| platform.clone())
+        .unwrap_or_else(|| StreamingPlatform::Other(s.trim().to_string()))
+}
+
+/// Normalize and de-duplicate a list of platform names
+pub fn normalize_streaming_platforms(values: &[String]) -> Vec<StreamingPlatform> {
+    let mut platforms: Vec<StreamingPlatform> = Vec::new();
+    for platform in values
+        .iter()
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| normalize_streaming_platform(v))
+    {
+        if !platforms.contains(&platform) {
+            platforms.push(platform);
+        }
+    }
+    platforms
+}
+
+/// Trim, lower-case and de-duplicate user tags, dropping blanks
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| canonical(t).replace(' ', "-")) {
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_media_type() {
+        assert_eq!(normalize_media_type("Netflix"), Some(MediaTypeCode::Svod));
+        assert_eq!(normalize_media_type("Online VOD"), Some(MediaTypeCode::Svod));
+        assert_eq!(normalize_media_type("OTT"), Some(MediaTypeCode::Svod));
+        assert_eq!(normalize_media_type("AVOD"), Some(MediaTypeCode::Avod));
+        assert_eq!(normalize_media_type("CABLE"), Some(MediaTypeCode::PayTV));
+        assert_eq!(normalize_media_type("Free-to-Air"), Some(MediaTypeCode::FreeTV));
+        assert_eq!(normalize_media_type("In-Flight"), Some(MediaTypeCode::AirlineInFlight));
+        assert_eq!(normalize_media_type("Theatrical Exhibition"), Some(MediaTypeCode::Theatrical));
+        assert_eq!(
+            normalize_media_type("Mobile Gaming"),
+            Some(MediaTypeCode::Other("Mobile Gaming".to_string()))
+        );
+        assert_eq!(normalize_media_type("  "), None);
+    }
+
+    #[test]
+    fn test_normalize_streaming_platform() {
+        assert_eq!(normalize_streaming_platform("Amazon Prime"), StreamingPlatform::AmazonPrimeVideo);
+        assert_eq!(normalize_streaming_platform("Disney Plus"), StreamingPlatform::DisneyPlus);
+        assert_eq!(normalize_streaming_platform("Disney+ Hotstar"), StreamingPlatform::Hotstar);
+        assert_eq!(normalize_streaming_platform("NETFLIX"), StreamingPlatform::Netflix);
+        assert_eq!(
+            normalize_streaming_platform("Aha"),
+            StreamingPlatform::Other("Aha".to_string())
+        );
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = ["High-Value", " streaming ", "high value", "", "Q1 2025"].map(String::from);
+        assert_eq!(normalize_tags(&tags), vec!["high-value", "streaming", "q1-2025"]);
+    }
+
+    #[test]
+    fn test_media_type_codes_round_trip_as_strings() {
+        let codes = normalize_media_types(&[
+            "Netflix".to_string(),
+            "SVOD".to_string(),
+            "Mobile Gaming".to_string(),
+        ]);
+        let json = serde_json::to_string(&codes).unwrap();
+        assert_eq!(json, r#"["SVOD","Mobile Gaming"]"#);
+
+        let back: Vec<MediaTypeCode> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, codes);
+    }
+}
@@ -0,0 +1,208 @@
+// src/notifier.rs - Email notifications to agreement stakeholders
+use anyhow::{Context, Result};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tracing::{info, warn};
+
+/// SMTP settings read from `SMTP_HOST`, `SMTP_PORT`, `SMTP_USER` and `SMTP_PASSWORD`
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// Sender address, defaults to `user`
+    pub from: Option<String>,
+    /// Addresses that may always be notified, from `NOTIFY_ALLOWED_RECIPIENTS`
+    pub allowed_recipients: Vec<String>,
+}
+
+/// Agreement lifecycle events stakeholders can be notified about
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationEvent {
+    Executed,
+    Amended,
+    Expiring,
+}
+
+impl NotificationEvent {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "executed" => Some(Self::Executed),
+            "amended" => Some(Self::Amended),
+            "expiring" => Some(Self::Expiring),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Executed => "has been executed",
+            Self::Amended => "has been amended",
+            Self::Expiring => "is expiring soon",
+        }
+    }
+}
+
+/// Agreement details used to fill in the email template
+pub struct AgreementSummary<'a> {
+    pub title: &'a str,
+    pub licensor: &'a str,
+    pub licensee: &'a str,
+}
+
+/// Subject and plain-text body for a notification
+pub fn render_email(
+    event: NotificationEvent,
+    agreement: &AgreementSummary<'_>,
+    cid: &str,
+    message: Option<&str>,
+) -> (String, String) {
+    let subject = format!("Agreement update: {} {}", agreement.title, event.describe());
+
+    let mut body = format!(
+        "The rights agreement for \"{}\" between {} (licensor) and {} (licensee) {}.\n\nIPFS CID: {}\n",
+        agreement.title,
+        agreement.licensor,
+        agreement.licensee,
+        event.describe(),
+        cid
+    );
+    if let Some(message) = message.map(str::trim).filter(|m| !m.is_empty()) {
+        body.push_str(&format!("\nMessage:\n{}\n", message));
+    }
+
+    (subject, body)
+}
+
+/// The first recipient that is neither a contact on the agreement nor on the
+/// configured allowlist, so the endpoint cannot mail arbitrary addresses
+pub fn unlisted_recipient<'a>(recipients: &'a [Mailbox], contacts: &[&str], allowed: &[String]) -> Option<&'a Mailbox> {
+    recipients.iter().find(|recipient| {
+        let address: &str = recipient.email.as_ref();
+        !contacts
+            .iter()
+            .copied()
+            .chain(allowed.iter().map(String::as_str))
+            .any(|listed| listed.trim().eq_ignore_ascii_case(address))
+    })
+}
+
+pub struct EmailNotifier {
+    transport: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    from: Option<Mailbox>,
+    allowed_recipients: Vec<String>,
+}
+
+impl EmailNotifier {
+    /// Build the SMTP transport, or a disabled notifier if SMTP is not configured
+    pub fn new(config: Option<SmtpConfig>) -> Self {
+        let Some(config) = config else {
+            return Self { transport: None, from: None, allowed_recipients: Vec::new() };
+        };
+
+        let from = config
+            .from
+            .as_deref()
+            .or(config.user.as_deref())
+            .and_then(|f| f.parse::<Mailbox>().ok());
+        if from.is_none() {
+            warn!("No valid sender address for SMTP, email notifications disabled");
+        }
+
+        // Port 465 uses implicit TLS, everything else upgrades with STARTTLS
+        let builder = if config.port == 465 {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+        };
+
+        let transport = match builder {
+            Ok(builder) => {
+                let builder = builder.port(config.port);
+                let builder = match (config.user, config.password) {
+                    (Some(user), Some(password)) => builder.credentials(Credentials::new(user, password)),
+                    _ => builder,
+                };
+                info!("Initializing email notifier ({}:{})", config.host, config.port);
+                Some(builder.build())
+            }
+            Err(e) => {
+                warn!("Invalid SMTP configuration, email notifications disabled: {}", e);
+                None
+            }
+        };
+
+        Self { transport, from, allowed_recipients: config.allowed_recipients }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.transport.is_some() && self.from.is_some()
+    }
+
+    pub fn allowed_recipients(&self) -> &[String] {
+        &self.allowed_recipients
+    }
+
+    /// Send one email per recipient. Returns how many were delivered.
+    pub async fn send(&self, recipients: &[Mailbox], subject: &str, body: &str) -> Result<usize> {
+        let transport = self.transport.as_ref().context("SMTP is not configured")?;
+        let from = self.from.clone().context("No sender address configured")?;
+
+        let mut sent = 0;
+        for recipient in recipients {
+            let email = Message::builder()
+                .from(from.clone())
+                .to(recipient.clone())
+                .subject(subject)
+                .body(body.to_string())
+                .context("Failed to build email")?;
+
+            match transport.send(email).await {
+                Ok(_) => sent += 1,
+                Err(e) => warn!("Failed to send notification to {}: {}", recipient, e),
+            }
+        }
+
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_email() {
+        let agreement = AgreementSummary {
+            title: "Kalki 2898 AD",
+            licensor: "Vyjayanthi Movies",
+            licensee: "Netflix India",
+        };
+
+        let event = NotificationEvent::parse("Expiring").unwrap();
+        let (subject, body) = render_email(event, &agreement, "QmTest", Some("Renewal talks start next week"));
+
+        assert_eq!(subject, "Agreement update: Kalki 2898 AD is expiring soon");
+        assert!(body.contains("Vyjayanthi Movies (licensor) and Netflix India (licensee)"));
+        assert!(body.contains("QmTest"));
+        assert!(body.contains("Renewal talks start next week"));
+        assert!(NotificationEvent::parse("terminated").is_none());
+    }
+
+    #[test]
+    fn test_unlisted_recipient() {
+        let recipients: Vec<Mailbox> = ["Legal@Vyjayanthi.example", "ops@studio.example", "attacker@spam.example"]
+            .iter()
+            .map(|r| r.parse().unwrap())
+            .collect();
+        let contacts = ["legal@vyjayanthi.example"];
+        let allowed = vec!["ops@studio.example".to_string()];
+
+        let unlisted = unlisted_recipient(&recipients, &contacts, &allowed).unwrap();
+        assert_eq!(unlisted.email.to_string(), "attacker@spam.example");
+        assert!(unlisted_recipient(&recipients[..2], &contacts, &allowed).is_none());
+        assert!(unlisted_recipient(&recipients[..1], &[], &[]).is_some());
+    }
+}
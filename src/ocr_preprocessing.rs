@@ -0,0 +1,285 @@
+// src/ocr_preprocessing.rs - Page image cleanup before OCR
+use image::{DynamicImage, GrayImage, Luma};
+use tracing::{debug, info};
+
+/// A single transform applied to a rendered page before OCR
+pub trait PreprocessingStep: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn apply(&self, image: DynamicImage, page: usize) -> DynamicImage;
+}
+
+/// Which preprocessing steps to run, and their tuning
+#[derive(Debug, Clone)]
+pub struct OCRPreprocessingOptions {
+    pub grayscale: bool,
+    pub adaptive_threshold: bool,
+    pub deskew: bool,
+    /// Half-size of the local window used for thresholding, in pixels
+    pub threshold_block_radius: u32,
+    /// How far below the local mean a pixel must be to count as ink
+    pub threshold_offset: f32,
+    /// Largest skew angle searched for, in degrees
+    pub max_skew_degrees: f32,
+}
+
+impl Default for OCRPreprocessingOptions {
+    fn default() -> Self {
+        Self {
+            grayscale: true,
+            adaptive_threshold: true,
+            deskew: true,
+            threshold_block_radius: 15,
+            threshold_offset: 10.0,
+            max_skew_degrees: 5.0,
+        }
+    }
+}
+
+impl OCRPreprocessingOptions {
+    /// Build the enabled steps in pipeline order
+    pub fn steps(&self) -> Vec<Box<dyn PreprocessingStep>> {
+        let mut steps: Vec<Box<dyn PreprocessingStep>> = Vec::new();
+
+        if self.grayscale {
+            steps.push(Box::new(Grayscale));
+        }
+        if self.adaptive_threshold {
+            steps.push(Box::new(AdaptiveThreshold {
+                block_radius: self.threshold_block_radius,
+                offset: self.threshold_offset,
+            }));
+        }
+        if self.deskew {
+            steps.push(Box::new(Deskew {
+                max_degrees: self.max_skew_degrees,
+            }));
+        }
+
+        steps
+    }
+}
+
+/// Run every enabled step over a page image
+pub fn preprocess(image: DynamicImage, page: usize, options: &OCRPreprocessingOptions) -> DynamicImage {
+    options.steps().iter().fold(image, |img, step| {
+        debug!("Applying {} to page {}", step.name(), page);
+        step.apply(img, page)
+    })
+}
+
+pub struct Grayscale;
+
+impl PreprocessingStep for Grayscale {
+    fn name(&self) -> &'static str {
+        "grayscale"
+    }
+
+    fn apply(&self, image: DynamicImage, _page: usize) -> DynamicImage {
+        DynamicImage::ImageLuma8(image.to_luma8())
+    }
+}
+
+/// Binarize against the mean of each pixel's neighbourhood, which copes with
+/// uneven lighting and faded scans far better than a global threshold
+pub struct AdaptiveThreshold {
+    pub block_radius: u32,
+    pub offset: f32,
+}
+
+impl PreprocessingStep for AdaptiveThreshold {
+    fn name(&self) -> &'static str {
+        "adaptive_threshold"
+    }
+
+    fn apply(&self, image: DynamicImage, _page: usize) -> DynamicImage {
+        let gray = image.to_luma8();
+        let (width, height) = gray.dimensions();
+        let (w, h) = (width as usize, height as usize);
+
+        // Integral image with a zero border row/column
+        let mut integral = vec![0u64; (w + 1) * (h + 1)];
+        for y in 0..h {
+            let mut row_sum = 0u64;
+            for x in 0..w {
+                row_sum += gray.get_pixel(x as u32, y as u32)[0] as u64;
+                integral[(y + 1) * (w + 1) + x + 1] = integral[y * (w + 1) + x + 1] + row_sum;
+            }
+        }
+
+        let r = self.block_radius as usize;
+        let out = GrayImage::from_fn(width, height, |x, y| {
+            let (x, y) = (x as usize, y as usize);
+            let (x0, y0) = (x.saturating_sub(r), y.saturating_sub(r));
+            let (x1, y1) = ((x + r + 1).min(w), (y + r + 1).min(h));
+
+            let sum = integral[y1 * (w + 1) + x1] + integral[y0 * (w + 1) + x0]
+                - integral[y0 * (w + 1) + x1]
+                - integral[y1 * (w + 1) + x0];
+            let mean = sum as f32 / ((x1 - x0) * (y1 - y0)) as f32;
+
+            let value = gray.get_pixel(x as u32, y as u32)[0] as f32;
+            if value < mean - self.offset {
+                Luma([0])
+            } else {
+                Luma([255])
+            }
+        });
+
+        DynamicImage::ImageLuma8(out)
+    }
+}
+
+/// Detect skew with a projection profile and rotate the page level
+pub struct Deskew {
+    pub max_degrees: f32,
+}
+
+impl Deskew {
+    const STEP_DEGREES: f32 = 0.25;
+
+    /// Angle (degrees) at which text rows project most sharply
+    pub fn detect_angle(&self, image: &GrayImage) -> f32 {
+        let (width, height) = image.dimensions();
+
+        let ink: Vec<(f32, f32)> = image
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p[0] < 128)
+            .map(|(x, y, _)| (x as f32, y as f32))
+            .collect();
+
+        if ink.is_empty() {
+            return 0.0;
+        }
+
+        // Room for rows to shift when projected at the largest angle
+        let margin = (width as f32 * self.max_degrees.to_radians().tan()).ceil() as usize + 1;
+        let bins = height as usize + 2 * margin;
+
+        let mut best_angle = 0.0;
+        let mut best_score = f64::MIN;
+        let steps = (self.max_degrees / Self::STEP_DEGREES).round() as i32;
+
+        for i in -steps..=steps {
+            let angle = i as f32 * Self::STEP_DEGREES;
+            let (sin, cos) = angle.to_radians().sin_cos();
+
+            let mut profile = vec![0u32; bins];
+            for &(x, y) in &ink {
+                let row = (y * cos - x * sin).round() as isize + margin as isize;
+                if row >= 0 && (row as usize) < bins {
+                    profile[row as usize] += 1;
+                }
+            }
+
+            // Sharp peaks between text lines and gaps maximise adjacent differences
+            let score: f64 = profile
+                .windows(2)
+                .map(|w| (w[1] as f64 - w[0] as f64).powi(2))
+                .sum();
+
+            if score > best_score {
+                best_score = score;
+                best_angle = angle;
+            }
+        }
+
+        best_angle
+    }
+
+    fn rotate(image: &GrayImage, degrees: f32) -> GrayImage {
+        let (width, height) = image.dimensions();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+
+        GrayImage::from_fn(width, height, |x, y| {
+            let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+            let sx = (cx + dx * cos - dy * sin).round();
+            let sy = (cy + dx * sin + dy * cos).round();
+
+            if sx >= 0.0 && sy >= 0.0 && (sx as u32) < width && (sy as u32) < height {
+                *image.get_pixel(sx as u32, sy as u32)
+            } else {
+                Luma([255])
+            }
+        })
+    }
+}
+
+impl PreprocessingStep for Deskew {
+    fn name(&self) -> &'static str {
+        "deskew"
+    }
+
+    fn apply(&self, image: DynamicImage, page: usize) -> DynamicImage {
+        let gray = image.to_luma8();
+        let angle = self.detect_angle(&gray);
+
+        info!("📐 Page {}: detected skew {:.2}°", page, angle);
+
+        if angle == 0.0 {
+            return DynamicImage::ImageLuma8(gray);
+        }
+
+        DynamicImage::ImageLuma8(Self::rotate(&gray, angle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// White page with dark horizontal "text lines" tilted by `degrees`
+    fn skewed_page(degrees: f32) -> GrayImage {
+        let mut img = GrayImage::from_pixel(400, 300, Luma([255]));
+        let slope = degrees.to_radians().tan();
+
+        for line in 0..8 {
+            let y0 = 40.0 + line as f32 * 30.0;
+            for x in 20..380 {
+                for thickness in 0..3 {
+                    let y = (y0 + x as f32 * slope).round() as u32 + thickness;
+                    if y < 300 {
+                        img.put_pixel(x, y, Luma([0]));
+                    }
+                }
+            }
+        }
+
+        img
+    }
+
+    #[test]
+    fn test_detects_skew_angle() {
+        let deskew = Deskew { max_degrees: 5.0 };
+        let angle = deskew.detect_angle(&skewed_page(2.0));
+        assert!((angle - 2.0).abs() <= 0.25, "detected {}", angle);
+
+        let angle = deskew.detect_angle(&skewed_page(0.0));
+        assert!(angle.abs() <= 0.25, "detected {}", angle);
+    }
+
+    #[test]
+    fn test_adaptive_threshold_is_binary() {
+        let mut img = GrayImage::from_fn(64, 64, |x, _| Luma([120 + (x as u8)]));
+        img.put_pixel(32, 32, Luma([20]));
+
+        let step = AdaptiveThreshold { block_radius: 5, offset: 10.0 };
+        let out = step.apply(DynamicImage::ImageLuma8(img), 1).to_luma8();
+
+        assert!(out.pixels().all(|p| p[0] == 0 || p[0] == 255));
+        assert_eq!(out.get_pixel(32, 32)[0], 0);
+        assert_eq!(out.get_pixel(5, 5)[0], 255);
+    }
+
+    #[test]
+    fn test_disabled_steps_are_skipped() {
+        let options = OCRPreprocessingOptions {
+            adaptive_threshold: false,
+            deskew: false,
+            ..Default::default()
+        };
+
+        let names: Vec<_> = options.steps().iter().map(|s| s.name()).collect();
+        assert_eq!(names, vec!["grayscale"]);
+    }
+}
@@ -0,0 +1,205 @@
+// src/pdf_download.rs - Download agreement PDFs referenced by URL
+use axum::http::StatusCode;
+use reqwest::{header, redirect, Client, Url};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use tracing::info;
+
+/// Content types accepted for a downloaded agreement
+const ACCEPTED_CONTENT_TYPES: &[&str] = &["application/pdf", "application/octet-stream", "binary/octet-stream"];
+
+#[derive(Debug)]
+pub enum DownloadError {
+    InvalidUrl(String),
+    Request(String),
+    UnsupportedContentType(String),
+    TooLarge(usize),
+}
+
+impl DownloadError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::InvalidUrl(_) => StatusCode::BAD_REQUEST,
+            Self::Request(_) => StatusCode::BAD_GATEWAY,
+            Self::UnsupportedContentType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUrl(reason) => write!(f, "Invalid pdf_url: {}", reason),
+            Self::Request(reason) => write!(f, "Failed to download PDF: {}", reason),
+            Self::UnsupportedContentType(content_type) => {
+                write!(f, "pdf_url did not return a PDF (Content-Type: {})", content_type)
+            }
+            Self::TooLarge(max_bytes) => write!(f, "PDF exceeds the {} byte upload limit", max_bytes),
+        }
+    }
+}
+
+/// Parse a download URL, allowing only `https://`
+pub fn validate_url(raw: &str) -> Result<Url, DownloadError> {
+    let url = Url::parse(raw.trim()).map_err(|e| DownloadError::InvalidUrl(e.to_string()))?;
+    if url.scheme() != "https" {
+        return Err(DownloadError::InvalidUrl("only https:// URLs are allowed".to_string()));
+    }
+    if url.host_str().is_none() {
+        return Err(DownloadError::InvalidUrl("URL has no host".to_string()));
+    }
+    Ok(url)
+}
+
+/// Whether `ip` is reachable on the public internet. Private, loopback,
+/// link-local (including cloud metadata), CGNAT, multicast and reserved
+/// ranges are not.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                || a >= 240
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 192 && b == 0 && c == 0))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let [first, second, ..] = v6.segments();
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first == 0x2001 && second == 0x0db8))
+        }
+    }
+}
+
+/// A client for `url` that only connects to public addresses and does not
+/// follow redirects. Host names are resolved here and the client is pinned
+/// to the answer, so a later DNS change cannot point it at an internal host.
+pub async fn public_client(url: &Url) -> Result<Client, DownloadError> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| DownloadError::InvalidUrl("URL has no host".to_string()))?;
+    let non_public = || DownloadError::InvalidUrl(format!("{} is not a public address", host));
+    let mut builder = Client::builder().redirect(redirect::Policy::none());
+
+    // IPv6 literals keep their brackets in `host_str`
+    match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(ip) if !is_public_ip(ip) => return Err(non_public()),
+        Ok(_) => {}
+        Err(_) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|e| DownloadError::Request(format!("could not resolve {}: {}", host, e)))?
+                .collect();
+            if addrs.is_empty() || addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+                return Err(non_public());
+            }
+            builder = builder.resolve_to_addrs(host, &addrs);
+        }
+    }
+
+    builder.build().map_err(|e| DownloadError::Request(e.to_string()))
+}
+
+/// File name from the last path segment, e.g. `contract.pdf`
+pub fn file_name(url: &Url) -> String {
+    url.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("document.pdf")
+        .to_string()
+}
+
+/// Download a PDF, stopping as soon as it exceeds `max_bytes`
+pub async fn download_pdf(client: &Client, url: &Url, max_bytes: usize) -> Result<Vec<u8>, DownloadError> {
+    info!("🌐 Downloading PDF from {}", url.host_str().unwrap_or_default());
+
+    let mut response = client
+        .get(url.clone())
+        .timeout(std::time::Duration::from_secs(60))
+        .send()
+        .await
+        .map_err(|e| DownloadError::Request(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(DownloadError::Request(format!("server returned {}", response.status())));
+    }
+
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+    if !ACCEPTED_CONTENT_TYPES.contains(&mime.as_str()) {
+        return Err(DownloadError::UnsupportedContentType(content_type));
+    }
+
+    if response.content_length().map_or(false, |len| len > max_bytes as u64) {
+        return Err(DownloadError::TooLarge(max_bytes));
+    }
+
+    // Content-Length may be missing or wrong, so enforce the limit while reading
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| DownloadError::Request(e.to_string()))? {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(DownloadError::TooLarge(max_bytes));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    // Generic binary types are only accepted if the body really is a PDF
+    if mime != "application/pdf" && !bytes.starts_with(b"%PDF") {
+        return Err(DownloadError::UnsupportedContentType(content_type));
+    }
+
+    info!("✅ Downloaded {} bytes", bytes.len());
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_url() {
+        let url = validate_url("https://bucket.s3.amazonaws.com/contracts/kalki.pdf?X-Amz-Signature=abc").unwrap();
+        assert_eq!(file_name(&url), "kalki.pdf");
+
+        assert!(matches!(validate_url("http://example.com/a.pdf"), Err(DownloadError::InvalidUrl(_))));
+        assert!(matches!(validate_url("file:///etc/passwd"), Err(DownloadError::InvalidUrl(_))));
+        assert!(matches!(validate_url("not a url"), Err(DownloadError::InvalidUrl(_))));
+        assert_eq!(file_name(&validate_url("https://example.com/").unwrap()), "document.pdf");
+    }
+
+    #[tokio::test]
+    async fn test_internal_addresses_rejected() {
+        for ip in ["10.0.0.8", "127.0.0.1", "169.254.169.254", "100.64.0.1", "::1", "fd00::1", "fe80::1", "::ffff:192.168.1.1"] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{} must not be public", ip);
+        }
+        assert!(is_public_ip("93.184.216.34".parse().unwrap()));
+        assert!(is_public_ip("2606:4700::1111".parse().unwrap()));
+
+        for url in ["https://169.254.169.254/latest/meta-data/", "https://[::1]/a.pdf", "https://10.1.2.3/a.pdf"] {
+            let url = validate_url(url).unwrap();
+            assert!(matches!(public_client(&url).await, Err(DownloadError::InvalidUrl(_))));
+        }
+        assert!(public_client(&validate_url("https://93.184.216.34/a.pdf").unwrap()).await.is_ok());
+    }
+}
@@ -1,143 +1,1440 @@
 use anyhow::{Context, Result};
-use pdf_extract::extract_text_from_mem;
-use tracing::{info, warn};
+use async_trait::async_trait;
+use pdf_extract::{extract_text_from_mem_by_pages, extract_text_from_mem_by_pages_encrypted};
+use tracing::{info, trace_span, warn};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, LazyLock};
+use tempfile::TempDir;
+use std::time::{Duration, SystemTime};
 
-pub struct PDFExtractor;
+use crate::ocr_preprocessing::{self, OCRPreprocessingOptions};
+
+/// The document information dictionary embedded in an uploaded PDF. Kept
+/// here rather than in `models` so the benches can compile this file alone.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PdfDocumentMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Application that created the original document, e.g. a scanner driver
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_date: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modification_date: Option<chrono::DateTime<chrono::Utc>>,
+    pub page_count: u32,
+}
+
+/// Directory the extractor's own temp directory is created in unless configured otherwise
+pub const DEFAULT_TEMP_DIR: &str = "/tmp";
+
+/// Name prefix of the directory each extractor creates for its temp files
+const TEMP_DIR_PREFIX: &str = "rights-parser-";
+
+/// Prefix of the per-document directories `ocr` renders pages into
+const OCR_DIR_PREFIX: &str = "ocr-";
+
+/// Longest a temp file write or delete may take before the disk is treated as degraded
+const TEMP_FILE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A temp file write or removal took longer than `TEMP_FILE_TIMEOUT`
+#[derive(Debug)]
+pub struct TempFileTimeout(&'static str, PathBuf);
+
+impl std::fmt::Display for TempFileTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timed out {} {}", self.0, self.1.display())
+    }
+}
+
+impl std::error::Error for TempFileTimeout {}
+
+/// Whether extraction failed because the temp disk is too slow, which is
+/// worth retrying later, rather than because of the document
+pub fn is_temp_file_timeout(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<TempFileTimeout>())
+}
+
+/// Remove a temp file or directory on the blocking pool, giving up after
+/// `TEMP_FILE_TIMEOUT` so a hung disk cannot stall the request
+async fn close_temp<T, F>(guard: T, close: F, path: PathBuf) -> Result<()>
+where
+    T: Send + 'static,
+    F: FnOnce(T) -> std::io::Result<()> + Send + 'static,
+{
+    match tokio::time::timeout(TEMP_FILE_TIMEOUT, tokio::task::spawn_blocking(move || close(guard))).await {
+        Ok(Ok(Ok(()))) => {}
+        Ok(Ok(Err(e))) => warn!("Failed to remove {}: {}", path.display(), e),
+        Ok(Err(e)) => warn!("Removing {} panicked: {}", path.display(), e),
+        Err(_) => return Err(TempFileTimeout("removing", path).into()),
+    }
+    Ok(())
+}
+
+/// Fewer characters than this from the text layer means a scanned PDF
+pub const MIN_TEXT_CHARS: usize = 100;
+
+/// Resolution pages are rendered at for OCR; Tesseract works best near 300 DPI
+const OCR_DPI: u32 = 300;
+
+/// Gap between text runs on a line, in multiples of the font size, that separates table cells
+const CELL_GAP_EMS: f64 = 2.0;
+
+/// Cells whose left or right edges are within this many points share a column
+const COLUMN_TOLERANCE: f64 = 6.0;
+
+/// Fewest aligned lines, header included, reported as a table
+const MIN_TABLE_ROWS: usize = 3;
+
+/// Plain text from an uploaded document, whatever its format
+#[async_trait]
+pub trait TextExtractor: Send + Sync {
+    async fn extract_text(&self, data: &[u8], password: Option<&str>) -> Result<String>;
+}
+
+/// Lines on more than this share of pages are running headers or footers
+pub const DEFAULT_RUNNING_ELEMENT_THRESHOLD: f64 = 0.5;
+
+/// Fewer pages than this are too few to tell running elements from content
+const MIN_PAGES_FOR_RUNNING_ELEMENTS: usize = 3;
+
+/// Start of the line `format_with_page_markers` puts before each page
+pub const PAGE_MARKER_PREFIX: &str = "--- PAGE ";
+
+/// Extracted text, page by page. `full_text` joins the non-empty pages.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PagedText {
+    pub pages: Vec<String>,
+    pub full_text: String,
+}
+
+impl PagedText {
+    pub fn from_pages(pages: Vec<String>) -> Self {
+        let full_text = pages
+            .iter()
+            .map(|page| page.as_str())
+            .filter(|page| !page.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        Self { pages, full_text }
+    }
+
+    /// Text without page boundaries, such as a Word document or caller-provided text
+    pub fn unpaged(text: String) -> Self {
+        Self { pages: Vec::new(), full_text: text }
+    }
+}
+
+/// Which extractor produced the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractionMethod {
+    PdfExtract,
+    Pdftotext,
+    Ocr,
+}
+
+impl ExtractionMethod {
+    /// Name recorded in `FileMetadata.extraction_method`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PdfExtract => "pdf",
+            Self::Pdftotext => "pdftotext",
+            Self::Ocr => "ocr",
+        }
+    }
+}
+
+/// Pages `pdftoppm` rendered into `dir`, in page order. Page numbers are
+/// zero-padded to the same width, so name order is page order.
+fn rendered_pages(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut pages: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("page-") && n.ends_with(".png"))
+        })
+        .collect();
+    pages.sort();
+    Ok(pages)
+}
+
+/// Render each page with pdftoppm, clean it up with `ocr_preprocessing` (grayscale,
+/// threshold, deskew) and read it with tesseract
+fn ocr_pages(dir: &Path, pdf_path: &Path) -> Result<Vec<String>> {
+    let output = Command::new("pdftoppm")
+        .args(["-r", &OCR_DPI.to_string(), "-png"])
+        .arg(pdf_path).arg(dir.join("page")).output().context("pdftoppm failed")?;
+    if !output.status.success() {
+        anyhow::bail!("pdftoppm exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let options = OCRPreprocessingOptions::default();
+    let mut pages = Vec::new();
+    for (page, path) in rendered_pages(dir)?.iter().enumerate() {
+        let image = image::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        ocr_preprocessing::preprocess(image, page, &options)
+            .save(path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        let output = Command::new("tesseract").arg(path).arg("stdout").output().context("tesseract failed")?;
+        if !output.status.success() {
+            anyhow::bail!("tesseract exited with {} on page {}", output.status, page + 1);
+        }
+        pages.push(String::from_utf8_lossy(&output.stdout).into_owned());
+    }
+    Ok(pages)
+}
+
+#[derive(Debug, Clone)]
+pub struct PDFExtractor {
+    /// Holds only this extractor's files and is deleted when the last clone drops
+    temp_dir: Arc<TempDir>,
+    running_element_threshold: f64,
+}
+
+impl Default for PDFExtractor {
+    fn default() -> Self {
+        Self::with_temp_dir(DEFAULT_TEMP_DIR).expect("default temp directory is writable")
+    }
+}
+
+/// "Page 3" or "Page 3 of 12" anywhere in a line
+static PAGE_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bpage\s+\d+(\s*(of|/)\s*\d+)?\b").expect("valid page number regex"));
+
+/// A line that is only a page number: "3", "- 3 -", "3 of 12" or "3/12"
+static LONE_PAGE_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^[\s\-–—(\[]*\d+(\s*(of|/)\s*\d+)?[\s\-–—)\]]*$").expect("valid page number regex"));
+
+/// A line as compared across pages. Page numbers differ from page to page,
+/// so they count as the same; any other digits, such as amounts, must match.
+fn running_element_key(line: &str) -> String {
+    let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if LONE_PAGE_NUMBER.is_match(&line) {
+        return "#".to_string();
+    }
+    PAGE_NUMBER.replace_all(&line, "page #").into_owned()
+}
+
+/// Common section headers in rights agreements
+const SECTION_HEADERS: &[&str] = &[
+    "PARTIES",
+    "TERRITORY",
+    "MEDIA RIGHTS",
+    "TERM",
+    "FINANCIAL TERMS",
+    "PAYMENT",
+    "DELIVERABLES",
+    "WARRANTIES",
+    "INDEMNIFICATION",
+    "GOVERNING LAW",
+];
+
+/// Sections with fewer words than this are table of contents entries or
+/// cross-references rather than the clause itself
+const MIN_SECTION_WORDS: usize = 5;
+
+/// Any of `SECTION_HEADERS` as an uppercase whole word, so "TERM" matches none
+/// of "TERMS", "TERMINATION", "DETERMINE" or "term"
+static SECTION_HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    let alternation = SECTION_HEADERS.iter().map(|h| regex::escape(h)).collect::<Vec<_>>().join("|");
+    Regex::new(&format!(r"\b(?:{})\b", alternation)).expect("valid section header regex")
+});
+
+/// A section of the agreement found by `extract_sections`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub header: String,
+    /// Text from the header to the next one, across pages if need be
+    pub content: String,
+    /// Page the header is on, when the text has `format_with_page_markers` markers
+    pub page: Option<usize>,
+}
+
+/// The first occurrence of each `SECTION_HEADERS` header in `text`, in
+/// document order. Headers are found anywhere in a line, since `clean_text`
+/// leaves each page on a single line.
+pub fn extract_sections(text: &str) -> Vec<Section> {
+    let headers: Vec<_> = SECTION_HEADER.find_iter(text).collect();
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (i, header) in headers.iter().enumerate() {
+        if sections.iter().any(|s| s.header == header.as_str()) {
+            continue;
+        }
+        let end = headers.get(i + 1).map_or(text.len(), |next| next.start());
+        let content = text[header.end()..end].trim();
+        let words = content
+            .lines()
+            .filter(|line| !line.starts_with(PAGE_MARKER_PREFIX))
+            .flat_map(str::split_whitespace)
+            .count();
+        if words < MIN_SECTION_WORDS {
+            continue;
+        }
+        sections.push(Section {
+            header: header.as_str().to_string(),
+            content: content.to_string(),
+            page: page_at(text, header.start()),
+        });
+    }
+
+    sections
+}
+
+/// Page that byte `offset` of `format_with_page_markers` output falls on
+fn page_at(text: &str, offset: usize) -> Option<usize> {
+    let marker = text[..offset].rfind(PAGE_MARKER_PREFIX)?;
+    text[marker + PAGE_MARKER_PREFIX.len()..].split(' ').next()?.parse().ok()
+}
+
+/// Entries `cleanup_old_temp_files` may delete from the extractor's temp
+/// directory: PDFs, and OCR directories with their rendered pages
+fn is_temp_entry(name: &str, is_dir: bool) -> bool {
+    if is_dir {
+        name.starts_with(OCR_DIR_PREFIX)
+    } else {
+        name.ends_with(".pdf")
+    }
+}
+
+/// Characters of extracted text logged from each end
+const TEXT_PREVIEW_CHARS: usize = 500;
+
+/// The first and last `chars` characters of `text`, split on character
+/// boundaries so OCR and Devanagari output cannot panic
+fn text_preview(text: &str, chars: usize) -> (&str, &str) {
+    let head_end = text.char_indices().nth(chars).map_or(text.len(), |(i, _)| i);
+    let tail_start = match chars.checked_sub(1) {
+        Some(skip) => text.char_indices().rev().nth(skip).map_or(0, |(i, _)| i),
+        None => text.len(),
+    };
+    (&text[..head_end], &text[tail_start..])
+}
+
+/// Share of U+FFFD characters above which the text layer is treated as garbled,
+/// usually because a font's `ToUnicode` CMap is missing or wrong
+pub const MAX_UNICODE_ERROR_RATIO: f64 = 0.02;
+
+/// How trustworthy an extracted text layer is
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ExtractionQuality {
+    /// U+FFFD replacement characters as a share of non-whitespace characters
+    pub unicode_error_ratio: f64,
+}
+
+impl ExtractionQuality {
+    pub fn measure(text: &str) -> Self {
+        let (replaced, total) = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .fold((0usize, 0usize), |(replaced, total), c| {
+                (replaced + (c == char::REPLACEMENT_CHARACTER) as usize, total + 1)
+            });
+        let unicode_error_ratio = if total == 0 { 0.0 } else { replaced as f64 / total as f64 };
+        Self { unicode_error_ratio }
+    }
+
+    pub fn is_garbled(&self) -> bool {
+        self.unicode_error_ratio > MAX_UNICODE_ERROR_RATIO
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedLanguage {
+    English,
+    French,
+    Hindi,
+    Unknown,
+}
+
+/// A run of text written in a single language
+#[derive(Debug, Clone)]
+pub struct LanguageSection {
+    pub language: DetectedLanguage,
+    /// Canonical section topic (e.g. "TERRITORY") if a heading was recognised
+    pub topic: Option<String>,
+    pub text: String,
+    /// Index of the section this one is a translation of
+    pub parallel_of: Option<usize>,
+}
+
+// Section headings in each supported language, keyed by canonical topic
+const SECTION_TOPICS: &[(&str, &[&str])] = &[
+    ("PARTIES", &["PARTIES", "पक्षकार"]),
+    ("TERRITORY", &["TERRITORY", "TERRITOIRE", "क्षेत्र"]),
+    ("TERM", &["TERM", "DURÉE", "DUREE", "अवधि"]),
+    ("PAYMENT", &["PAYMENT", "PAIEMENT", "भुगतान"]),
+    ("DELIVERABLES", &["DELIVERABLES", "LIVRABLES"]),
+    ("WARRANTIES", &["WARRANTIES", "GARANTIES", "वारंटी"]),
+    ("INDEMNIFICATION", &["INDEMNIFICATION", "INDEMNISATION", "क्षतिपूर्ति"]),
+    ("GOVERNING LAW", &["GOVERNING", "APPLICABLE", "शासी"]),
+];
+
+const ENGLISH_STOPWORDS: &[&str] = &[
+    "the", "and", "of", "to", "shall", "in", "is", "by", "for", "this", "with", "agreement",
+];
+const FRENCH_STOPWORDS: &[&str] = &[
+    "le", "la", "les", "et", "des", "du", "de", "un", "une", "est", "par", "pour", "dans",
+    "ce", "cette", "contrat", "sera",
+];
 
 impl PDFExtractor {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Keep temp files in a new directory inside `base`, so cleanup never
+    /// touches files other processes left there
+    pub fn with_temp_dir(base: impl AsRef<Path>) -> Result<Self> {
+        let base = base.as_ref();
+        let temp_dir = tempfile::Builder::new()
+            .prefix(TEMP_DIR_PREFIX)
+            .tempdir_in(base)
+            .with_context(|| format!("Failed to create a temp directory in {}", base.display()))?;
+        Ok(Self {
+            temp_dir: Arc::new(temp_dir),
+            running_element_threshold: DEFAULT_RUNNING_ELEMENT_THRESHOLD,
+        })
+    }
+
+    /// Share of pages a line must appear on to be stripped as a header or footer
+    pub fn with_running_element_threshold(mut self, threshold: f64) -> Self {
+        self.running_element_threshold = threshold;
+        self
+    }
+
+    /// Remove running headers and footers: lines, ignoring page numbers, that
+    /// appear on more than the threshold share of pages. Documents with fewer
+    /// than three pages are returned unchanged.
+    pub fn strip_running_elements(&self, paged_text: &[String]) -> Vec<String> {
+        if paged_text.len() < MIN_PAGES_FOR_RUNNING_ELEMENTS {
+            return paged_text.to_vec();
+        }
+
+        let mut page_counts: HashMap<String, usize> = HashMap::new();
+        for page in paged_text {
+            let keys: HashSet<String> = page
+                .lines()
+                .map(running_element_key)
+                .filter(|key| !key.is_empty())
+                .collect();
+            for key in keys {
+                *page_counts.entry(key).or_default() += 1;
+            }
+        }
+
+        let min_pages = paged_text.len() as f64 * self.running_element_threshold;
+        let running: HashSet<&str> = page_counts
+            .iter()
+            .filter(|(_, count)| **count as f64 > min_pages)
+            .map(|(key, _)| key.as_str())
+            .collect();
+        if running.is_empty() {
+            return paged_text.to_vec();
+        }
+        info!("✂️  Stripping {} running header/footer lines", running.len());
+
+        paged_text
+            .iter()
+            .map(|page| {
+                page.lines()
+                    .filter(|line| !running.contains(running_element_key(line).as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect()
+    }
+
+    pub fn temp_dir(&self) -> &Path {
+        self.temp_dir.path()
+    }
+
+    /// Delete temp PDFs and OCR directories older than `max_age_secs`, left
+    /// behind when processing was interrupted. Returns how many were removed.
+    pub async fn cleanup_old_temp_files(&self, max_age_secs: u64) -> Result<usize> {
+        let max_age = Duration::from_secs(max_age_secs);
+        let mut entries = tokio::fs::read_dir(self.temp_dir())
+            .await
+            .with_context(|| format!("Failed to read {}", self.temp_dir().display()))?;
+
+        let mut removed = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            let Ok(metadata) = entry.metadata().await else { continue };
+            if !entry.file_name().to_str().is_some_and(|name| is_temp_entry(name, metadata.is_dir())) {
+                continue;
+            }
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+            if !(metadata.is_file() || metadata.is_dir()) || age.map_or(true, |age| age <= max_age) {
+                continue;
+            }
+
+            let removal = if metadata.is_dir() {
+                tokio::fs::remove_dir_all(entry.path()).await
+            } else {
+                tokio::fs::remove_file(entry.path()).await
+            };
+            match removal {
+                Ok(()) => removed += 1,
+                Err(e) => warn!("Failed to remove old temp file {}: {}", entry.path().display(), e),
+            }
+        }
+
+        if removed > 0 {
+            info!("🧹 Removed {} old temp files from {}", removed, self.temp_dir().display());
+        }
+        Ok(removed)
+    }
+
+    /// `password` opens PDFs encrypted with a user password
+    pub async fn extract_text(&self, pdf_data: &[u8], password: Option<&str>) -> Result<PagedText> {
+        self.extract_text_with_quality(pdf_data, password).await.map(|(text, _, _)| text)
+    }
+
+    /// The text with a `--- PAGE N ---` line before each non-empty page, so
+    /// the LLM can say where a clause came from. Unpaged text is returned as is.
+    pub fn format_with_page_markers(&self, paged: &PagedText) -> String {
+        if paged.pages.is_empty() {
+            return paged.full_text.clone();
+        }
+        paged
+            .pages
+            .iter()
+            .enumerate()
+            .filter(|(_, page)| !page.is_empty())
+            .map(|(i, page)| format!("{}{} ---\n{}", PAGE_MARKER_PREFIX, i + 1, page))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Strip running headers and footers, then clean each page. Must see the
+    /// raw pages, since `clean_text` joins lines.
+    fn clean_pages(&self, pages: &[String]) -> PagedText {
+        let pages = self.strip_running_elements(pages);
+        PagedText::from_pages(pages.iter().map(|page| self.clean_text(page)).collect())
     }
 
-    pub async fn extract_text(&self, pdf_data: &[u8]) -> Result<String> {
+    /// Extract text and report its quality and how it was obtained. When the
+    /// text layer yields fewer than `MIN_TEXT_CHARS` the pages are OCR'd.
+    pub async fn extract_text_with_quality(
+        &self,
+        pdf_data: &[u8],
+        password: Option<&str>,
+    ) -> Result<(PagedText, ExtractionQuality, ExtractionMethod)> {
         info!("📖 Extracting text from PDF ({} bytes)", pdf_data.len());
-        
+
+        let layer = self.extract_text_layer(pdf_data, password).await;
+        let layer_chars = match &layer {
+            Ok((text, ..)) if text.full_text.len() >= MIN_TEXT_CHARS => return layer,
+            Ok((text, ..)) => text.full_text.len(),
+            Err(_) => 0,
+        };
+
+        warn!("Falling back to OCR");
+        match self.ocr(pdf_data, password).await {
+            Ok(text) if text.full_text.len() > layer_chars => {
+                let quality = ExtractionQuality::measure(&text.full_text);
+                self.print_extracted_text(&text.full_text);
+                Ok((text, quality, ExtractionMethod::Ocr))
+            }
+            Ok(_) => {
+                info!("OCR found no more text than the text layer");
+                layer
+            }
+            Err(e) if is_temp_file_timeout(&e) => Err(e),
+            Err(e) => {
+                warn!("OCR failed: {:#}", e);
+                layer
+            }
+        }
+    }
+
+    /// OCR every page of a scanned PDF with Tesseract
+    async fn ocr(&self, pdf_data: &[u8], password: Option<&str>) -> Result<PagedText> {
+        // Removed on drop, so an early return cannot leave rendered pages behind
+        let dir = tempfile::Builder::new()
+            .prefix(OCR_DIR_PREFIX)
+            .tempdir_in(self.temp_dir())
+            .with_context(|| format!("Failed to create an OCR directory in {}", self.temp_dir().display()))?;
+        let pdf_path = dir.path().join("source.pdf");
+        let pdf_data = decrypted_pdf(pdf_data, password)?;
+        match tokio::time::timeout(TEMP_FILE_TIMEOUT, tokio::fs::write(&pdf_path, &pdf_data)).await {
+            Ok(result) => result.with_context(|| format!("Failed to write {}", pdf_path.display()))?,
+            Err(_) => return Err(TempFileTimeout("writing", pdf_path).into()),
+        }
+
+        // Rendering, image cleanup and Tesseract are all CPU-bound
+        let task_dir = dir.path().to_path_buf();
+        let text = tokio::task::spawn_blocking(move || ocr_pages(&task_dir, &pdf_path))
+            .await
+            .context("OCR task panicked");
+
+        let dir_path = dir.path().to_path_buf();
+        close_temp(dir, TempDir::close, dir_path).await?;
+        let text = self.clean_pages(&text??);
+        info!("✅ OCR extracted {} characters from {} pages", text.full_text.len(), text.pages.len());
+        Ok(text)
+    }
+
+    /// Text from the PDF's text layer. Text with many replacement characters
+    /// is re-extracted with pdftotext, keeping whichever is cleaner. The PDF
+    /// is only written to disk when pdftotext is needed.
+    async fn extract_text_layer(
+        &self,
+        pdf_data: &[u8],
+        password: Option<&str>,
+    ) -> Result<(PagedText, ExtractionQuality, ExtractionMethod)> {
         // Try pdf_extract first
-        match extract_text_from_mem(pdf_data) {
-            Ok(text) => {
-                info!("✅ pdf_extract succeeded");
-                let cleaned = self.clean_text(&text);
+        let extracted = match password {
+            Some(password) => extract_text_from_mem_by_pages_encrypted(pdf_data, password),
+            None => extract_text_from_mem_by_pages(pdf_data),
+        };
+        match extracted {
+            Ok(pages) => {
+                info!("✅ pdf_extract succeeded ({} pages)", pages.len());
+                let cleaned = self.clean_pages(&pages);
+                let quality = ExtractionQuality::measure(&cleaned.full_text);
+                info!("   Unicode error ratio: {:.4}", quality.unicode_error_ratio);
+
+                if quality.is_garbled() {
+                    warn!(
+                        "Text layer looks garbled ({:.1}% replacement characters), retrying with pdftotext",
+                        quality.unicode_error_ratio * 100.0
+                    );
+                    match self.extract_with_pdftotext(pdf_data, password).await {
+                        Ok((retried, retried_quality)) if retried_quality.unicode_error_ratio < quality.unicode_error_ratio => {
+                            return Ok((retried, retried_quality, ExtractionMethod::Pdftotext));
+                        }
+                        Ok(_) => info!("pdftotext was no cleaner, keeping pdf_extract output"),
+                        Err(e) => warn!("pdftotext re-extraction failed: {}", e),
+                    }
+                } else if cleaned.full_text.len() < MIN_TEXT_CHARS {
+                    info!("pdf_extract found only {} characters, trying pdftotext", cleaned.full_text.len());
+                    match self.extract_with_pdftotext(pdf_data, password).await {
+                        Ok((retried, retried_quality)) if retried.full_text.len() > cleaned.full_text.len() => {
+                            return Ok((retried, retried_quality, ExtractionMethod::Pdftotext));
+                        }
+                        Ok(_) => {}
+                        Err(e) if is_temp_file_timeout(&e) => return Err(e),
+                        Err(e) => warn!("pdftotext re-extraction failed: {}", e),
+                    }
+                }
                 
                 // Print extracted text
-                self.print_extracted_text(&cleaned);
+                self.print_extracted_text(&cleaned.full_text);
                 
-                Ok(cleaned)
+                Ok((cleaned, quality, ExtractionMethod::PdfExtract))
             }
             Err(e) => {
                 warn!("pdf_extract failed: {}. Falling back to pdftotext", e);
-                self.extract_with_pdftotext(pdf_data).await
+                let (text, quality) = self.extract_with_pdftotext(pdf_data, password).await?;
+                Ok((text, quality, ExtractionMethod::Pdftotext))
             }
         }
     }
 
-    
-
-    async fn extract_with_pdftotext(&self, pdf_data: &[u8]) -> Result<String> {
-        let temp_path = "/tmp/temp.pdf";
-        std::fs::write(temp_path, pdf_data)?;
+    async fn extract_with_pdftotext(&self, pdf_data: &[u8], password: Option<&str>) -> Result<(PagedText, ExtractionQuality)> {
+        // Removed on drop, so an early return cannot leave the PDF behind
+        let temp_file = tempfile::Builder::new()
+            .prefix("pdftotext-")
+            .suffix(".pdf")
+            .tempfile_in(self.temp_dir())
+            .with_context(|| format!("Failed to create a temp file in {}", self.temp_dir().display()))?;
+        let temp_path = temp_file.path().to_path_buf();
+        let pdf_data = decrypted_pdf(pdf_data, password)?;
+        match tokio::time::timeout(TEMP_FILE_TIMEOUT, tokio::fs::write(&temp_path, &pdf_data)).await {
+            Ok(result) => result.with_context(|| format!("Failed to write {}", temp_path.display()))?,
+            Err(_) => return Err(TempFileTimeout("writing", temp_path).into()),
+        }
         
+        // Poppler decodes glyphs with its own font handling, which often recovers
+        // text whose embedded ToUnicode CMap is broken. `-enc UTF-8` only sets
+        // the output encoding, to match the `from_utf8_lossy` below.
         let output = Command::new("pdftotext")
-            .args(&["-layout", temp_path, "-"])
-            .output()
-            .context("pdftotext failed")?;
-        
-        std::fs::remove_file(temp_path)?;
+            .args(["-layout", "-enc", "UTF-8"])
+            .arg(&temp_path)
+            .arg("-")
+            .output();
+
+        close_temp(temp_file, tempfile::NamedTempFile::close, temp_path).await?;
+
+        let output = output.context("pdftotext failed")?;
+        if !output.status.success() {
+            // Exit code 1 with "Incorrect password" for encrypted PDFs
+            anyhow::bail!(
+                "pdftotext exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
         
+        // pdftotext ends every page with a form feed
         let text = String::from_utf8_lossy(&output.stdout).to_string();
-        let cleaned = self.clean_text(&text);
+        let pages: Vec<String> = text.strip_suffix('\x0c').unwrap_or(&text).split('\x0c').map(str::to_string).collect();
+        let cleaned = self.clean_pages(&pages);
+        let quality = ExtractionQuality::measure(&cleaned.full_text);
+        info!("   Unicode error ratio (pdftotext): {:.4}", quality.unicode_error_ratio);
         
         // Print extracted text
-        self.print_extracted_text(&cleaned);
+        self.print_extracted_text(&cleaned.full_text);
         
-        Ok(cleaned)
+        Ok((cleaned, quality))
+    }
+
+    /// Tables found by column alignment, such as payment schedules and
+    /// territory grids, in a PDF opened with `load_document`
+    pub fn extract_tables(&self, doc: &pdf_extract::Document) -> Result<Vec<Table>> {
+        let mut collector = TextRunCollector::default();
+        pdf_extract::output_doc(doc, &mut collector).map_err(|e| anyhow::anyhow!("Failed to read PDF layout: {}", e))?;
+
+        Ok(collector
+            .pages
+            .into_iter()
+            .flat_map(|(page, runs)| detect_tables(page, runs))
+            .collect())
+    }
+
+    /// Author, creator, dates and page count from the document information
+    /// dictionary of a PDF opened with `load_document`
+    pub fn extract_metadata(&self, doc: &pdf_extract::Document) -> Result<PdfDocumentMeta> {
+        let info = doc
+            .trailer
+            .get(b"Info")
+            .ok()
+            .and_then(|info| doc.dereference(info).ok())
+            .and_then(|(_, info)| info.as_dict().ok());
+        let entry = |key: &[u8]| {
+            let value = info?.get(key).ok()?;
+            let (_, value) = doc.dereference(value).ok()?;
+            let text = pdf_text_string(value.as_str().ok()?);
+            let text = text.trim();
+            (!text.is_empty()).then(|| text.to_string())
+        };
+
+        Ok(PdfDocumentMeta {
+            title: entry(b"Title"),
+            author: entry(b"Author"),
+            creator: entry(b"Creator"),
+            creation_date: entry(b"CreationDate").as_deref().and_then(parse_pdf_date),
+            modification_date: entry(b"ModDate").as_deref().and_then(parse_pdf_date),
+            page_count: doc.get_pages().len() as u32,
+        })
+    }
+
+    fn print_tables(&self, tables: &[Table]) {
+        info!("📊 Found {} tables", tables.len());
+        for table in tables {
+            info!("Page {} ({} rows):", table.page, table.rows.len());
+            for line in table.to_markdown().lines() {
+                info!("{}", line);
+            }
+        }
+    }
+
+    /// `extract_tables`, logging the result. Failures only cost the tables.
+    pub fn extract_and_print_tables(&self, doc: &pdf_extract::Document) -> Vec<Table> {
+        match self.extract_tables(doc) {
+            Ok(tables) => {
+                if !tables.is_empty() {
+                    self.print_tables(&tables);
+                }
+                tables
+            }
+            Err(e) => {
+                warn!("Table detection failed: {:#}", e);
+                Vec::new()
+            }
+        }
     }
 
     fn print_extracted_text(&self, text: &str) {
     info!("📄 ========== EXTRACTED TEXT ==========");
-    info!("Length: {} characters", text.len());
+    info!("Length: {} characters", text.chars().count());
     info!("Length: {} words", text.split_whitespace().count());
     info!("");
-    
-    // SAVE FULL TEXT TO FILE
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-    let filename = format!("/tmp/extracted_text_{}.txt", timestamp);
-    
-    if let Err(e) = std::fs::write(&filename, text) {
-        warn!("Failed to save extracted text: {}", e);
-    } else {
-        info!("💾 Full text saved to: {}", filename);
-    }
-    
-    // Print preview
-    info!("First 500 characters:");
-    info!("{}", &text[..500.min(text.len())]);
+
+    // Print preview. Contract text is never written to disk.
+    let (head, tail) = text_preview(text, TEXT_PREVIEW_CHARS);
+    info!("First {} characters:", TEXT_PREVIEW_CHARS);
+    info!("{}", head);
     info!("");
     info!("...");
     info!("");
-    info!("Last 500 characters:");
-    let start = text.len().saturating_sub(500);
-    info!("{}", &text[start..]);
+    info!("Last {} characters:", TEXT_PREVIEW_CHARS);
+    info!("{}", tail);
     info!("📄 ====================================");
 }
 
-    fn clean_text(&self, text: &str) -> String {
-        text
-            // Remove excessive whitespace
-            .split_whitespace()
-            .collect::<Vec<&str>>()
-            .join(" ")
-            // Remove control characters
-            .chars()
-            .filter(|c| !c.is_control() || *c == '\n')
-            .collect::<String>()
-            // Normalize line breaks
-            .replace("\n\n\n", "\n\n")
-            .trim()
-            .to_string()
-    }
-
-    pub fn extract_sections(&self, text: &str) -> Vec<(String, String)> {
-        // Extract key sections from agreement
-        let mut sections = Vec::new();
-
-        // Common section headers in rights agreements
-        let headers = vec![
-            "PARTIES",
-            "TERRITORY",
-            "MEDIA RIGHTS",
-            "TERM",
-            "FINANCIAL TERMS",
-            "PAYMENT",
-            "DELIVERABLES",
-            "WARRANTIES",
-            "INDEMNIFICATION",
-            "GOVERNING LAW",
-        ];
+    /// Collapse whitespace, drop control characters and normalize line breaks.
+    /// Each step runs in its own trace span recording input and output sizes.
+    pub fn clean_text(&self, text: &str) -> String {
+        // Remove excessive whitespace
+        let joined = {
+            let span = trace_span!("split_whitespace", input_bytes = text.len(), output_bytes = tracing::field::Empty);
+            let _guard = span.enter();
+            let joined = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+            span.record("output_bytes", joined.len());
+            joined
+        };
+
+        // Remove control characters, reserving the output up front instead
+        // of letting collect() grow it repeatedly on long documents
+        let filtered = {
+            let span = trace_span!("filter_control_chars", input_bytes = joined.len(), output_bytes = tracing::field::Empty);
+            let _guard = span.enter();
+            let mut filtered = String::with_capacity(joined.len());
+            filtered.extend(joined.chars().filter(|c| !c.is_control() || *c == '\n'));
+            span.record("output_bytes", filtered.len());
+            filtered
+        };
+
+        // Normalize line breaks
+        let span = trace_span!("replace_line_breaks", input_bytes = filtered.len(), output_bytes = tracing::field::Empty);
+        let _guard = span.enter();
+        let normalized = filtered.replace("\n\n\n", "\n\n").trim().to_string();
+        span.record("output_bytes", normalized.len());
+        normalized
+    }
+
+    /// Split a (possibly bilingual) document into monolingual blocks,
+    /// linking blocks that translate the same section
+    pub fn detect_language_sections(&self, text: &str) -> Vec<LanguageSection> {
+        let mut sections: Vec<LanguageSection> = Vec::new();
+        // Headings and numbering are too short to classify, so they join the next sentence
+        let mut pending = String::new();
+
+        for sentence in split_sentences(text) {
+            let language = Self::detect_language(sentence);
+            if language == DetectedLanguage::Unknown && sentence.split_whitespace().count() < 4 {
+                pending.push_str(sentence);
+                pending.push(' ');
+                continue;
+            }
+
+            // A recognised heading always opens a new section
+            let starts_section = Self::detect_topic(&pending).is_some();
+            let sentence = format!("{}{}", std::mem::take(&mut pending), sentence);
+            match sections.last_mut() {
+                Some(current) if current.language == language && !starts_section => {
+                    current.text.push(' ');
+                    current.text.push_str(&sentence);
+                }
+                _ => sections.push(LanguageSection {
+                    language,
+                    topic: None,
+                    text: sentence,
+                    parallel_of: None,
+                }),
+            }
+        }
+
+        if !pending.is_empty() {
+            match sections.last_mut() {
+                Some(current) => {
+                    current.text.push(' ');
+                    current.text.push_str(pending.trim_end());
+                }
+                None => sections.push(LanguageSection {
+                    language: DetectedLanguage::Unknown,
+                    topic: None,
+                    text: pending.trim_end().to_string(),
+                    parallel_of: None,
+                }),
+            }
+        }
 
-        for header in headers {
-            if let Some(section_text) = self.find_section(text, header) {
-                sections.push((header.to_string(), section_text));
+        for section in sections.iter_mut() {
+            section.topic = Self::detect_topic(&section.text);
+        }
+
+        // Link each block to the first unpaired block covering the same topic
+        for i in 0..sections.len() {
+            if sections[i].parallel_of.is_some() || sections[i].topic.is_none() {
+                continue;
+            }
+            let counterpart = (0..sections.len()).find(|&j| {
+                j != i
+                    && sections[j].parallel_of.is_none()
+                    && sections[j].topic == sections[i].topic
+                    && sections[j].language != sections[i].language
+            });
+            if let Some(j) = counterpart {
+                sections[i].parallel_of = Some(j);
+                sections[j].parallel_of = Some(i);
             }
         }
 
         sections
     }
 
-    fn find_section(&self, text: &str, header: &str) -> Option<String> {
-        // Simple section extraction logic
-        // In production, use more sophisticated parsing
-        let lines: Vec<&str> = text.lines().collect();
-        
-        for (i, line) in lines.iter().enumerate() {
-            if line.to_uppercase().contains(header) {
-                // Get next 10 lines as section content
-                let end = (i + 10).min(lines.len());
-                let section_content = lines[i+1..end].join("\n");
-                return Some(section_content);
+    /// Text to send to the LLM: English where available, with other-language
+    /// blocks kept only when they have no English translation
+    pub fn select_llm_text(&self, text: &str) -> String {
+        let sections = self.detect_language_sections(text);
+
+        let languages: Vec<DetectedLanguage> = sections.iter().map(|s| s.language).collect();
+        if !languages.iter().any(|l| *l != languages[0]) {
+            return text.to_string();
+        }
+
+        let kept: Vec<&str> = sections
+            .iter()
+            .filter(|s| {
+                s.language == DetectedLanguage::English
+                    || s.parallel_of
+                        .map(|j| sections[j].language != DetectedLanguage::English)
+                        .unwrap_or(true)
+            })
+            .map(|s| s.text.as_str())
+            .collect();
+
+        info!(
+            "🌐 Multilingual document: {} language blocks, keeping {} for LLM",
+            sections.len(),
+            kept.len()
+        );
+
+        kept.join("\n")
+    }
+
+    fn detect_language(sentence: &str) -> DetectedLanguage {
+        let letters = sentence.chars().filter(|c| c.is_alphabetic()).count();
+        if letters == 0 {
+            return DetectedLanguage::Unknown;
+        }
+
+        let devanagari = sentence
+            .chars()
+            .filter(|c| ('\u{0900}'..='\u{097F}').contains(c))
+            .count();
+        if devanagari * 10 > letters * 3 {
+            return DetectedLanguage::Hindi;
+        }
+
+        let words: Vec<String> = sentence
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphabetic()).to_lowercase())
+            .collect();
+        let english = words.iter().filter(|w| ENGLISH_STOPWORDS.contains(&w.as_str())).count();
+        let french = words.iter().filter(|w| FRENCH_STOPWORDS.contains(&w.as_str())).count()
+            + sentence.chars().filter(|c| "éèêàçùôî".contains(*c)).count();
+
+        match english.cmp(&french) {
+            std::cmp::Ordering::Greater => DetectedLanguage::English,
+            std::cmp::Ordering::Less => DetectedLanguage::French,
+            std::cmp::Ordering::Equal => DetectedLanguage::Unknown,
+        }
+    }
+
+    fn detect_topic(text: &str) -> Option<String> {
+        let words: Vec<String> = text
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_uppercase())
+            .collect();
+
+        SECTION_TOPICS
+            .iter()
+            .find(|(_, headings)| words.iter().any(|w| headings.contains(&w.as_str())))
+            .map(|(topic, _)| topic.to_string())
+    }
+}
+
+/// Load a PDF once for `extract_tables` and `extract_metadata`, decrypting
+/// it if only an owner password is set. PDFs with a user password fail.
+pub fn load_document(pdf_data: &[u8]) -> Result<pdf_extract::Document> {
+    let mut doc = pdf_extract::Document::load_mem(pdf_data).context("Failed to load PDF")?;
+    if doc.is_encrypted() {
+        doc.decrypt("").map_err(|e| anyhow::anyhow!("PDF needs a password: {}", e))?;
+    }
+    Ok(doc)
+}
+
+/// The PDF to hand to poppler: decrypted here when a password is given, since
+/// passing it with `-upw` would show it to anyone who can list processes
+fn decrypted_pdf<'a>(pdf_data: &'a [u8], password: Option<&str>) -> Result<Cow<'a, [u8]>> {
+    let Some(password) = password else {
+        return Ok(Cow::Borrowed(pdf_data));
+    };
+    let mut doc = pdf_extract::Document::load_mem(pdf_data).context("Failed to load PDF")?;
+    if doc.is_encrypted() {
+        doc.decrypt(password).map_err(|e| anyhow::anyhow!("Failed to decrypt PDF: {}", e))?;
+    }
+    let mut decrypted = Vec::new();
+    doc.save_to(&mut decrypted).context("Failed to write decrypted PDF")?;
+    Ok(Cow::Owned(decrypted))
+}
+
+/// Decode a PDF text string: UTF-16BE or UTF-8 with a byte order mark,
+/// otherwise PDFDocEncoding, which matches Latin-1 for printable text
+fn pdf_text_string(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// Parse a PDF date such as `D:20240801103000+05'30'`. Everything after the
+/// year is optional; a missing offset is taken as UTC.
+fn parse_pdf_date(raw: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    use chrono::TimeZone;
+
+    let date = raw.trim().trim_start_matches("D:");
+    let digits = date.bytes().take_while(u8::is_ascii_digit).count();
+    let field = |start: usize, default: u32| {
+        date.get(start..start + 2)
+            .filter(|_| start + 2 <= digits)
+            .and_then(|d| d.parse().ok())
+            .unwrap_or(default)
+    };
+    let year = date.get(..4).filter(|_| digits >= 4)?.parse().ok()?;
+    let local = chrono::NaiveDate::from_ymd_opt(year, field(4, 1), field(6, 1))?
+        .and_hms_opt(field(8, 0), field(10, 0), field(12, 0))?;
+
+    let zone = &date[digits..];
+    let offset_seconds = match zone.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let zone_digits: String = zone.chars().filter(char::is_ascii_digit).collect();
+            let hours: i32 = zone_digits.get(..2)?.parse().ok()?;
+            let minutes: i32 = zone_digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+            let seconds = hours * 3600 + minutes * 60;
+            if sign == '-' { -seconds } else { seconds }
+        }
+        _ => 0,
+    };
+    chrono::FixedOffset::east_opt(offset_seconds)?
+        .from_local_datetime(&local)
+        .single()
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+}
+
+#[async_trait]
+impl TextExtractor for PDFExtractor {
+    async fn extract_text(&self, data: &[u8], password: Option<&str>) -> Result<String> {
+        PDFExtractor::extract_text(self, data, password).await.map(|text| text.full_text)
+    }
+}
+
+/// Rows of cells found by column alignment, header row first
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub page: u32,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// GitHub-flavoured markdown, the first row as the header
+    pub fn to_markdown(&self) -> String {
+        let row = |cells: &[String]| {
+            let cells: Vec<String> = cells.iter().map(|c| c.replace('|', "\\|")).collect();
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let mut lines = Vec::with_capacity(self.rows.len() + 1);
+        if let Some((header, body)) = self.rows.split_first() {
+            lines.push(row(header));
+            lines.push(format!("|{}", " --- |".repeat(header.len())));
+            lines.extend(body.iter().map(|cells| row(cells)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Characters drawn close together on one line, in points from the top left
+#[derive(Debug, Clone)]
+struct TextRun {
+    x: f64,
+    end: f64,
+    y: f64,
+    font_size: f64,
+    text: String,
+}
+
+/// `OutputDev` recording where text sits on each page, for table detection
+#[derive(Default)]
+struct TextRunCollector {
+    pages: Vec<(u32, Vec<TextRun>)>,
+    page_height: f64,
+}
+
+impl pdf_extract::OutputDev for TextRunCollector {
+    fn begin_page(
+        &mut self,
+        page_num: u32,
+        media_box: &pdf_extract::MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), pdf_extract::OutputError> {
+        self.page_height = media_box.ury - media_box.lly;
+        self.pages.push((page_num, Vec::new()));
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &pdf_extract::Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        char: &str,
+    ) -> Result<(), pdf_extract::OutputError> {
+        let Some((_, runs)) = self.pages.last_mut() else {
+            return Ok(());
+        };
+        let font_size = font_size * (trm.m11 * trm.m22).abs().sqrt();
+        let (x, y) = (trm.m31, self.page_height - trm.m32);
+        let end = x + width * font_size;
+
+        match runs.last_mut() {
+            Some(run)
+                if (y - run.y).abs() < run.font_size * 0.5
+                    && x > run.end - run.font_size * 0.5
+                    && x - run.end < run.font_size * CELL_GAP_EMS =>
+            {
+                if x - run.end > run.font_size * 0.1 && !run.text.ends_with(' ') {
+                    run.text.push(' ');
+                }
+                run.text.push_str(char);
+                run.end = end;
+            }
+            _ => runs.push(TextRun { x, end, y, font_size, text: char.to_string() }),
+        }
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), pdf_extract::OutputError> {
+        Ok(())
+    }
+}
+
+/// Group a page's runs into lines, then report consecutive lines whose cells
+/// line up column for column. Numeric columns are often right-aligned, so
+/// either edge lining up counts.
+fn detect_tables(page: u32, mut runs: Vec<TextRun>) -> Vec<Table> {
+    runs.retain(|run| !run.text.trim().is_empty());
+    runs.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+    let mut lines: Vec<Vec<TextRun>> = Vec::new();
+    for run in runs {
+        match lines.last_mut() {
+            Some(line) if (run.y - line[0].y).abs() < line[0].font_size * 0.5 => line.push(run),
+            _ => lines.push(vec![run]),
+        }
+    }
+    for line in &mut lines {
+        line.sort_by(|a, b| a.x.total_cmp(&b.x));
+    }
+
+    let aligned = |a: &[TextRun], b: &[TextRun]| {
+        a.len() == b.len()
+            && a.iter().zip(b).all(|(a, b)| {
+                (a.x - b.x).abs() < COLUMN_TOLERANCE || (a.end - b.end).abs() < COLUMN_TOLERANCE
+            })
+    };
+
+    let mut tables = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let mut end = start + 1;
+        if lines[start].len() >= 2 {
+            while end < lines.len() && aligned(&lines[start], &lines[end]) {
+                end += 1;
+            }
+        }
+        if end - start >= MIN_TABLE_ROWS {
+            tables.push(Table {
+                page,
+                rows: lines[start..end]
+                    .iter()
+                    .map(|line| line.iter().map(|run| run.text.trim().to_string()).collect())
+                    .collect(),
+            });
+        }
+        start = end;
+    }
+    tables
+}
+
+/// Split on sentence terminators, including the Devanagari danda
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        if matches!(c, '.' | '!' | '?' | '।') {
+            let end = i + c.len_utf8();
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence);
             }
+            start = end;
         }
+    }
+
+    let rest = text[start..].trim();
+    if !rest.is_empty() {
+        sentences.push(rest);
+    }
+
+    sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BILINGUAL: &str = "TERRITORY. The licensee shall have the rights in the territory of India. \
+        TERRITOIRE. Le licencié aura les droits dans le territoire de l'Inde. \
+        PAIEMENT. Le paiement sera effectué par virement dans les trente jours.";
+
+    #[test]
+    fn test_unicode_error_ratio() {
+        let clean = ExtractionQuality::measure("The licensee shall pay");
+        assert_eq!(clean.unicode_error_ratio, 0.0);
+        assert!(!clean.is_garbled());
+
+        let garbled = ExtractionQuality::measure("a\u{FFFD}cd e\u{FFFD}ghij");
+        assert_eq!(garbled.unicode_error_ratio, 0.2);
+        assert!(garbled.is_garbled());
+        assert_eq!(ExtractionQuality::measure("  ").unicode_error_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_strip_running_elements() {
+        let clauses = ["Grant of rights.", "Territory: India.", "Payment terms.", "Governing law."];
+        let pages: Vec<String> = clauses
+            .iter()
+            .enumerate()
+            .map(|(i, clause)| format!("CONFIDENTIAL DRAFT\n{}\nPage {} of 4", clause, i + 1))
+            .collect();
+        let extractor = PDFExtractor::default();
+
+        let stripped = extractor.strip_running_elements(&pages);
+        assert_eq!(stripped, clauses.map(String::from).to_vec());
+
+        // Two pages are too few to call anything a running element
+        assert_eq!(extractor.strip_running_elements(&pages[..2]), pages[..2].to_vec());
+        // No line can appear on more than every page
+        let strict = PDFExtractor::default().with_running_element_threshold(1.0);
+        assert_eq!(strict.strip_running_elements(&pages), pages);
+
+        // Lines differing only in their amounts are content, not running elements
+        let amounts: Vec<String> = (1..=4)
+            .map(|i| format!("CONFIDENTIAL DRAFT\nInstalment {}: Rs. {},00,000\n- {} -", i, i * 5, i))
+            .collect();
+        let stripped = extractor.strip_running_elements(&amounts);
+        assert_eq!(stripped[2], "Instalment 3: Rs. 15,00,000");
+        assert_eq!(running_element_key("Page 2 of 9"), running_element_key("Page 10 of 12"));
+    }
+
+    #[test]
+    fn test_extract_sections_within_pages() {
+        let text = "--- PAGE 1 ---\nCONTENTS PARTIES TERM TERRITORY\n\n\
+            --- PAGE 2 ---\n1. PARTIES Vyjayanthi Movies and Netflix India, together the parties. \
+            Capitalised TERMS are defined below and the arbitrator shall DETERMINE disputes. \
+            2. TERM Five years from the Effective Date. 3. TERMINATION Either party may end this on breach.\n\n\
+            --- PAGE 3 ---\n4. TERRITORY India, Nepal and Sri Lanka only.";
+        let sections = extract_sections(text);
+
+        let headers: Vec<_> = sections.iter().map(|s| (s.header.as_str(), s.page)).collect();
+        assert_eq!(headers, vec![("PARTIES", Some(2)), ("TERM", Some(2)), ("TERRITORY", Some(3))]);
+        assert!(sections[0].content.ends_with("shall DETERMINE disputes. 2."));
+        assert!(sections[1].content.contains("3. TERMINATION Either party"));
+        assert_eq!(sections[2].content, "India, Nepal and Sri Lanka only.");
+        assert_eq!(extract_sections("PARTIES Vyjayanthi Movies and Netflix India")[0].page, None);
+    }
+
+    #[test]
+    fn test_page_markers_skip_empty_pages() {
+        let extractor = PDFExtractor::default();
+        let pages = ["LICENSE  AGREEMENT\n", "", "Payment:\n 5,00,000"].map(String::from);
+        let paged = extractor.clean_pages(&pages);
+        assert_eq!(paged.full_text, "LICENSE AGREEMENT\n\nPayment: 5,00,000");
+        assert_eq!(
+            extractor.format_with_page_markers(&paged),
+            "--- PAGE 1 ---\nLICENSE AGREEMENT\n\n--- PAGE 3 ---\nPayment: 5,00,000"
+        );
+        assert_eq!(extractor.format_with_page_markers(&PagedText::unpaged("Direct".to_string())), "Direct");
+    }
+
+    #[test]
+    fn test_rendered_pages_in_page_order() {
+        let dir = std::env::temp_dir().join(format!("pdf_extractor_pages_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["page-10.png", "page-02.png", "source.pdf", "page-01.png"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+
+        let pages: Vec<String> = rendered_pages(&dir)
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(pages, vec!["page-01.png", "page-02.png", "page-10.png"]);
+        assert_eq!(ExtractionMethod::Ocr.as_str(), "ocr");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_detect_tables_by_column_alignment() {
+        let run = |x: f64, y: f64, text: &str| TextRun {
+            x,
+            end: x + text.len() as f64 * 5.0,
+            y,
+            font_size: 10.0,
+            text: text.to_string(),
+        };
+        let runs = vec![
+            run(72.0, 90.0, "The Licensee shall pay as follows:"),
+            run(72.0, 100.0, "Milestone"),
+            run(300.0, 100.0, "Amount"),
+            run(72.0, 112.0, "Signing"),
+            run(300.0, 112.0, "5,00,000"),
+            run(72.0, 124.0, "Delivery | final"),
+            run(300.0, 124.0, "5,00,000"),
+            run(72.0, 160.0, "Licensor"),
+            run(250.0, 160.0, "Licensee"),
+        ];
+
+        let tables = detect_tables(3, runs);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].rows[1], vec!["Signing", "5,00,000"]);
+        assert_eq!(
+            tables[0].to_markdown(),
+            "| Milestone | Amount |\n| --- | --- |\n| Signing | 5,00,000 |\n| Delivery \\| final | 5,00,000 |"
+        );
+    }
+
+    #[test]
+    fn test_parse_pdf_metadata_values() {
+        let ist = parse_pdf_date("D:20240801103000+05'30'").unwrap();
+        assert_eq!(ist.to_rfc3339(), "2024-08-01T05:00:00+00:00");
+        assert_eq!(parse_pdf_date("D:2024").unwrap().to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert_eq!(parse_pdf_date("D:20240801120000Z").unwrap().to_rfc3339(), "2024-08-01T12:00:00+00:00");
+        assert!(parse_pdf_date("August 2024").is_none());
+
+        assert_eq!(pdf_text_string(b"Vyjayanthi Movies"), "Vyjayanthi Movies");
+        assert_eq!(pdf_text_string(&[0xFE, 0xFF, 0x00, 0x52, 0x00, 0xE9]), "Ré");
+    }
+
+    #[test]
+    fn test_temp_file_timeout_is_recognised() {
+        let timeout = anyhow::Error::from(TempFileTimeout("writing", PathBuf::from("/tmp/x.pdf"))).context("pdftotext failed");
+        assert!(is_temp_file_timeout(&timeout));
+        assert!(!is_temp_file_timeout(&anyhow::anyhow!("Timed out writing /tmp/x.pdf")));
+        // Without a password the PDF is passed on untouched
+        assert!(matches!(decrypted_pdf(b"%PDF-1.4", None).unwrap(), Cow::Borrowed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_close_temp_removes_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        std::fs::write(path.join("page-1.png"), b"x").unwrap();
+
+        close_temp(dir, TempDir::close, path.clone()).await.unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_text_preview_splits_on_characters() {
+        let text = "अनुबंध ".repeat(200);
+        let (head, tail) = text_preview(&text, 500);
+        assert_eq!(head.chars().count(), 500);
+        assert_eq!(tail.chars().count(), 500);
+
+        assert_eq!(text_preview("short", 500), ("short", "short"));
+        assert_eq!(text_preview("short", 0), ("", ""));
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_old_temp_files() {
+        let base = tempfile::tempdir().unwrap();
+        // Another program's PDF next to the extractor's directory
+        std::fs::write(base.path().join("other.pdf"), b"x").unwrap();
+        let extractor = PDFExtractor::with_temp_dir(base.path()).unwrap();
+        let dir = extractor.temp_dir().to_path_buf();
+        for name in ["pdftotext-1.pdf", "pdftotext-2.pdf", "notes.txt"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+        // An interrupted OCR run leaves its rendered pages behind
+        for sub in ["ocr-1", "fonts"] {
+            std::fs::create_dir(dir.join(sub)).unwrap();
+            std::fs::write(dir.join(sub).join("page-1.png"), b"x").unwrap();
+        }
+
+        assert_eq!(extractor.cleanup_old_temp_files(3600).await.unwrap(), 0);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(extractor.cleanup_old_temp_files(0).await.unwrap(), 3);
+        assert!(dir.join("notes.txt").exists());
+        assert!(!dir.join("ocr-1").exists());
+        assert!(dir.join("fonts").exists());
+        assert!(base.path().join("other.pdf").exists());
+
+        drop(extractor);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_detects_parallel_translations() {
+        let extractor = PDFExtractor::new();
+        let sections = extractor.detect_language_sections(BILINGUAL);
+
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].language, DetectedLanguage::English);
+        assert_eq!(sections[1].language, DetectedLanguage::French);
+        assert_eq!(sections[2].topic.as_deref(), Some("PAYMENT"));
+        assert_eq!(sections[2].parallel_of, None);
+        assert_eq!(sections[0].topic.as_deref(), Some("TERRITORY"));
+        assert_eq!(sections[0].parallel_of, Some(1));
+        assert_eq!(sections[1].parallel_of, Some(0));
+    }
+
+    #[test]
+    fn test_select_llm_text_prefers_english() {
+        let extractor = PDFExtractor::new();
+        let text = extractor.select_llm_text(BILINGUAL);
+
+        assert!(text.contains("The licensee shall"));
+        assert!(!text.contains("Le licencié"));
+        // No English payment section, so the French one is kept
+        assert!(text.contains("Le paiement"));
+        // Kept blocks stay on separate lines
+        assert_eq!(text.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_hindi_only_document_is_unchanged() {
+        let extractor = PDFExtractor::new();
+        let text = "यह अनुबंध भारत में लागू होगा। भुगतान तीस दिनों में किया जाएगा।";
 
-        None
+        assert_eq!(extractor.select_llm_text(text), text);
     }
 }
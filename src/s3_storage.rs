@@ -0,0 +1,119 @@
+// src/s3_storage.rs - Presigned S3 URLs for large PDF uploads
+use anyhow::{Context, Result};
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::presigning::PresigningConfig;
+use std::time::Duration;
+use tracing::info;
+use uuid::Uuid;
+
+/// How long presigned upload and download URLs stay valid
+pub const PRESIGNED_URL_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Prefix under which clients may upload agreements
+pub const UPLOAD_PREFIX: &str = "uploads/";
+
+/// S3 settings read from `AWS_BUCKET`, `AWS_REGION`, `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+pub struct S3Storage {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+}
+
+impl S3Storage {
+    pub fn new(config: S3Config) -> Self {
+        info!("Initializing S3 storage (bucket: {}, region: {})", config.bucket, config.region);
+
+        let credentials = Credentials::new(
+            config.access_key_id,
+            config.secret_access_key,
+            None,
+            None,
+            "rights-parser-env",
+        );
+        let sdk_config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new(config.region))
+            .credentials_provider(credentials)
+            .build();
+
+        Self {
+            client: aws_sdk_s3::Client::from_conf(sdk_config),
+            bucket: config.bucket,
+        }
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// A fresh, unguessable object key for one upload
+    pub fn new_upload_key() -> String {
+        format!("{}{}.pdf", UPLOAD_PREFIX, Uuid::new_v4())
+    }
+
+    /// Whether `key` is one this service hands out, so requests cannot reach
+    /// arbitrary objects in the bucket
+    pub fn is_upload_key(key: &str) -> bool {
+        key.strip_prefix(UPLOAD_PREFIX)
+            .and_then(|name| name.strip_suffix(".pdf"))
+            .map_or(false, |id| Uuid::parse_str(id).is_ok())
+    }
+
+    /// Presigned PUT URL the client uploads the PDF to
+    pub async fn presigned_upload_url(&self, key: &str) -> Result<String> {
+        let request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type("application/pdf")
+            .presigned(PresigningConfig::expires_in(PRESIGNED_URL_TTL)?)
+            .await
+            .context("Failed to presign S3 upload")?;
+        Ok(request.uri().to_string())
+    }
+
+    /// Presigned GET URL used to read an uploaded PDF
+    pub async fn presigned_download_url(&self, key: &str) -> Result<String> {
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .presigned(PresigningConfig::expires_in(PRESIGNED_URL_TTL)?)
+            .await
+            .context("Failed to presign S3 download")?;
+        Ok(request.uri().to_string())
+    }
+
+    /// Size of an uploaded object, or `None` if it does not exist yet
+    pub async fn object_size(&self, key: &str) -> Result<Option<i64>> {
+        match self.client.head_object().bucket(&self.bucket).key(key).send().await {
+            Ok(head) => Ok(Some(head.content_length().unwrap_or(0))),
+            Err(e) if e.as_service_error().map_or(false, |s| s.is_not_found()) => Ok(None),
+            Err(e) => Err(anyhow::anyhow!("S3 HEAD failed: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upload_keys() {
+        let key = S3Storage::new_upload_key();
+        assert!(S3Storage::is_upload_key(&key));
+
+        assert!(!S3Storage::is_upload_key("uploads/../secrets.pdf"));
+        assert!(!S3Storage::is_upload_key("backups/db.pdf"));
+        assert!(!S3Storage::is_upload_key(key.trim_end_matches(".pdf")));
+    }
+}
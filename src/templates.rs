@@ -0,0 +1,170 @@
+// src/templates.rs - Supported agreement types and their prompts
+use serde::Serialize;
+
+/// An agreement type the parser is tuned for
+#[derive(Debug, Clone, Serialize)]
+pub struct AgreementTemplate {
+    pub name: String,
+    pub description: String,
+    pub content_categories: Vec<String>,
+    pub example_fields: Vec<String>,
+    pub prompt_template_name: String,
+}
+
+/// Instructions placed before the contract text for one agreement type
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub prefix: String,
+}
+
+/// Model settings used for one agreement type
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModelConfig {
+    /// Ollama model to use instead of the default
+    pub model: Option<String>,
+}
+
+pub struct TemplateRegistry {
+    templates: Vec<AgreementTemplate>,
+    prompts: Vec<PromptTemplate>,
+    models: Vec<(String, ModelConfig)>,
+}
+
+/// Name of the template used when detection fails
+pub const DEFAULT_TEMPLATE: &str = "film";
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+impl TemplateRegistry {
+    pub fn new() -> Self {
+        let templates = vec![
+            AgreementTemplate {
+                name: "film".to_string(),
+                description: "Film and series licensing: theatrical, broadcast and streaming rights".to_string(),
+                content_categories: strings(&["Feature Film", "Web Series", "Documentary", "TV Series"]),
+                example_fields: strings(&["content.title", "rights.territories", "rights.mediaTypes", "financial.dealValue"]),
+                prompt_template_name: "film_rights".to_string(),
+            },
+            AgreementTemplate {
+                name: "music".to_string(),
+                description: "Music licensing: sync, mechanical, performance and master use".to_string(),
+                content_categories: strings(&["Soundtrack", "Album", "Single", "Background Score"]),
+                example_fields: strings(&["content.title", "rights.mediaTypes", "financial.paymentStructure", "rights.term"]),
+                prompt_template_name: "music_rights".to_string(),
+            },
+            AgreementTemplate {
+                name: "publishing".to_string(),
+                description: "Book and print publishing, translation and adaptation rights".to_string(),
+                content_categories: strings(&["Novel", "Non-Fiction", "Comic", "Screenplay"]),
+                example_fields: strings(&["content.title", "rights.languages", "rights.territories", "financial.paymentStructure"]),
+                prompt_template_name: "publishing_rights".to_string(),
+            },
+            AgreementTemplate {
+                name: "software".to_string(),
+                description: "Software and game licensing, distribution and white-label rights".to_string(),
+                content_categories: strings(&["Game", "Application", "SDK", "Platform"]),
+                example_fields: strings(&["content.title", "rights.territories", "rights.exclusivity", "financial.dealValue"]),
+                prompt_template_name: "software_rights".to_string(),
+            },
+        ];
+
+        let prompts = vec![
+            PromptTemplate {
+                name: "film_rights".to_string(),
+                prefix: "This is a film or series licensing agreement. Pay close attention to media windows \
+                         (theatrical, SVOD, satellite), holdbacks, territories and the license term."
+                    .to_string(),
+            },
+            PromptTemplate {
+                name: "music_rights".to_string(),
+                prefix: "This is a music licensing agreement. Identify the licensed works, whether sync, \
+                         mechanical, performance or master rights are granted, and any royalty split."
+                    .to_string(),
+            },
+            PromptTemplate {
+                name: "publishing_rights".to_string(),
+                prefix: "This is a publishing agreement. Identify the work, licensed languages and formats, \
+                         advance and royalty rates, and any adaptation or translation rights."
+                    .to_string(),
+            },
+            PromptTemplate {
+                name: "software_rights".to_string(),
+                prefix: "This is a software licensing agreement. Identify the licensed product, permitted \
+                         platforms and distribution channels, license fees and any source code terms."
+                    .to_string(),
+            },
+        ];
+
+        Self {
+            templates,
+            prompts,
+            models: Vec::new(),
+        }
+    }
+
+    /// Route agreement types to specific models, from `music=llama3.1:8b,software=qwen2.5`
+    pub fn with_model_overrides(mut self, spec: &str) -> Self {
+        for (name, model) in spec.split(',').filter_map(|pair| pair.split_once('=')) {
+            let (name, model) = (name.trim().to_lowercase(), model.trim());
+            if self.get(&name).is_some() && !model.is_empty() {
+                self.models.push((name, ModelConfig { model: Some(model.to_string()) }));
+            }
+        }
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.templates.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    pub fn templates(&self) -> &[AgreementTemplate] {
+        &self.templates
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AgreementTemplate> {
+        self.templates.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Prompt used for a template
+    pub fn prompt_for(&self, template: &AgreementTemplate) -> Option<&PromptTemplate> {
+        self.prompts.iter().find(|p| p.name == template.prompt_template_name)
+    }
+
+    /// Model settings for a template, or the defaults if none are configured
+    pub fn model_for(&self, template: &AgreementTemplate) -> ModelConfig {
+        self.models
+            .iter()
+            .find(|(name, _)| *name == template.name)
+            .map(|(_, config)| config.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_template_has_a_prompt() {
+        let registry = TemplateRegistry::new();
+        for template in registry.templates() {
+            assert!(registry.prompt_for(template).is_some(), "{} has no prompt", template.name);
+        }
+        assert!(registry.get("Music").is_some());
+        assert!(registry.get("unknown").is_none());
+    }
+
+    #[test]
+    fn test_model_overrides() {
+        let registry = TemplateRegistry::new().with_model_overrides("music=llama3.1:8b, poetry=x,software=");
+        let music = registry.get("music").unwrap();
+        let film = registry.get("film").unwrap();
+
+        assert_eq!(registry.model_for(music).model.as_deref(), Some("llama3.1:8b"));
+        assert!(registry.model_for(film).model.is_none());
+        assert!(registry.model_for(registry.get("software").unwrap()).model.is_none());
+    }
+}
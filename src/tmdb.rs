@@ -0,0 +1,264 @@
+// src/tmdb.rs - Fill in missing content metadata from The Movie Database
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::info;
+
+const TMDB_API_URL: &str = "https://api.themoviedb.org/3";
+
+/// Value recorded in `metadata.enrichmentSource`
+pub const ENRICHMENT_SOURCE: &str = "tmdb";
+
+/// Content details found on TMDb for an agreement's title
+#[derive(Debug, Clone, Default)]
+pub struct TmdbMovie {
+    pub original_title: Option<String>,
+    pub release_date: Option<String>,
+    pub director: Option<String>,
+    pub genres: Vec<String>,
+    pub runtime_minutes: Option<u32>,
+    /// India (CBFC) certification
+    pub cbfc_rating: Option<String>,
+    /// US (MPAA) certification
+    pub mpaa_rating: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    results: Vec<SearchResult>,
+}
+
+#[derive(Deserialize)]
+struct SearchResult {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct MovieDetails {
+    original_title: Option<String>,
+    release_date: Option<String>,
+    runtime: Option<u32>,
+    #[serde(default)]
+    genres: Vec<Genre>,
+    credits: Option<Credits>,
+    release_dates: Option<ReleaseDates>,
+}
+
+#[derive(Deserialize)]
+struct Genre {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Credits {
+    #[serde(default)]
+    crew: Vec<CrewMember>,
+}
+
+#[derive(Deserialize)]
+struct CrewMember {
+    name: String,
+    job: String,
+}
+
+#[derive(Deserialize)]
+struct ReleaseDates {
+    #[serde(default)]
+    results: Vec<CountryReleases>,
+}
+
+#[derive(Deserialize)]
+struct CountryReleases {
+    iso_3166_1: String,
+    #[serde(default)]
+    release_dates: Vec<Certification>,
+}
+
+#[derive(Deserialize)]
+struct Certification {
+    #[serde(default)]
+    certification: String,
+}
+
+impl ReleaseDates {
+    fn certification(&self, country: &str) -> Option<String> {
+        self.results
+            .iter()
+            .find(|r| r.iso_3166_1 == country)?
+            .release_dates
+            .iter()
+            .map(|d| d.certification.trim())
+            .find(|c| !c.is_empty())
+            .map(str::to_string)
+    }
+}
+
+pub struct TmdbClient {
+    client: Client,
+    api_key: String,
+}
+
+impl TmdbClient {
+    pub fn new(api_key: String) -> Self {
+        info!("Initializing TMDb enrichment");
+        Self {
+            client: Client::new(),
+            api_key,
+        }
+    }
+
+    /// Look up the best match for `title`, or `None` if TMDb has no match
+    pub async fn find_movie(&self, title: &str) -> Result<Option<TmdbMovie>> {
+        info!("🎬 Looking up '{}' on TMDb", title);
+
+        let search: SearchResponse = self
+            .get(&format!("{}/search/movie", TMDB_API_URL), &[("query", title)])
+            .await
+            .context("TMDb search failed")?;
+        let Some(result) = search.results.first() else {
+            return Ok(None);
+        };
+
+        let details: MovieDetails = self
+            .get(
+                &format!("{}/movie/{}", TMDB_API_URL, result.id),
+                &[("append_to_response", "credits,release_dates")],
+            )
+            .await
+            .context("TMDb movie lookup failed")?;
+
+        Ok(Some(TmdbMovie {
+            original_title: details.original_title,
+            release_date: details.release_date.filter(|d| !d.is_empty()),
+            director: details
+                .credits
+                .and_then(|c| c.crew.into_iter().find(|m| m.job == "Director"))
+                .map(|m| m.name),
+            genres: details.genres.into_iter().map(|g| g.name).collect(),
+            runtime_minutes: details.runtime.filter(|r| *r > 0),
+            cbfc_rating: details.release_dates.as_ref().and_then(|r| r.certification("IN")),
+            mpaa_rating: details.release_dates.as_ref().and_then(|r| r.certification("US")),
+        }))
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: &str, query: &[(&str, &str)]) -> Result<T> {
+        let response = self
+            .client
+            .get(url)
+            .query(&[("api_key", self.api_key.as_str())])
+            .query(query)
+            .timeout(std::time::Duration::from_secs(10))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("TMDb returned {}", response.status());
+        }
+        Ok(response.json().await?)
+    }
+}
+
+/// Whether the LLM left a field empty
+fn is_missing(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.trim().is_empty() || s.eq_ignore_ascii_case("unknown"),
+        Some(Value::Array(a)) => a.is_empty(),
+        Some(Value::Number(n)) => n.as_u64() == Some(0),
+        _ => false,
+    }
+}
+
+/// Copy TMDb details into fields the agreement is missing, for either the flat
+/// LLM format or the structured `content` object. Returns the keys filled in.
+pub fn apply_enrichment(agreement: &mut Value, movie: &TmdbMovie) -> Vec<&'static str> {
+    let structured = agreement.get("content").is_some_and(Value::is_object);
+    let target = if structured {
+        agreement.get_mut("content")
+    } else {
+        Some(&mut *agreement)
+    };
+    let Some(Value::Object(map)) = target else {
+        return Vec::new();
+    };
+
+    let key = |flat: &'static str, camel: &'static str| if structured { camel } else { flat };
+    let candidates: [(&'static str, Option<Value>); 5] = [
+        (key("original_title", "originalTitle"), movie.original_title.clone().map(Value::from)),
+        (key("release_date", "releaseDate"), movie.release_date.clone().map(Value::from)),
+        ("director", movie.director.clone().map(Value::from)),
+        ("genre", Some(json!(movie.genres)).filter(|_| !movie.genres.is_empty())),
+        ("duration", movie.runtime_minutes.map(Value::from)),
+    ];
+
+    let mut filled = Vec::new();
+    for (field, value) in candidates {
+        if let Some(value) = value {
+            if is_missing(map.get(field)) {
+                map.insert(field.to_string(), value);
+                filled.push(field);
+            }
+        }
+    }
+
+    // Ratings are an object in the structured format and flat keys otherwise
+    let ratings = [
+        (key("cbfc_rating", "cbfc"), &movie.cbfc_rating),
+        (key("mpaa_rating", "mpaa"), &movie.mpaa_rating),
+    ];
+    for (field, rating) in ratings {
+        let Some(rating) = rating else { continue };
+        let slot = if structured {
+            match map.entry("rating").or_insert_with(|| json!({})) {
+                Value::Object(rating_map) => rating_map,
+                _ => continue,
+            }
+        } else {
+            &mut *map
+        };
+        if is_missing(slot.get(field)) {
+            slot.insert(field.to_string(), json!(rating));
+            filled.push(field);
+        }
+    }
+
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movie() -> TmdbMovie {
+        TmdbMovie {
+            original_title: Some("Kalki 2898 AD".to_string()),
+            release_date: Some("2024-06-27".to_string()),
+            director: Some("Nag Ashwin".to_string()),
+            genres: vec!["Science Fiction".to_string()],
+            runtime_minutes: Some(181),
+            cbfc_rating: Some("UA".to_string()),
+            mpaa_rating: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_enrichment_only_fills_missing_fields() {
+        let mut flat = json!({"title": "Kalki", "director": "Someone Else", "release_date": null});
+        let filled = apply_enrichment(&mut flat, &movie());
+
+        assert_eq!(flat["director"], "Someone Else");
+        assert_eq!(flat["release_date"], "2024-06-27");
+        assert_eq!(flat["duration"], 181);
+        assert!(!filled.contains(&"director"));
+
+        let mut structured = json!({"content": {"title": "Kalki", "director": "Unknown", "duration": 0, "rating": {"cbfc": ""}}});
+        apply_enrichment(&mut structured, &movie());
+
+        assert_eq!(structured["content"]["director"], "Nag Ashwin");
+        assert_eq!(structured["content"]["duration"], 181);
+        assert_eq!(structured["content"]["originalTitle"], "Kalki 2898 AD");
+        assert_eq!(structured["content"]["rating"]["cbfc"], "UA");
+    }
+}
@@ -0,0 +1,177 @@
+// src/webhooks.rs - Client-registered webhooks for agreement lifecycle events
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::worker::post_webhook;
+
+/// Events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "agreement.parsed")]
+    AgreementParsed,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AgreementParsed => "agreement.parsed",
+        }
+    }
+}
+
+/// A registered webhook URL and the events it receives
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WebhookSubscription {
+    pub id: Uuid,
+    /// The API key that registered it and whose agreements it hears about
+    #[serde(skip)]
+    pub api_key_id: Uuid,
+    pub url: String,
+    pub events: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Summary of a newly stored agreement, sent as `agreement.parsed`
+#[derive(Debug, Clone)]
+pub struct AgreementParsedEvent<'a> {
+    pub cid: &'a str,
+    pub agreement_id: Option<&'a str>,
+    pub licensor: Option<&'a str>,
+    pub deal_value: Option<i64>,
+}
+
+impl AgreementParsedEvent<'_> {
+    pub fn payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "event": WebhookEvent::AgreementParsed.as_str(),
+            "cid": self.cid,
+            "agreement_id": self.agreement_id,
+            "licensor": self.licensor,
+            "deal_value": self.deal_value,
+            "timestamp": Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+async fn subscribers(db: &PgPool, api_key_id: Uuid, event: WebhookEvent) -> Result<Vec<String>> {
+    sqlx::query_scalar("SELECT url FROM webhook_subscriptions WHERE api_key_id = $1 AND $2 = ANY(events)")
+        .bind(api_key_id)
+        .bind(event.as_str())
+        .fetch_all(db)
+        .await
+        .context("Failed to load webhook subscriptions")
+}
+
+/// Delivers events to the subscriptions of the API key they concern
+pub struct WebhookEmitter {
+    db: PgPool,
+}
+
+impl WebhookEmitter {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    pub async fn register(&self, api_key_id: Uuid, url: &str, events: &[WebhookEvent]) -> Result<WebhookSubscription> {
+        let events: Vec<&str> = events.iter().map(WebhookEvent::as_str).collect();
+        let subscription = sqlx::query_as::<_, WebhookSubscription>(
+            r#"
+            INSERT INTO webhook_subscriptions (api_key_id, url, events)
+            VALUES ($1, $2, $3)
+            RETURNING id, api_key_id, url, events, created_at
+            "#,
+        )
+        .bind(api_key_id)
+        .bind(url)
+        .bind(&events)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to register webhook")?;
+
+        info!("🪝 Registered webhook {} for {}", subscription.id, events.join(", "));
+        Ok(subscription)
+    }
+
+    /// Webhooks registered with `api_key_id`, newest first
+    pub async fn list(&self, api_key_id: Uuid) -> Result<Vec<WebhookSubscription>> {
+        sqlx::query_as::<_, WebhookSubscription>(
+            r#"
+            SELECT id, api_key_id, url, events, created_at
+            FROM webhook_subscriptions
+            WHERE api_key_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(api_key_id)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list webhooks")
+    }
+
+    /// Delete a webhook registered with `api_key_id`, returning whether one was found
+    pub async fn delete(&self, api_key_id: Uuid, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM webhook_subscriptions WHERE id = $1 AND api_key_id = $2")
+            .bind(id)
+            .bind(api_key_id)
+            .execute(&self.db)
+            .await
+            .context("Failed to delete webhook")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Send `agreement.parsed` to the webhooks of the key the agreement was
+    /// parsed with, in the background. Agreements parsed without a key are
+    /// not announced. Delivery failures only log.
+    pub fn agreement_parsed(&self, api_key_id: Option<Uuid>, agreement: &AgreementParsedEvent<'_>) {
+        if let Some(api_key_id) = api_key_id {
+            self.emit(api_key_id, WebhookEvent::AgreementParsed, agreement.payload());
+        }
+    }
+
+    fn emit(&self, api_key_id: Uuid, event: WebhookEvent, payload: serde_json::Value) {
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            let urls = match subscribers(&db, api_key_id, event).await {
+                Ok(urls) => urls,
+                Err(e) => {
+                    warn!("{:#}", e);
+                    return;
+                }
+            };
+            for url in urls {
+                post_webhook(&url, &payload).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsed_payload_and_event_names() {
+        let payload = AgreementParsedEvent {
+            cid: "bafkrei-test",
+            agreement_id: Some("VYJAYANTHI-KALKI-2024"),
+            licensor: Some("Vyjayanthi Movies"),
+            deal_value: Some(1_000_000_000),
+        }
+        .payload();
+
+        assert_eq!(payload["event"], "agreement.parsed");
+        assert_eq!(payload["cid"], "bafkrei-test");
+        assert_eq!(payload["deal_value"], 1_000_000_000i64);
+        assert!(payload["timestamp"].is_string());
+
+        let events: Vec<WebhookEvent> = serde_json::from_str(r#"["agreement.parsed"]"#).unwrap();
+        assert_eq!(events, vec![WebhookEvent::AgreementParsed]);
+        // Nothing emits expiry events, so subscribing to them is rejected
+        assert!(serde_json::from_str::<WebhookEvent>(r#""agreement.expired""#).is_err());
+        assert!(serde_json::from_str::<WebhookEvent>(r#""agreement.deleted""#).is_err());
+    }
+}
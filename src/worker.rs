@@ -1,9 +1,100 @@
 // src/worker.rs - Background worker for processing PDF jobs
+use crate::agreement_store::{self, DeliveryNotice};
+use crate::docx_extractor::{self, DocxExtractor};
+use crate::integrity;
+use crate::llm_service::{self, ParseOptions, RetryHook};
+use crate::pdf_download;
+use crate::pdf_extractor::TextExtractor;
+use crate::webhooks::AgreementParsedEvent;
 use crate::AppState;
-use sqlx::PgPool;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// How often leftover temp files are swept
+const TEMP_FILE_CLEANUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// Temp files older than this belong to requests that never finished
+const TEMP_FILE_MAX_AGE_SECS: u64 = 60 * 60;
+
+/// Agreements updated at once by a bulk status job
+const BULK_CONCURRENCY: usize = 10;
+
+/// Most agreements one bulk status job accepts
+pub const MAX_BULK_AGREEMENTS: usize = 500;
+
+/// How long a finished bulk job's progress stays available
+pub const BULK_JOB_TTL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// In-memory progress of bulk status jobs, keyed by job id
+pub type BulkJobRegistry = Arc<Mutex<HashMap<Uuid, BulkJobProgress>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkJobState {
+    Queued,
+    Running,
+    Completed,
+}
+
+/// Outcome for one agreement in a bulk job
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkItemResult {
+    pub ipfs_cid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_ipfs_cid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkJobProgress {
+    pub job_id: Uuid,
+    pub state: BulkJobState,
+    pub new_status: String,
+    pub total: usize,
+    pub processed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+    #[serde(skip)]
+    pub finished_at: Option<std::time::Instant>,
+}
+
+impl BulkJobProgress {
+    pub fn new(job_id: Uuid, new_status: &str, total: usize) -> Self {
+        Self {
+            job_id,
+            state: BulkJobState::Queued,
+            new_status: new_status.to_string(),
+            total,
+            processed: 0,
+            succeeded: 0,
+            failed: 0,
+            results: Vec::new(),
+            finished_at: None,
+        }
+    }
+}
+
+/// Drop jobs that finished more than `ttl` ago, so `bulk_jobs` does not
+/// grow for the life of the process
+pub fn prune_bulk_jobs(bulk_jobs: &mut HashMap<Uuid, BulkJobProgress>, ttl: std::time::Duration) {
+    bulk_jobs.retain(|_, job| !job.finished_at.is_some_and(|at| at.elapsed() >= ttl));
+}
+
+#[derive(sqlx::FromRow)]
+struct PendingJob {
+    id: Uuid,
+    file_path: String,
+    webhook_url: Option<String>,
+    /// Key the job was queued with, whose webhooks hear about the result
+    api_key_id: Option<Uuid>,
+}
+
 pub async fn start_worker(state: AppState) {
     info!("🔧 Background worker started");
 
@@ -20,12 +111,13 @@ pub async fn start_worker(state: AppState) {
 
 async fn process_pending_jobs(state: &AppState) -> anyhow::Result<()> {
     // Fetch pending jobs
-    let pending_jobs = sqlx::query!(
+    let pending_jobs: Vec<PendingJob> = sqlx::query_as(
         r#"
-        SELECT id, file_path, webhook_url
-        FROM jobs
-        WHERE status = 'pending'
-        ORDER BY created_at ASC
+        SELECT j.id, j.file_path, j.webhook_url, k.id AS api_key_id
+        FROM jobs j
+        LEFT JOIN api_keys k ON k.key_hash = j.api_key_hash
+        WHERE j.status = 'pending'
+        ORDER BY j.created_at ASC
         LIMIT 5
         "#
     )
@@ -36,26 +128,24 @@ async fn process_pending_jobs(state: &AppState) -> anyhow::Result<()> {
         info!("🔄 Processing job: {}", job.id);
         
         // Mark as processing
-        sqlx::query!(
-            "UPDATE jobs SET status = 'processing', started_at = NOW() WHERE id = $1",
-            job.id
-        )
-        .execute(&state.db)
+        sqlx::query("UPDATE jobs SET status = 'processing', started_at = NOW() WHERE id = $1")
+            .bind(job.id)
+            .execute(&state.db)
         .await?;
 
         // Process the job
         match process_job(state, job.id, &job.file_path).await {
             Ok((ipfs_cid, encryption_key, parsed_json)) => {
                 // Update job as completed
-                let processing_time = sqlx::query_scalar!(
-                    "SELECT EXTRACT(epoch FROM (NOW() - started_at))::bigint * 1000 FROM jobs WHERE id = $1",
-                    job.id
+                let processing_time: i64 = sqlx::query_scalar(
+                    "SELECT EXTRACT(epoch FROM (NOW() - started_at))::bigint * 1000 FROM jobs WHERE id = $1"
                 )
+                .bind(job.id)
                 .fetch_one(&state.db)
                 .await
                 .unwrap_or(0);
 
-                sqlx::query!(
+                sqlx::query(
                     r#"
                     UPDATE jobs
                     SET status = 'completed',
@@ -65,18 +155,27 @@ async fn process_pending_jobs(state: &AppState) -> anyhow::Result<()> {
                         encryption_key = $4,
                         parsed_json = $5
                     WHERE id = $1
-                    "#,
-                    job.id,
-                    processing_time,
-                    ipfs_cid,
-                    encryption_key,
-                    parsed_json
+                    "#
                 )
+                .bind(job.id)
+                .bind(processing_time)
+                .bind(&ipfs_cid)
+                .bind(&encryption_key)
+                .bind(&parsed_json)
                 .execute(&state.db)
                 .await?;
 
                 info!("✅ Job completed: {} ({}ms)", job.id, processing_time);
 
+                state.webhooks.agreement_parsed(job.api_key_id, &AgreementParsedEvent {
+                    cid: &ipfs_cid,
+                    agreement_id: crate::json_str(&parsed_json, &["/agreementId"]),
+                    licensor: crate::json_str(&parsed_json, &["/licensor", "/parties/licensor/name"]),
+                    deal_value: ["/total_fee", "/financial/dealValue"]
+                        .iter()
+                        .find_map(|p| parsed_json.pointer(p).and_then(|v| v.as_i64())),
+                });
+
                 // Send webhook if configured
                 if let Some(webhook_url) = job.webhook_url {
                     tokio::spawn(async move {
@@ -88,7 +187,7 @@ async fn process_pending_jobs(state: &AppState) -> anyhow::Result<()> {
                 error!("❌ Job failed: {} - {}", job.id, e);
                 
                 // Mark as failed
-                sqlx::query!(
+                sqlx::query(
                     r#"
                     UPDATE jobs
                     SET status = 'failed',
@@ -96,10 +195,10 @@ async fn process_pending_jobs(state: &AppState) -> anyhow::Result<()> {
                         error_message = $2,
                         retry_count = retry_count + 1
                     WHERE id = $1
-                    "#,
-                    job.id,
-                    e.to_string()
+                    "#
                 )
+                .bind(job.id)
+                .bind(e.to_string())
                 .execute(&state.db)
                 .await?;
             }
@@ -109,17 +208,75 @@ async fn process_pending_jobs(state: &AppState) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Queue a PDF for background parsing. `file_path` is a local path or an
+/// `s3://bucket/key` location.
+pub async fn enqueue_job(
+    db: &sqlx::PgPool,
+    file_name: &str,
+    file_path: &str,
+    file_size: i64,
+    api_key_hash: Option<&str>,
+    webhook_url: Option<&str>,
+) -> anyhow::Result<Uuid> {
+    // Jobs queued without a key are stored with an empty hash
+    let id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO jobs (file_name, file_path, file_size, api_key_hash, webhook_url)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+    )
+    .bind(file_name)
+    .bind(file_path)
+    .bind(file_size)
+    .bind(api_key_hash.unwrap_or_default())
+    .bind(webhook_url)
+    .fetch_one(db)
+    .await?;
+
+    info!("📥 Queued job {} for {}", id, file_path);
+    Ok(id)
+}
+
+/// Read a job's PDF from disk or, for `s3://` paths, through a presigned URL
+async fn read_job_file(state: &AppState, file_path: &str) -> anyhow::Result<Vec<u8>> {
+    let Some(location) = file_path.strip_prefix("s3://") else {
+        return Ok(tokio::fs::read(file_path).await?);
+    };
+
+    let storage = state
+        .s3_storage
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Job references S3 but S3 is not configured"))?;
+    let key = location
+        .strip_prefix(storage.bucket())
+        .and_then(|k| k.strip_prefix('/'))
+        .ok_or_else(|| anyhow::anyhow!("Job references a different bucket: {}", file_path))?;
+
+    let url = pdf_download::validate_url(&storage.presigned_download_url(key).await?)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    pdf_download::download_pdf(&reqwest::Client::new(), &url, state.max_upload_bytes)
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))
+}
+
 async fn process_job(
     state: &AppState,
     job_id: Uuid,
     file_path: &str,
 ) -> anyhow::Result<(String, String, serde_json::Value)> {
     // Read PDF file
-    let pdf_bytes = tokio::fs::read(file_path).await?;
+    let pdf_bytes = read_job_file(state, file_path).await?;
     
     // Extract text
-    info!("🔍 Extracting text from PDF");
-    let pdf_text = state.pdf_extractor.extract_text(&pdf_bytes).await?;
+    let extractor: &dyn TextExtractor = if docx_extractor::is_docx(file_path, None, &pdf_bytes) {
+        info!("🔍 Extracting text from Word document");
+        &DocxExtractor
+    } else {
+        info!("🔍 Extracting text from PDF");
+        state.pdf_extractor.as_ref()
+    };
+    let pdf_text = extractor.extract_text(&pdf_bytes, None).await?;
     
     if pdf_text.len() < 100 {
         anyhow::bail!("Extracted text too short: {} chars", pdf_text.len());
@@ -127,9 +284,27 @@ async fn process_job(
     
     info!("✅ Extracted {} characters", pdf_text.len());
 
-    // Parse with LLM
-    info!("🤖 Calling LLM for parsing");
-    let json_string = state.llm_service.parse_agreement(&pdf_text).await?;
+    // Parse with LLM, unless this document was parsed recently
+    let cache_key = llm_service::parse_cache_key(&pdf_bytes, None);
+    let json_string = match state.llm_service.cached_parse(&cache_key) {
+        Some((parsed, _)) => {
+            info!("♻️  Using cached parse of this document");
+            parsed.json
+        }
+        None => {
+            info!("🤖 Calling LLM for parsing");
+            let log_retry = |attempt: u32, delay: std::time::Duration, e: &anyhow::Error| {
+                warn!("🔁 Job {}: LLM call failed ({:#}), attempt {} in {:?}", job_id, e, attempt, delay);
+            };
+            let options = ParseOptions {
+                on_retry: Some(RetryHook(&log_retry)),
+                ..ParseOptions::default()
+            };
+            let parsed = state.llm_service.parse_agreement(&pdf_text, &options).await?;
+            state.llm_service.cache_parse(cache_key, &parsed, None);
+            parsed.json
+        }
+    };
     
     info!("✅ Got JSON from LLM ({} bytes)", json_string.len());
 
@@ -149,9 +324,68 @@ async fn process_job(
     Ok((ipfs_cid, encryption_key, parsed_json))
 }
 
+/// Periodically notify webhooks about delivery deadlines within `notice_days`
+pub async fn start_delivery_monitor(state: AppState, notice_days: i32) {
+    info!("📅 Delivery deadline monitor started (notice: {} days)", notice_days);
+
+    loop {
+        if let Err(e) = send_delivery_notices(&state, notice_days).await {
+            error!("Delivery monitor error: {}", e);
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(3600)).await;
+    }
+}
+
+/// Periodically remove temp files orphaned by crashed or interrupted requests
+pub async fn start_temp_file_cleanup(state: AppState) {
+    info!("🧹 Temp file cleanup started for {}", state.pdf_extractor.temp_dir().display());
+
+    loop {
+        if let Err(e) = state.pdf_extractor.cleanup_old_temp_files(TEMP_FILE_MAX_AGE_SECS).await {
+            error!("Temp file cleanup error: {:#}", e);
+        }
+
+        tokio::time::sleep(TEMP_FILE_CLEANUP_INTERVAL).await;
+    }
+}
+
+async fn send_delivery_notices(state: &AppState, notice_days: i32) -> anyhow::Result<()> {
+    let today = chrono::Utc::now().date_naive();
+
+    for notice in agreement_store::due_delivery_notices(&state.db, notice_days).await? {
+        let days_until_deadline = (notice.delivery_deadline - today).num_days();
+        info!(
+            "📅 Agreement {} delivery due in {} days ({})",
+            notice.ipfs_cid, days_until_deadline, notice.delivery_deadline
+        );
+
+        if send_delivery_notice(&notice, days_until_deadline).await {
+            agreement_store::mark_delivery_notice_sent(&state.db, notice.id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn send_delivery_notice(notice: &DeliveryNotice, days_until_deadline: i64) -> bool {
+    let status = if days_until_deadline < 0 { "overdue" } else { "due_soon" };
+
+    let payload = serde_json::json!({
+        "event": "delivery_deadline",
+        "status": status,
+        "ipfs_cid": notice.ipfs_cid,
+        "agreement_id": notice.agreement_id,
+        "title": notice.title,
+        "delivery_deadline": notice.delivery_deadline.to_string(),
+        "days_until_deadline": days_until_deadline,
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    });
+
+    post_webhook(&notice.webhook_url, &payload).await
+}
+
 async fn send_webhook(url: &str, job_id: Uuid, ipfs_cid: &str, encryption_key: &str) {
-    let client = reqwest::Client::new();
-    
     let payload = serde_json::json!({
         "job_id": job_id.to_string(),
         "status": "completed",
@@ -160,18 +394,253 @@ async fn send_webhook(url: &str, job_id: Uuid, ipfs_cid: &str, encryption_key: &
         "timestamp": chrono::Utc::now().to_rfc3339()
     });
 
+    post_webhook(url, &payload).await;
+}
+
+/// POST a JSON payload, returning whether the receiver accepted it. The URL
+/// is checked again here because its DNS may have changed since it was
+/// registered; internal addresses and redirects are refused.
+pub(crate) async fn post_webhook(url: &str, payload: &serde_json::Value) -> bool {
+    let client = match reqwest::Url::parse(url) {
+        Ok(parsed) => match pdf_download::public_client(&parsed).await {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("⚠️  Webhook to {} refused: {}", url, e);
+                return false;
+            }
+        },
+        Err(e) => {
+            warn!("⚠️  Webhook URL {} is invalid: {}", url, e);
+            return false;
+        }
+    };
+
     match client
         .post(url)
-        .json(&payload)
+        .json(payload)
         .timeout(std::time::Duration::from_secs(10))
         .send()
         .await
     {
         Ok(resp) => {
             info!("✅ Webhook sent to {} (status: {})", url, resp.status());
+            resp.status().is_success()
         }
         Err(e) => {
             warn!("⚠️  Webhook failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Set the status of every `(cid, key)` agreement, re-encrypting each with its
+/// existing key. Agreements are re-encrypted `BULK_CONCURRENCY` at a time and
+/// each group is stored with one `batch_upload`. Progress is recorded in
+/// `bulk_jobs` as each group finishes.
+pub async fn run_bulk_status_update(
+    state: AppState,
+    bulk_jobs: BulkJobRegistry,
+    job_id: Uuid,
+    agreements: Vec<(String, String)>,
+    new_status: String,
+    reason: Option<String>,
+) {
+    info!("📦 Bulk job {} started: {} agreements → {}", job_id, agreements.len(), new_status);
+    if let Some(progress) = bulk_jobs.lock().await.get_mut(&job_id) {
+        progress.state = BulkJobState::Running;
+    }
+
+    for group in agreements.chunks(BULK_CONCURRENCY) {
+        let sealed = futures::future::join_all(
+            group
+                .iter()
+                .map(|(cid, key)| restatused_document(&state, cid, key, &new_status, reason.as_deref())),
+        )
+        .await;
+        let outcomes = store_bulk_group(&state, group, sealed, &new_status).await;
+
+        let mut registry = bulk_jobs.lock().await;
+        let Some(progress) = registry.get_mut(&job_id) else { continue };
+        for ((cid, _), outcome) in group.iter().zip(outcomes) {
+            progress.processed += 1;
+            let result = match outcome {
+                Ok(new_cid) => {
+                    progress.succeeded += 1;
+                    BulkItemResult { ipfs_cid: cid.clone(), new_ipfs_cid: Some(new_cid), error: None }
+                }
+                Err(e) => {
+                    warn!("Bulk job {}: {} failed: {:#}", job_id, cid, e);
+                    progress.failed += 1;
+                    BulkItemResult { ipfs_cid: cid.clone(), new_ipfs_cid: None, error: Some(format!("{:#}", e)) }
+                }
+            };
+            progress.results.push(result);
+        }
+
+        info!("📦 Bulk job {} progress: {}/{} ({} failed)", job_id, progress.processed, progress.total, progress.failed);
+    }
+
+    if let Some(progress) = bulk_jobs.lock().await.get_mut(&job_id) {
+        progress.state = BulkJobState::Completed;
+        progress.finished_at = Some(std::time::Instant::now());
+        info!("✅ Bulk job {} completed: {} updated, {} failed", job_id, progress.succeeded, progress.failed);
+    }
+}
+
+/// Upload the re-encrypted agreements of one group together and record each
+/// new version. Returns each agreement's new CID, or why it failed, in order.
+async fn store_bulk_group(
+    state: &AppState,
+    group: &[(String, String)],
+    sealed: Vec<anyhow::Result<Vec<u8>>>,
+    new_status: &str,
+) -> Vec<anyhow::Result<String>> {
+    // Indexed, since the same CID may be listed twice
+    let names: Vec<String> = group
+        .iter()
+        .enumerate()
+        .map(|(i, (cid, _))| format!("{}-{}.json.enc", i, cid))
+        .collect();
+
+    let mut failures = Vec::with_capacity(group.len());
+    let mut items = Vec::new();
+    for (name, document) in names.iter().zip(sealed) {
+        match document {
+            Ok(data) => {
+                items.push((name.as_str(), data));
+                failures.push(None);
+            }
+            Err(e) => failures.push(Some(e)),
         }
     }
-}
\ No newline at end of file
+    let uploaded = if items.is_empty() {
+        Ok(HashMap::new())
+    } else {
+        state.ipfs_client.batch_upload(items).await.map(|cids| cids.into_iter().collect::<HashMap<_, _>>())
+    };
+
+    let mut outcomes = Vec::with_capacity(group.len());
+    for (((cid, _), name), failure) in group.iter().zip(&names).zip(failures) {
+        let outcome = match (failure, &uploaded) {
+            (Some(e), _) => Err(e),
+            (None, Err(e)) => Err(anyhow::anyhow!("Batch upload failed: {:#}", e)),
+            (None, Ok(cids)) => match cids.get(name) {
+                Some(new_cid) => record_new_status(state, cid, new_cid, new_status).await.map(|()| new_cid.clone()),
+                None => Err(anyhow::anyhow!("{} is missing from the batch upload", name)),
+            },
+        };
+        outcomes.push(outcome);
+    }
+    outcomes
+}
+
+/// Point the database record at the restatused agreement and record the version
+async fn record_new_status(state: &AppState, cid: &str, new_cid: &str, new_status: &str) -> anyhow::Result<()> {
+    if !agreement_store::update_status(&state.db, cid, new_cid, new_status).await? {
+        warn!("No database record for {}, status only stored on IPFS", cid);
+    }
+    if let Err(e) = agreement_store::record_version(&state.db, new_cid, Some(cid), None, Some(new_status)).await {
+        warn!("{:#}", e);
+    }
+    Ok(())
+}
+
+/// Fetch one agreement, update its status and re-encrypt it with the same
+/// key, ready to upload. Field-encrypted agreements stay field-encrypted.
+async fn restatused_document(
+    state: &AppState,
+    cid: &str,
+    key: &str,
+    new_status: &str,
+    reason: Option<&str>,
+) -> anyhow::Result<Vec<u8>> {
+    let encrypted_data = state.ipfs_client.fetch(cid).await?;
+    let document = state.encryption_service.open_document(&encrypted_data, key)?;
+    let mut agreement: serde_json::Value = serde_json::from_str(&document.plaintext)?;
+
+    apply_status(&mut agreement, new_status, reason, cid)?;
+    integrity::stamp_content_hashes(&mut agreement, None);
+
+    state
+        .encryption_service
+        .seal_document(&agreement.to_string(), &document.encrypted_fields, key)
+}
+
+/// Write the status, reason, modification date and `previous_cid` into the
+/// agreement's metadata
+fn apply_status(
+    agreement: &mut serde_json::Value,
+    new_status: &str,
+    reason: Option<&str>,
+    previous_cid: &str,
+) -> anyhow::Result<()> {
+    let metadata = agreement
+        .as_object_mut()
+        .map(|root| {
+            root.entry("metadata")
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+        })
+        .and_then(|m| m.as_object_mut())
+        .ok_or_else(|| anyhow::anyhow!("Agreement JSON is not an object"))?;
+
+    metadata.insert("status".to_string(), serde_json::json!(new_status));
+    metadata.insert("lastModified".to_string(), serde_json::json!(chrono::Utc::now().format("%Y-%m-%d").to_string()));
+    match reason {
+        Some(reason) => metadata.insert("statusReason".to_string(), serde_json::json!(reason)),
+        None => metadata.remove("statusReason"),
+    };
+    metadata.insert("previousVersionCid".to_string(), serde_json::json!(previous_cid));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_status() {
+        let mut agreement = serde_json::json!({ "title": "Kalki 2898 AD", "metadata": { "status": "Active" } });
+        apply_status(&mut agreement, "Terminated", Some("Licensee acquired"), "bafyold").unwrap();
+
+        assert_eq!(agreement["metadata"]["status"], "Terminated");
+        assert_eq!(agreement["metadata"]["statusReason"], "Licensee acquired");
+        assert!(apply_status(&mut serde_json::json!([]), "Terminated", None, "bafyold").is_err());
+    }
+
+    #[test]
+    fn test_prune_bulk_jobs() {
+        let (running, finished) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut bulk_jobs = HashMap::new();
+        bulk_jobs.insert(running, BulkJobProgress::new(running, "Terminated", 3));
+        let mut done = BulkJobProgress::new(finished, "Terminated", 1);
+        done.finished_at = Some(std::time::Instant::now());
+        bulk_jobs.insert(finished, done);
+
+        prune_bulk_jobs(&mut bulk_jobs, BULK_JOB_TTL);
+        assert_eq!(bulk_jobs.len(), 2);
+
+        prune_bulk_jobs(&mut bulk_jobs, std::time::Duration::ZERO);
+        assert!(bulk_jobs.contains_key(&running));
+        assert!(!bulk_jobs.contains_key(&finished));
+    }
+
+    #[tokio::test]
+    async fn test_restatus_keeps_field_encryption() {
+        let state = AppState::in_memory();
+        let key = crate::memory_store::MemoryEncryptionService::KEY;
+        let agreement = serde_json::json!({ "title": "Kalki 2898 AD", "financial": { "dealValue": 1000 } });
+        let protected = state.encryption_service.encrypt_fields(&agreement, &["financial"], key).unwrap();
+        let cid = state.ipfs_client.upload(protected.to_string().as_bytes()).await.unwrap();
+
+        let sealed = restatused_document(&state, &cid, key, "Terminated", None).await.unwrap();
+        let stored: serde_json::Value = serde_json::from_slice(&sealed).unwrap();
+        assert_eq!(stored["title"], "Kalki 2898 AD");
+        assert!(stored["financial"][crate::encryption::ENCRYPTED_FIELD_KEY].is_string());
+
+        let document = state.encryption_service.open_document(&stored.to_string().into_bytes(), key).unwrap();
+        let restatused: serde_json::Value = serde_json::from_str(&document.plaintext).unwrap();
+        assert_eq!(restatused["metadata"]["status"], "Terminated");
+        assert_eq!(restatused["metadata"]["previousVersionCid"], cid.as_str());
+        assert_eq!(restatused["financial"]["dealValue"], 1000);
+    }
+}